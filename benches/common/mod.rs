@@ -0,0 +1,64 @@
+//! Timing harness shared by the benchmarks, which run on stable Rust
+//! without a benchmark framework.
+use std::env;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Minimum time a benchmark is repeated for, so small inputs are
+/// measured over many runs.
+const MIN_TIME: Duration = Duration::from_millis(500);
+
+/// Pseudo random input of `len` bytes, the same on every run.
+///
+/// # Arguments
+///
+/// * `len` - input size in bytes.
+pub fn input(len: usize) -> Vec<u8> {
+    let mut state: u32 = 0x2545_f491;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+/// Returns true if the benchmark named `name` was selected: every
+/// benchmark runs unless a name filter is given, as in
+/// `cargo bench --bench pipeline -- dump`.
+pub fn selected(name: &str) -> bool {
+    let filters: Vec<String> = env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with('-'))
+        .collect();
+    filters.is_empty() || filters.iter().any(|filter| name.contains(filter.as_str()))
+}
+
+/// Run `f` over `input` until `MIN_TIME` has passed, at least once, and
+/// print the mean time per run and the throughput.
+///
+/// # Arguments
+///
+/// * `name` - benchmark name, printed and matched by the name filter.
+/// * `input` - bytes processed by each run.
+/// * `f` - benchmarked code.
+pub fn bench(name: &str, input: &[u8], mut f: impl FnMut(&[u8])) {
+    if !selected(name) {
+        return;
+    }
+    let mut runs: u32 = 0;
+    let started = Instant::now();
+    while runs == 0 || started.elapsed() < MIN_TIME {
+        f(black_box(input));
+        runs += 1;
+    }
+    let seconds = started.elapsed().as_secs_f64() / f64::from(runs);
+    println!(
+        "{:<40} {:>12.3} ms {:>10.1} MiB/s",
+        name,
+        seconds * 1000.0,
+        input.len() as f64 / seconds / (1 << 20) as f64
+    );
+}
@@ -0,0 +1,46 @@
+//! Throughput of octet formatting and whole dumps.
+//!
+//! Run with `cargo bench`. `format!` is the allocating rendering that
+//! `print_byte` replaced, timed for comparison.
+mod common;
+
+use common::{bench, input};
+use hx::config::HexConfig;
+use hx::output::print_byte;
+use hx::Format;
+use std::io::{self, Write};
+
+/// Size of the benchmark input.
+const INPUT_LEN: usize = 4 << 20;
+
+fn main() {
+    let input = input(INPUT_LEN);
+    bench("format! per octet", &input, |input| {
+        let mut out = io::sink();
+        for b in input {
+            let octet = format!("{:#04x}", b);
+            write!(out, "{} ", octet).unwrap();
+        }
+    });
+    bench("print_byte", &input, |input| {
+        let mut out = io::sink();
+        for b in input {
+            print_byte(&mut out, *b, Format::LowerHex, None, true).unwrap();
+        }
+    });
+    for (name, format) in [
+        ("x", Format::LowerHex),
+        ("X", Format::UpperHex),
+        ("o", Format::Octal),
+        ("b", Format::Binary),
+        ("d", Format::Decimal),
+    ] {
+        for color in [false, true] {
+            let config = HexConfig::new().columns(16).format(format).color(color);
+            let label = format!("dump -f{} -t{}", name, color as u8);
+            bench(&label, &input, |mut input| {
+                hx::dump(&mut input, &mut io::sink(), &config).unwrap();
+            });
+        }
+    }
+}
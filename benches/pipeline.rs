@@ -0,0 +1,69 @@
+//! Baseline of the rendering pipeline on 1 KiB, 1 MiB and 100 MiB of
+//! synthetic input: reading lines, formatting octets, the dump loop and
+//! array output.
+//!
+//! Run with `cargo bench --bench pipeline`, optionally followed by `--`
+//! and a part of the benchmark names to run, e.g. `-- 1KiB`.
+mod common;
+
+use common::{bench, input, selected};
+use hx::buffer::buf_to_array;
+use hx::config::HexConfig;
+use hx::output::{output_array_to_writer, print_byte, ArrayOptions};
+use hx::Format;
+use std::io::{self, BufRead};
+
+/// Input sizes, named in the benchmark names.
+const SIZES: [(&str, usize); 3] = [("1KiB", 1 << 10), ("1MiB", 1 << 20), ("100MiB", 100 << 20)];
+
+/// `buf_to_array` keeps every line of the input in memory, so it is only
+/// run on inputs up to this size.
+const PAGE_LIMIT: usize = 1 << 20;
+
+fn main() {
+    for (size, len) in SIZES {
+        // generating 100 MiB takes a while, skip it when filtered out
+        let names = [
+            "buf_to_array",
+            "print_byte",
+            "dump -t0",
+            "dump -t1",
+            "array -ar",
+            "array -ac",
+            "array -ahexstr",
+        ];
+        if !names
+            .iter()
+            .any(|name| selected(&format!("{} {}", name, size)))
+        {
+            continue;
+        }
+        let input = input(len);
+        if len <= PAGE_LIMIT {
+            bench(&format!("buf_to_array {}", size), &input, |mut input| {
+                buf_to_array(&mut input, 0, 16, 0).unwrap();
+            });
+        }
+        bench(&format!("print_byte {}", size), &input, |input| {
+            let mut out = io::sink();
+            for b in input {
+                print_byte(&mut out, *b, Format::LowerHex, None, true).unwrap();
+            }
+        });
+        for color in [false, true] {
+            let config = HexConfig::new().columns(16).color(color);
+            let name = format!("dump -t{} {}", color as u8, size);
+            bench(&name, &input, |mut input| {
+                hx::dump(&mut input, &mut io::sink(), &config).unwrap();
+            });
+        }
+        for array in ["r", "c", "hexstr"] {
+            bench(&format!("array -a{} {}", array, size), &input, |input| {
+                // array output takes an owned reader, so the copy is timed too
+                let buf: Box<dyn BufRead> = Box::new(io::Cursor::new(input.to_vec()));
+                let options = ArrayOptions::default();
+                output_array_to_writer(&mut io::sink(), array, &options, buf, 0, 16).unwrap();
+            });
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! Size, offset, range and highlight option values.
+#![no_main]
+
+use ansi_term::Color;
+use hx::args;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|value: &str| {
+    let _ = args::parse_u64(value);
+    let _ = args::parse_char(value);
+    let _ = args::parse_size(value);
+    let _ = args::parse_seek(value);
+    if let Ok(range) = args::parse_range(value) {
+        let _ = range.length();
+    }
+    let _ = args::parse_color(value);
+    if let Ok(mark) = args::parse_highlight(value) {
+        let _ = mark.highlight(Color::Red);
+    }
+});
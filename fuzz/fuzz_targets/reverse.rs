@@ -0,0 +1,55 @@
+//! `-R`, `-R --plain` and `--from-array` on arbitrary dump text.
+#![no_main]
+
+use hx::output::OffsetFormat;
+use hx::reverse::{reverse, reverse_array, reverse_plain, Layout};
+use hx::Format;
+use libfuzzer_sys::fuzz_target;
+use std::io::{self, Write};
+
+/// Output limit, as a dump line may fill a gap of any size with zeros.
+const LIMIT: u64 = 1 << 20;
+
+/// Sink failing once `LIMIT` bytes are written.
+struct Limited(u64);
+
+impl Write for Limited {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.0 + buf.len() as u64 > LIMIT {
+            return Err(io::Error::other("output limit"));
+        }
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let formats = [
+        Format::Octal,
+        Format::LowerHex,
+        Format::UpperHex,
+        Format::Binary,
+        Format::Decimal,
+        Format::SignedDecimal,
+    ];
+    for format in formats {
+        for offset_format in [OffsetFormat::Hex, OffsetFormat::Dec] {
+            for base_address in [0, 0x10] {
+                let _ = reverse(
+                    &mut &data[..],
+                    &mut Limited(0),
+                    format,
+                    offset_format,
+                    base_address,
+                    &Layout::default(),
+                );
+            }
+        }
+    }
+    let _ = reverse_plain(&mut &data[..], &mut Limited(0));
+    let _ = reverse_array(&mut &data[..], &mut Limited(0));
+});
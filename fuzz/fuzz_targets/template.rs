@@ -0,0 +1,21 @@
+//! `--template` parsing, and decoding the template's own text with it.
+#![no_main]
+
+use hx::buffer::read_line;
+use hx::template::{Annotator, Template};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    let template = match Template::parse(text) {
+        Ok(template) => template,
+        Err(_) => return,
+    };
+    let _ = template.highlights();
+    let mut annotator = Annotator::new(template);
+    let mut input = text.as_bytes();
+    let mut offset = 0;
+    while let Ok(Some(line)) = read_line(&mut input, 7, offset) {
+        offset += line.bytes;
+        let _ = annotator.feed(&line);
+    }
+});
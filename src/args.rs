@@ -0,0 +1,1635 @@
+//! command line definition and argument value parsing
+use crate::config::ConfigFile;
+use crate::output::{
+    ArrayElement, AsciiStyle, Charset, ColorMode, Endian, Highlight, OffsetFormat, Summary, Theme,
+};
+use crate::patch::{Edit, Patch};
+use crate::transform::Op;
+use ansi_term::Color;
+use clap::builder::{PossibleValue, PossibleValuesParser, TypedValueParser, ValueParser};
+use clap::{Arg, Command};
+use std::error::Error;
+use std::fmt;
+use std::io::SeekFrom;
+use std::num::ParseIntError;
+use std::ops::Range;
+
+/// Error parsing a size argument.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseSizeError {
+    /// the digits are not a valid integer
+    Int(ParseIntError),
+    /// the digits have a sign
+    Sign,
+    /// the size does not fit in 64 bits once the suffix is applied
+    Overflow,
+}
+
+impl fmt::Display for ParseSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int(e) => write!(f, "{}", e),
+            Self::Sign => write!(f, "size must not have a sign"),
+            Self::Overflow => write!(f, "size too large"),
+        }
+    }
+}
+
+impl Error for ParseSizeError {}
+
+impl From<ParseIntError> for ParseSizeError {
+    fn from(e: ParseIntError) -> ParseSizeError {
+        Self::Int(e)
+    }
+}
+
+/// Parse an argument that must be a single character.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn parse_char(value: &str) -> Option<char> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// When output is colored, from `--color`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum When {
+    /// always color
+    Always,
+    /// color only a terminal
+    Auto,
+    /// never color
+    Never,
+}
+
+/// Parse an on or off switch: `1`, `true`, `yes` or `on`, or `0`, `false`,
+/// `no` or `off`.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn parse_switch(value: &str) -> Option<bool> {
+    match value {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse a `--color` value, `always`, `auto` or `never`, or a switch as
+/// accepted by `parse_switch` for the older `-t 0|1` form.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn parse_when(value: &str) -> Option<When> {
+    match value {
+        "always" => Some(When::Always),
+        "auto" => Some(When::Auto),
+        "never" => Some(When::Never),
+        _ => parse_switch(value).map(|on| if on { When::Always } else { When::Never }),
+    }
+}
+
+/// Parse an unsigned integer given in decimal or as `0x` prefixed hex.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn parse_u64(value: &str) -> Result<u64, ParseIntError> {
+    let value = value.trim();
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse::<u64>(),
+    }
+}
+
+/// Parse a size given in decimal, or with a `0x`, `0o` or `0b` prefix, and
+/// an optional `k`, `M`, `G` or `T` unit, e.g. `4096`, `0x1000` or `4k`.
+/// Like `dd`, a unit alone or followed by `iB` multiplies by powers of
+/// 1024, and a unit followed by `B` by powers of 1000, so `4KiB` is 4096
+/// and `4kB` is 4000.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn parse_size(value: &str) -> Result<u64, ParseSizeError> {
+    let value = value.trim();
+    let lower = value.to_ascii_lowercase();
+    let is_unit = |stem: &str| stem.ends_with(['k', 'm', 'g', 't']);
+    let (stem, base) = match lower.strip_suffix("ib") {
+        Some(stem) if is_unit(stem) => (stem, 1024u64),
+        _ => match lower.strip_suffix('b') {
+            Some(stem) if is_unit(stem) => (stem, 1000),
+            _ => (lower.as_str(), 1024),
+        },
+    };
+    let (digits, multiplier) = match stem.chars().last() {
+        Some(c) if is_unit(stem) => {
+            let power = "kmgt".find(c).unwrap() as u32 + 1;
+            (&value[..stem.len() - 1], base.pow(power))
+        }
+        _ => (value, 1),
+    };
+    let (digits, radix) = match digits.get(..2) {
+        Some("0x") | Some("0X") => (&digits[2..], 16),
+        Some("0o") | Some("0O") => (&digits[2..], 8),
+        Some("0b") | Some("0B") => (&digits[2..], 2),
+        _ => (digits, 10),
+    };
+    // from_str_radix accepts a leading sign
+    if digits.starts_with('+') {
+        return Err(ParseSizeError::Sign);
+    }
+    u64::from_str_radix(digits, radix)?
+        .checked_mul(multiplier)
+        .ok_or(ParseSizeError::Overflow)
+}
+
+/// Parse a `--len` value, a size as accepted by `parse_size`, or `all`
+/// for 0, reading to the end of input.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn len_value(value: &str) -> Result<u64, ParseSizeError> {
+    match value {
+        "all" => Ok(0),
+        _ => parse_size(value),
+    }
+}
+
+/// Parse a `--sector-size` value, a size as accepted by `parse_size` of
+/// at least 1.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn sector_size_value(value: &str) -> Result<u64, String> {
+    match parse_size(value) {
+        Ok(0) => Err("sector size must be at least 1".to_string()),
+        Ok(size) => Ok(size),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parse a `--name` or `--module` identifier, `[A-Za-z_][A-Za-z0-9_]*`,
+/// so the array source it is written into compiles.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn identifier_value(value: &str) -> Result<String, String> {
+    let mut chars = value.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    match valid {
+        true => Ok(value.to_string()),
+        false => Err("expected a letter or _ followed by letters, digits or _".to_string()),
+    }
+}
+
+/// Bytes per line from `--cols`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Columns {
+    /// as many as fit the terminal width
+    Auto,
+    /// this many
+    Count(u64),
+}
+
+/// Most bytes per line `--cols` accepts.
+pub const MAX_COLUMNS: u64 = 4096;
+
+/// Parse a `--cols` value, `auto` or a size as accepted by `parse_size`
+/// of at most `MAX_COLUMNS`.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn cols_value(value: &str) -> Result<Columns, String> {
+    match value {
+        "auto" => Ok(Columns::Auto),
+        _ => match parse_size(value) {
+            Ok(count) if count > MAX_COLUMNS => {
+                Err(format!("columns must be at most {}", MAX_COLUMNS))
+            }
+            Ok(count) => Ok(Columns::Count(count)),
+            Err(e) => Err(e.to_string()),
+        },
+    }
+}
+
+/// Parse a `--group-size` value, a size as accepted by `parse_size` of
+/// at least 1.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn group_size_value(value: &str) -> Result<u64, String> {
+    match parse_size(value) {
+        Ok(0) => Err("group size must be at least 1".to_string()),
+        Ok(size) => Ok(size),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parse a `--word-size`, 1, 2, 4 or 8 bytes.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn parse_word_size(value: &str) -> Option<u64> {
+    value
+        .parse::<u64>()
+        .ok()
+        .filter(|size| [1, 2, 4, 8].contains(size))
+}
+
+/// Parse an argument that must be a single character, like
+/// `--nonprintable-char`.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn char_value(value: &str) -> Result<char, String> {
+    parse_char(value).ok_or_else(|| "expected a single character".to_string())
+}
+
+/// Parse a color argument, like `--offset-color`, as accepted by
+/// `parse_color`.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn color_value(value: &str) -> Result<Color, String> {
+    parse_color(value).ok_or_else(|| format!("unknown color: {}", value))
+}
+
+/// Look up an `--offset-format` by name. `sector` has a size of 0 here,
+/// the size comes from `--sector-size`.
+///
+/// # Arguments
+///
+/// * `name` - offset format name.
+pub fn offset_format_by_name(name: &str) -> Option<OffsetFormat> {
+    match name {
+        "sector" => Some(OffsetFormat::Sector(0)),
+        _ => OffsetFormat::by_name(name),
+    }
+}
+
+/// Case of hex offset digits, from `--offset-case`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OffsetCase {
+    /// uppercase digits
+    Upper,
+    /// lowercase digits
+    Lower,
+    /// the case of the `--format` digits
+    MatchFormat,
+}
+
+impl OffsetCase {
+    /// Look up an offset case by its `--offset-case` name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - offset case name.
+    pub fn by_name(name: &str) -> Option<OffsetCase> {
+        match name {
+            "upper" => Some(Self::Upper),
+            "lower" => Some(Self::Lower),
+            "match-format" => Some(Self::MatchFormat),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a `--patch` or `--insert` spec, as accepted by `Patch::parse`.
+///
+/// # Arguments
+///
+/// * `spec` - argument value.
+pub fn patch_value(spec: &str) -> Result<Patch, String> {
+    Patch::parse(spec).map_err(|e| e.to_string())
+}
+
+/// Parse a `--fill` spec, as accepted by `Edit::parse_fill`.
+///
+/// # Arguments
+///
+/// * `spec` - argument value.
+pub fn fill_value(spec: &str) -> Result<Edit, String> {
+    Edit::parse_fill(spec).map_err(|e| e.to_string())
+}
+
+/// Value parser for an option whose values are the `names` of a `T`,
+/// offered as its possible values and looked up with `by_name`.
+///
+/// # Arguments
+///
+/// * `names` - possible values.
+/// * `by_name` - lookup of a value by name, which knows every name.
+fn named<T: Clone + Send + Sync + 'static>(
+    names: impl IntoIterator<Item = impl Into<PossibleValue>>,
+    by_name: fn(&str) -> Option<T>,
+) -> ValueParser {
+    ValueParser::new(
+        PossibleValuesParser::new(names)
+            .map(move |name| by_name(&name).expect("possible value has a name")),
+    )
+}
+
+/// Parse a `--seek` offset. A plain offset is absolute, `+offset` is
+/// relative to the current position and `-offset` is relative to the
+/// end of input.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn parse_seek(value: &str) -> Result<SeekFrom, ParseSizeError> {
+    let value = value.trim();
+    if let Some(relative) = value.strip_prefix('+') {
+        let relative = i64::try_from(parse_size(relative)?).or(Err(ParseSizeError::Overflow))?;
+        Ok(SeekFrom::Current(relative))
+    } else if let Some(from_end) = value.strip_prefix('-') {
+        let from_end = i64::try_from(parse_size(from_end)?).or(Err(ParseSizeError::Overflow))?;
+        Ok(SeekFrom::End(-from_end))
+    } else {
+        Ok(SeekFrom::Start(parse_size(value)?))
+    }
+}
+
+/// Error parsing a `--range` argument.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseRangeError {
+    /// the range has no `..` separator, or `..=` has no end
+    Syntax,
+    /// the start or end is not a valid size
+    Size(ParseSizeError),
+    /// the range selects no bytes
+    Empty,
+}
+
+impl fmt::Display for ParseRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax => write!(f, "range must be start..end, start..=end, start.. or ..end"),
+            Self::Size(e) => write!(f, "{}", e),
+            Self::Empty => write!(f, "range is empty"),
+        }
+    }
+}
+
+impl Error for ParseRangeError {}
+
+impl From<ParseSizeError> for ParseRangeError {
+    fn from(e: ParseSizeError) -> ParseRangeError {
+        Self::Size(e)
+    }
+}
+
+/// Byte range selected by `--range`, with an exclusive end.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ByteRange {
+    /// offset of the first byte
+    pub start: u64,
+    /// offset after the last byte, `None` runs to the end of input
+    pub end: Option<u64>,
+}
+
+impl ByteRange {
+    /// Number of bytes in the range, `None` runs to the end of input.
+    pub fn length(&self) -> Option<u64> {
+        self.end.map(|end| end - self.start)
+    }
+}
+
+/// Parse a `--range` given as `start..end` (end exclusive), `start..=end`
+/// (end inclusive), `start..` or `..end`. Both ends are sizes as accepted
+/// by `parse_size`, e.g. `0x100..0x200` or `4k..=8k`.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn parse_range(value: &str) -> Result<ByteRange, ParseRangeError> {
+    let value = value.trim();
+    let (start, end) = value.split_once("..").ok_or(ParseRangeError::Syntax)?;
+    let start = match start.trim() {
+        "" => 0,
+        start => parse_size(start)?,
+    };
+    let end = match end.strip_prefix('=') {
+        Some(inclusive) => match inclusive.trim() {
+            "" => return Err(ParseRangeError::Syntax),
+            inclusive => Some(
+                parse_size(inclusive)?
+                    .checked_add(1)
+                    .ok_or(ParseSizeError::Overflow)?,
+            ),
+        },
+        None => match end.trim() {
+            "" => None,
+            end => Some(parse_size(end)?),
+        },
+    };
+    if end.is_some_and(|end| end <= start) {
+        return Err(ParseRangeError::Empty);
+    }
+    Ok(ByteRange { start, end })
+}
+
+/// Error parsing a `--highlight` argument.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseHighlightError {
+    /// the highlight has no `:` between start and length
+    Syntax,
+    /// the start or length is not a valid size
+    Size(ParseSizeError),
+    /// the highlight covers no bytes
+    Empty,
+    /// the color is not a color name or 256 color palette index
+    Color(String),
+}
+
+impl fmt::Display for ParseHighlightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax => write!(f, "highlight must be start:len or start:len:color"),
+            Self::Size(e) => write!(f, "{}", e),
+            Self::Empty => write!(f, "highlight is empty"),
+            Self::Color(name) => write!(f, "unknown color: {}", name),
+        }
+    }
+}
+
+impl Error for ParseHighlightError {}
+
+impl From<ParseSizeError> for ParseHighlightError {
+    fn from(e: ParseSizeError) -> ParseHighlightError {
+        Self::Size(e)
+    }
+}
+
+/// Look up a color by name, such as `red`, or by its index in the
+/// terminal 256 color palette, such as `89`.
+///
+/// # Arguments
+///
+/// * `name` - color name or palette index.
+pub fn parse_color(name: &str) -> Option<Color> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "black" => Some(Color::Black),
+        "red" => Some(Color::Red),
+        "green" => Some(Color::Green),
+        "yellow" => Some(Color::Yellow),
+        "blue" => Some(Color::Blue),
+        "magenta" | "purple" => Some(Color::Purple),
+        "cyan" => Some(Color::Cyan),
+        "white" => Some(Color::White),
+        index => index.parse::<u8>().ok().map(Color::Fixed),
+    }
+}
+
+/// A `--highlight` as given, before the theme supplies a missing color.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mark {
+    /// highlighted offsets
+    pub range: Range<u64>,
+    /// color given after the length, if any
+    pub color: Option<Color>,
+}
+
+impl Mark {
+    /// Highlight of the mark, in its own color or `default`.
+    ///
+    /// # Arguments
+    ///
+    /// * `default` - color used when none is given, usually the theme's.
+    pub fn highlight(&self, default: Color) -> Highlight {
+        Highlight {
+            range: self.range.clone(),
+            color: self.color.unwrap_or(default),
+        }
+    }
+}
+
+/// Parse a `--highlight` given as `start:len` or `start:len:color`. Start
+/// and length are sizes as accepted by `parse_size`, the color as
+/// accepted by `parse_color`.
+///
+/// # Arguments
+///
+/// * `value` - argument value.
+pub fn parse_highlight(value: &str) -> Result<Mark, ParseHighlightError> {
+    let mut fields = value.trim().splitn(3, ':');
+    let start = parse_size(fields.next().unwrap_or_default())?;
+    let len = parse_size(fields.next().ok_or(ParseHighlightError::Syntax)?)?;
+    let color = match fields.next() {
+        Some(name) => {
+            Some(parse_color(name).ok_or_else(|| ParseHighlightError::Color(name.to_string()))?)
+        }
+        None => None,
+    };
+    if len == 0 {
+        return Err(ParseHighlightError::Empty);
+    }
+    let end = start.checked_add(len).ok_or(ParseSizeError::Overflow)?;
+    Ok(Mark {
+        range: start..end,
+        color,
+    })
+}
+
+/// The `hx` command line: options, flags and their help.
+pub fn command() -> Command {
+    let desc = &format!(
+        "{}\n{}",
+        env!("CARGO_PKG_DESCRIPTION"),
+        env!("CARGO_PKG_HOMEPAGE")
+    );
+    Command::new(env!("CARGO_PKG_NAME"))
+        .version(env!("CARGO_PKG_VERSION"))
+        .about(desc)
+        .arg(
+            Arg::new(crate::ARG_COL)
+                .action(clap::ArgAction::Set)
+                .short('c')
+                .long(crate::ARG_COL)
+                .value_name("columns")
+                .help("Set column length, or auto to fit the terminal width, the default on a terminal")
+                .value_parser(cols_value)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_LEN)
+                .action(clap::ArgAction::Set)
+                .short('l')
+                .long(crate::ARG_LEN)
+                .value_name(crate::ARG_LEN)
+                .help("Set <len> bytes to read, with an optional k, M, G or T unit, or all")
+                .value_parser(len_value)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_FMT)
+                .action(clap::ArgAction::Append)
+                .short('f')
+                .long(crate::ARG_FMT)
+                .value_name("format")
+                .help("Set format of octet: Octal (o), LowerHex (x), UpperHex (X), Binary (b), Nibbles (n), Decimal (d), SignedDecimal (i), or any radix from r2 to r36, such as r4 or r32. Repeat to print a row in each further format below every line, like od -t x1 -t o1")
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_INP)
+                .help("Pass file paths as arguments, or input data may be passed via stdin")
+                .required(false)
+                .action(clap::ArgAction::Append)
+                .num_args(1..)
+                .index(1),
+        )
+        .arg(
+            Arg::new(crate::ARG_CLR)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_CLR)
+                .value_name("when")
+                .help("Color the output always, never or auto, only on a terminal, the default")
+                .value_parser(named(
+                    [
+                        PossibleValue::new("always"),
+                        PossibleValue::new("auto"),
+                        PossibleValue::new("never"),
+                        PossibleValue::new("0").hide(true),
+                        PossibleValue::new("1").hide(true),
+                    ],
+                    parse_when,
+                ))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_CLZ)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_CLZ)
+                .help("Color the output, the same as --color always")
+                .conflicts_with(crate::ARG_CLR)
+        )
+        .arg(
+            Arg::new(crate::ARG_TNT)
+                .action(clap::ArgAction::Set)
+                .short('t')
+                .hide(true)
+                .value_parser(named(["0", "1", "always", "auto", "never"], parse_when))
+                .num_args(1)
+                .conflicts_with_all([crate::ARG_CLR, crate::ARG_CLZ])
+        )
+        .arg(
+            Arg::new(crate::ARG_ARR)
+                .action(clap::ArgAction::Set)
+                .short('a')
+                .long(crate::ARG_ARR)
+                .value_name("array_format")
+                .help("Set source code format output: rust (r), rust module (rmod), C (c), C include (i), golang (g), python (p), kotlin (k), java (j), swift (s), fsharp (f), javascript (js), typescript (ts), C# (cs), zig (zig), nim (nim), ruby (rb), or a quoted hex string (hexstr) or base64 (base64) literal wrapped at --cols bytes")
+                .value_parser(
+                    crate::output::ArrayLanguage::ALL
+                        .map(|language| language.name())
+                        .into_iter()
+                        .chain(["hexstr", "base64"])
+                        .collect::<Vec<_>>()
+                )
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_NAM)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_NAM)
+                .value_name("ident")
+                .help("Set the identifier of --array output")
+                .requires(crate::ARG_ARR)
+                .value_parser(identifier_value)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_ACN)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_ACN)
+                .help("Declare rust (pub const) and C (const) --array output as a constant")
+                .requires(crate::ARG_ARR)
+                .conflicts_with(crate::ARG_AST)
+        )
+        .arg(
+            Arg::new(crate::ARG_AST)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_AST)
+                .help("Declare rust (pub static) and C (static) --array output as a static")
+                .requires(crate::ARG_ARR)
+        )
+        .arg(
+            Arg::new(crate::ARG_ATY)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_ATY)
+                .help("Set element type of rust and C --array output")
+                .value_parser(named(["u8", "i8", "u32"], ArrayElement::by_name))
+                .requires(crate::ARG_ARR)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_RFS)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_RFS)
+                .help("Precede rust --array output with #[rustfmt::skip]")
+                .requires(crate::ARG_ARR)
+        )
+        .arg(
+            Arg::new(crate::ARG_MOD)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_MOD)
+                .value_name("ident")
+                .help("Wrap rust module (rmod) --array output in pub mod <ident>")
+                .requires(crate::ARG_ARR)
+                .value_parser(identifier_value)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_FNC)
+                .short('u')
+                .long(crate::ARG_FNC)
+                .value_name("func_length")
+                .help("Set function wave length")
+                .value_parser(clap::value_parser!(u64))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_PLC)
+                .short('p')
+                .long(crate::ARG_PLC)
+                .value_name("func_places")
+                .help("Set function wave output decimal places")
+                .value_parser(clap::value_parser!(usize))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_PFX)
+                .action(clap::ArgAction::Set)
+                .short('r')
+                .long(crate::ARG_PFX)
+                .hide(true)
+                .value_parser(named(["0", "1"], parse_switch))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_NPX)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_NPX)
+                .help("Leave out the prefix of the octets (e.g. 0x/0b/0o)")
+                .conflicts_with(crate::ARG_PFX)
+        )
+        .arg(
+            Arg::new(crate::ARG_SKP)
+                .action(clap::ArgAction::Set)
+                .short('s')
+                .long(crate::ARG_SKP)
+                .value_name("[+|-]offset")
+                .help("Start at <offset> bytes into input, hex (0x) or decimal. + is relative to the current position, - to the end of input")
+                .allow_hyphen_values(true)
+                .value_parser(parse_seek)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_TAL)
+                .action(clap::ArgAction::Set)
+                .short('T')
+                .long(crate::ARG_TAL)
+                .value_name("n")
+                .help("Show the last <n> bytes of input")
+                .conflicts_with(crate::ARG_SKP)
+                .value_parser(parse_size)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_MAP)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_MAP)
+                .help("Memory-map the input file instead of reading it (requires the mmap feature). The file must not shrink while it is dumped, or hx is killed by SIGBUS")
+                .conflicts_with(crate::ARG_TAL)
+        )
+        .arg(
+            Arg::new(crate::ARG_CMD)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_CMD)
+                .value_name("mode")
+                .help("Set how bytes are colored: by byte value, by byte class (NUL, letter, digit, whitespace, control, high-bit), or by value with offsets tinted by entropy")
+                .value_parser(named(["value", "semantic", "entropy"], ColorMode::by_name))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_THM)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_THM)
+                .value_name("name")
+                .help("Set color theme")
+                .value_parser(named(Theme::ALL.map(|theme| theme.name), Theme::by_name))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_OCL)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_OCL)
+                .value_name("color")
+                .help("Set the color of the offset column and header ruler: a color name or 256 color palette index")
+                .value_parser(color_value)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_SCL)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_SCL)
+                .value_name("color")
+                .help("Set the color of the separator and frame borders: a color name or 256 color palette index")
+                .value_parser(color_value)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_TCL)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_TCL)
+                .value_name("color")
+                .help("Set the color of the bytes: trailer: a color name or 256 color palette index")
+                .value_parser(color_value)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_FND)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_FND)
+                .value_name("hexstring")
+                .help("Highlight and list the offsets of a byte sequence, e.g. deadbeef, DE??BE?F or deadbeef&ffff00ff")
+                .value_parser(|value: &str| crate::search::Pattern::parse_masked(value).map_err(|e| e.to_string()))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_FRX)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_FRX)
+                .value_name("re")
+                .help("Print the matches of a regex over the input as text with their offsets, e.g. 'User-Agent:.*'")
+                .value_parser(|value: &str| regex::bytes::Regex::new(value))
+                .num_args(1)
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR, crate::ARG_FND, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_CPR, crate::ARG_INT, crate::ARG_THR])
+        )
+        .arg(
+            Arg::new(crate::ARG_CTX)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_CTX)
+                .value_name("n")
+                .help("Print only the lines within <n> lines of a --find or --find-regex match")
+                .value_parser(parse_u64)
+                .num_args(1)
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_CPR, crate::ARG_INT, crate::ARG_THR])
+        )
+        .arg(
+            Arg::new(crate::ARG_CNT)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_CNT)
+                .help("Print only the number of --find or --find-regex matches")
+                .conflicts_with_all([crate::ARG_CTX, crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_CPR, crate::ARG_INT, crate::ARG_THR])
+        )
+        .arg(
+            Arg::new(crate::ARG_STR)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_STR)
+                .value_name("minlen")
+                .help("Print printable strings of at least <minlen> characters with their offsets")
+                .value_parser(parse_size)
+                .num_args(0..=1)
+                .default_missing_value("4")
+                .conflicts_with(crate::ARG_ARR)
+        )
+        .arg(
+            Arg::new(crate::ARG_SEN)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_SEN)
+                .value_name("encoding")
+                .help("Set character encoding scanned for by --strings")
+                .value_parser(named(["ascii", "utf8", "utf16le", "utf16be"], crate::strings::Encoding::by_name))
+                .requires(crate::ARG_STR)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_DIF)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_DIF)
+                .value_names(["file1", "file2"])
+                .help("Compare two files side by side, printing only the lines that differ")
+                .num_args(2)
+                .conflicts_with_all([crate::ARG_INP, crate::ARG_ARR, crate::ARG_STR])
+        )
+        .arg(
+            Arg::new(crate::ARG_CPR)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_CPR)
+                .value_names(["file1", "file2"])
+                .help("Compare two files, printing only the differing offset ranges; exits with 1 if they differ")
+                .num_args(2)
+                .conflicts_with_all([crate::ARG_INP, crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_DIF, crate::ARG_REV, crate::ARG_INT, crate::ARG_THR, crate::ARG_FOL])
+        )
+        .arg(
+            Arg::new(crate::ARG_REV)
+                .action(clap::ArgAction::SetTrue)
+                .short('R')
+                .long(crate::ARG_REV)
+                .help("Convert hx dump output back into binary. Use the -f format and the layout options, such as --no-ascii or --style, the dump was printed with")
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR])
+        )
+        .arg(
+            Arg::new(crate::ARG_OUT)
+                .action(clap::ArgAction::Set)
+                .short('o')
+                .long(crate::ARG_OUT)
+                .value_name("path")
+                .help("Write output to <path> instead of stdout, replacing it once complete")
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_PLN)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_PLN)
+                .help("Output a continuous hex string without offsets or ascii, wrapped at --cols bytes (default 30, 0 to disable). With --reverse, read a plain hex string")
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR])
+        )
+        .arg(
+            Arg::new(crate::ARG_GRP)
+                .action(clap::ArgAction::Set)
+                .short('g')
+                .long(crate::ARG_GRP)
+                .value_name("bytes")
+                .help("Print octets in groups of <bytes>, e.g. 2, 4 or 8, separated by a single space")
+                .value_parser(group_size_value)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_BLK)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_BLK)
+                .value_name("bytes")
+                .help("Print an extra space after every <bytes> bytes of a line, like the middle gap of hexdump -C")
+                .value_parser(parse_size)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_OFF)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_OFF)
+                .help("Set radix of the offset column")
+                .value_parser(named(["hex", "bare-hex", "dec", "oct", "sector"], offset_format_by_name))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_OFW)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_OFW)
+                .value_name("digits")
+                .help("Zero pad the offset column to <digits> digits, default 6")
+                .value_parser(clap::value_parser!(usize))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_OCS)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_OCS)
+                .help("Set the case of hex offsets, match-format is upper for -f X, default lower")
+                .value_parser(named(["upper", "lower", "match-format"], OffsetCase::by_name))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_OSP)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_OSP)
+                .value_name("str")
+                .help("Print <str> between the offset column and the octets, default \": \"")
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_WSZ)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_WSZ)
+                .help("Print octets as 1, 2, 4 or 8 byte words, like od -t x4")
+                .value_parser(named(["1", "2", "4", "8"], parse_word_size))
+                .conflicts_with_all([crate::ARG_GRP, crate::ARG_REV])
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_LEG)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_LEG)
+                .help("Print each --group-size group as a little endian value, like xxd -e; groups default to 4")
+                .conflicts_with_all([crate::ARG_WSZ, crate::ARG_REV])
+        )
+        .arg(
+            Arg::new(crate::ARG_END)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_END)
+                .help("Set byte order of --word-size words, default le")
+                .value_parser(named(["le", "be"], Endian::by_name))
+                .requires(crate::ARG_WSZ)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_OFM)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_OFM)
+                .help("Set output format: text dump, or one json record per line")
+                .value_parser(["text", "json"])
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF])
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_INT)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_INT)
+                .help("Open the input file in a scrollable viewer (requires the tui feature)")
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_OUT, crate::ARG_OFM, crate::ARG_FNC])
+        )
+        .arg(
+            Arg::new(crate::ARG_FOL)
+                .action(clap::ArgAction::SetTrue)
+                .short('F')
+                .long(crate::ARG_FOL)
+                .help("Keep reading as the input file grows, like tail -f")
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_REV, crate::ARG_DIF, crate::ARG_OUT, crate::ARG_MAP, crate::ARG_INT])
+        )
+        .arg(
+            Arg::new(crate::ARG_STA)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_STA)
+                .help("Print byte histogram, entropy and printable/NUL ratios after the dump, or only the statistics")
+                .value_parser(["after", "only"])
+                .num_args(0..=1)
+                .default_missing_value("after")
+                .require_equals(true)
+                .conflicts_with_all([crate::ARG_REV, crate::ARG_DIF, crate::ARG_INT, crate::ARG_FOL])
+        )
+        .arg(
+            Arg::new(crate::ARG_DEC)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_DEC)
+                .value_name("offset")
+                .help("Print the bytes at <offset> decoded as integers, floats, UNIX timestamps and LEB128 varints, in both byte orders, instead of the dump")
+                .conflicts_with_all([crate::ARG_SKP, crate::ARG_TAL, crate::ARG_RNG, crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_OFM, crate::ARG_INT, crate::ARG_FOL, crate::ARG_STA, crate::ARG_THR])
+                .value_parser(parse_size)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_EXT)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_EXT)
+                .value_name("start..end")
+                .help("Copy the bytes of a range, such as 0x100..0x200, to the output as is, for carving a payload out of the input with -o")
+                .conflicts_with_all([crate::ARG_SKP, crate::ARG_TAL, crate::ARG_RNG, crate::ARG_LEN, crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_OFM, crate::ARG_INT, crate::ARG_FOL, crate::ARG_STA, crate::ARG_DGS, crate::ARG_DET, crate::ARG_THR, crate::ARG_DEC, crate::ARG_FNC])
+                .value_parser(parse_range)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_XOR)
+                .action(clap::ArgAction::Append)
+                .long(crate::ARG_XOR)
+                .value_name("hexkey")
+                .help("Xor every byte with <hexkey>, repeated from offset 0, before it is printed")
+                .conflicts_with_all([crate::ARG_REV, crate::ARG_DIF, crate::ARG_INT, crate::ARG_THR, crate::ARG_PCH, crate::ARG_FIL, crate::ARG_INS, crate::ARG_DEL, crate::ARG_FNC])
+                .value_parser(|value: &str| Op::parse(crate::ARG_XOR, value).map_err(|e| e.to_string()))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_ROT)
+                .action(clap::ArgAction::Append)
+                .long(crate::ARG_ROT)
+                .value_name("n")
+                .allow_hyphen_values(true)
+                .help("Rotate the bits of every byte left by <n>, right if negative, before it is printed")
+                .conflicts_with_all([crate::ARG_REV, crate::ARG_DIF, crate::ARG_INT, crate::ARG_THR, crate::ARG_PCH, crate::ARG_FIL, crate::ARG_INS, crate::ARG_DEL, crate::ARG_FNC])
+                .value_parser(|value: &str| Op::parse(crate::ARG_ROT, value).map_err(|e| e.to_string()))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_ADD)
+                .action(clap::ArgAction::Append)
+                .long(crate::ARG_ADD)
+                .value_name("n")
+                .allow_hyphen_values(true)
+                .help("Add <n> to every byte, wrapping, before it is printed; transforms apply in command line order")
+                .conflicts_with_all([crate::ARG_REV, crate::ARG_DIF, crate::ARG_INT, crate::ARG_THR, crate::ARG_PCH, crate::ARG_FIL, crate::ARG_INS, crate::ARG_DEL, crate::ARG_FNC])
+                .value_parser(|value: &str| Op::parse(crate::ARG_ADD, value).map_err(|e| e.to_string()))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_IEN)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_IEN)
+                .value_name("encoding")
+                .value_parser(named(["base64", "hex", "gzip", "zlib"], crate::encoding::Encoding::by_name))
+                .help("Decode or decompress the input before it is printed; offsets count decoded bytes")
+                .conflicts_with_all([crate::ARG_REV, crate::ARG_DIF, crate::ARG_INT, crate::ARG_THR, crate::ARG_MAP, crate::ARG_PCH, crate::ARG_FIL, crate::ARG_INS, crate::ARG_DEL, crate::ARG_FNC])
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_PCH)
+                .action(clap::ArgAction::Append)
+                .long(crate::ARG_PCH)
+                .value_name("offset=hexbytes")
+                .help("Write <hexbytes> at <offset> and output the edited input instead of the dump, may be repeated")
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_OFM, crate::ARG_INT, crate::ARG_FOL, crate::ARG_STA, crate::ARG_THR, crate::ARG_DEC, crate::ARG_FNC])
+                .value_parser(patch_value)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_FIL)
+                .action(clap::ArgAction::Append)
+                .long(crate::ARG_FIL)
+                .value_name("range=byte")
+                .help("Set every byte of <range>, such as 0x10..0x20, to <byte> and output the edited input, may be repeated")
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_OFM, crate::ARG_INT, crate::ARG_FOL, crate::ARG_STA, crate::ARG_THR, crate::ARG_DEC, crate::ARG_FNC, crate::ARG_IPL])
+                .value_parser(fill_value)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_INS)
+                .action(clap::ArgAction::Append)
+                .long(crate::ARG_INS)
+                .value_name("offset=hexbytes")
+                .help("Insert <hexbytes> before the byte at <offset> and output the edited input, may be repeated")
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_OFM, crate::ARG_INT, crate::ARG_FOL, crate::ARG_STA, crate::ARG_THR, crate::ARG_DEC, crate::ARG_FNC, crate::ARG_IPL])
+                .value_parser(patch_value)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_DEL)
+                .action(clap::ArgAction::Append)
+                .long(crate::ARG_DEL)
+                .value_name("range")
+                .help("Remove the bytes of <range> and output the edited input, may be repeated")
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_OFM, crate::ARG_INT, crate::ARG_FOL, crate::ARG_STA, crate::ARG_THR, crate::ARG_DEC, crate::ARG_FNC, crate::ARG_IPL])
+                .value_parser(parse_range)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_IPL)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_IPL)
+                .help("Write the --patch bytes into the input file instead of a copy")
+                .requires(crate::ARG_PCH)
+                .conflicts_with(crate::ARG_OUT)
+        )
+        .arg(
+            Arg::new(crate::ARG_AGR)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_AGR)
+                .help("Let --patch, --fill and --insert extend the input past its end, zero filling any gap")
+        )
+        .arg(
+            Arg::new(crate::ARG_DGS)
+                .action(clap::ArgAction::Append)
+                .long(crate::ARG_DGS)
+                .value_name("algorithm")
+                .help("Print digests of the dumped bytes after the byte count, several may be given comma separated")
+                .value_parser(named(crate::digest::Algorithm::ALL.map(|algorithm| algorithm.name()), crate::digest::Algorithm::by_name))
+                .value_delimiter(',')
+                .num_args(1)
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_PLN, crate::ARG_OFM, crate::ARG_REV, crate::ARG_DIF, crate::ARG_INT, crate::ARG_FOL])
+        )
+        .arg(
+            Arg::new(crate::ARG_RNG)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_RNG)
+                .value_name("start..end")
+                .help("Dump the bytes from start to end, exclusive, or inclusive with ..=, either end may be left open")
+                .conflicts_with_all([crate::ARG_SKP, crate::ARG_LEN, crate::ARG_TAL])
+                .value_parser(parse_range)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_NSQ)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_NSQ)
+                .help("Print every line instead of collapsing runs of identical lines into *")
+        )
+        .arg(
+            Arg::new(crate::ARG_BAS)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_BAS)
+                .value_name("address")
+                .help("Start the offset column at <address>, e.g. a load address like 0x08000000")
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_PLN])
+                .value_parser(parse_size)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_HIL)
+                .action(clap::ArgAction::Append)
+                .long(crate::ARG_HIL)
+                .value_name("start:len[:color]")
+                .help("Highlight <len> bytes from <start>, in a color name or 256 color palette index, may be repeated")
+                .value_parser(parse_highlight)
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_OFM, crate::ARG_INT])
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_TPL)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_TPL)
+                .value_name("file")
+                .help("Highlight the fields described in a TOML layout file and print their decoded values")
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_OFM, crate::ARG_INT])
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_DET)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_DET)
+                .help("Print the file type detected from magic numbers before the dump")
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_OFM, crate::ARG_INT, crate::ARG_FOL])
+        )
+        .arg(
+            Arg::new(crate::ARG_CHS)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_CHS)
+                .value_name("charset")
+                .help("Set the character set of the text column: ascii, utf8 multi-byte characters, ebcdic (code page 037), cp437 (DOS) or latin1")
+                .value_parser(named(["ascii", "utf8", "ebcdic", "cp437", "latin1"], Charset::by_name))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_ASY)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_ASY)
+                .value_name("style")
+                .help("Show control characters in the text column as dots, caret notation (^C) or control pictures")
+                .value_parser(named(["dots", "caret", "pictures"], AsciiStyle::by_name))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_NPC)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_NPC)
+                .value_name("char")
+                .help("Show unprintable characters in the text column as <char>, default .")
+                .value_parser(char_value)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_NUL)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_NUL)
+                .value_name("char")
+                .help("Show NUL bytes in the text column as <char>, to tell padding from other unprintable bytes")
+                .value_parser(char_value)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_SEP)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_SEP)
+                .value_name("str")
+                .help("Print <str> between the octets and the text column, default a space, or \" | \" with --frame")
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_FRM)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_FRM)
+                .help("Draw | borders around the octets and the text column")
+        )
+        .arg(
+            Arg::new(crate::ARG_GUT)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_GUT)
+                .help("Wrap the text column in | without padding it, like hexdump -C")
+                .conflicts_with(crate::ARG_FRM)
+        )
+        .arg(
+            Arg::new(crate::ARG_NAS)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_NAS)
+                .help("Print the offsets and octets without the text column")
+        )
+        .arg(
+            Arg::new(crate::ARG_AOL)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_AOL)
+                .help("Print the offsets and text column without the octets")
+                .conflicts_with(crate::ARG_NAS)
+        )
+        .arg(
+            Arg::new(crate::ARG_LNO)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_LNO)
+                .help("Number the lines of the dump from 1 in a column before the offsets")
+                .conflicts_with_all([crate::ARG_DIF, crate::ARG_CPR])
+        )
+        .arg(
+            Arg::new(crate::ARG_EOS)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_EOS)
+                .help("Repeat the offset of the last byte of each line at the end of the line")
+                .conflicts_with_all([crate::ARG_DIF, crate::ARG_CPR])
+        )
+        .arg(
+            Arg::new(crate::ARG_HDR)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_HDR)
+                .value_name("lines")
+                .help("Print a ruler of column indices above the dump, repeated every <lines> lines with --header=<lines>")
+                .value_parser(clap::value_parser!(u64))
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("0")
+        )
+        .arg(
+            Arg::new(crate::ARG_SUM)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_SUM)
+                .help("Set what is printed after the dump: none, the bytes: line, or verbose adding the range shown and the time taken, or the offset after the last byte")
+                .value_parser(named(["none", "bytes", "verbose", "offset"], Summary::by_name))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_NSM)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_NSM)
+                .help("Omit the bytes: line after the dump, same as --summary none")
+                .conflicts_with(crate::ARG_SUM)
+        )
+        .arg(
+            Arg::new(crate::ARG_STY)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_STY)
+                .help("Preset the dump options to match another tool: xxd prints the output of plain xxd, and reads it back with -R, canonical prints the output of hexdump -C")
+                .value_parser(named(["hx", "xxd", "canonical"], ConfigFile::style))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_BIT)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_BIT)
+                .help("Print 8 binary octets per line, like xxd -b")
+                .conflicts_with(crate::ARG_FMT)
+        )
+        .arg(
+            Arg::new(crate::ARG_PAG)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_PAG)
+                .value_name("when")
+                .value_parser(named(["auto", "always", "never"], crate::pager::PagerMode::by_name))
+                .help("Page output through $PAGER, or less -R; auto pages output longer than the terminal")
+                .num_args(1)
+                .conflicts_with_all([crate::ARG_OUT, crate::ARG_INT])
+        )
+        .arg(
+            Arg::new(crate::ARG_LSN)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_LSN)
+                .value_name("addr")
+                .help("Dump the bytes of the first connection to <addr>, host:port or unix:<path>, as they arrive")
+                .num_args(1)
+                .conflicts_with_all([crate::ARG_INP, crate::ARG_CON, crate::ARG_DIF, crate::ARG_CPR, crate::ARG_INT, crate::ARG_THR, crate::ARG_MAP, crate::ARG_PCH, crate::ARG_FIL, crate::ARG_INS, crate::ARG_DEL, crate::ARG_FNC])
+        )
+        .arg(
+            Arg::new(crate::ARG_CON)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_CON)
+                .value_name("addr")
+                .help("Dump the bytes received after connecting to <addr>, host:port or unix:<path>, as they arrive")
+                .num_args(1)
+                .conflicts_with_all([crate::ARG_INP, crate::ARG_DIF, crate::ARG_CPR, crate::ARG_INT, crate::ARG_THR, crate::ARG_MAP, crate::ARG_PCH, crate::ARG_FIL, crate::ARG_INS, crate::ARG_DEL, crate::ARG_FNC])
+        )
+        .arg(
+            Arg::new(crate::ARG_SER)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_SER)
+                .value_name("device")
+                .help("Dump the bytes arriving on a serial device as they arrive, each line stamped with the seconds since it was opened (requires the serial feature)")
+                .num_args(1)
+                .conflicts_with_all([crate::ARG_INP, crate::ARG_LSN, crate::ARG_CON, crate::ARG_DIF, crate::ARG_CPR, crate::ARG_INT, crate::ARG_THR, crate::ARG_MAP, crate::ARG_PCH, crate::ARG_FIL, crate::ARG_INS, crate::ARG_DEL, crate::ARG_FNC])
+        )
+        .arg(
+            Arg::new(crate::ARG_BAU)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_BAU)
+                .value_name("n")
+                .help("Baud rate of the --serial device [default: 115200]")
+                .num_args(1)
+                .value_parser(clap::value_parser!(u32))
+        )
+        .arg(
+            Arg::new(crate::ARG_TSP)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_TSP)
+                .value_name("when")
+                .help("Print when the first byte of each line of stdin, a socket or a serial device arrived, in seconds since the input was opened, or as UTC with =absolute; a line waiting for more bytes is printed after a short pause")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("relative")
+                .value_parser(["relative", "absolute"])
+                .conflicts_with_all(crate::ARGS_UNTIMED)
+        )
+        .arg(
+            Arg::new(crate::ARG_IFL)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_IFL)
+                .value_name("ms")
+                .help("Print a partial line of stdin, a socket, a serial device or a --follow file once no bytes have arrived for <ms> milliseconds, instead of waiting for it to fill")
+                .num_args(1)
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .conflicts_with_all(crate::ARGS_UNTIMED.iter().filter(|name| **name != crate::ARG_INP))
+        )
+        .arg(
+            Arg::new(crate::ARG_PID)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_PID)
+                .value_name("n")
+                .help("Dump the --range of virtual addresses of the memory of process <n>, on Linux")
+                .num_args(1)
+                .value_parser(clap::value_parser!(u32))
+                .requires(crate::ARG_RNG)
+                .conflicts_with_all([crate::ARG_INP, crate::ARG_LSN, crate::ARG_CON, crate::ARG_SER, crate::ARG_DIF, crate::ARG_CPR, crate::ARG_INT, crate::ARG_THR, crate::ARG_MAP, crate::ARG_FOL, crate::ARG_PCH, crate::ARG_FIL, crate::ARG_INS, crate::ARG_DEL, crate::ARG_FNC])
+        )
+        .arg(
+            Arg::new(crate::ARG_SSZ)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_SSZ)
+                .value_name("bytes")
+                .help("Widen the dump out to whole sectors of <bytes>, from the start of the sector of the seek offset to the end of the sector of the last byte, for block devices and disk images; --offset-format sector prints sector:offset")
+                .num_args(1)
+                .value_parser(sector_size_value)
+                .conflicts_with_all([crate::ARG_DIF, crate::ARG_CPR, crate::ARG_INT, crate::ARG_PCH, crate::ARG_FIL, crate::ARG_INS, crate::ARG_DEL, crate::ARG_FNC])
+        )
+        .arg(
+            Arg::new(crate::ARG_FRA)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_FRA)
+                .help("Convert an array literal printed by --array, in any of its languages, back into binary. Comments are ignored")
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR, crate::ARG_REV, crate::ARG_PLN, crate::ARG_DIF, crate::ARG_CPR, crate::ARG_INT, crate::ARG_THR, crate::ARG_OFM, crate::ARG_FOL, crate::ARG_DEC, crate::ARG_EXT, crate::ARG_PCH, crate::ARG_FIL, crate::ARG_INS, crate::ARG_DEL, crate::ARG_FNC])
+        )
+        .arg(
+            Arg::new(crate::ARG_CMP)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_CMP)
+                .value_name("shell")
+                .help("Print a tab completion script for <shell> and exit")
+                .value_parser(named(["bash", "zsh", "fish", "powershell"], crate::completions::Shell::by_name))
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_PRG)
+                .action(clap::ArgAction::SetTrue)
+                .long(crate::ARG_PRG)
+                .help("Show bytes read, throughput and time left on stderr while reading, when stderr is a terminal")
+                .conflicts_with_all([crate::ARG_INT, crate::ARG_FOL])
+        )
+        .arg(
+            Arg::new(crate::ARG_THR)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_THR)
+                .value_name("n")
+                .help("Render the dump of a large file in chunks on <n> threads, 0 for one per core")
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_OFM, crate::ARG_INT, crate::ARG_FOL, crate::ARG_MAP, crate::ARG_FND, crate::ARG_TPL, crate::ARG_STA, crate::ARG_DGS, crate::ARG_DET, crate::ARG_PRG])
+                .value_parser(parse_u64)
+                .num_args(1)
+        )
+        .arg(
+            Arg::new(crate::ARG_DAS)
+                .action(clap::ArgAction::Set)
+                .long(crate::ARG_DAS)
+                .value_name("arch")
+                .help("Disassemble the bytes with objdump, showing the instructions starting on each line as a third column (requires the disasm feature)")
+                .value_parser(named(["x86_64", "aarch64", "riscv"], crate::disasm::Arch::by_name))
+                .conflicts_with_all([crate::ARG_ARR, crate::ARG_STR, crate::ARG_PLN, crate::ARG_REV, crate::ARG_DIF, crate::ARG_CPR, crate::ARG_OFM, crate::ARG_INT, crate::ARG_FND, crate::ARG_FRX, crate::ARG_CNT, crate::ARG_CTX, crate::ARG_TPL, crate::ARG_THR, crate::ARG_FOL])
+                .num_args(1)
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_char() {
+        assert_eq!(parse_char(" "), Some(' '));
+        assert_eq!(parse_char("\u{b7}"), Some('\u{b7}'));
+        assert_eq!(parse_char(""), None);
+        assert_eq!(parse_char("ab"), None);
+    }
+
+    #[test]
+    fn test_parse_u64() {
+        assert_eq!(parse_u64("16").unwrap(), 16);
+        assert_eq!(parse_u64("0x10").unwrap(), 16);
+        assert_eq!(parse_u64("0XfF").unwrap(), 255);
+        assert!(parse_u64("ten").is_err());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert_eq!(parse_size("0x1000").unwrap(), 4096);
+        assert_eq!(parse_size("0o10000").unwrap(), 4096);
+        assert_eq!(parse_size("0b1000000000000").unwrap(), 4096);
+        assert_eq!(parse_size("4k").unwrap(), 4096);
+        assert_eq!(parse_size("2M").unwrap(), 2 << 20);
+        assert_eq!(parse_size("1G").unwrap(), 1 << 30);
+        assert_eq!(parse_size("0x10k").unwrap(), 16 << 10);
+        assert_eq!(parse_size("0xfb").unwrap(), 0xfb);
+        assert_eq!(parse_size("4KiB").unwrap(), 4096);
+        assert_eq!(parse_size("4kB").unwrap(), 4000);
+        assert_eq!(parse_size("1MB").unwrap(), 1_000_000);
+        assert_eq!(parse_size("0x1kib").unwrap(), 1024);
+        assert!(parse_size("4iB").is_err());
+        assert!(parse_size("KiB").is_err());
+        assert_eq!(parse_size("16777216T"), Err(ParseSizeError::Overflow));
+        assert!(parse_size("k").is_err());
+        assert_eq!(parse_size("+4"), Err(ParseSizeError::Sign));
+        assert!(parse_size("4q").is_err());
+    }
+
+    #[test]
+    fn test_identifier_value() {
+        assert_eq!(identifier_value("_data1").unwrap(), "_data1");
+        assert!(identifier_value("1 bad-name").is_err());
+        assert!(identifier_value("bad-name").is_err());
+        assert!(identifier_value("").is_err());
+    }
+
+    #[test]
+    fn test_command() {
+        command().debug_assert();
+        let matches = command()
+            .try_get_matches_from(["hx", "-l", "all", "-c", "auto", "-u", "8"])
+            .unwrap();
+        assert_eq!(matches.get_one::<u64>(crate::ARG_LEN), Some(&0));
+        assert_eq!(
+            matches.get_one::<Columns>(crate::ARG_COL),
+            Some(&Columns::Auto)
+        );
+        assert_eq!(matches.get_one::<u64>(crate::ARG_FNC), Some(&8));
+        assert!(command()
+            .try_get_matches_from(["hx", "--cols", "4q"])
+            .is_err());
+        let matches = command()
+            .try_get_matches_from(["hx", "--theme", "tango", "--offset-format", "sector"])
+            .unwrap();
+        assert_eq!(
+            matches.get_one::<Theme>(crate::ARG_THM),
+            Theme::by_name("tango").as_ref()
+        );
+        assert_eq!(
+            matches.get_one::<OffsetFormat>(crate::ARG_OFF),
+            Some(&OffsetFormat::Sector(0))
+        );
+        assert!(command()
+            .try_get_matches_from(["hx", "--group-size", "0"])
+            .is_err());
+        assert_eq!(len_value("4KiB"), Ok(4096));
+    }
+
+    #[test]
+    fn test_parse_when() {
+        assert_eq!(parse_when("auto"), Some(When::Auto));
+        assert_eq!(parse_when("1"), Some(When::Always));
+        assert_eq!(parse_when("false"), Some(When::Never));
+        assert_eq!(parse_when("sometimes"), None);
+        assert_eq!(parse_switch("off"), Some(false));
+        assert_eq!(parse_switch("2"), None);
+    }
+
+    #[test]
+    fn test_parse_seek() {
+        assert_eq!(parse_seek("0x20").unwrap(), SeekFrom::Start(32));
+        assert_eq!(parse_seek("+8").unwrap(), SeekFrom::Current(8));
+        assert_eq!(parse_seek("-0x10").unwrap(), SeekFrom::End(-16));
+        assert_eq!(parse_seek("+1k").unwrap(), SeekFrom::Current(1024));
+        assert!(parse_seek("-").is_err());
+        assert!(parse_seek("-16E").is_err());
+    }
+
+    #[test]
+    fn test_parse_range() {
+        let range = |start, end| ByteRange { start, end };
+        assert_eq!(
+            parse_range("0x100..0x200").unwrap(),
+            range(0x100, Some(0x200))
+        );
+        assert_eq!(
+            parse_range("0x100..=0x1ff").unwrap(),
+            range(0x100, Some(0x200))
+        );
+        assert_eq!(parse_range("0x100..").unwrap(), range(0x100, None));
+        assert_eq!(parse_range("..0x200").unwrap(), range(0, Some(0x200)));
+        assert_eq!(parse_range("..=0").unwrap(), range(0, Some(1)));
+        assert_eq!(parse_range("..").unwrap(), range(0, None));
+        assert_eq!(parse_range(" 1k .. 2k ").unwrap(), range(1024, Some(2048)));
+        assert_eq!(parse_range("16..32").unwrap().length(), Some(16));
+        assert_eq!(parse_range("16..").unwrap().length(), None);
+    }
+
+    #[test]
+    fn test_parse_range_errors() {
+        assert_eq!(parse_range("0x100"), Err(ParseRangeError::Syntax));
+        assert_eq!(parse_range("0x100..="), Err(ParseRangeError::Syntax));
+        assert_eq!(parse_range("8..8"), Err(ParseRangeError::Empty));
+        assert_eq!(parse_range("8..4"), Err(ParseRangeError::Empty));
+        assert_eq!(parse_range("8..=7"), Err(ParseRangeError::Empty));
+        assert!(matches!(parse_range("a..b"), Err(ParseRangeError::Size(_))));
+        assert!(matches!(
+            parse_range("+1..2"),
+            Err(ParseRangeError::Size(_))
+        ));
+        assert_eq!(
+            parse_range("..=0xffffffffffffffff"),
+            Err(ParseRangeError::Size(ParseSizeError::Overflow))
+        );
+    }
+
+    #[test]
+    fn test_parse_highlight() {
+        assert_eq!(
+            parse_highlight("0x10:4").unwrap(),
+            Mark {
+                range: 0x10..0x14,
+                color: None
+            }
+        );
+        assert_eq!(
+            parse_highlight("2:1k:red").unwrap(),
+            Mark {
+                range: 2..1026,
+                color: Some(Color::Red)
+            }
+        );
+        assert_eq!(
+            parse_highlight("0:1:214")
+                .unwrap()
+                .highlight(Color::Fixed(89))
+                .color,
+            Color::Fixed(214)
+        );
+        assert_eq!(parse_highlight("16"), Err(ParseHighlightError::Syntax));
+        assert_eq!(parse_highlight("16:0"), Err(ParseHighlightError::Empty));
+        assert_eq!(
+            parse_highlight("16:1:mauve"),
+            Err(ParseHighlightError::Color("mauve".to_string()))
+        );
+        assert!(matches!(
+            parse_highlight("x:1"),
+            Err(ParseHighlightError::Size(_))
+        ));
+    }
+}
@@ -0,0 +1,907 @@
+//! input buffering
+use crate::config::HexConfig;
+use crate::output::{render_line, render_page};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, SeekFrom};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Number of bytes `PageReader` reads at a time.
+const READ_CHUNK: usize = 1 << 16;
+
+/// Line structure for hex output
+#[derive(Clone, Debug, Default)]
+pub struct Line {
+    /// offset
+    pub offset: u64,
+    /// hex body
+    pub hex_body: Vec<u8>,
+    /// ascii text
+    pub ascii: Vec<u8>,
+    /// total bytes in Line
+    pub bytes: u64,
+}
+/// Line implementation
+impl Line {
+    /// Line constructor
+    pub fn new() -> Line {
+        Line {
+            offset: 0x0,
+            hex_body: Vec::new(),
+            ascii: Vec::new(),
+            bytes: 0x0,
+        }
+    }
+
+    /// Fill `ascii` from `hex_body`: printable ASCII bytes as they are and
+    /// every other byte as a dot. Colors, other charsets and text styles
+    /// are applied to `hex_body` when the line is printed.
+    pub fn fill_ascii(&mut self) {
+        self.ascii.clear();
+        self.ascii.extend(self.hex_body.iter().map(|&b| match b {
+            b' '..=b'~' => b,
+            _ => b'.',
+        }));
+    }
+
+    /// Indices of the bytes that differ between two lines. Bytes present
+    /// in only one of the lines count as differing.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - line to compare against.
+    pub fn diff(&self, other: &Line) -> Vec<usize> {
+        let len = self.hex_body.len().max(other.hex_body.len());
+        (0..len)
+            .filter(|i| self.hex_body.get(*i) != other.hex_body.get(*i))
+            .collect()
+    }
+}
+
+/// Page structure
+#[derive(Clone, Debug, Default)]
+pub struct Page {
+    /// page offset
+    pub offset: u64,
+    /// page body
+    pub body: Vec<Line>,
+    /// total bytes in page
+    pub bytes: u64,
+}
+
+/// Page implementation
+impl Page {
+    /// Page constructor
+    pub fn new() -> Page {
+        Page {
+            offset: 0x0,
+            body: Vec::new(),
+            bytes: 0x0,
+        }
+    }
+
+    /// Indices of the lines that differ between two pages. Lines present
+    /// in only one of the pages count as differing.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - page to compare against.
+    pub fn diff(&self, other: &Page) -> Vec<usize> {
+        let empty = Line::new();
+        let len = self.body.len().max(other.body.len());
+        (0..len)
+            .filter(|i| {
+                let left = self.body.get(*i).unwrap_or(&empty);
+                let right = other.body.get(*i).unwrap_or(&empty);
+                !left.diff(right).is_empty()
+            })
+            .collect()
+    }
+}
+
+/// Plain hex dump of the line with default options, as wide as the line.
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = HexConfig::new().columns(self.bytes.max(1)).dump_options();
+        let rendered = render_line(self, &options);
+        f.write_str(rendered.strip_suffix('\n').unwrap_or(&rendered))
+    }
+}
+
+/// Plain hex dump of the page with default options, as wide as its widest
+/// line.
+impl fmt::Display for Page {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.body.iter().map(|line| line.bytes).max().unwrap_or(0);
+        let options = HexConfig::new().columns(width.max(1)).dump_options();
+        let rendered = render_page(self, &options);
+        f.write_str(rendered.strip_suffix('\n').unwrap_or(&rendered))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Line {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("Line", 4)?;
+        state.serialize_field("offset", &self.offset)?;
+        state.serialize_field("hex_body", &self.hex_body)?;
+        state.serialize_field("ascii", &self.ascii)?;
+        state.serialize_field("bytes", &self.bytes)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Line {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Line, D::Error> {
+        use serde::de::{self, IgnoredAny, MapAccess, SeqAccess};
+
+        struct LineVisitor;
+
+        impl<'de> de::Visitor<'de> for LineVisitor {
+            type Value = Line;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a Line")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Line, A::Error> {
+                let missing = |i: usize| de::Error::invalid_length(i, &self);
+                Ok(Line {
+                    offset: seq.next_element()?.ok_or_else(|| missing(0))?,
+                    hex_body: seq.next_element()?.ok_or_else(|| missing(1))?,
+                    ascii: seq.next_element()?.ok_or_else(|| missing(2))?,
+                    bytes: seq.next_element()?.ok_or_else(|| missing(3))?,
+                })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Line, A::Error> {
+                let (mut offset, mut hex_body, mut ascii, mut bytes) = (None, None, None, None);
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "offset" => offset = Some(map.next_value()?),
+                        "hex_body" => hex_body = Some(map.next_value()?),
+                        "ascii" => ascii = Some(map.next_value()?),
+                        "bytes" => bytes = Some(map.next_value()?),
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(Line {
+                    offset: offset.ok_or_else(|| de::Error::missing_field("offset"))?,
+                    hex_body: hex_body.ok_or_else(|| de::Error::missing_field("hex_body"))?,
+                    ascii: ascii.ok_or_else(|| de::Error::missing_field("ascii"))?,
+                    bytes: bytes.ok_or_else(|| de::Error::missing_field("bytes"))?,
+                })
+            }
+        }
+
+        const FIELDS: &[&str] = &["offset", "hex_body", "ascii", "bytes"];
+        deserializer.deserialize_struct("Line", FIELDS, LineVisitor)
+    }
+}
+
+// the lines come last, as formats like TOML need plain values before
+// nested ones
+#[cfg(feature = "serde")]
+impl serde::Serialize for Page {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("Page", 3)?;
+        state.serialize_field("offset", &self.offset)?;
+        state.serialize_field("bytes", &self.bytes)?;
+        state.serialize_field("body", &self.body)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Page {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Page, D::Error> {
+        use serde::de::{self, IgnoredAny, MapAccess, SeqAccess};
+
+        struct PageVisitor;
+
+        impl<'de> de::Visitor<'de> for PageVisitor {
+            type Value = Page;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a Page")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Page, A::Error> {
+                let missing = |i: usize| de::Error::invalid_length(i, &self);
+                Ok(Page {
+                    offset: seq.next_element()?.ok_or_else(|| missing(0))?,
+                    bytes: seq.next_element()?.ok_or_else(|| missing(1))?,
+                    body: seq.next_element()?.ok_or_else(|| missing(2))?,
+                })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Page, A::Error> {
+                let (mut offset, mut bytes, mut body) = (None, None, None);
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "offset" => offset = Some(map.next_value()?),
+                        "bytes" => bytes = Some(map.next_value()?),
+                        "body" => body = Some(map.next_value()?),
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(Page {
+                    offset: offset.ok_or_else(|| de::Error::missing_field("offset"))?,
+                    bytes: bytes.ok_or_else(|| de::Error::missing_field("bytes"))?,
+                    body: body.ok_or_else(|| de::Error::missing_field("body"))?,
+                })
+            }
+        }
+
+        const FIELDS: &[&str] = &["offset", "bytes", "body"];
+        deserializer.deserialize_struct("Page", FIELDS, PageVisitor)
+    }
+}
+
+/// Read the next line of at most `column_width` bytes.
+/// Returns `None` once the input is exhausted, so callers can stream
+/// arbitrarily large inputs one line at a time.
+///
+/// # Arguments
+///
+/// * `buf` - Buffer to be read.
+/// * `column_width` - column width for output.
+/// * `offset` - offset of the next byte of `buf` in the input.
+pub fn read_line(buf: &mut dyn Read, column_width: u64, offset: u64) -> io::Result<Option<Line>> {
+    let width = column_width.max(1) as usize;
+    let mut line: Line = Line::new();
+    line.offset = offset;
+    line.hex_body.resize(width, 0x0);
+    let mut filled: usize = 0;
+    while filled < width {
+        match buf.read(&mut line.hex_body[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    if filled == 0 {
+        return Ok(None);
+    }
+    line.hex_body.truncate(filled);
+    line.bytes = filled as u64;
+    line.fill_ascii();
+    Ok(Some(line))
+}
+
+/// Read-only memory map of a file, so large inputs are paged in on
+/// demand instead of being copied into heap buffers. Wrap it in an
+/// `io::Cursor` to read and seek through the mapping.
+///
+/// The file must not be truncated while it is mapped: reading the pages
+/// past its new end kills the process with `SIGBUS`.
+#[cfg(all(unix, feature = "mmap"))]
+#[derive(Debug)]
+pub struct Mmap {
+    mapping: crate::sys::Mapping,
+}
+
+#[cfg(all(unix, feature = "mmap"))]
+impl Mmap {
+    /// Map the whole of `file` read-only.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - file to be mapped.
+    pub fn open(file: &std::fs::File) -> io::Result<Mmap> {
+        Ok(Mmap {
+            mapping: crate::sys::Mapping::new(file)?,
+        })
+    }
+}
+
+#[cfg(all(unix, feature = "mmap"))]
+impl AsRef<[u8]> for Mmap {
+    fn as_ref(&self) -> &[u8] {
+        self.mapping.bytes()
+    }
+}
+
+/// Skip forward in a non-seekable input such as stdin by reading and
+/// discarding bytes. Returns the resulting position.
+///
+/// # Arguments
+///
+/// * `buf` - Buffer to be read.
+/// * `pos` - position to skip to, relative to the current position.
+pub fn skip(buf: &mut dyn Read, pos: SeekFrom) -> io::Result<u64> {
+    let n = match pos {
+        SeekFrom::Start(n) => n,
+        SeekFrom::Current(n) if n >= 0 => n as u64,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot seek backwards or from the end of a stream",
+            ))
+        }
+    };
+    io::copy(&mut buf.take(n), &mut io::sink())
+}
+
+/// Keep a rolling buffer of the last `n` bytes of a non-seekable input
+/// such as stdin. Returns the offset of the first kept byte along with
+/// the kept bytes.
+///
+/// # Arguments
+///
+/// * `buf` - Buffer to be read.
+/// * `n` - number of trailing bytes to keep.
+pub fn tail(buf: &mut dyn Read, n: u64) -> io::Result<(u64, Vec<u8>)> {
+    let mut kept: VecDeque<u8> = VecDeque::new();
+    let mut chunk = [0x0; 0x2000];
+    let mut total: u64 = 0x0;
+    loop {
+        let read = match buf.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        total += read as u64;
+        kept.extend(&chunk[..read]);
+        while kept.len() as u64 > n {
+            kept.pop_front();
+        }
+    }
+    Ok((total - kept.len() as u64, kept.into()))
+}
+
+/// Reader over a growing file, like `tail -f`. Reaching the end of the
+/// inner reader waits for more data instead of ending the input, so
+/// the reader only ends when the inner reader fails. With an idle flush,
+/// a read fails with `WouldBlock` once no data has been added for that
+/// long, which `PageReader` takes as the end of a partial line, as for
+/// `Timed`.
+#[derive(Debug)]
+pub struct Follow<R: Read> {
+    inner: R,
+    poll: Duration,
+    idle_flush: Option<Duration>,
+    /// when data was last read, unless a partial line was ended since
+    last_read: Option<Instant>,
+}
+
+impl<R: Read> Follow<R> {
+    /// Follow constructor.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - reader positioned where following starts.
+    /// * `poll` - time to wait before reading again at the end of input.
+    /// * `idle_flush` - time without new data that ends a partial line.
+    pub fn new(inner: R, poll: Duration, idle_flush: Option<Duration>) -> Follow<R> {
+        Follow {
+            inner,
+            poll,
+            idle_flush,
+            last_read: None,
+        }
+    }
+}
+
+impl<R: Read> Read for Follow<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match self.inner.read(buf)? {
+                0 => {
+                    if let (Some(idle_flush), Some(last_read)) = (self.idle_flush, self.last_read) {
+                        if last_read.elapsed() >= idle_flush {
+                            self.last_read = None;
+                            return Err(io::Error::new(
+                                io::ErrorKind::WouldBlock,
+                                "no data was added before the idle flush",
+                            ));
+                        }
+                    }
+                    thread::sleep(
+                        self.idle_flush
+                            .map_or(self.poll, |idle| idle.min(self.poll)),
+                    );
+                }
+                n => {
+                    self.last_read = Some(Instant::now());
+                    return Ok(n);
+                }
+            }
+        }
+    }
+}
+
+/// Times at which the bytes of a live input arrived, kept by `Timed` and
+/// looked up by offset when the lines are printed.
+#[derive(Debug, Default)]
+pub struct Arrivals {
+    /// offset of the first byte of each chunk and when it arrived
+    chunks: VecDeque<(u64, SystemTime)>,
+}
+
+impl Arrivals {
+    /// Arrivals constructor.
+    pub fn new() -> Arrivals {
+        Arrivals::default()
+    }
+
+    /// Record that the bytes from `offset` arrived at `time`.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - offset of the first byte that arrived.
+    /// * `time` - when it arrived.
+    pub fn record(&mut self, offset: u64, time: SystemTime) {
+        self.chunks.push_back((offset, time));
+    }
+
+    /// When the byte at `offset` arrived, or `None` if it has not. Lines
+    /// are looked up in order, so earlier chunks are forgotten.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - offset of the byte.
+    pub fn at(&mut self, offset: u64) -> Option<SystemTime> {
+        while self.chunks.len() > 1 && self.chunks[1].0 <= offset {
+            self.chunks.pop_front();
+        }
+        self.chunks
+            .front()
+            .filter(|(start, _)| *start <= offset)
+            .map(|(_, time)| *time)
+    }
+}
+
+/// Data read by the thread of a `Timed` reader, empty at the end of the
+/// input.
+type Arrived = io::Result<(SystemTime, Vec<u8>)>;
+
+/// Reader over a live input, such as a socket or a serial port, read on
+/// a thread of its own so the time each chunk arrives can be recorded in
+/// `Arrivals`. When nothing arrives for `timeout`, `read` fails with
+/// `WouldBlock`, which `PageReader` takes as the end of a partial line,
+/// so a line is printed without waiting for it to fill.
+#[derive(Debug)]
+pub struct Timed {
+    receiver: Receiver<Arrived>,
+    /// bytes received but not read yet
+    pending: Vec<u8>,
+    start: usize,
+    offset: u64,
+    timeout: Duration,
+    arrivals: Option<Arc<Mutex<Arrivals>>>,
+    done: bool,
+}
+
+impl Timed {
+    /// Timed constructor, which starts reading `inner`.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - live input.
+    /// * `timeout` - time to wait for more bytes before ending a line.
+    /// * `arrivals` - where the arrival times are recorded, if anywhere.
+    pub fn new<R: Read + Send + 'static>(
+        mut inner: R,
+        timeout: Duration,
+        arrivals: Option<Arc<Mutex<Arrivals>>>,
+    ) -> Timed {
+        let (sender, receiver) = mpsc::channel::<Arrived>();
+        thread::spawn(move || {
+            let mut chunk = vec![0u8; READ_CHUNK];
+            loop {
+                let arrived = match inner.read(&mut chunk) {
+                    Ok(n) => Ok((SystemTime::now(), chunk[..n].to_vec())),
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => Err(e),
+                };
+                let last = !matches!(&arrived, Ok((_, bytes)) if !bytes.is_empty());
+                if sender.send(arrived).is_err() || last {
+                    break;
+                }
+            }
+        });
+        Timed {
+            receiver,
+            pending: Vec::new(),
+            start: 0,
+            offset: 0,
+            timeout,
+            arrivals,
+            done: false,
+        }
+    }
+}
+
+impl Read for Timed {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.start == self.pending.len() {
+            if self.done || buf.is_empty() {
+                return Ok(0);
+            }
+            match self.receiver.recv_timeout(self.timeout) {
+                Ok(Ok((time, bytes))) => {
+                    self.done = bytes.is_empty();
+                    if let Some(arrivals) = &self.arrivals {
+                        arrivals.lock().unwrap().record(self.offset, time);
+                    }
+                    self.pending = bytes;
+                    self.start = 0;
+                }
+                Ok(Err(e)) => {
+                    self.done = true;
+                    return Err(e);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::WouldBlock,
+                        "no input arrived before the timeout",
+                    ))
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.done = true;
+                    return Ok(0);
+                }
+            }
+        }
+        let n = buf.len().min(self.pending.len() - self.start);
+        buf[..n].copy_from_slice(&self.pending[self.start..self.start + n]);
+        self.start += n;
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
+
+/// Iterator over the lines of an input, read lazily in chunks of
+/// `READ_CHUNK` bytes, so arbitrarily large inputs can be processed one
+/// line at a time. Every line but the last is `column_width` bytes,
+/// unless the input fails a read with `WouldBlock`, which ends the line
+/// read so far.
+///
+/// Like `read_line`, a line is otherwise only yielded once it is full or
+/// the input is exhausted, and the input is never read past the length
+/// limit.
+#[derive(Debug)]
+pub struct PageReader<R: Read> {
+    inner: R,
+    chunk: Vec<u8>,
+    /// unconsumed bytes of `chunk`
+    start: usize,
+    end: usize,
+    column_width: usize,
+    offset: u64,
+    /// bytes left to read from `inner`
+    remaining: u64,
+}
+
+impl<R: Read> PageReader<R> {
+    /// PageReader constructor.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - input to be read.
+    /// * `column_width` - column width for output.
+    /// * `start_offset` - offset of the first byte of `inner` in the input.
+    /// * `len` - number of bytes to read, or 0 to read to the end of `inner`.
+    pub fn new(inner: R, column_width: u64, start_offset: u64, len: u64) -> PageReader<R> {
+        PageReader {
+            inner,
+            chunk: vec![0x0; READ_CHUNK],
+            start: 0,
+            end: 0,
+            column_width: column_width.max(1) as usize,
+            offset: start_offset,
+            remaining: if len > 0 { len } else { u64::MAX },
+        }
+    }
+
+    /// Offset of the next line in the input.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Collect the remaining lines into a page. Like the rendered dump, a
+    /// page whose last line is full ends with an empty line.
+    pub fn into_page(mut self) -> io::Result<Page> {
+        let mut page: Page = Page::new();
+        page.offset = self.offset;
+        for line in self.by_ref() {
+            let line = line?;
+            page.bytes += line.bytes;
+            page.body.push(line);
+        }
+        if page
+            .body
+            .last()
+            .is_none_or(|line| line.hex_body.len() == self.column_width)
+        {
+            let mut line: Line = Line::new();
+            line.offset = self.offset;
+            page.body.push(line);
+        }
+        Ok(page)
+    }
+}
+
+impl<R: Read> Iterator for PageReader<R> {
+    type Item = io::Result<Line>;
+
+    fn next(&mut self) -> Option<io::Result<Line>> {
+        let mut line: Line = Line::new();
+        line.offset = self.offset;
+        while line.hex_body.len() < self.column_width {
+            if self.start == self.end {
+                let want = self.remaining.min(self.chunk.len() as u64) as usize;
+                if want == 0 {
+                    break;
+                }
+                match self.inner.read(&mut self.chunk[..want]) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        self.start = 0;
+                        self.end = n;
+                        self.remaining -= n as u64;
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    // a live input has stalled, see `Timed`
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        if line.hex_body.is_empty() {
+                            continue;
+                        }
+                        break;
+                    }
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            let take = (self.column_width - line.hex_body.len()).min(self.end - self.start);
+            line.hex_body
+                .extend_from_slice(&self.chunk[self.start..self.start + take]);
+            self.start += take;
+        }
+        if line.hex_body.is_empty() {
+            return None;
+        }
+        line.bytes = line.hex_body.len() as u64;
+        line.fill_ascii();
+        self.offset += line.bytes;
+        Some(Ok(line))
+    }
+}
+
+/// Buffer to array, the lines of a `PageReader` collected into a page.
+///
+/// # Arguments
+///
+/// * `buf` - Buffer to be read.
+/// * `buf_len` - number of bytes to read, or 0 to read to the end of `buf`.
+/// * `column_width` - column width for output.
+/// * `start_offset` - offset of the first byte of `buf` in the input.
+pub fn buf_to_array(
+    buf: &mut dyn Read,
+    buf_len: u64,
+    column_width: u64,
+    start_offset: u64,
+) -> Result<Page, Box<dyn Error>> {
+    Ok(PageReader::new(buf, column_width, start_offset, buf_len).into_page()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Input that is still being written: yields each chunk once, with an
+    /// empty chunk as an end of input, and fails once they run out.
+    struct Growing(VecDeque<&'static [u8]>);
+
+    impl Read for Growing {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.pop_front() {
+                Some(chunk) => {
+                    buf[..chunk.len()].copy_from_slice(chunk);
+                    Ok(chunk.len())
+                }
+                None => Err(io::Error::other("closed")),
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_line_streams_columns() {
+        let mut input: &[u8] = b"0123456789abc";
+        let first = read_line(&mut input, 10, 0).unwrap().unwrap();
+        assert_eq!(first.hex_body, b"0123456789");
+        assert_eq!(first.bytes, 10);
+        let second = read_line(&mut input, 10, 10).unwrap().unwrap();
+        assert_eq!(second.hex_body, b"abc");
+        assert_eq!(second.ascii, b"abc");
+        assert_eq!(second.offset, 10);
+        assert!(read_line(&mut input, 10, 13).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_line_and_page_diff() {
+        let mut left = Line::new();
+        left.hex_body = b"abcd".to_vec();
+        let mut right = Line::new();
+        right.hex_body = b"abXdef".to_vec();
+        assert_eq!(left.diff(&right), vec![2, 4, 5]);
+        assert!(left.diff(&left).is_empty());
+
+        let left_page = buf_to_array(&mut &b"0123456789"[..], 0, 4, 0).unwrap();
+        let right_page = buf_to_array(&mut &b"0123456x89"[..], 0, 4, 0).unwrap();
+        assert_eq!(left_page.diff(&right_page), vec![1]);
+    }
+
+    #[test]
+    fn test_buf_to_array_offsets() {
+        let page = buf_to_array(&mut &b"0123456789"[..], 0, 4, 0x100).unwrap();
+        assert_eq!(page.offset, 0x100);
+        let offsets: Vec<u64> = page.body.iter().map(|line| line.offset).collect();
+        assert_eq!(offsets, vec![0x100, 0x104, 0x108]);
+    }
+
+    #[test]
+    fn test_page_reader() {
+        let mut lines = PageReader::new(&b"0123456789"[..], 4, 0x10, 0);
+        let line = lines.next().unwrap().unwrap();
+        assert_eq!((line.offset, line.hex_body), (0x10, b"0123".to_vec()));
+        assert_eq!(lines.offset(), 0x14);
+        let rest: Vec<Vec<u8>> = lines.map(|line| line.unwrap().hex_body).collect();
+        assert_eq!(rest, vec![b"4567".to_vec(), b"89".to_vec()]);
+        let lens: Vec<u64> = PageReader::new(&b"0123456789"[..], 4, 0, 6)
+            .map(|line| line.unwrap().bytes)
+            .collect();
+        assert_eq!(lens, vec![4, 2]);
+        let mut lines = PageReader::new(&b"a\x00\x7f\xff \n"[..], 4, 0, 0);
+        assert_eq!(lines.next().unwrap().unwrap().ascii, b"a...");
+        assert_eq!(lines.next().unwrap().unwrap().ascii, b" .");
+    }
+
+    #[test]
+    fn test_display() {
+        let page = buf_to_array(&mut &b"abcdef"[..], 0, 4, 0x10).unwrap();
+        assert_eq!(page.body[1].to_string(), "0x000014: 0x65 0x66 ef");
+        assert_eq!(
+            page.to_string(),
+            "0x000010: 0x61 0x62 0x63 0x64 abcd\n0x000014: 0x65 0x66           ef"
+        );
+        assert_eq!(Page::new().to_string(), "");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let page = buf_to_array(&mut &b"abcdef"[..], 0, 4, 0x10).unwrap();
+        let text = toml::to_string(&page).unwrap();
+        let read: Page = toml::from_str(&text).unwrap();
+        assert_eq!(read.to_string(), page.to_string());
+        assert_eq!((read.offset, read.bytes, read.body.len()), (0x10, 6, 2));
+        assert!(toml::from_str::<Page>("offset = 0").is_err());
+    }
+
+    #[test]
+    fn test_buf_to_array_len() {
+        let input = vec![0u8; 0x20000];
+        let page = buf_to_array(&mut &input[..], 0x10001, 16, 0).unwrap();
+        assert_eq!(page.bytes, 0x10001);
+        let page = buf_to_array(&mut &input[..], 0, 16, 0).unwrap();
+        assert_eq!(page.bytes, 0x20000);
+        // reading stops at the length, and a line ending at it is full
+        let mut input: &[u8] = b"0123456789";
+        let page = buf_to_array(&mut input, 8, 4, 0).unwrap();
+        assert_eq!(input, b"89");
+        let lens: Vec<u64> = page.body.iter().map(|line| line.bytes).collect();
+        assert_eq!(lens, vec![4, 4, 0]);
+    }
+
+    #[test]
+    fn test_skip_stream() {
+        let mut input: &[u8] = b"0123456789";
+        assert_eq!(skip(&mut input, SeekFrom::Start(4)).unwrap(), 4);
+        assert_eq!(input, b"456789");
+        assert!(skip(&mut input, SeekFrom::End(-2)).is_err());
+    }
+
+    #[cfg(all(unix, feature = "mmap"))]
+    #[test]
+    fn test_mmap_file() {
+        let file = std::fs::File::open("tests/files/tiny.txt").unwrap();
+        let map = Mmap::open(&file).unwrap();
+        assert_eq!(map.as_ref(), b"il\n");
+        let mut cursor = io::Cursor::new(map);
+        let line = read_line(&mut cursor, 2, 0).unwrap().unwrap();
+        assert_eq!(line.hex_body, b"il");
+    }
+
+    #[test]
+    fn test_follow_waits_for_data() {
+        let chunks: VecDeque<&'static [u8]> = vec![&b"012"[..], b"", b"", b"345", b""].into();
+        let mut follow = Follow::new(Growing(chunks), Duration::ZERO, None);
+        let line = read_line(&mut follow, 6, 0).unwrap().unwrap();
+        assert_eq!(line.hex_body, b"012345");
+        assert!(read_line(&mut follow, 6, 0).is_err());
+    }
+
+    #[test]
+    fn test_follow_idle_flush() {
+        let chunks: VecDeque<&'static [u8]> = vec![&b"012"[..], b"", b"", b"345678", b""].into();
+        let follow = Follow::new(Growing(chunks), Duration::ZERO, Some(Duration::ZERO));
+        let mut lines = PageReader::new(follow, 4, 0, 0);
+        let mut line = || {
+            lines
+                .next()
+                .unwrap()
+                .map(|line| (line.offset, line.hex_body))
+        };
+        assert_eq!(line().unwrap(), (0, b"012".to_vec()));
+        assert_eq!(line().unwrap(), (3, b"3456".to_vec()));
+        assert_eq!(line().unwrap(), (7, b"78".to_vec()));
+        assert!(line().is_err());
+    }
+
+    #[test]
+    fn test_arrivals() {
+        let at = |secs: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let mut arrivals = Arrivals::new();
+        assert_eq!(arrivals.at(0), None);
+        arrivals.record(0, at(1));
+        arrivals.record(4, at(2));
+        arrivals.record(6, at(3));
+        assert_eq!(arrivals.at(2), Some(at(1)));
+        assert_eq!(arrivals.at(4), Some(at(2)));
+        assert_eq!(arrivals.at(7), Some(at(3)));
+        // earlier chunks are forgotten
+        assert_eq!(arrivals.at(0), None);
+    }
+
+    #[test]
+    fn test_timed_ends_stalled_lines() {
+        // yields each chunk after a pause
+        struct Slow(VecDeque<&'static [u8]>);
+        impl Read for Slow {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                thread::sleep(Duration::from_millis(300));
+                let chunk = self.0.pop_front().unwrap_or_default();
+                buf[..chunk.len()].copy_from_slice(chunk);
+                Ok(chunk.len())
+            }
+        }
+        let arrivals = Arc::new(Mutex::new(Arrivals::new()));
+        let chunks: VecDeque<&'static [u8]> = vec![&b"01"[..], b"2345"].into();
+        let timed = Timed::new(
+            Slow(chunks),
+            Duration::from_millis(20),
+            Some(arrivals.clone()),
+        );
+        let lines: Vec<(u64, Vec<u8>)> = PageReader::new(timed, 4, 0, 0)
+            .map(|line| line.map(|line| (line.offset, line.hex_body)).unwrap())
+            .collect();
+        assert_eq!(lines, vec![(0, b"01".to_vec()), (2, b"2345".to_vec())]);
+        let mut arrivals = arrivals.lock().unwrap();
+        assert!(arrivals.at(0).unwrap() < arrivals.at(2).unwrap());
+    }
+
+    #[test]
+    fn test_tail_stream() {
+        let mut input: &[u8] = b"0123456789";
+        assert_eq!(tail(&mut input, 3).unwrap(), (7, b"789".to_vec()));
+        let mut input: &[u8] = b"01";
+        assert_eq!(tail(&mut input, 3).unwrap(), (0, b"01".to_vec()));
+    }
+}
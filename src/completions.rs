@@ -0,0 +1,409 @@
+//! shell completion scripts generated from the command line definition
+use clap::{ArgAction, Command};
+use std::io::{self, Write};
+
+/// Shell to generate a completion script for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Shell {
+    /// bash, sourced from a bash-completion directory
+    Bash,
+    /// zsh, installed as `_hx` on `$fpath`
+    Zsh,
+    /// fish, installed in `~/.config/fish/completions`
+    Fish,
+    /// PowerShell, sourced from the profile
+    PowerShell,
+}
+
+impl Shell {
+    /// Look up a shell by its `--completions` name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - shell name.
+    pub fn by_name(name: &str) -> Option<Shell> {
+        match name {
+            "bash" => Some(Self::Bash),
+            "zsh" => Some(Self::Zsh),
+            "fish" => Some(Self::Fish),
+            "powershell" => Some(Self::PowerShell),
+            _ => None,
+        }
+    }
+}
+
+/// Option of the command, as needed by the completion scripts.
+#[derive(Clone, Debug)]
+struct Opt {
+    short: Option<char>,
+    long: Option<String>,
+    help: String,
+    /// possible values, empty for free form values
+    values: Vec<String>,
+    /// number of values taken, 0 for flags
+    num_values: usize,
+    /// the value may be left out
+    optional: bool,
+    /// the option may be repeated
+    repeated: bool,
+}
+
+impl Opt {
+    /// Option names, short first, with their dashes.
+    fn names(&self) -> Vec<String> {
+        let short = self.short.map(|short| format!("-{}", short));
+        let long = self.long.as_ref().map(|long| format!("--{}", long));
+        short.into_iter().chain(long).collect()
+    }
+}
+
+/// Options of `cmd`, without positional and hidden arguments.
+fn options(cmd: &mut Command) -> Vec<Opt> {
+    cmd.build();
+    cmd.get_arguments()
+        .filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+        .map(|arg| {
+            let range = arg.get_num_args();
+            let takes_values = arg.get_action().takes_values();
+            Opt {
+                short: arg.get_short(),
+                long: arg.get_long().map(str::to_string),
+                help: arg
+                    .get_help()
+                    .map(|help| help.to_string())
+                    .unwrap_or_default(),
+                values: arg
+                    .get_possible_values()
+                    .iter()
+                    .filter(|value| !value.is_hide_set())
+                    .map(|value| value.get_name().to_string())
+                    .collect(),
+                num_values: match (takes_values, range) {
+                    (false, _) => 0,
+                    (true, Some(range)) => range.min_values().max(1),
+                    (true, None) => 1,
+                },
+                optional: takes_values && range.is_some_and(|range| range.min_values() == 0),
+                repeated: matches!(arg.get_action(), ArgAction::Append | ArgAction::Count),
+            }
+        })
+        .collect()
+}
+
+/// Write a completion script for `cmd` to `w`.
+///
+/// # Arguments
+///
+/// * `shell` - shell the script is written for.
+/// * `cmd` - command line definition.
+/// * `w` - output sink.
+pub fn generate(shell: Shell, cmd: &mut Command, w: &mut impl Write) -> io::Result<()> {
+    let name = cmd.get_name().to_string();
+    let options = options(cmd);
+    match shell {
+        Shell::Bash => write_bash(&name, &options, w),
+        Shell::Zsh => write_zsh(&name, &options, w),
+        Shell::Fish => write_fish(&name, &options, w),
+        Shell::PowerShell => write_powershell(&name, &options, w),
+    }
+}
+
+fn write_bash(name: &str, options: &[Opt], w: &mut impl Write) -> io::Result<()> {
+    let function = format!("_{}", name.replace('-', "_"));
+    writeln!(w, "{}() {{", function)?;
+    writeln!(w, "    local cur prev")?;
+    writeln!(w, "    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"")?;
+    writeln!(w, "    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"")?;
+    writeln!(w, "    case \"${{prev}}\" in")?;
+    // free form values fall through to file names below
+    for opt in options {
+        if opt.values.is_empty() || opt.optional {
+            continue;
+        }
+        writeln!(w, "        {})", opt.names().join("|"))?;
+        writeln!(
+            w,
+            "            COMPREPLY=($(compgen -W \"{}\" -- \"${{cur}}\"))",
+            opt.values.join(" ")
+        )?;
+        writeln!(w, "            return 0")?;
+        writeln!(w, "            ;;")?;
+    }
+    writeln!(w, "    esac")?;
+    writeln!(w, "    if [[ \"${{cur}}\" == -* ]]; then")?;
+    let names: Vec<String> = options.iter().flat_map(Opt::names).collect();
+    writeln!(
+        w,
+        "        COMPREPLY=($(compgen -W \"{}\" -- \"${{cur}}\"))",
+        names.join(" ")
+    )?;
+    writeln!(w, "        return 0")?;
+    writeln!(w, "    fi")?;
+    writeln!(w, "    COMPREPLY=($(compgen -f -- \"${{cur}}\"))")?;
+    writeln!(w, "}}")?;
+    writeln!(w)?;
+    writeln!(w, "complete -F {} -o filenames {}", function, name)
+}
+
+/// Escape text for an `_arguments` spec in single quotes.
+fn zsh_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\'' => escaped.push_str("'\\''"),
+            '[' | ']' | ':' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn write_zsh(name: &str, options: &[Opt], w: &mut impl Write) -> io::Result<()> {
+    let function = format!("_{}", name.replace('-', "_"));
+    writeln!(w, "#compdef {}", name)?;
+    writeln!(w)?;
+    writeln!(w, "{}() {{", function)?;
+    writeln!(w, "    _arguments -s -S \\")?;
+    for opt in options {
+        let names = opt.names();
+        let (short_suffix, long_suffix) = match (opt.num_values, opt.optional) {
+            (0, _) => ("", ""),
+            (_, true) => ("", "=-"),
+            (_, false) => ("+", "="),
+        };
+        let mut specs: Vec<String> = Vec::with_capacity(2);
+        if let Some(short) = opt.short {
+            specs.push(format!("-{}{}", short, short_suffix));
+        }
+        if let Some(long) = &opt.long {
+            specs.push(format!("--{}{}", long, long_suffix));
+        }
+        let exclusion = match (opt.repeated, names.len()) {
+            (true, _) => "'*'".to_string(),
+            (false, 1) => String::new(),
+            (false, _) => format!("'({})'", names.join(" ")),
+        };
+        let specs = match specs.len() {
+            1 => specs.remove(0),
+            _ => format!("{{{}}}", specs.join(",")),
+        };
+        let action = match opt.values.is_empty() {
+            true => "_default".to_string(),
+            false => format!("({})", opt.values.join(" ")),
+        };
+        let colons = if opt.optional { "::" } else { ":" };
+        let value = zsh_escape(opt.long.as_deref().unwrap_or("value"));
+        let values: String = (0..opt.num_values)
+            .map(|_| format!("{}{}:{}", colons, value, action))
+            .collect();
+        writeln!(
+            w,
+            "        {}{}'[{}]{}' \\",
+            exclusion,
+            specs,
+            zsh_escape(&opt.help),
+            values
+        )?;
+    }
+    writeln!(w, "        '*::input:_files'")?;
+    writeln!(w, "}}")?;
+    writeln!(w)?;
+    writeln!(w, "{} \"$@\"", function)
+}
+
+/// Quote text for fish in single quotes.
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn write_fish(name: &str, options: &[Opt], w: &mut impl Write) -> io::Result<()> {
+    for opt in options {
+        write!(w, "complete -c {}", name)?;
+        if let Some(short) = opt.short {
+            write!(w, " -s {}", short)?;
+        }
+        if let Some(long) = &opt.long {
+            write!(w, " -l {}", long)?;
+        }
+        if !opt.help.is_empty() {
+            write!(w, " -d {}", fish_quote(&opt.help))?;
+        }
+        if opt.num_values > 0 && !opt.optional {
+            write!(w, " -r")?;
+        }
+        if !opt.values.is_empty() {
+            write!(w, " -f -a {}", fish_quote(&opt.values.join(" ")))?;
+        }
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+/// Quote text for PowerShell in single quotes.
+fn powershell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn write_powershell(name: &str, options: &[Opt], w: &mut impl Write) -> io::Result<()> {
+    writeln!(w, "using namespace System.Management.Automation")?;
+    writeln!(w)?;
+    writeln!(
+        w,
+        "Register-ArgumentCompleter -Native -CommandName {} -ScriptBlock {{",
+        powershell_quote(name)
+    )?;
+    writeln!(
+        w,
+        "    param($wordToComplete, $commandAst, $cursorPosition)"
+    )?;
+    // option names differ only in case, e.g. -r and -R
+    writeln!(
+        w,
+        "    $values = [Collections.Generic.Dictionary[string, string[]]]::new([StringComparer]::Ordinal)"
+    )?;
+    for opt in options {
+        if opt.values.is_empty() || opt.optional {
+            continue;
+        }
+        let values: Vec<String> = opt.values.iter().map(|v| powershell_quote(v)).collect();
+        for option_name in opt.names() {
+            writeln!(
+                w,
+                "    $values.Add({}, @({}))",
+                powershell_quote(&option_name),
+                values.join(", ")
+            )?;
+        }
+    }
+    writeln!(w, "    $options = @(")?;
+    for opt in options {
+        let tooltip = match opt.help.is_empty() {
+            true => opt.names().join(", "),
+            false => opt.help.clone(),
+        };
+        for option_name in opt.names() {
+            writeln!(
+                w,
+                "        [CompletionResult]::new({0}, {0}, [CompletionResultType]::ParameterName, {1})",
+                powershell_quote(&option_name),
+                powershell_quote(&tooltip)
+            )?;
+        }
+    }
+    writeln!(w, "    )")?;
+    writeln!(w, "    $previous = @($commandAst.CommandElements |")?;
+    writeln!(
+        w,
+        "        Where-Object {{ $_.Extent.EndOffset -lt $cursorPosition }} |"
+    )?;
+    writeln!(w, "        ForEach-Object {{ $_.ToString() }})[-1]")?;
+    writeln!(
+        w,
+        "    if ($null -ne $previous -and $values.ContainsKey($previous)) {{"
+    )?;
+    writeln!(
+        w,
+        "        $values[$previous] | Where-Object {{ $_ -clike \"$wordToComplete*\" }} |"
+    )?;
+    writeln!(
+        w,
+        "            ForEach-Object {{ [CompletionResult]::new($_, $_, [CompletionResultType]::ParameterValue, $_) }}"
+    )?;
+    writeln!(w, "        return")?;
+    writeln!(w, "    }}")?;
+    writeln!(
+        w,
+        "    $options | Where-Object {{ $_.CompletionText -clike \"$wordToComplete*\" }}"
+    )?;
+    writeln!(w, "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+
+    fn command() -> Command {
+        Command::new("hx")
+            .arg(
+                Arg::new("format")
+                    .short('f')
+                    .long("format")
+                    .help("Set format: x, o [octets]")
+                    .value_parser(["x", "o"]),
+            )
+            .arg(
+                Arg::new("frame")
+                    .long("frame")
+                    .help("Draw | borders")
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("highlight")
+                    .long("highlight")
+                    .help("Highlight a range, it's repeatable")
+                    .action(ArgAction::Append),
+            )
+            .arg(Arg::new("input"))
+    }
+
+    fn script(shell: Shell) -> String {
+        let mut out: Vec<u8> = Vec::new();
+        generate(shell, &mut command(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_shell_by_name() {
+        assert_eq!(Shell::by_name("zsh"), Some(Shell::Zsh));
+        assert_eq!(Shell::by_name("powershell"), Some(Shell::PowerShell));
+        assert_eq!(Shell::by_name("tcsh"), None);
+    }
+
+    #[test]
+    fn test_generate_bash() {
+        let script = script(Shell::Bash);
+        assert!(script.contains(
+            "        -f|--format)\n            COMPREPLY=($(compgen -W \"x o\" -- \"${cur}\"))\n"
+        ));
+        assert!(script.contains("compgen -W \"-f --format --frame --highlight -h --help\""));
+        assert!(script.ends_with("complete -F _hx -o filenames hx\n"));
+    }
+
+    #[test]
+    fn test_generate_zsh() {
+        let script = script(Shell::Zsh);
+        assert!(script.starts_with("#compdef hx\n"));
+        assert!(script.contains(
+            "        '(-f --format)'{-f+,--format=}'[Set format\\: x, o \\[octets\\]]:format:(x o)' \\\n"
+        ));
+        assert!(script.contains("        --frame'[Draw | borders]' \\\n"));
+        assert!(script.contains(
+            "        '*'--highlight='[Highlight a range, it'\\''s repeatable]:highlight:_default' \\\n"
+        ));
+    }
+
+    #[test]
+    fn test_generate_fish() {
+        let script = script(Shell::Fish);
+        assert!(script.contains(
+            "complete -c hx -s f -l format -d 'Set format: x, o [octets]' -r -f -a 'x o'\n"
+        ));
+        assert!(script.contains(
+            "complete -c hx -l highlight -d 'Highlight a range, it\\'s repeatable' -r\n"
+        ));
+        assert!(script.contains("complete -c hx -l frame -d 'Draw | borders'\n"));
+    }
+
+    #[test]
+    fn test_generate_powershell() {
+        let script = script(Shell::PowerShell);
+        assert!(script.contains(
+            "    $values.Add('-f', @('x', 'o'))\n    $values.Add('--format', @('x', 'o'))\n"
+        ));
+        assert!(script.contains("[CompletionResult]::new('--highlight', '--highlight', [CompletionResultType]::ParameterName, 'Highlight a range, it''s repeatable')"));
+    }
+}
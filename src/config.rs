@@ -0,0 +1,558 @@
+//! library configuration, decoupled from command line parsing
+use crate::output::{
+    AsciiStyle, Charset, ColorMode, DumpOptions, Endian, OffsetFormat, Panes, Summary, Theme,
+};
+use crate::HxError;
+use crate::{
+    Format, ARG_AOL, ARG_ASY, ARG_BLK, ARG_CHS, ARG_CLR, ARG_CMD, ARG_COL, ARG_END, ARG_EOS,
+    ARG_FMT, ARG_FRM, ARG_GRP, ARG_GUT, ARG_HDR, ARG_LEG, ARG_LNO, ARG_NAS, ARG_NPC, ARG_NSQ,
+    ARG_NUL, ARG_OCL, ARG_OCS, ARG_OFF, ARG_OFW, ARG_OSP, ARG_PFX, ARG_SCL, ARG_SEP, ARG_SUM,
+    ARG_TCL, ARG_THM, ARG_WSZ,
+};
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use toml::Value;
+
+/// Options that take a value and may be set in the config file.
+const FILE_OPTIONS: [&str; 24] = [
+    ARG_COL, ARG_FMT, ARG_CLR, ARG_CMD, ARG_THM, ARG_OCL, ARG_SCL, ARG_TCL, ARG_PFX, ARG_GRP,
+    ARG_BLK, ARG_WSZ, ARG_END, ARG_OFF, ARG_OFW, ARG_OCS, ARG_OSP, ARG_CHS, ARG_ASY, ARG_NPC,
+    ARG_NUL, ARG_SEP, ARG_HDR, ARG_SUM,
+];
+
+/// Flags that may be set in the config file.
+const FILE_FLAGS: [&str; 8] = [
+    ARG_FRM, ARG_GUT, ARG_LEG, ARG_NSQ, ARG_NAS, ARG_AOL, ARG_LNO, ARG_EOS,
+];
+
+/// Option presets selected with `--style`, by style name.
+const STYLES: [(&str, &[(&str, &str)]); 3] = [
+    ("hx", &[]),
+    (
+        "xxd",
+        &[
+            (ARG_COL, "16"),
+            (ARG_GRP, "2"),
+            (ARG_PFX, "0"),
+            (ARG_CLR, "0"),
+            (ARG_OFF, "bare-hex"),
+            (ARG_OFW, "8"),
+            (ARG_SEP, "  "),
+            (ARG_SUM, "none"),
+            (ARG_NSQ, "true"),
+        ],
+    ),
+    (
+        "canonical",
+        &[
+            (ARG_COL, "16"),
+            (ARG_GRP, "1"),
+            (ARG_BLK, "8"),
+            (ARG_PFX, "0"),
+            (ARG_CLR, "0"),
+            (ARG_OFF, "bare-hex"),
+            (ARG_OFW, "8"),
+            (ARG_OSP, "  "),
+            (ARG_SEP, "  "),
+            (ARG_GUT, "true"),
+            (ARG_SUM, "offset"),
+        ],
+    ),
+];
+
+/// Option presets of `--bits`, which apply on top of any `--style`.
+const BITS: [(&str, &str); 5] = [
+    (ARG_FMT, "b"),
+    (ARG_COL, "8"),
+    (ARG_GRP, "1"),
+    (ARG_PFX, "0"),
+    (ARG_SEP, "  "),
+];
+
+/// Builder for everything that controls a hex dump, for programs that
+/// embed the renderer through `hx::dump` instead of the command line.
+///
+/// Starts from the command line defaults, except that colorization is
+/// off since the output is not known to be a terminal.
+#[derive(Clone, Debug)]
+pub struct HexConfig {
+    options: DumpOptions,
+    length: u64,
+    seek: u64,
+}
+
+impl HexConfig {
+    /// HexConfig constructor
+    pub fn new() -> HexConfig {
+        HexConfig {
+            options: DumpOptions {
+                column_width: 10,
+                group_size: 1,
+                word_size: 1,
+                block_size: 0,
+                little_endian_groups: false,
+                endian: Endian::Little,
+                format: Format::LowerHex,
+                colorize: false,
+                color_mode: ColorMode::Value,
+                theme: Theme::DEFAULT,
+                prefix: true,
+                offset_format: OffsetFormat::Hex,
+                offset_width: 6,
+                offset_separator: ": ".to_string(),
+                squeeze: true,
+                base_address: 0,
+                charset: Charset::Ascii,
+                ascii_style: AsciiStyle::Dots,
+                nonprintable: '.',
+                nul: None,
+                separator: " ".to_string(),
+                frame: false,
+                gutter: false,
+                panes: Panes::Both,
+                line_numbers: None,
+                end_offsets: false,
+                timestamps: None,
+                stacked: Vec::new(),
+                header: None,
+                summary: Summary::Bytes,
+            },
+            length: 0x0,
+            seek: 0x0,
+        }
+    }
+
+    /// Set number of bytes per line.
+    pub fn columns(mut self, columns: u64) -> HexConfig {
+        self.options.column_width = columns;
+        self
+    }
+
+    /// Set number of bytes printed together without separating spaces.
+    pub fn group_size(mut self, group_size: u64) -> HexConfig {
+        self.options.group_size = group_size;
+        self
+    }
+
+    /// Set number of bytes between the extra spaces splitting a line into
+    /// blocks, 0 for no blocks.
+    pub fn block_size(mut self, block_size: u64) -> HexConfig {
+        self.options.block_size = block_size;
+        self
+    }
+
+    /// Set number of bytes printed as a single word value.
+    pub fn word_size(mut self, word_size: u64) -> HexConfig {
+        self.options.word_size = word_size;
+        self
+    }
+
+    /// Set byte order of words.
+    pub fn endian(mut self, endian: Endian) -> HexConfig {
+        self.options.endian = endian;
+        self
+    }
+
+    /// Set octet format.
+    pub fn format(mut self, format: Format) -> HexConfig {
+        self.options.format = format;
+        self
+    }
+
+    /// Set formats of further rows printed below each line.
+    pub fn stacked(mut self, stacked: Vec<Format>) -> HexConfig {
+        self.options.stacked = stacked;
+        self
+    }
+
+    /// Enable or disable colorized output.
+    pub fn color(mut self, color: bool) -> HexConfig {
+        self.options.colorize = color;
+        self
+    }
+
+    /// Set how colors are assigned to bytes.
+    pub fn color_mode(mut self, color_mode: ColorMode) -> HexConfig {
+        self.options.color_mode = color_mode;
+        self
+    }
+
+    /// Set color theme.
+    pub fn theme(mut self, theme: Theme) -> HexConfig {
+        self.options.theme = theme;
+        self
+    }
+
+    /// Include or omit the format prefix of octets.
+    pub fn prefix(mut self, prefix: bool) -> HexConfig {
+        self.options.prefix = prefix;
+        self
+    }
+
+    /// Set radix of the offset column.
+    pub fn offset_format(mut self, offset_format: OffsetFormat) -> HexConfig {
+        self.options.offset_format = offset_format;
+        self
+    }
+
+    /// Set number of zero padded digits in the offset column.
+    pub fn offset_width(mut self, offset_width: usize) -> HexConfig {
+        self.options.offset_width = offset_width;
+        self
+    }
+
+    /// Set text between the offset column and the octets.
+    pub fn offset_separator(mut self, offset_separator: &str) -> HexConfig {
+        self.options.offset_separator = offset_separator.to_string();
+        self
+    }
+
+    /// Collapse runs of identical lines into a single `*` line.
+    pub fn squeeze(mut self, squeeze: bool) -> HexConfig {
+        self.options.squeeze = squeeze;
+        self
+    }
+
+    /// Set the character set of the text column.
+    pub fn charset(mut self, charset: Charset) -> HexConfig {
+        self.options.charset = charset;
+        self
+    }
+
+    /// Set how unprintable characters are shown in the text column.
+    pub fn ascii_style(mut self, ascii_style: AsciiStyle) -> HexConfig {
+        self.options.ascii_style = ascii_style;
+        self
+    }
+
+    /// Set the text column placeholder for unprintable characters.
+    pub fn nonprintable(mut self, nonprintable: char) -> HexConfig {
+        self.options.nonprintable = nonprintable;
+        self
+    }
+
+    /// Set the text column character for NUL, `None` shows the placeholder.
+    pub fn nul(mut self, nul: Option<char>) -> HexConfig {
+        self.options.nul = nul;
+        self
+    }
+
+    /// Set the text between the octets and the text column.
+    pub fn separator(mut self, separator: &str) -> HexConfig {
+        self.options.separator = separator.to_string();
+        self
+    }
+
+    /// Set whether `|` borders are drawn around the octets and the text column.
+    pub fn frame(mut self, frame: bool) -> HexConfig {
+        self.options.frame = frame;
+        self
+    }
+
+    /// Set whether the octets of each group are printed in reverse, like
+    /// `xxd -e`.
+    pub fn little_endian_groups(mut self, little_endian_groups: bool) -> HexConfig {
+        self.options.little_endian_groups = little_endian_groups;
+        self
+    }
+
+    /// Set whether the text column is wrapped in `|` like `hexdump -C`.
+    pub fn gutter(mut self, gutter: bool) -> HexConfig {
+        self.options.gutter = gutter;
+        self
+    }
+
+    /// Set whether lines are numbered from 1 in a column before the
+    /// offset.
+    pub fn line_numbers(mut self, line_numbers: bool) -> HexConfig {
+        self.options.line_numbers = line_numbers.then_some(0);
+        self
+    }
+
+    /// Set whether the offset of the last byte of each line is repeated
+    /// after the line.
+    pub fn end_offsets(mut self, end_offsets: bool) -> HexConfig {
+        self.options.end_offsets = end_offsets;
+        self
+    }
+
+    /// Set which of the octets and the text column are printed.
+    pub fn panes(mut self, panes: Panes) -> HexConfig {
+        self.options.panes = panes;
+        self
+    }
+
+    /// Set whether a column index ruler is printed above the dump,
+    /// repeated every `n` lines for `Some(n)`, or only once for `Some(0)`.
+    pub fn header(mut self, header: Option<u64>) -> HexConfig {
+        self.options.header = header;
+        self
+    }
+
+    /// Set what is printed after the dump.
+    pub fn summary(mut self, summary: Summary) -> HexConfig {
+        self.options.summary = summary;
+        self
+    }
+
+    /// Set the address added to offsets in the offset column.
+    pub fn base_address(mut self, base_address: u64) -> HexConfig {
+        self.options.base_address = base_address;
+        self
+    }
+
+    /// Set number of bytes to dump, 0 dumps to the end of input.
+    pub fn length(mut self, length: u64) -> HexConfig {
+        self.length = length;
+        self
+    }
+
+    /// Set number of bytes skipped before the dump starts.
+    pub fn seek(mut self, seek: u64) -> HexConfig {
+        self.seek = seek;
+        self
+    }
+
+    /// Rendering options of this configuration.
+    pub fn dump_options(&self) -> DumpOptions {
+        self.options.clone()
+    }
+
+    /// Number of bytes to dump, 0 dumps to the end of input.
+    pub fn truncate_len(&self) -> u64 {
+        self.length
+    }
+
+    /// Number of bytes skipped before the dump starts.
+    pub fn start_offset(&self) -> u64 {
+        self.seek
+    }
+}
+
+impl Default for HexConfig {
+    fn default() -> HexConfig {
+        HexConfig::new()
+    }
+}
+
+/// Defaults read from the config file or the environment, kept as command
+/// line values keyed by long option name, so they go through the same
+/// parsing as the command line, which takes precedence.
+///
+/// ```toml
+/// cols = 16
+/// format = "X"
+/// theme = "solarized-dark"
+/// prefix = false
+/// group-size = 2
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConfigFile {
+    values: HashMap<String, String>,
+}
+
+impl ConfigFile {
+    /// Path of the config file: `$HX_CONFIG`, else `hx/config.toml` in
+    /// `$XDG_CONFIG_HOME`, or in `~/.config` if that is not set.
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("HX_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        let dir = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(dir.join("hx").join("config.toml"))
+    }
+
+    /// Load the config file, empty if there is none.
+    pub fn load() -> Result<ConfigFile, HxError> {
+        let path = match ConfigFile::path() {
+            Some(path) => path,
+            None => return Ok(ConfigFile::default()),
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => ConfigFile::parse(&text)
+                .map_err(|e| HxError::Parse(format!("config file {}: {}", path.display(), e))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(ConfigFile::default()),
+            Err(e) => Err(HxError::Io(io::Error::new(
+                e.kind(),
+                format!("config file {}: {}", path.display(), e),
+            ))),
+        }
+    }
+
+    /// Parse config file text, a table of long option names. Flags take
+    /// booleans; `color` and `prefix` take booleans or 0 and 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - TOML config text.
+    pub fn parse(text: &str) -> Result<ConfigFile, Box<dyn Error>> {
+        let table = match text.parse::<Value>()? {
+            Value::Table(table) => table,
+            _ => return Err("expected a table of options".into()),
+        };
+        let mut values = HashMap::with_capacity(table.len());
+        for (name, value) in table {
+            let value = match (value, name.as_str()) {
+                (Value::Boolean(b), name) if FILE_FLAGS.contains(&name) => b.to_string(),
+                (_, name) if FILE_FLAGS.contains(&name) => {
+                    return Err(format!("{} must be true or false", name).into())
+                }
+                (_, name) if !FILE_OPTIONS.contains(&name) => {
+                    return Err(format!("unknown option: {}", name).into())
+                }
+                (Value::Boolean(b), ARG_CLR | ARG_PFX) => u8::from(b).to_string(),
+                (Value::String(text), _) => text,
+                (Value::Integer(i), _) => i.to_string(),
+                (_, name) => return Err(format!("{} must be a string or an integer", name).into()),
+            };
+            values.insert(name, value);
+        }
+        Ok(ConfigFile { values })
+    }
+
+    /// Defaults from `HX_*` environment variables, named after the long
+    /// options of the config file, e.g. `HX_COLS` or `HX_GROUP_SIZE`.
+    /// Flags take 1, 0, true or false.
+    ///
+    /// # Arguments
+    ///
+    /// * `vars` - environment variables.
+    pub fn from_env(
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<ConfigFile, HxError> {
+        let mut values = HashMap::new();
+        for (var, value) in vars {
+            let name = match var.strip_prefix("HX_") {
+                Some(name) => name.to_lowercase().replace('_', "-"),
+                None => continue,
+            };
+            let value = match (name.as_str(), value.as_str()) {
+                (name, "1" | "true") if FILE_FLAGS.contains(&name) => "true".to_string(),
+                (name, "0" | "false") if FILE_FLAGS.contains(&name) => "false".to_string(),
+                (name, _) if FILE_FLAGS.contains(&name) => {
+                    return Err(HxError::Usage(format!(
+                        "{} must be 1, 0, true or false",
+                        var
+                    )))
+                }
+                (name, _) if FILE_OPTIONS.contains(&name) => value,
+                // HX_CONFIG and unrelated variables
+                _ => continue,
+            };
+            values.insert(name, value);
+        }
+        Ok(ConfigFile { values })
+    }
+
+    /// Defaults of the `--style` preset `name`, which make the dump look
+    /// like the output of another tool.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - style name.
+    pub fn style(name: &str) -> Option<ConfigFile> {
+        let (_, preset) = STYLES.iter().find(|(style, _)| *style == name)?;
+        let values = preset
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        Some(ConfigFile { values })
+    }
+
+    /// These defaults with the `--bits` presets, which show 8 binary
+    /// octets per line like `xxd -b`, set on top.
+    pub fn bits(mut self) -> ConfigFile {
+        for (name, value) in BITS {
+            self.values.insert(name.to_string(), value.to_string());
+        }
+        self
+    }
+
+    /// Value of option `name`, if set.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - long option name.
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.values.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_file_parse() {
+        let file = ConfigFile::parse(
+            "cols = 16\nformat = \"X\"\ntheme = \"tango\"\nprefix = false\ngroup-size = 2\nframe = true\n",
+        )
+        .unwrap();
+        assert_eq!(file.get(ARG_COL).map(String::as_str), Some("16"));
+        assert_eq!(file.get(ARG_FMT).map(String::as_str), Some("X"));
+        assert_eq!(file.get(ARG_THM).map(String::as_str), Some("tango"));
+        assert_eq!(file.get(ARG_PFX).map(String::as_str), Some("0"));
+        assert_eq!(file.get(ARG_GRP).map(String::as_str), Some("2"));
+        assert_eq!(file.get("len"), None);
+        assert_eq!(file.get(ARG_FRM).map(String::as_str), Some("true"));
+        assert_eq!(file.get(ARG_NSQ), None);
+        assert_eq!(ConfigFile::parse("").unwrap(), ConfigFile::default());
+        assert!(ConfigFile::parse("len = 4").is_err());
+        assert!(ConfigFile::parse("frame = 1").is_err());
+        assert!(ConfigFile::parse("cols = [16]").is_err());
+        assert!(ConfigFile::parse("cols = ").is_err());
+    }
+
+    #[test]
+    fn test_config_file_from_env() {
+        let vars = |vars: &[(&str, &str)]| {
+            ConfigFile::from_env(
+                vars.iter()
+                    .map(|(var, value)| (var.to_string(), value.to_string())),
+            )
+        };
+        let env = vars(&[
+            ("HX_COLS", "8"),
+            ("HX_GROUP_SIZE", "2"),
+            ("HX_FRAME", "1"),
+            ("HX_CONFIG", "hx.toml"),
+            ("HOME", "/root"),
+        ])
+        .unwrap();
+        assert_eq!(env.get(ARG_COL).map(String::as_str), Some("8"));
+        assert_eq!(env.get(ARG_GRP).map(String::as_str), Some("2"));
+        assert_eq!(env.get(ARG_FRM).map(String::as_str), Some("true"));
+        assert_eq!(env.get("config"), None);
+        assert_eq!(env.get("home"), None);
+        assert!(vars(&[("HX_FRAME", "yes")]).is_err());
+    }
+
+    #[test]
+    fn test_config_file_style() {
+        let xxd = ConfigFile::style("xxd").unwrap();
+        assert_eq!(xxd.get(ARG_COL).map(String::as_str), Some("16"));
+        assert_eq!(xxd.get(ARG_OFF).map(String::as_str), Some("bare-hex"));
+        assert_eq!(xxd.get(ARG_NSQ).map(String::as_str), Some("true"));
+        let canonical = ConfigFile::style("canonical").unwrap();
+        assert_eq!(canonical.get(ARG_BLK).map(String::as_str), Some("8"));
+        assert_eq!(canonical.get(ARG_GUT).map(String::as_str), Some("true"));
+        assert_eq!(ConfigFile::style("hx"), Some(ConfigFile::default()));
+        assert_eq!(ConfigFile::style("od"), None);
+    }
+
+    #[test]
+    fn test_config_file_bits() {
+        let bits = ConfigFile::style("xxd").unwrap().bits();
+        assert_eq!(bits.get(ARG_FMT).map(String::as_str), Some("b"));
+        assert_eq!(bits.get(ARG_COL).map(String::as_str), Some("8"));
+        assert_eq!(bits.get(ARG_GRP).map(String::as_str), Some("1"));
+        assert_eq!(bits.get(ARG_OFF).map(String::as_str), Some("bare-hex"));
+    }
+}
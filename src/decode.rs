@@ -0,0 +1,186 @@
+//! data inspector, the bytes at an offset decoded as numbers
+use crate::output::Endian;
+use std::io::{self, Write};
+
+/// Number of bytes decoded, enough for the longest LEB128 encoding of a
+/// 64 bit value.
+pub const DECODE_LEN: usize = 10;
+
+/// Width of the little endian column.
+const VALUE_WIDTH: usize = 26;
+
+/// Decode an unsigned LEB128 varint, as used by protobuf, DWARF and
+/// WebAssembly. Returns the value and the number of bytes it takes, or
+/// `None` if `bytes` ends inside it or it does not fit 64 bits.
+///
+/// # Arguments
+///
+/// * `bytes` - bytes starting with the varint.
+pub fn uleb128(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value: u64 = 0;
+    for (i, b) in bytes.iter().enumerate().take(DECODE_LEN) {
+        let low = u64::from(b & 0x7f);
+        if i == DECODE_LEN - 1 && low > 1 {
+            return None;
+        }
+        value |= low << (7 * i);
+        if b & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Decode a signed LEB128 varint. Returns the value and the number of
+/// bytes it takes, or `None` if `bytes` ends inside it.
+///
+/// # Arguments
+///
+/// * `bytes` - bytes starting with the varint.
+pub fn sleb128(bytes: &[u8]) -> Option<(i64, usize)> {
+    let mut value: i64 = 0;
+    for (i, b) in bytes.iter().enumerate().take(DECODE_LEN) {
+        let shift = 7 * i as u32;
+        value |= i64::from(b & 0x7f).checked_shl(shift).unwrap_or(0);
+        if b & 0x80 == 0 {
+            // sign extend from the last bit read
+            if shift + 7 < 64 && b & 0x40 != 0 {
+                value |= -1 << (shift + 7);
+            }
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// UTC date and time of a UNIX timestamp, like `1970-01-01 00:00:00 UTC`.
+///
+/// # Arguments
+///
+/// * `secs` - seconds since the UNIX epoch.
+pub fn unix_time(secs: i64) -> String {
+    format!("{} UTC", date_time(secs))
+}
+
+/// UTC date and time of a UNIX timestamp without the zone, like
+/// `1970-01-01 00:00:00`.
+///
+/// # Arguments
+///
+/// * `secs` - seconds since the UNIX epoch.
+pub fn date_time(secs: i64) -> String {
+    let days = secs.div_euclid(86400);
+    let time = secs.rem_euclid(86400);
+    // civil date from days since the epoch, after Howard Hinnant's
+    // days_from_civil algorithm
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Write the bytes at `offset` decoded as integers and floats in both
+/// byte orders, as a UNIX timestamp and as LEB128 varints, like the
+/// data inspector of a GUI hex editor. Types longer than `bytes` are
+/// left out.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `offset` - offset of the bytes, as printed in the offset column.
+/// * `bytes` - at most `DECODE_LEN` bytes from the offset.
+pub fn write_report(w: &mut impl Write, offset: &str, bytes: &[u8]) -> io::Result<()> {
+    writeln!(w, "  offset: {}", offset)?;
+    writeln!(w, "  endian: {:<1$}be", "le", VALUE_WIDTH)?;
+    let mut row = |label: &str, size: usize, decode: &dyn Fn(u64) -> String| {
+        if bytes.len() < size {
+            return Ok(());
+        }
+        let le = decode(Endian::Little.word(&bytes[..size]));
+        match size {
+            1 => writeln!(w, "{:>8}: {}", label, le),
+            _ => {
+                let be = decode(Endian::Big.word(&bytes[..size]));
+                writeln!(w, "{:>8}: {:<3$}{}", label, le, be, VALUE_WIDTH)
+            }
+        }
+    };
+    row("u8", 1, &|word| word.to_string())?;
+    row("i8", 1, &|word| (word as i8).to_string())?;
+    row("u16", 2, &|word| word.to_string())?;
+    row("i16", 2, &|word| (word as i16).to_string())?;
+    row("u32", 4, &|word| word.to_string())?;
+    row("i32", 4, &|word| (word as i32).to_string())?;
+    row("u64", 8, &|word| word.to_string())?;
+    row("i64", 8, &|word| (word as i64).to_string())?;
+    // the debug format switches to an exponent for very large and
+    // small values
+    row("f32", 4, &|word| {
+        format!("{:?}", f32::from_bits(word as u32))
+    })?;
+    row("f64", 8, &|word| format!("{:?}", f64::from_bits(word)))?;
+    row("unix32", 4, &|word| unix_time(word as i64))?;
+    row("unix64", 8, &|word| match word as i64 {
+        // beyond year 9999 the date is not meaningful
+        secs if (-62167219200..253402300800).contains(&secs) => unix_time(secs),
+        _ => "-".to_string(),
+    })?;
+    let plural = |len: usize| if len == 1 { "byte" } else { "bytes" };
+    if let Some((value, len)) = uleb128(bytes) {
+        writeln!(w, " uleb128: {} ({} {})", value, len, plural(len))?;
+    }
+    if let Some((value, len)) = sleb128(bytes) {
+        writeln!(w, " sleb128: {} ({} {})", value, len, plural(len))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leb128() {
+        assert_eq!(uleb128(&[0x02]), Some((2, 1)));
+        assert_eq!(uleb128(&[0xe5, 0x8e, 0x26, 0xff]), Some((624485, 3)));
+        assert_eq!(uleb128(&[0x80]), None);
+        let max = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        assert_eq!(uleb128(&max), Some((u64::MAX, 10)));
+        assert_eq!(uleb128(&[0xff; 10]), None);
+        assert_eq!(sleb128(&[0x7f]), Some((-1, 1)));
+        assert_eq!(sleb128(&[0xc0, 0xbb, 0x78]), Some((-123456, 3)));
+        assert_eq!(sleb128(&[0x3f]), Some((63, 1)));
+    }
+
+    #[test]
+    fn test_unix_time() {
+        assert_eq!(unix_time(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(unix_time(951782400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(unix_time(u32::MAX as i64), "2106-02-07 06:28:15 UTC");
+        assert_eq!(unix_time(-1), "1969-12-31 23:59:59 UTC");
+    }
+
+    #[test]
+    fn test_write_report() {
+        let mut out: Vec<u8> = Vec::new();
+        write_report(&mut out, "0x000002", &[0xff, 0x01, 0x00]).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "  offset: 0x000002\n  endian: le                        be\n      u8: 255\n      i8: -1\n     u16: 511                       65281\n     i16: 511                       -255\n uleb128: 255 (2 bytes)\n sleb128: 255 (2 bytes)\n"
+        );
+    }
+}
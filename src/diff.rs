@@ -0,0 +1,288 @@
+//! side by side comparison of two inputs
+use crate::buffer::{read_line, Line};
+use crate::output::{
+    print_octets, print_offset, print_padding, print_trailer, DumpOptions, Highlight,
+};
+use std::io::{self, Read, Write};
+use std::ops::Range;
+
+/// Number of bytes of each input compared at a time by `--cmp`.
+const CMP_CHUNK: u64 = 65536;
+
+/// Walk two inputs in lockstep and print the lines where they differ side
+/// by side, highlighting the differing bytes. Returns the number of
+/// differing lines.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `left` - first input.
+/// * `right` - second input.
+/// * `truncate_len` - truncate both inputs to length, 0 reads to the end.
+/// * `options` - dump options.
+pub fn output_diff(
+    w: &mut impl Write,
+    left: &mut dyn Read,
+    right: &mut dyn Read,
+    truncate_len: u64,
+    options: &DumpOptions,
+) -> io::Result<u64> {
+    let limit = if truncate_len > 0 {
+        truncate_len
+    } else {
+        u64::MAX
+    };
+    let mut left = left.take(limit);
+    let mut right = right.take(limit);
+    let mut offset_counter: u64 = 0x0;
+    let mut left_bytes: u64 = 0x0;
+    let mut right_bytes: u64 = 0x0;
+    let mut differing: u64 = 0x0;
+    loop {
+        let left_line = read_line(&mut left, options.column_width, offset_counter)?;
+        let right_line = read_line(&mut right, options.column_width, offset_counter)?;
+        if left_line.is_none() && right_line.is_none() {
+            break;
+        }
+        let left_line = left_line.unwrap_or_default();
+        let right_line = right_line.unwrap_or_default();
+        left_bytes += left_line.bytes;
+        right_bytes += right_line.bytes;
+
+        let highlights: Vec<Highlight> = left_line
+            .diff(&right_line)
+            .into_iter()
+            .map(|i| {
+                let position = offset_counter + i as u64;
+                Highlight {
+                    range: position..position + 1,
+                    color: options.theme.highlight,
+                }
+            })
+            .collect();
+        if !highlights.is_empty() {
+            differing += 1;
+            print_diff_line(
+                w,
+                &left_line,
+                &right_line,
+                offset_counter,
+                options,
+                &highlights,
+            )?;
+        }
+        offset_counter += left_line.bytes.max(right_line.bytes);
+    }
+    print_trailer(w, format_args!("{} | {}", left_bytes, right_bytes), options)?;
+    Ok(differing)
+}
+
+/// Compare two inputs byte by byte and print only the ranges where they
+/// differ, one per line with its length, like `cmp` for test scripts.
+/// Bytes past the end of the shorter input are a range of their own.
+/// Returns the number of differing bytes.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `left` - first input.
+/// * `right` - second input.
+/// * `names` - names of the inputs.
+/// * `truncate_len` - truncate both inputs to length, 0 reads to the end.
+/// * `options` - dump options, for the offset format.
+pub fn output_cmp(
+    w: &mut impl Write,
+    left: &mut dyn Read,
+    right: &mut dyn Read,
+    names: [&str; 2],
+    truncate_len: u64,
+    options: &DumpOptions,
+) -> io::Result<u64> {
+    let limit = if truncate_len > 0 {
+        truncate_len
+    } else {
+        u64::MAX
+    };
+    let mut left = left.take(limit);
+    let mut right = right.take(limit);
+    let mut offset_counter: u64 = 0x0;
+    let mut lens: [u64; 2] = [0x0; 2];
+    let mut start: Option<u64> = None;
+    let mut differing: u64 = 0x0;
+    loop {
+        let left_chunk = read_line(&mut left, CMP_CHUNK, offset_counter)?;
+        let right_chunk = read_line(&mut right, CMP_CHUNK, offset_counter)?;
+        if left_chunk.is_none() && right_chunk.is_none() {
+            break;
+        }
+        let left_chunk = left_chunk.unwrap_or_default();
+        let right_chunk = right_chunk.unwrap_or_default();
+        lens[0] += left_chunk.bytes;
+        lens[1] += right_chunk.bytes;
+        let pairs = left_chunk.hex_body.iter().zip(&right_chunk.hex_body);
+        for (i, (l, r)) in pairs.enumerate() {
+            let position = offset_counter + i as u64;
+            match (l != r, start) {
+                (true, None) => start = Some(position),
+                (false, Some(range_start)) => {
+                    print_range(w, range_start..position, "", options)?;
+                    differing += position - range_start;
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        offset_counter += left_chunk.bytes.max(right_chunk.bytes);
+    }
+    // comparing stops at the end of the shorter input
+    let common = lens[0].min(lens[1]);
+    if let Some(range_start) = start {
+        print_range(w, range_start..common, "", options)?;
+        differing += common - range_start;
+    }
+    if lens[0] != lens[1] {
+        let longer = if lens[0] > lens[1] {
+            names[0]
+        } else {
+            names[1]
+        };
+        print_range(
+            w,
+            common..offset_counter,
+            &format!(" only in {}", longer),
+            options,
+        )?;
+        differing += offset_counter - common;
+    }
+    Ok(differing)
+}
+
+/// Print a differing range of `--cmp`.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `range` - differing bytes.
+/// * `note` - appended to the length.
+/// * `options` - dump options, for the offset format.
+fn print_range(
+    w: &mut impl Write,
+    range: Range<u64>,
+    note: &str,
+    options: &DumpOptions,
+) -> io::Result<()> {
+    let offset = |b: u64| {
+        options
+            .offset_format
+            .format(options.base_address.wrapping_add(b), options.offset_width)
+    };
+    let len = range.end - range.start;
+    writeln!(
+        w,
+        "{}: {} {}{}",
+        options.paint(
+            options.theme.offset,
+            &format!("{}..{}", offset(range.start), offset(range.end))
+        ),
+        len,
+        if len == 1 { "byte" } else { "bytes" },
+        note
+    )
+}
+
+/// Print a differing line of both inputs side by side.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `left` - line of the first input.
+/// * `right` - line of the second input.
+/// * `offset` - offset of the first byte in the lines.
+/// * `options` - dump options.
+/// * `highlights` - differing bytes.
+pub fn print_diff_line(
+    w: &mut impl Write,
+    left: &Line,
+    right: &Line,
+    offset: u64,
+    options: &DumpOptions,
+    highlights: &[Highlight],
+) -> io::Result<()> {
+    print_offset(w, offset, options)?;
+    print_octets(w, left, options, highlights)?;
+    if options.frame {
+        // framed text is already padded to the column width
+        write!(w, " ")?;
+    } else {
+        // pad the ascii text of the first input so the second lines up
+        print_padding(w, options.column_width.saturating_sub(left.bytes))?;
+        write!(w, " | ")?;
+    }
+    print_octets(w, right, options, highlights)?;
+    writeln!(w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HexConfig;
+
+    fn test_options() -> DumpOptions {
+        HexConfig::new().columns(4).dump_options()
+    }
+
+    #[test]
+    fn test_output_diff() {
+        let options = test_options();
+        let mut out: Vec<u8> = Vec::new();
+        let differing = output_diff(
+            &mut out,
+            &mut &b"abcdefghij"[..],
+            &mut &b"abcdefgXij!"[..],
+            0,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(differing, 2);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x000004: 0x65 0x66 0x67 0x68 efgh | 0x65 0x66 0x67 0x58 efgX\n\
+             0x000008: 0x69 0x6a           ij   | 0x69 0x6a 0x21      ij!\n   bytes: 10 | 11\n"
+        );
+    }
+
+    #[test]
+    fn test_output_cmp() {
+        let options = DumpOptions {
+            offset_width: 4,
+            ..test_options()
+        };
+        let mut out: Vec<u8> = Vec::new();
+        let differing = output_cmp(
+            &mut out,
+            &mut &b"abcdefghij"[..],
+            &mut &b"aBCdefgXij!!"[..],
+            ["left", "right"],
+            0,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(differing, 5);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x0001..0x0003: 2 bytes\n0x0007..0x0008: 1 byte\n0x000a..0x000c: 2 bytes only in right\n"
+        );
+        let mut out: Vec<u8> = Vec::new();
+        let differing = output_cmp(
+            &mut out,
+            &mut &b"abcX"[..],
+            &mut &b"abcdefghij"[..],
+            ["left", "right"],
+            4,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(differing, 1);
+        assert_eq!(String::from_utf8(out).unwrap(), "0x0003..0x0004: 1 byte\n");
+    }
+}
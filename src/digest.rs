@@ -0,0 +1,440 @@
+//! digests of the dumped bytes
+use std::cell::RefCell;
+use std::io::{self, Read};
+use std::rc::Rc;
+
+/// Digest algorithms accepted by `--digest`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// MD5, RFC 1321
+    Md5,
+    /// SHA-1, FIPS 180-4
+    Sha1,
+    /// SHA-256, FIPS 180-4
+    Sha256,
+    /// CRC-32 as used by zip, gzip and png
+    Crc32,
+}
+
+impl Algorithm {
+    /// Every algorithm, in `--digest` order.
+    pub const ALL: [Algorithm; 4] = [
+        Algorithm::Md5,
+        Algorithm::Sha1,
+        Algorithm::Sha256,
+        Algorithm::Crc32,
+    ];
+
+    /// Name as accepted by `--digest`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "md5",
+            Algorithm::Sha1 => "sha1",
+            Algorithm::Sha256 => "sha256",
+            Algorithm::Crc32 => "crc32",
+        }
+    }
+
+    /// Look up an algorithm by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - algorithm name.
+    pub fn by_name(name: &str) -> Option<Algorithm> {
+        Algorithm::ALL
+            .iter()
+            .find(|algorithm| algorithm.name() == name)
+            .copied()
+    }
+}
+
+const MD5_S: [[u32; 4]; 4] = [
+    [7, 12, 17, 22],
+    [5, 9, 14, 20],
+    [4, 11, 16, 23],
+    [6, 10, 15, 21],
+];
+
+#[rustfmt::skip]
+const MD5_K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee,
+    0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be,
+    0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa,
+    0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed,
+    0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c,
+    0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05,
+    0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039,
+    0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1,
+    0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+#[rustfmt::skip]
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5,
+    0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3,
+    0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc,
+    0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7,
+    0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13,
+    0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3,
+    0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5,
+    0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208,
+    0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// 64 byte blocks of a Merkle–Damgård hash, with the pending partial block.
+#[derive(Copy, Clone, Debug)]
+struct Blocks {
+    buffer: [u8; 64],
+    len: usize,
+    total: u64,
+}
+
+impl Blocks {
+    fn new() -> Blocks {
+        Blocks {
+            buffer: [0; 64],
+            len: 0,
+            total: 0,
+        }
+    }
+
+    fn update(&mut self, mut bytes: &[u8], mut compress: impl FnMut(&[u8; 64])) {
+        self.total += bytes.len() as u64;
+        if self.len > 0 {
+            let take = (64 - self.len).min(bytes.len());
+            self.buffer[self.len..self.len + take].copy_from_slice(&bytes[..take]);
+            self.len += take;
+            bytes = &bytes[take..];
+            if self.len < 64 {
+                return;
+            }
+            compress(&self.buffer);
+            self.len = 0;
+        }
+        let mut blocks = bytes.chunks_exact(64);
+        for block in &mut blocks {
+            compress(block.try_into().unwrap());
+        }
+        let rest = blocks.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.len = rest.len();
+    }
+
+    /// Pad with 0x80, zeros and the message length in bits.
+    fn finish(mut self, little_endian: bool, mut compress: impl FnMut(&[u8; 64])) {
+        let bits = self.total.wrapping_mul(8);
+        self.update(&[0x80], &mut compress);
+        while self.len != 56 {
+            self.update(&[0x00], &mut compress);
+        }
+        let length = if little_endian {
+            bits.to_le_bytes()
+        } else {
+            bits.to_be_bytes()
+        };
+        self.update(&length, &mut compress);
+    }
+}
+
+fn md5_compress(state: &mut [u32; 4], block: &[u8; 64]) {
+    let mut m = [0u32; 16];
+    for (word, bytes) in m.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+    let [mut a, mut b, mut c, mut d] = *state;
+    for i in 0..64 {
+        let (f, g) = match i / 16 {
+            0 => ((b & c) | (!b & d), i),
+            1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+            2 => (b ^ c ^ d, (3 * i + 5) % 16),
+            _ => (c ^ (b | !d), (7 * i) % 16),
+        };
+        let f = f.wrapping_add(a).wrapping_add(MD5_K[i]).wrapping_add(m[g]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(f.rotate_left(MD5_S[i / 16][i % 4]));
+    }
+    for (h, v) in state.iter_mut().zip([a, b, c, d]) {
+        *h = h.wrapping_add(v);
+    }
+}
+
+fn sha1_compress(state: &mut [u32; 5], block: &[u8; 64]) {
+    let mut w = [0u32; 80];
+    for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes(bytes.try_into().unwrap());
+    }
+    for i in 16..80 {
+        w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+    }
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+    for (i, word) in w.iter().enumerate() {
+        let (f, k) = match i / 20 {
+            0 => ((b & c) | (!b & d), 0x5a827999),
+            1 => (b ^ c ^ d, 0x6ed9eba1),
+            2 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+            _ => (b ^ c ^ d, 0xca62c1d6),
+        };
+        let t = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(*word);
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = t;
+    }
+    for (h, v) in state.iter_mut().zip([a, b, c, d, e]) {
+        *h = h.wrapping_add(v);
+    }
+}
+
+fn sha256_compress(state: &mut [u32; 8], block: &[u8; 64]) {
+    let mut w = [0u32; 64];
+    for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes(bytes.try_into().unwrap());
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (k, word) in SHA256_K.iter().zip(w.iter()) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(*k)
+            .wrapping_add(*word);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+    for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *s = s.wrapping_add(v);
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+enum State {
+    Md5(Blocks, [u32; 4]),
+    Sha1(Blocks, [u32; 5]),
+    Sha256(Blocks, [u32; 8]),
+    Crc32(u32),
+}
+
+/// Incremental digest of a byte stream.
+#[derive(Copy, Clone, Debug)]
+pub struct Digest {
+    algorithm: Algorithm,
+    state: State,
+}
+
+impl Digest {
+    /// Digest constructor.
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm` - digest algorithm.
+    pub fn new(algorithm: Algorithm) -> Digest {
+        let state = match algorithm {
+            Algorithm::Md5 => State::Md5(
+                Blocks::new(),
+                [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
+            ),
+            Algorithm::Sha1 => State::Sha1(
+                Blocks::new(),
+                [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0],
+            ),
+            Algorithm::Sha256 => State::Sha256(
+                Blocks::new(),
+                [
+                    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c,
+                    0x1f83d9ab, 0x5be0cd19,
+                ],
+            ),
+            Algorithm::Crc32 => State::Crc32(0xffffffff),
+        };
+        Digest { algorithm, state }
+    }
+
+    /// Algorithm of this digest.
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Hash `bytes`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - next bytes of the input.
+    pub fn update(&mut self, bytes: &[u8]) {
+        match &mut self.state {
+            State::Md5(blocks, state) => blocks.update(bytes, |block| md5_compress(state, block)),
+            State::Sha1(blocks, state) => blocks.update(bytes, |block| sha1_compress(state, block)),
+            State::Sha256(blocks, state) => {
+                blocks.update(bytes, |block| sha256_compress(state, block))
+            }
+            State::Crc32(crc) => {
+                for b in bytes {
+                    *crc ^= u32::from(*b);
+                    for _ in 0..8 {
+                        *crc = if *crc & 1 == 1 {
+                            (*crc >> 1) ^ 0xedb88320
+                        } else {
+                            *crc >> 1
+                        };
+                    }
+                }
+            }
+        }
+    }
+
+    /// Digest of the bytes hashed so far. Hashing may continue afterwards.
+    pub fn finish(&self) -> Vec<u8> {
+        match self.state {
+            State::Md5(blocks, mut state) => {
+                blocks.finish(true, |block| md5_compress(&mut state, block));
+                state.iter().flat_map(|word| word.to_le_bytes()).collect()
+            }
+            State::Sha1(blocks, mut state) => {
+                blocks.finish(false, |block| sha1_compress(&mut state, block));
+                state.iter().flat_map(|word| word.to_be_bytes()).collect()
+            }
+            State::Sha256(blocks, mut state) => {
+                blocks.finish(false, |block| sha256_compress(&mut state, block));
+                state.iter().flat_map(|word| word.to_be_bytes()).collect()
+            }
+            State::Crc32(crc) => (!crc).to_be_bytes().to_vec(),
+        }
+    }
+
+    /// Digest of the bytes hashed so far as a lowercase hex string.
+    pub fn hex(&self) -> String {
+        self.finish().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Reader that hashes every byte read through it, so digests cover
+/// exactly the bytes that were dumped.
+#[derive(Debug)]
+pub struct Hashed<R: Read> {
+    inner: R,
+    digests: Rc<RefCell<Vec<Digest>>>,
+}
+
+impl<R: Read> Hashed<R> {
+    /// Hashed constructor.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - reader to be hashed.
+    /// * `digests` - digests fed with the bytes read.
+    pub fn new(inner: R, digests: Rc<RefCell<Vec<Digest>>>) -> Hashed<R> {
+        Hashed { inner, digests }
+    }
+}
+
+impl<R: Read> Read for Hashed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        for digest in self.digests.borrow_mut().iter_mut() {
+            digest.update(&buf[..n]);
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(algorithm: Algorithm, input: &[u8]) -> String {
+        let mut digest = Digest::new(algorithm);
+        digest.update(input);
+        digest.hex()
+    }
+
+    #[test]
+    fn test_digest_vectors() {
+        assert_eq!(hex(Algorithm::Md5, b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(
+            hex(Algorithm::Md5, b"abc"),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            hex(Algorithm::Sha1, b"abc"),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hex(Algorithm::Sha256, b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(Algorithm::Sha256, b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(hex(Algorithm::Crc32, b"123456789"), "cbf43926");
+    }
+
+    #[test]
+    fn test_digest_streams_across_blocks() {
+        // 1000 bytes fed unevenly must match a single update
+        let input: Vec<u8> = (0..1000).map(|i| (i * 7) as u8).collect();
+        for algorithm in Algorithm::ALL {
+            let mut streamed = Digest::new(algorithm);
+            for chunk in input.chunks(37) {
+                streamed.update(chunk);
+            }
+            assert_eq!(streamed.hex(), hex(algorithm, &input), "{:?}", algorithm);
+        }
+        assert_eq!(
+            hex(Algorithm::Sha256, &[b'a'; 1000]),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+    }
+
+    #[test]
+    fn test_hashed_reader() {
+        let digests = Rc::new(RefCell::new(vec![Digest::new(Algorithm::Crc32)]));
+        let mut sink: Vec<u8> = Vec::new();
+        io::copy(
+            &mut Hashed::new(&b"123456789"[..], digests.clone()),
+            &mut sink,
+        )
+        .unwrap();
+        assert_eq!(digests.borrow()[0].hex(), "cbf43926");
+        assert_eq!(Algorithm::by_name("sha1"), Some(Algorithm::Sha1));
+    }
+}
@@ -0,0 +1,247 @@
+//! disassembly of dumped bytes, shown as a third column through objdump
+use crate::buffer::{Line, PageReader};
+use crate::output::{print_octets, print_offset, DumpOptions};
+use std::io::{self, Read, Write};
+
+/// objdump run when `HX_OBJDUMP` is not set.
+#[cfg(feature = "disasm")]
+const DEFAULT_OBJDUMP: &str = "objdump";
+
+/// Instruction set the bytes are disassembled as, selected by `--disasm`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arch {
+    /// x86_64, in Intel syntax
+    X86_64,
+    /// 64 bit ARM
+    Aarch64,
+    /// 64 bit RISC-V
+    Riscv,
+}
+
+impl Arch {
+    /// Architecture with the given name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name as given to `--disasm`.
+    pub fn by_name(name: &str) -> Option<Arch> {
+        match name {
+            "x86_64" => Some(Arch::X86_64),
+            "aarch64" => Some(Arch::Aarch64),
+            "riscv" => Some(Arch::Riscv),
+            _ => None,
+        }
+    }
+
+    /// objdump `-m` machine of the architecture.
+    pub fn machine(&self) -> &'static str {
+        match self {
+            Arch::X86_64 => "i386:x86-64",
+            Arch::Aarch64 => "aarch64",
+            Arch::Riscv => "riscv:rv64",
+        }
+    }
+}
+
+/// A disassembled instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    /// offset of the first byte of the instruction
+    pub offset: u64,
+    /// mnemonic and operands
+    pub text: String,
+}
+
+/// Instructions listed by `objdump -D`, offset by `start_offset`. Lines
+/// that are not instructions, such as headers and the continued bytes of
+/// a long instruction, are skipped.
+///
+/// # Arguments
+///
+/// * `listing` - objdump output.
+/// * `start_offset` - offset of the first disassembled byte in the input.
+pub fn parse_objdump(listing: &str, start_offset: u64) -> Vec<Instruction> {
+    listing
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let offset = fields.next()?.trim().strip_suffix(':')?;
+            let offset = u64::from_str_radix(offset, 16).ok()?;
+            let text = fields.nth(1)?.split_whitespace().collect::<Vec<_>>();
+            (!text.is_empty()).then(|| Instruction {
+                offset: start_offset + offset,
+                text: text.join(" "),
+            })
+        })
+        .collect()
+}
+
+/// Disassemble `bytes` as `arch` with `HX_OBJDUMP`, or `objdump`.
+///
+/// # Arguments
+///
+/// * `bytes` - code to be disassembled.
+/// * `arch` - instruction set.
+/// * `start_offset` - offset of `bytes` in the input.
+#[cfg(feature = "disasm")]
+pub fn disassemble(bytes: &[u8], arch: Arch, start_offset: u64) -> io::Result<Vec<Instruction>> {
+    use std::process::Command;
+
+    let objdump = std::env::var("HX_OBJDUMP")
+        .ok()
+        .filter(|objdump| !objdump.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_OBJDUMP.to_string());
+    // objdump reads raw binaries from a file, not a pipe
+    let path = std::env::temp_dir().join(format!("hx-disasm-{}.bin", std::process::id()));
+    std::fs::write(&path, bytes)?;
+    let mut words = objdump.split_whitespace();
+    let output = Command::new(words.next().unwrap_or(DEFAULT_OBJDUMP))
+        .args(words)
+        .args(["-D", "-b", "binary", "-m", arch.machine()])
+        .args(matches!(arch, Arch::X86_64).then_some("-Mintel"))
+        .arg(&path)
+        .output();
+    let _ = std::fs::remove_file(&path);
+    let output = output
+        .map_err(|e| io::Error::new(e.kind(), format!("--disasm runs {}: {}", objdump, e)))?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{} cannot disassemble {:?}: {}",
+            objdump,
+            arch,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(parse_objdump(
+        &String::from_utf8_lossy(&output.stdout),
+        start_offset,
+    ))
+}
+
+/// Disassemble `bytes` as `arch` with `HX_OBJDUMP`, or `objdump`.
+///
+/// # Arguments
+///
+/// * `bytes` - code to be disassembled.
+/// * `arch` - instruction set.
+/// * `start_offset` - offset of `bytes` in the input.
+#[cfg(not(feature = "disasm"))]
+pub fn disassemble(_bytes: &[u8], _arch: Arch, _start_offset: u64) -> io::Result<Vec<Instruction>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "--disasm requires hx to be built with the disasm feature",
+    ))
+}
+
+/// Number of characters `print_octets` prints for `line`.
+///
+/// # Arguments
+///
+/// * `line` - line to be measured.
+/// * `options` - dump options.
+fn octets_width(line: &Line, options: &DumpOptions) -> io::Result<usize> {
+    let options = DumpOptions {
+        colorize: false,
+        ..options.clone()
+    };
+    let mut rendered: Vec<u8> = Vec::new();
+    print_octets(&mut rendered, line, &options, &[])?;
+    Ok(String::from_utf8_lossy(&rendered).chars().count())
+}
+
+/// Dump `buf` with the instructions starting on each line as a third
+/// column. The input is read whole, since instructions cross lines.
+/// Returns the number of bytes dumped.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `buf` - input to be dumped.
+/// * `start_offset` - offset of the first byte of `buf` in the input.
+/// * `truncate_len` - truncate to length, 0 reads to the end of input.
+/// * `arch` - instruction set.
+/// * `options` - dump options.
+pub fn output_disasm(
+    w: &mut impl Write,
+    buf: &mut dyn Read,
+    start_offset: u64,
+    truncate_len: u64,
+    arch: Arch,
+    options: &DumpOptions,
+) -> io::Result<u64> {
+    let mut bytes: Vec<u8> = Vec::new();
+    match truncate_len {
+        0 => buf.read_to_end(&mut bytes)?,
+        len => buf.take(len).read_to_end(&mut bytes)?,
+    };
+    let instructions = disassemble(&bytes, arch, start_offset)?;
+    let mut next = instructions.iter().peekable();
+    for line in PageReader::new(&bytes[..], options.column_width, start_offset, 0) {
+        let line = line?;
+        print_offset(w, line.offset, options)?;
+        print_octets(w, &line, options, &[])?;
+        let end = line.offset + line.bytes;
+        let mut column: Vec<&str> = Vec::new();
+        while let Some(instruction) = next.next_if(|instruction| instruction.offset < end) {
+            column.push(&instruction.text);
+        }
+        if !column.is_empty() {
+            // line up the column after a short last line
+            let mut full = line.clone();
+            full.hex_body.resize(options.column_width as usize, b' ');
+            full.bytes = options.column_width;
+            let padding = octets_width(&full, options)? - octets_width(&line, options)?;
+            write!(
+                w,
+                "{:padding$}  {}",
+                "",
+                column.join("; "),
+                padding = padding
+            )?;
+        }
+        writeln!(w)?;
+    }
+    Ok(bytes.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_objdump() {
+        let listing = "\ncode.bin:     file format binary\n\n\nDisassembly of section .data:\n\n\
+                       0000000000000000 <.data>:\n   \
+                       0:\tb8 01 00 00 00       \tmov    eax,0x1\n   \
+                       5:\t48 b8 88 77 66 55 44 \tmovabs rax,0x1122334455667788\n   \
+                       c:\t33 22 11 \n   \
+                       f:\tc3                   \tret\n";
+        assert_eq!(
+            parse_objdump(listing, 0x100),
+            vec![
+                Instruction {
+                    offset: 0x100,
+                    text: "mov eax,0x1".to_string()
+                },
+                Instruction {
+                    offset: 0x105,
+                    text: "movabs rax,0x1122334455667788".to_string()
+                },
+                Instruction {
+                    offset: 0x10f,
+                    text: "ret".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arch_by_name() {
+        assert_eq!(Arch::by_name("x86_64"), Some(Arch::X86_64));
+        assert_eq!(
+            Arch::by_name("riscv").map(|arch| arch.machine()),
+            Some("riscv:rv64")
+        );
+        assert_eq!(Arch::by_name("mips"), None);
+    }
+}
@@ -0,0 +1,707 @@
+//! streaming decoders for encoded or compressed input
+use crate::digest::{Algorithm, Digest};
+use std::io::{self, BufRead, BufReader, Read};
+
+/// Size of the DEFLATE history window.
+const WINDOW: usize = 32768;
+
+/// Number of bytes inflated before they are handed to the reader.
+const CHUNK: usize = 16384;
+
+/// Base lengths of the DEFLATE length codes 257 to 285.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+
+/// Extra bits of the DEFLATE length codes 257 to 285.
+const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Base distances of the DEFLATE distance codes.
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+
+/// Extra bits of the DEFLATE distance codes.
+const DIST_EXTRA: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Order of the code length code lengths in a dynamic block header.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Encoding of the input, selected by `--input-encoding`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// base64 text, standard or URL safe alphabet
+    Base64,
+    /// hex digit pairs
+    Hex,
+    /// gzip compressed, RFC 1952
+    Gzip,
+    /// zlib compressed, RFC 1950
+    Zlib,
+}
+
+impl Encoding {
+    /// All encodings, in the order they are listed in the help.
+    pub const ALL: [Encoding; 4] = [
+        Encoding::Base64,
+        Encoding::Hex,
+        Encoding::Gzip,
+        Encoding::Zlib,
+    ];
+
+    /// Name of the encoding as given to `--input-encoding`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Base64 => "base64",
+            Encoding::Hex => "hex",
+            Encoding::Gzip => "gzip",
+            Encoding::Zlib => "zlib",
+        }
+    }
+
+    /// Encoding with the given name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name as given to `--input-encoding`.
+    pub fn by_name(name: &str) -> Option<Encoding> {
+        Encoding::ALL.into_iter().find(|e| e.name() == name)
+    }
+}
+
+/// Wrap `inner` in a reader that decodes `encoding` as it is read, or
+/// return it as is if there is no encoding. Malformed input is an
+/// `InvalidData` error.
+///
+/// # Arguments
+///
+/// * `encoding` - encoding of `inner`.
+/// * `inner` - encoded input.
+pub fn decode(encoding: Option<Encoding>, inner: impl BufRead + 'static) -> Box<dyn BufRead> {
+    match encoding {
+        None => Box::new(inner),
+        Some(encoding @ (Encoding::Base64 | Encoding::Hex)) => {
+            Box::new(BufReader::new(Text::new(inner, encoding)))
+        }
+        Some(encoding) => Box::new(BufReader::new(Inflate::new(inner, encoding))),
+    }
+}
+
+/// `InvalidData` error with `message`.
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Decoder of base64 or hex text. Every input character yields at most
+/// one byte, so decoding never has to hold back output.
+struct Text<R: BufRead> {
+    inner: R,
+    encoding: Encoding,
+    /// decoded bits not yet making up a byte
+    bits: u32,
+    count: u32,
+    /// base64 padding seen, only more padding may follow
+    padded: bool,
+    /// offset of the next input character, for errors
+    offset: u64,
+}
+
+impl<R: BufRead> Text<R> {
+    fn new(inner: R, encoding: Encoding) -> Text<R> {
+        Text {
+            inner,
+            encoding,
+            bits: 0,
+            count: 0,
+            padded: false,
+            offset: 0,
+        }
+    }
+
+    /// Number of bits each input digit carries.
+    fn width(&self) -> u32 {
+        match self.encoding {
+            Encoding::Base64 => 6,
+            _ => 4,
+        }
+    }
+
+    /// Value of the digit `c`, `None` for whitespace and padding.
+    fn digit(&mut self, c: u8) -> io::Result<Option<u32>> {
+        let value = match (self.encoding, c) {
+            (_, c) if c.is_ascii_whitespace() => return Ok(None),
+            (Encoding::Base64, b'=') => {
+                self.padded = true;
+                return Ok(None);
+            }
+            (Encoding::Base64, b'A'..=b'Z') => Some(c - b'A'),
+            (Encoding::Base64, b'a'..=b'z') => Some(c - b'a' + 26),
+            (Encoding::Base64, b'0'..=b'9') => Some(c - b'0' + 52),
+            (Encoding::Base64, b'+' | b'-') => Some(62),
+            (Encoding::Base64, b'/' | b'_') => Some(63),
+            (_, c) => (c as char).to_digit(16).map(|d| d as u8),
+        };
+        match value {
+            Some(value) if !self.padded => Ok(Some(u32::from(value))),
+            _ => Err(invalid(format!(
+                "invalid {} input: unexpected {:?} at offset 0x{:x}",
+                self.encoding.name(),
+                c as char,
+                self.offset
+            ))),
+        }
+    }
+}
+
+impl<R: BufRead> Read for Text<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+        while n == 0 && !buf.is_empty() {
+            let input = {
+                let input = self.inner.fill_buf()?;
+                input[..input.len().min(buf.len())].to_vec()
+            };
+            if input.is_empty() {
+                // a trailing digit that does not complete a byte
+                if self.count >= self.width() {
+                    return Err(invalid(format!(
+                        "invalid {} input: truncated at offset 0x{:x}",
+                        self.encoding.name(),
+                        self.offset
+                    )));
+                }
+                return Ok(0);
+            }
+            for &c in &input {
+                if let Some(value) = self.digit(c)? {
+                    self.bits = (self.bits << self.width()) | value;
+                    self.count += self.width();
+                    if self.count >= 8 {
+                        self.count -= 8;
+                        buf[n] = (self.bits >> self.count) as u8;
+                        self.bits &= (1 << self.count) - 1;
+                        n += 1;
+                    }
+                }
+                self.offset += 1;
+            }
+            self.inner.consume(input.len());
+        }
+        Ok(n)
+    }
+}
+
+/// Canonical Huffman code, as the number of codes of each length and
+/// the symbols ordered by code.
+#[derive(Default)]
+struct Huffman {
+    count: [u16; 16],
+    symbol: Vec<u16>,
+}
+
+impl Huffman {
+    /// Huffman code from the code length of every symbol, 0 for unused
+    /// symbols. Incomplete codes are allowed, a lone distance code is one.
+    fn new(lengths: &[u8]) -> io::Result<Huffman> {
+        let mut count = [0u16; 16];
+        for &len in lengths {
+            count[len as usize] += 1;
+        }
+        let mut left: i32 = 1;
+        for &count in &count[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err(invalid(
+                    "invalid deflate data: over-subscribed code".to_string(),
+                ));
+            }
+        }
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + count[len];
+        }
+        let mut symbol = vec![0u16; lengths.len()];
+        for (sym, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbol[offsets[len as usize] as usize] = sym as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Ok(Huffman { count, symbol })
+    }
+
+    /// Fixed literal/length and distance codes of block type 1.
+    fn fixed() -> (Huffman, Huffman) {
+        let mut lengths = [8u8; 288];
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        // neither code can be over-subscribed
+        (
+            Huffman::new(&lengths).unwrap_or_default(),
+            Huffman::new(&[5; 30]).unwrap_or_default(),
+        )
+    }
+}
+
+/// Bit reader over the compressed input, least significant bit first.
+struct Bits<R: BufRead> {
+    inner: R,
+    buf: u32,
+    count: u32,
+}
+
+impl<R: BufRead> Bits<R> {
+    fn byte(&mut self) -> io::Result<u8> {
+        let byte = self.inner.fill_buf()?.first().copied();
+        match byte {
+            Some(byte) => {
+                self.inner.consume(1);
+                Ok(byte)
+            }
+            None => Err(invalid("compressed input is truncated".to_string())),
+        }
+    }
+
+    fn bits(&mut self, need: u32) -> io::Result<u32> {
+        let mut value = self.buf;
+        while self.count < need {
+            value |= u32::from(self.byte()?) << self.count;
+            self.count += 8;
+        }
+        self.buf = value >> need;
+        self.count -= need;
+        Ok(value & ((1 << need) - 1))
+    }
+
+    /// Drop the bits left in the current byte.
+    fn align(&mut self) {
+        self.buf = 0;
+        self.count = 0;
+    }
+
+    /// Little endian `u32` of the next four bytes.
+    fn u32_le(&mut self) -> io::Result<u32> {
+        Ok(self.bits(16)? | self.bits(16)? << 16)
+    }
+
+    fn decode(&mut self, code: &Huffman) -> io::Result<usize> {
+        let (mut value, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            value |= self.bits(1)? as i32;
+            let count = i32::from(code.count[len]);
+            if value - count < first {
+                return Ok(code.symbol[(index + value - first) as usize] as usize);
+            }
+            index += count;
+            first = (first + count) << 1;
+            value <<= 1;
+        }
+        Err(invalid("invalid deflate data: bad code".to_string()))
+    }
+}
+
+/// Position in the compressed stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Header,
+    Block,
+    Stored(usize),
+    Codes,
+    Trailer,
+    Done,
+}
+
+/// DEFLATE decoder, RFC 1951, of a gzip or zlib stream. Output is
+/// produced a chunk at a time and only the 32 KiB window is kept.
+struct Inflate<R: BufRead> {
+    bits: Bits<R>,
+    container: Encoding,
+    state: State,
+    last: bool,
+    lit: Huffman,
+    dist: Huffman,
+    window: Vec<u8>,
+    /// bytes inflated from the current member
+    written: u64,
+    out: Vec<u8>,
+    pos: usize,
+    crc: Digest,
+    adler: u32,
+}
+
+impl<R: BufRead> Inflate<R> {
+    fn new(inner: R, container: Encoding) -> Inflate<R> {
+        Inflate {
+            bits: Bits {
+                inner,
+                buf: 0,
+                count: 0,
+            },
+            container,
+            state: State::Header,
+            last: false,
+            lit: Huffman::default(),
+            dist: Huffman::default(),
+            window: vec![0; WINDOW],
+            written: 0,
+            out: Vec::with_capacity(CHUNK + 258),
+            pos: 0,
+            crc: Digest::new(Algorithm::Crc32),
+            adler: 1,
+        }
+    }
+
+    fn emit(&mut self, byte: u8) {
+        self.out.push(byte);
+        self.window[(self.written % WINDOW as u64) as usize] = byte;
+        self.written += 1;
+    }
+
+    /// Error for a malformed stream, naming the container.
+    fn invalid(&self, message: &str) -> io::Error {
+        invalid(format!(
+            "invalid {} data: {}",
+            self.container.name(),
+            message
+        ))
+    }
+
+    fn header(&mut self) -> io::Result<()> {
+        self.last = false;
+        self.written = 0;
+        self.crc = Digest::new(Algorithm::Crc32);
+        self.adler = 1;
+        match self.container {
+            Encoding::Gzip => {
+                for magic in [0x1f, 0x8b, 8] {
+                    if self.bits.byte()? != magic {
+                        return Err(self.invalid("bad header"));
+                    }
+                }
+                let flags = self.bits.byte()?;
+                // modification time, extra flags and OS
+                for _ in 0..6 {
+                    self.bits.byte()?;
+                }
+                if flags & 0x04 != 0 {
+                    for _ in 0..self.bits.bits(16)? {
+                        self.bits.byte()?;
+                    }
+                }
+                // file name and comment, zero terminated
+                for flag in [0x08, 0x10] {
+                    while flags & flag != 0 && self.bits.byte()? != 0 {}
+                }
+                if flags & 0x02 != 0 {
+                    self.bits.bits(16)?;
+                }
+            }
+            _ => {
+                let cmf = self.bits.byte()?;
+                let flg = self.bits.byte()?;
+                if cmf & 0x0f != 8
+                    || cmf >> 4 > 7
+                    || (u16::from(cmf) << 8 | u16::from(flg)) % 31 != 0
+                {
+                    return Err(self.invalid("bad header"));
+                }
+                if flg & 0x20 != 0 {
+                    return Err(self.invalid("preset dictionaries are not supported"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn block(&mut self) -> io::Result<State> {
+        if self.last {
+            return Ok(State::Trailer);
+        }
+        self.last = self.bits.bits(1)? == 1;
+        match self.bits.bits(2)? {
+            0 => {
+                self.bits.align();
+                let len = self.bits.bits(16)?;
+                if self.bits.bits(16)? != !len & 0xffff {
+                    return Err(self.invalid("stored block length mismatch"));
+                }
+                Ok(State::Stored(len as usize))
+            }
+            1 => {
+                (self.lit, self.dist) = Huffman::fixed();
+                Ok(State::Codes)
+            }
+            2 => {
+                self.dynamic()?;
+                Ok(State::Codes)
+            }
+            _ => Err(self.invalid("bad block type")),
+        }
+    }
+
+    /// Read the code lengths of a dynamic block.
+    fn dynamic(&mut self) -> io::Result<()> {
+        let nlen = self.bits.bits(5)? as usize + 257;
+        let ndist = self.bits.bits(5)? as usize + 1;
+        let ncode = self.bits.bits(4)? as usize + 4;
+        if nlen > 286 || ndist > 30 {
+            return Err(self.invalid("bad code counts"));
+        }
+        let mut lengths = [0u8; 316];
+        for &i in &CODE_LENGTH_ORDER[..ncode] {
+            lengths[i] = self.bits.bits(3)? as u8;
+        }
+        let code = Huffman::new(&lengths[..19])?;
+        lengths[..19].fill(0);
+        let mut index = 0;
+        while index < nlen + ndist {
+            let (value, repeat) = match self.bits.decode(&code)? {
+                sym @ 0..=15 => (sym as u8, 1),
+                16 if index == 0 => return Err(self.invalid("repeat with no first length")),
+                16 => (lengths[index - 1], 3 + self.bits.bits(2)? as usize),
+                17 => (0, 3 + self.bits.bits(3)? as usize),
+                _ => (0, 11 + self.bits.bits(7)? as usize),
+            };
+            if index + repeat > nlen + ndist {
+                return Err(self.invalid("too many code lengths"));
+            }
+            lengths[index..index + repeat].fill(value);
+            index += repeat;
+        }
+        if lengths[256] == 0 {
+            return Err(self.invalid("no end of block code"));
+        }
+        self.lit = Huffman::new(&lengths[..nlen])?;
+        self.dist = Huffman::new(&lengths[nlen..nlen + ndist])?;
+        Ok(())
+    }
+
+    /// Inflate symbols until the chunk is full or the block ends.
+    fn codes(&mut self) -> io::Result<State> {
+        while self.out.len() < CHUNK {
+            let sym = self.bits.decode(&self.lit)?;
+            if sym < 256 {
+                self.emit(sym as u8);
+                continue;
+            }
+            if sym == 256 {
+                return Ok(State::Block);
+            }
+            let sym = sym - 257;
+            if sym >= LENGTH_BASE.len() {
+                return Err(self.invalid("bad length code"));
+            }
+            let len = LENGTH_BASE[sym] as usize + self.bits.bits(LENGTH_EXTRA[sym])? as usize;
+            let sym = self.bits.decode(&self.dist)?;
+            if sym >= DIST_BASE.len() {
+                return Err(self.invalid("bad distance code"));
+            }
+            let dist = u64::from(DIST_BASE[sym]) + u64::from(self.bits.bits(DIST_EXTRA[sym])?);
+            if dist > self.written {
+                return Err(self.invalid("distance too far back"));
+            }
+            for _ in 0..len {
+                let byte = self.window[((self.written - dist) % WINDOW as u64) as usize];
+                self.emit(byte);
+            }
+        }
+        Ok(State::Codes)
+    }
+
+    fn trailer(&mut self) -> io::Result<State> {
+        self.bits.align();
+        match self.container {
+            Encoding::Gzip => {
+                let crc = self.bits.u32_le()?;
+                let size = self.bits.u32_le()?;
+                if crc.to_be_bytes().to_vec() != self.crc.finish() {
+                    return Err(self.invalid("crc32 mismatch"));
+                }
+                if size != self.written as u32 {
+                    return Err(self.invalid("length mismatch"));
+                }
+                // concatenated gzip files are one stream
+                match self.bits.inner.fill_buf()?.is_empty() {
+                    true => Ok(State::Done),
+                    false => Ok(State::Header),
+                }
+            }
+            _ => {
+                let adler = self.bits.u32_le()?.swap_bytes();
+                if adler != self.adler {
+                    return Err(self.invalid("adler32 mismatch"));
+                }
+                Ok(State::Done)
+            }
+        }
+    }
+
+    /// Advance through the stream, leaving any output in `out`.
+    fn step(&mut self) -> io::Result<()> {
+        self.state = match self.state {
+            State::Header => {
+                self.header()?;
+                State::Block
+            }
+            State::Block => self.block()?,
+            State::Stored(len) => {
+                let n = len.min(CHUNK);
+                for _ in 0..n {
+                    let byte = self.bits.byte()?;
+                    self.emit(byte);
+                }
+                match len - n {
+                    0 => State::Block,
+                    len => State::Stored(len),
+                }
+            }
+            State::Codes => self.codes()?,
+            State::Trailer => self.trailer()?,
+            State::Done => State::Done,
+        };
+        match self.container {
+            Encoding::Gzip => self.crc.update(&self.out),
+            _ => self.adler = adler32(self.adler, &self.out),
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for Inflate<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.out.len() {
+            if self.state == State::Done {
+                return Ok(0);
+            }
+            self.out.clear();
+            self.pos = 0;
+            self.step()?;
+        }
+        let n = buf.len().min(self.out.len() - self.pos);
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Adler-32 checksum of zlib, continued over `bytes`.
+fn adler32(adler: u32, bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (adler & 0xffff, adler >> 16);
+    // the largest run that cannot overflow before the modulo
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoded(encoding: Encoding, input: &[u8]) -> io::Result<Vec<u8>> {
+        let mut out: Vec<u8> = Vec::new();
+        decode(Some(encoding), io::Cursor::new(input.to_vec())).read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        decoded(Encoding::Hex, hex.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_encoding_by_name() {
+        for encoding in Encoding::ALL {
+            assert_eq!(Encoding::by_name(encoding.name()), Some(encoding));
+        }
+        assert_eq!(Encoding::by_name("uu"), None);
+    }
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(unhex("68 78\n0A"), b"hx\n");
+        assert_eq!(decoded(Encoding::Base64, b"aHgK\n").unwrap(), b"hx\n");
+        assert_eq!(decoded(Encoding::Base64, b"aHg=").unwrap(), b"hx");
+        assert_eq!(decoded(Encoding::Base64, b"aA").unwrap(), b"h");
+        assert_eq!(decoded(Encoding::Base64, b"-_8=").unwrap(), [0xfb, 0xff]);
+        assert_eq!(
+            decoded(Encoding::Hex, b"6").unwrap_err().to_string(),
+            "invalid hex input: truncated at offset 0x1"
+        );
+        assert_eq!(
+            decoded(Encoding::Base64, b"aHg=aHgK")
+                .unwrap_err()
+                .to_string(),
+            "invalid base64 input: unexpected 'a' at offset 0x4"
+        );
+        assert_eq!(
+            decoded(Encoding::Base64, b"aHgKa").unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_decode_zlib() {
+        // fixed Huffman codes with a back reference
+        let input = unhex("789ccb48cdc9c957c84027b90070be08bb");
+        assert_eq!(
+            decoded(Encoding::Zlib, &input).unwrap(),
+            b"hello hello hello hello\n"
+        );
+        // dynamic Huffman codes
+        let input = unhex(concat!(
+            "78da1d8cc90d044108c412f2a3b921ffc4b676248438ec7a18c9618d17d1e4d1c91a8127398ce384d1",
+            "ce8908e6614e05275aae023427f558baf061974adc59a529aa30639b96b1846322b9608b39463dbefa",
+            "561df5ea3f24548234c93f1c12213e"
+        ));
+        let squares: String = (0..60).map(|i| format!("{},", i * i % 97)).collect();
+        assert_eq!(decoded(Encoding::Zlib, &input).unwrap(), squares.as_bytes());
+        // stored block
+        let input = unhex("7801010300fcff616263024d0127");
+        assert_eq!(decoded(Encoding::Zlib, &input).unwrap(), b"abc");
+        let mut input = unhex("789ccb48cdc9c957c84027b90070be08bb");
+        input[16] ^= 1;
+        assert_eq!(
+            decoded(Encoding::Zlib, &input).unwrap_err().to_string(),
+            "invalid zlib data: adler32 mismatch"
+        );
+        assert_eq!(
+            decoded(Encoding::Zlib, &input[..10])
+                .unwrap_err()
+                .to_string(),
+            "compressed input is truncated"
+        );
+    }
+
+    #[test]
+    fn test_decode_gzip() {
+        let member = unhex("1f8b0800000000000203cba8e002006a59b6be03000000");
+        assert_eq!(decoded(Encoding::Gzip, &member).unwrap(), b"hx\n");
+        assert_eq!(
+            decoded(Encoding::Gzip, &[member.clone(), member.clone()].concat()).unwrap(),
+            b"hx\nhx\n"
+        );
+        assert_eq!(
+            decoded(Encoding::Gzip, b"hx\n").unwrap_err().to_string(),
+            "invalid gzip data: bad header"
+        );
+    }
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(1, b"Wikipedia"), 0x11e60398);
+    }
+}
@@ -0,0 +1,119 @@
+//! error categories and exit codes
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Exit code for inputs that differ under `--cmp`, like `cmp`, which
+/// no other failure uses.
+pub const EXIT_DIFFER: i32 = 1;
+/// Exit code for invalid or conflicting command line options, as clap
+/// and `cmp` report trouble.
+pub const EXIT_USAGE: i32 = 2;
+/// Exit code for failures reading input or writing output.
+pub const EXIT_IO: i32 = 3;
+/// Exit code for malformed input, such as a dump read back with `-R`,
+/// a template or the config file.
+pub const EXIT_PARSE: i32 = 4;
+/// Exit code for octet formats that cannot be printed.
+pub const EXIT_FORMAT: i32 = 5;
+
+/// Failure of a hx run, categorized by cause.
+#[derive(Debug)]
+pub enum HxError {
+    /// invalid or conflicting command line options
+    Usage(String),
+    /// reading input or writing output failed
+    Io(io::Error),
+    /// malformed input
+    Parse(String),
+    /// octet format that cannot be printed
+    Format(String),
+    /// inputs compared with `--cmp` differ, already reported on the output
+    Differ,
+}
+
+impl HxError {
+    /// Returns true if the output was closed early, e.g. piped into
+    /// `head`, which ends the run without being reported.
+    pub fn is_broken_pipe(&self) -> bool {
+        matches!(self, Self::Io(e) if e.kind() == io::ErrorKind::BrokenPipe)
+    }
+
+    /// Process exit code for this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Usage(_) => EXIT_USAGE,
+            Self::Io(_) => EXIT_IO,
+            Self::Parse(_) => EXIT_PARSE,
+            Self::Format(_) => EXIT_FORMAT,
+            Self::Differ => EXIT_DIFFER,
+        }
+    }
+}
+
+impl fmt::Display for HxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usage(message) | Self::Parse(message) | Self::Format(message) => {
+                write!(f, "{}", message)
+            }
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Differ => write!(f, "inputs differ"),
+        }
+    }
+}
+
+impl Error for HxError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for HxError {
+    /// I/O errors reporting invalid data come from parsing the input,
+    /// such as a malformed dump read back with `-R`. Invalid input ones
+    /// come from options that do not go together or do not fit the input,
+    /// such as `--name` with a string literal, and unsupported ones from
+    /// options this build or platform lacks, such as `--mmap` without the
+    /// mmap feature.
+    fn from(e: io::Error) -> HxError {
+        match e.kind() {
+            io::ErrorKind::InvalidData => HxError::Parse(e.to_string()),
+            io::ErrorKind::InvalidInput | io::ErrorKind::Unsupported => {
+                HxError::Usage(e.to_string())
+            }
+            _ => HxError::Io(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(HxError::Usage("-c".to_string()).exit_code(), EXIT_USAGE);
+        let missing = HxError::from(io::Error::new(io::ErrorKind::NotFound, "missing"));
+        assert_eq!(missing.exit_code(), EXIT_IO);
+        assert!(missing.source().is_some());
+        let invalid = HxError::from(io::Error::new(io::ErrorKind::InvalidData, "line 1"));
+        assert_eq!(invalid.exit_code(), EXIT_PARSE);
+        assert_eq!(invalid.to_string(), "line 1");
+        let unsupported = HxError::from(io::Error::new(io::ErrorKind::Unsupported, "--mmap"));
+        assert_eq!(unsupported.exit_code(), EXIT_USAGE);
+        let option = HxError::from(io::Error::new(io::ErrorKind::InvalidInput, "--name"));
+        assert_eq!(option.exit_code(), EXIT_USAGE);
+        assert_eq!(HxError::Format("-fp".to_string()).exit_code(), EXIT_FORMAT);
+        assert!(HxError::from(io::Error::from(io::ErrorKind::BrokenPipe)).is_broken_pipe());
+        assert!(!missing.is_broken_pipe());
+        assert_eq!(HxError::Differ.exit_code(), EXIT_DIFFER);
+        // scripts tell differing inputs from trouble, as with cmp
+        for code in [EXIT_USAGE, EXIT_IO, EXIT_PARSE, EXIT_FORMAT] {
+            assert!(code > EXIT_DIFFER);
+        }
+    }
+}
@@ -15,15 +15,64 @@
 extern crate ansi_term;
 extern crate clap;
 
+pub mod args;
+pub mod buffer;
+pub mod completions;
+pub mod config;
+pub mod decode;
+pub mod diff;
+pub mod digest;
+pub mod disasm;
+pub mod encoding;
+pub mod error;
+pub mod magic;
+pub mod memory;
+pub mod net;
+pub mod output;
+pub mod pager;
+pub mod patch;
+pub mod progress;
+pub mod reverse;
+pub mod search;
+pub mod serial;
+pub mod serialize;
+pub mod stats;
+pub mod strings;
+#[cfg(unix)]
+#[allow(unsafe_code)]
+mod sys;
+pub mod template;
+pub mod transform;
+pub mod tui;
+
+pub use buffer::{buf_to_array, Line, Page, PageReader};
+use config::ConfigFile;
+pub use config::HexConfig;
+pub use error::HxError;
+pub use output::{
+    append_ascii, byte_to_color, offset, output_array, output_array_to_writer, output_function,
+    output_function_to_writer, print_byte, print_offset, render_line, render_page,
+};
+
+use clap::parser::ValueSource;
 use clap::ArgMatches;
 use no_color::is_no_color;
+use output::{
+    ArrayBinding, ArrayElement, ArrayOptions, AsciiStyle, Charset, ColorMode, DumpOptions, Endian,
+    Highlight, OffsetFormat, Panes, Summary, Theme,
+};
+use std::cell::RefCell;
 use std::env;
-use std::error::Error;
-use std::f64;
+
 use std::fs;
 use std::io::BufReader;
 use std::io::IsTerminal;
-use std::io::{self, BufRead, Read, Write};
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 /// arg cols
 pub const ARG_COL: &str = "cols";
@@ -35,6 +84,10 @@ pub const ARG_FMT: &str = "format";
 pub const ARG_INP: &str = "INPUTFILE";
 /// arg color
 pub const ARG_CLR: &str = "color";
+/// arg color always, the bare flag form of `--color always`
+pub const ARG_CLZ: &str = "colorize";
+/// arg color, the hidden `-t 0|1` form
+pub const ARG_TNT: &str = "tint";
 /// arg array
 pub const ARG_ARR: &str = "array";
 /// arg func
@@ -43,12 +96,198 @@ pub const ARG_FNC: &str = "func";
 pub const ARG_PLC: &str = "places";
 /// arg prefix
 pub const ARG_PFX: &str = "prefix";
+/// arg no prefix
+pub const ARG_NPX: &str = "no-prefix";
+/// arg seek
+pub const ARG_SKP: &str = "seek";
+/// arg tail
+pub const ARG_TAL: &str = "tail";
+/// arg mmap
+pub const ARG_MAP: &str = "mmap";
+/// arg color mode
+pub const ARG_CMD: &str = "color-mode";
+/// arg theme
+pub const ARG_THM: &str = "theme";
+/// arg offset color
+pub const ARG_OCL: &str = "offset-color";
+/// arg separator color
+pub const ARG_SCL: &str = "separator-color";
+/// arg trailer color
+pub const ARG_TCL: &str = "trailer-color";
+/// arg find
+pub const ARG_FND: &str = "find";
+/// arg find-regex
+pub const ARG_FRX: &str = "find-regex";
+/// arg context
+pub const ARG_CTX: &str = "context";
+/// arg count
+pub const ARG_CNT: &str = "count";
+/// arg strings
+pub const ARG_STR: &str = "strings";
+/// arg strings encoding
+pub const ARG_SEN: &str = "strings-encoding";
+/// arg diff
+pub const ARG_DIF: &str = "diff";
+/// arg cmp
+pub const ARG_CPR: &str = "cmp";
+/// arg reverse
+pub const ARG_REV: &str = "reverse";
+/// arg output
+pub const ARG_OUT: &str = "output";
+/// arg plain
+pub const ARG_PLN: &str = "plain";
+/// arg group size
+pub const ARG_GRP: &str = "group-size";
+/// arg offset format
+pub const ARG_OFF: &str = "offset-format";
+/// arg offset width
+pub const ARG_OFW: &str = "offset-width";
+/// arg offset case
+pub const ARG_OCS: &str = "offset-case";
+/// arg word size
+pub const ARG_WSZ: &str = "word-size";
+/// arg endian
+pub const ARG_END: &str = "endian";
+/// arg output format
+pub const ARG_OFM: &str = "output-format";
+/// arg array name
+pub const ARG_NAM: &str = "name";
+/// arg array const
+pub const ARG_ACN: &str = "array-const";
+/// arg array static
+pub const ARG_AST: &str = "array-static";
+/// arg array type
+pub const ARG_ATY: &str = "array-type";
+/// arg array rustfmt skip
+pub const ARG_RFS: &str = "rustfmt-skip";
+/// arg array module name
+pub const ARG_MOD: &str = "module-name";
+/// arg interactive
+pub const ARG_INT: &str = "interactive";
+/// arg follow
+pub const ARG_FOL: &str = "follow";
+/// arg stats
+pub const ARG_STA: &str = "stats";
+/// arg digest
+pub const ARG_DGS: &str = "digest";
+/// arg range
+pub const ARG_RNG: &str = "range";
+/// arg no squeeze
+pub const ARG_NSQ: &str = "no-squeeze";
+/// arg base address
+pub const ARG_BAS: &str = "base-address";
+/// arg highlight
+pub const ARG_HIL: &str = "highlight";
+/// arg template
+pub const ARG_TPL: &str = "template";
+/// arg detect
+pub const ARG_DET: &str = "detect";
+/// arg charset
+pub const ARG_CHS: &str = "charset";
+/// arg ascii style
+pub const ARG_ASY: &str = "ascii-style";
+/// arg nonprintable char
+pub const ARG_NPC: &str = "nonprintable-char";
+/// arg nul char
+pub const ARG_NUL: &str = "nul-char";
+/// arg separator
+pub const ARG_SEP: &str = "separator";
+/// arg frame
+pub const ARG_FRM: &str = "frame";
+/// arg header
+pub const ARG_HDR: &str = "header";
+/// arg summary
+pub const ARG_SUM: &str = "summary";
+/// arg no summary
+pub const ARG_NSM: &str = "no-summary";
+/// arg style
+pub const ARG_STY: &str = "style";
+/// arg block size
+pub const ARG_BLK: &str = "block-size";
+/// arg offset separator
+pub const ARG_OSP: &str = "offset-separator";
+/// arg gutter
+pub const ARG_GUT: &str = "gutter";
+/// arg no ascii
+pub const ARG_NAS: &str = "no-ascii";
+/// arg ascii only
+pub const ARG_AOL: &str = "ascii-only";
+/// arg line numbers
+pub const ARG_LNO: &str = "line-numbers";
+/// arg end offsets
+pub const ARG_EOS: &str = "end-offsets";
+/// arg little endian groups
+pub const ARG_LEG: &str = "little-endian-groups";
+/// arg bits
+pub const ARG_BIT: &str = "bits";
+/// arg decode
+pub const ARG_DEC: &str = "decode";
+/// arg patch
+pub const ARG_PCH: &str = "patch";
+/// arg fill
+pub const ARG_FIL: &str = "fill";
+/// arg insert
+pub const ARG_INS: &str = "insert";
+/// arg delete
+pub const ARG_DEL: &str = "delete";
+/// arg in place
+pub const ARG_IPL: &str = "in-place";
+/// arg allow grow
+pub const ARG_AGR: &str = "allow-grow";
+/// arg extract
+pub const ARG_EXT: &str = "extract";
+/// arg xor
+pub const ARG_XOR: &str = "xor";
+/// arg rot
+pub const ARG_ROT: &str = "rot";
+/// arg add
+pub const ARG_ADD: &str = "add";
+/// arg input-encoding
+pub const ARG_IEN: &str = "input-encoding";
+/// arg pager
+pub const ARG_PAG: &str = "pager";
+/// arg listen
+pub const ARG_LSN: &str = "listen";
+/// arg connect
+pub const ARG_CON: &str = "connect";
+/// arg serial
+pub const ARG_SER: &str = "serial";
+/// arg baud
+pub const ARG_BAU: &str = "baud";
+/// arg timestamps
+pub const ARG_TSP: &str = "timestamps";
+/// arg idle-flush
+pub const ARG_IFL: &str = "idle-flush";
+/// arg pid
+pub const ARG_PID: &str = "pid";
+/// arg sector-size
+pub const ARG_SSZ: &str = "sector-size";
+/// arg from-array
+pub const ARG_FRA: &str = "from-array";
+/// arg completions
+pub const ARG_CMP: &str = "completions";
+/// arg progress
+pub const ARG_PRG: &str = "progress";
+/// arg threads
+pub const ARG_THR: &str = "threads";
+/// arg disasm
+pub const ARG_DAS: &str = "disasm";
 
-const ARGS: [&str; 9] = [
-    ARG_COL, ARG_LEN, ARG_FMT, ARG_INP, ARG_CLR, ARG_ARR, ARG_FNC, ARG_PLC, ARG_PFX,
-];
+/// how often --follow checks a file for new data
+const FOLLOW_POLL: Duration = Duration::from_millis(250);
 
-const DBG: u8 = 0x0;
+/// how long a line of a live input waits for more bytes before it is
+/// printed with --timestamps, unless --idle-flush says otherwise
+const STREAM_FLUSH: Duration = Duration::from_millis(200);
+
+/// args that read the input in ways --timestamps and --idle-flush do not
+/// support, since a timed input ends partial lines by failing reads with
+/// `WouldBlock`. --idle-flush also reads an input file with --follow.
+pub const ARGS_UNTIMED: [&str; 27] = [
+    ARG_INP, ARG_ARR, ARG_STR, ARG_PLN, ARG_REV, ARG_FRA, ARG_DIF, ARG_CPR, ARG_INT, ARG_THR,
+    ARG_OFM, ARG_DEC, ARG_EXT, ARG_IEN, ARG_DET, ARG_SKP, ARG_TAL, ARG_RNG, ARG_CNT, ARG_CTX,
+    ARG_STA, ARG_MAP, ARG_PCH, ARG_FIL, ARG_INS, ARG_DEL, ARG_FNC,
+];
 
 /// nothing ⇒ Display
 /// ? ⇒ Debug
@@ -59,6 +298,10 @@ const DBG: u8 = 0x0;
 /// b ⇒ Binary
 /// e ⇒ LowerExp
 /// E ⇒ UpperExp
+/// d ⇒ Decimal
+/// i ⇒ SignedDecimal
+/// n ⇒ Nibble
+/// r4, r32 ⇒ Radix
 /// evaluate for traits implementation
 #[derive(Copy, Clone, Debug)]
 pub enum Format {
@@ -76,11 +319,71 @@ pub enum Format {
     LowerExp,
     /// upper exp format
     UpperExp,
+    /// unsigned decimal format, 0 to 255
+    Decimal,
+    /// signed decimal format, -128 to 127
+    SignedDecimal,
+    /// binary format split into 4 bit nibbles, like 0110_0001
+    Nibble,
+    /// digits of any radix from 2 to 36, with lower case letters above
+    /// 9, like base 4 or the base32hex alphabet of radix 32
+    Radix(u32),
     /// unknown format
     Unknown,
 }
 
 impl Format {
+    /// Look up a format by its `-f` name, `Unknown` if there is none.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - format name.
+    pub fn by_name(name: &str) -> Format {
+        // o, x, X, p, b, e, E, d, i, n, r<radix>
+        if let Some(radix) = name.strip_prefix('r') {
+            return match radix.parse::<u32>() {
+                Ok(radix) if (2..=36).contains(&radix) => Self::Radix(radix),
+                _ => Self::Unknown,
+            };
+        }
+        match name {
+            "o" => Self::Octal,
+            "x" => Self::LowerHex,
+            "X" => Self::UpperHex,
+            "p" => Self::Pointer,
+            "b" => Self::Binary,
+            "e" => Self::LowerExp,
+            "E" => Self::UpperExp,
+            "d" => Self::Decimal,
+            "i" => Self::SignedDecimal,
+            "n" => Self::Nibble,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Returns true if octets can be printed in this Format. Pointer and
+    /// exponential formats have no meaningful per-octet rendering.
+    pub fn is_implemented(&self) -> bool {
+        match self {
+            Self::Radix(radix) => (2..=36).contains(radix),
+            _ => !matches!(
+                self,
+                Self::Pointer | Self::LowerExp | Self::UpperExp | Self::Unknown
+            ),
+        }
+    }
+
+    /// Radix of the digits printed for this Format
+    pub fn radix(&self) -> u32 {
+        match &self {
+            Self::Octal => 8,
+            Self::Binary | Self::Nibble => 2,
+            Self::Decimal | Self::SignedDecimal => 10,
+            Self::Radix(radix) => *radix,
+            _ => 16,
+        }
+    }
+
     /// Formats a given u8 according to the base Format
     ///
     /// # Arguments
@@ -88,12 +391,17 @@ impl Format {
     /// * `data` - The byte to be formatted
     /// * `prefix` - whether or not to add a prefix
     fn format(&self, data: u8, prefix: bool) -> String {
+        if let Self::Nibble | Self::Radix(_) = self {
+            return self.format_word(data.into(), 1, prefix);
+        }
         if prefix {
             match &self {
                 Self::Octal => format!("{:#06o}", data),
                 Self::LowerHex => format!("{:#04x}", data),
                 Self::UpperHex => format!("{:#04X}", data),
                 Self::Binary => format!("{:#010b}", data),
+                Self::Decimal => format!("{:03}", data),
+                Self::SignedDecimal => format!("{:04}", data as i8),
                 _ => panic!("format is not implemented for this Format"),
             }
             .to_string()
@@ -103,6 +411,8 @@ impl Format {
                 Self::LowerHex => format!("{:02x}", data),
                 Self::UpperHex => format!("{:02X}", data),
                 Self::Binary => format!("{:08b}", data),
+                Self::Decimal => format!("{:03}", data),
+                Self::SignedDecimal => format!("{:04}", data as i8),
                 _ => panic!("format is not implemented for this Format"),
             }
             .to_string()
@@ -110,116 +420,111 @@ impl Format {
     }
 }
 
-/// Line structure for hex output
-#[derive(Clone, Debug, Default)]
-pub struct Line {
-    /// offset
-    pub offset: u64,
-    /// hex body
-    pub hex_body: Vec<u8>,
-    /// ascii text
-    pub ascii: Vec<u8>,
-    /// total bytes in Line
-    pub bytes: u64,
-}
-/// Line implementation
-impl Line {
-    /// Line constructor
-    pub fn new() -> Line {
-        Line {
-            offset: 0x0,
-            hex_body: Vec::new(),
-            ascii: Vec::new(),
-            bytes: 0x0,
+impl Format {
+    /// Prefix printed before an octet, or a group of octets.
+    fn prefix(&self) -> &'static str {
+        match &self {
+            Self::Octal => "0o",
+            Self::Binary | Self::Nibble => "0b",
+            // decimals and other radixes are printed without a prefix
+            Self::Decimal | Self::SignedDecimal | Self::Radix(_) => "",
+            _ => "0x",
         }
     }
-}
-
-/// Page structure
-#[derive(Clone, Debug, Default)]
-pub struct Page {
-    /// page offset
-    pub offset: u64,
-    /// page body
-    pub body: Vec<Line>,
-    /// total bytes in page
-    pub bytes: u64,
-}
 
-/// Page implementation
-impl Page {
-    /// Page constructor
-    pub fn new() -> Page {
-        Page {
-            offset: 0x0,
-            body: Vec::new(),
-            bytes: 0x0,
+    /// Number of digits printed for a word of `bytes` bytes.
+    fn digits(&self, bytes: usize) -> usize {
+        match &self {
+            Self::Octal if bytes == 1 => 4,
+            Self::Octal => (8 * bytes).div_ceil(3),
+            Self::Binary => 8 * bytes,
+            // a _ between every two nibbles
+            Self::Nibble => 10 * bytes - 1,
+            Self::Radix(radix) => to_radix(u64::MAX >> (64 - 8 * bytes), *radix).len(),
+            Self::Decimal => (u64::MAX >> (64 - 8 * bytes)).to_string().len(),
+            Self::SignedDecimal => (i64::MIN >> (64 - 8 * bytes)).to_string().len(),
+            _ => 2 * bytes,
         }
     }
-}
-
-/// offset column
-///
-/// # Arguments
-///
-/// * `b` - offset value.
-pub fn offset(b: u64) -> String {
-    format!("{:#08x}", b)
-}
-
-/// print offset to std out
-pub fn print_offset(w: &mut impl Write, b: u64) -> io::Result<()> {
-    write!(w, "{}: ", offset(b))
-}
 
-/// print byte to std out
-pub fn print_byte(
-    w: &mut impl Write,
-    b: u8,
-    format: Format,
-    colorize: bool,
-    prefix: bool,
-) -> io::Result<()> {
-    let fmt_string = format.format(b, prefix);
-    if colorize {
-        // note, for color testing: for (( i = 0; i < 256; i++ )); do echo "$(tput setaf $i)This is ($i) $(tput sgr0)"; done
-        let color = byte_to_color(b);
-        write!(
-            w,
-            "{} ",
-            ansi_term::Style::new()
-                .fg(ansi_term::Color::Fixed(color))
-                .paint(fmt_string)
-        )
-    } else {
-        write!(w, "{} ", fmt_string)
+    /// Every u8 formatted according to the base Format, like `format`
+    /// without allocating, as this is done for every octet of a dump.
+    /// The table is built once per Format and prefix.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - whether or not to add a prefix
+    fn octets(&self, prefix: bool) -> &'static [String] {
+        // one table per radix 0 to 36 follows the named formats
+        static TABLES: [[OnceLock<Vec<String>>; 2]; 44] =
+            [const { [const { OnceLock::new() }; 2] }; 44];
+        let index = match &self {
+            Self::Octal => 0,
+            Self::LowerHex => 1,
+            Self::UpperHex => 2,
+            Self::Binary => 3,
+            Self::Decimal => 4,
+            Self::SignedDecimal => 5,
+            Self::Nibble => 6,
+            Self::Radix(radix) if *radix <= 36 => 7 + *radix as usize,
+            _ => panic!("format is not implemented for this Format"),
+        };
+        TABLES[index][prefix as usize]
+            .get_or_init(|| (0..=u8::MAX).map(|b| self.format(b, prefix)).collect())
     }
-}
 
-/// get the color for a specific byte
-pub fn byte_to_color(b: u8) -> u8 {
-    let mut color: u8 = b;
-    if color < 1 {
-        color = 0x16;
+    /// Formats a word of `bytes` bytes according to the base Format
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The word to be formatted
+    /// * `bytes` - size of the word in bytes
+    /// * `prefix` - whether or not to add a prefix
+    fn format_word(&self, value: u64, bytes: usize, prefix: bool) -> String {
+        let prefix = if prefix { self.prefix() } else { "" };
+        let width = self.digits(bytes);
+        match &self {
+            Self::Octal => format!("{}{:02$o}", prefix, value, width),
+            Self::LowerHex => format!("{}{:02$x}", prefix, value, width),
+            Self::UpperHex => format!("{}{:02$X}", prefix, value, width),
+            Self::Binary => format!("{}{:02$b}", prefix, value, width),
+            Self::Nibble => {
+                let bits = format!("{:01$b}", value, 8 * bytes);
+                let nibbles: Vec<&str> = (0..bits.len())
+                    .step_by(4)
+                    .map(|i| &bits[i..i + 4])
+                    .collect();
+                format!("{}{}", prefix, nibbles.join("_"))
+            }
+            Self::Radix(radix) => format!("{:0>1$}", to_radix(value, *radix), width),
+            Self::Decimal => format!("{:01$}", value, width),
+            Self::SignedDecimal => {
+                // sign extend the word to 64 bits
+                let shift = 64 - 8 * bytes;
+                format!("{:01$}", ((value << shift) as i64) >> shift, width)
+            }
+            _ => panic!("format is not implemented for this Format"),
+        }
     }
-    color
 }
 
-/// append char representation of a byte to a buffer
-pub fn append_ascii(target: &mut Vec<u8>, b: u8, colorize: bool) {
-    let char = match b > 31 && b < 127 {
-        true => b as char,
-        false => '.',
-    };
-
-    if colorize {
-        let string = ansi_term::Style::new()
-            .fg(ansi_term::Color::Fixed(byte_to_color(b)))
-            .paint(char.to_string());
-        target.extend(format!("{}", string).as_bytes());
-    } else {
-        target.extend(format!("{}", char).as_bytes());
+/// Digits of `value` in `radix`, with lower case letters above 9 and
+/// without zero padding.
+///
+/// # Arguments
+///
+/// * `value` - The number to be formatted
+/// * `radix` - radix from 2 to 36
+fn to_radix(mut value: u64, radix: u32) -> String {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(char::from_digit((value % radix as u64) as u32, radix).unwrap());
+        value /= radix as u64;
+        if value == 0 {
+            break;
+        }
     }
+    digits.iter().rev().collect()
 }
 
 /// In most hex editor applications, the data of the computer file is
@@ -233,418 +538,3289 @@ pub fn append_ascii(target: &mut Vec<u8>, b: u8, colorize: bool) {
 /// # Arguments
 ///
 /// * `matches` - Argument matches from command line.
-pub fn run(matches: ArgMatches) -> Result<(), Box<dyn Error>> {
-    let mut column_width: u64 = 10;
-    let mut truncate_len: u64 = 0x0;
-    if let Some(len) = matches.get_one::<String>("func") {
-        let mut p: usize = 4;
-        if let Some(places) = matches.get_one::<String>("places") {
-            p = match places.parse::<usize>() {
-                Ok(p) => p,
-                Err(e) => {
-                    eprintln!("-p, --places <integer> expected. {:?}", e);
-                    return Err(Box::new(e));
+pub fn run(matches: ArgMatches) -> Result<(), HxError> {
+    // output goes to stdout, or atomically replaces the -o file once
+    // the output is complete
+    let mut file = match matches.get_one::<String>(ARG_OUT) {
+        Some(path) => Some(output::AtomicFile::create(path)?),
+        None => None,
+    };
+    let stdout = io::stdout();
+    let pager = matches
+        .get_one::<pager::PagerMode>(ARG_PAG)
+        .copied()
+        .unwrap_or(pager::PagerMode::Never);
+    let mut out: Box<dyn Write> = match (file.as_mut(), pager) {
+        (Some(file), _) => Box::new(file),
+        // auto only pages a terminal, once the output is too long for it
+        (None, pager::PagerMode::Auto) if io::stdout().is_terminal() => Box::new(
+            pager::Paged::new(stdout.lock(), pager, output::terminal_size().1),
+        ),
+        (None, pager::PagerMode::Always) => Box::new(pager::Paged::new(stdout.lock(), pager, 0)),
+        (None, _) => Box::new(stdout.lock()),
+    };
+
+    if let Some(&len) = matches.get_one::<u64>(ARG_FNC) {
+        let places = matches.get_one::<usize>(ARG_PLC).copied().unwrap_or(4);
+        output_function_to_writer(&mut out, len, places)?;
+    } else if [ARG_PCH, ARG_FIL, ARG_INS, ARG_DEL]
+        .iter()
+        .any(|name| matches.contains_id(name))
+    {
+        output_edits(&matches, &mut out)?;
+    } else {
+        let config = hex_config(&matches)?;
+        let options = config.dump_options();
+        let truncate_len = config.truncate_len();
+
+        if matches.get_flag(ARG_INT) {
+            // the terminal is the viewer's keyboard, so the input must be a file
+            let input = match matches.get_many::<String>(ARG_INP) {
+                Some(mut inputs) => inputs.next().unwrap().clone(),
+                _ => {
+                    return Err(HxError::Usage(
+                        "--interactive requires an input file".to_string(),
+                    ))
                 }
+            };
+            tui::run(&input, options)?;
+        } else if let Some(mut files) = matches.get_many::<String>(ARG_DIF) {
+            let mut left = BufReader::new(open_input(files.next().unwrap())?.0);
+            let mut right = BufReader::new(open_input(files.next().unwrap())?.0);
+            diff::output_diff(&mut out, &mut left, &mut right, truncate_len, &options)?;
+        } else if let Some(files) = matches.get_many::<String>(ARG_CPR) {
+            let names: Vec<&String> = files.collect();
+            let mut left = BufReader::new(open_input(names[0])?.0);
+            let mut right = BufReader::new(open_input(names[1])?.0);
+            let names = [names[0].as_str(), names[1].as_str()];
+            let differing = diff::output_cmp(
+                &mut out,
+                &mut left,
+                &mut right,
+                names,
+                truncate_len,
+                &options,
+            )?;
+            if differing > 0 {
+                drop(out);
+                if let Some(file) = file {
+                    file.commit()?;
+                }
+                return Err(HxError::Differ);
             }
-        }
-        output_function(len.parse::<u64>().unwrap(), p);
-    } else {
-        // cases:
-        //  $ cat Cargo.toml | target/debug/hx
-        //  $ cat Cargo.toml | target/debug/hx -a r
-        //  $ target/debug/hx Cargo.toml
-        //  $ target/debug/hx Cargo.toml -a r
-        let is_stdin = is_stdin(matches.clone());
-        let mut buf: Box<dyn BufRead> = if is_stdin.unwrap() {
-            Box::new(BufReader::new(io::stdin()))
-        } else {
-            Box::new(BufReader::new(fs::File::open(
-                matches.get_one::<String>(ARG_INP).unwrap(),
-            )?))
-        };
-        let mut format_out = Format::LowerHex;
-        let mut colorize = true;
-        let mut prefix = true;
-
-        if let Some(columns) = matches.get_one::<String>(ARG_COL) {
-            column_width = match columns.parse::<u64>() {
-                Ok(column_width) => column_width,
-                Err(e) => {
-                    eprintln!("-c, --cols <integer> expected. {:?}", e);
-                    return Err(Box::new(e));
+        } else if let Some(&pid) = matches.get_one::<u32>(ARG_PID) {
+            // the memory reads at virtual addresses, so the offsets of the
+            // dump are the addresses
+            let range = matches
+                .get_one::<args::ByteRange>(ARG_RNG)
+                .filter(|range| range.end.is_some())
+                .ok_or_else(|| {
+                    HxError::Usage(
+                        "--pid needs a --range with an end, see /proc/<pid>/maps".to_string(),
+                    )
+                })?;
+            let path = memory::mem_path(pid)?;
+            output_input(&matches, &mut out, &options, truncate_len, Some(&path)).map_err(|e| {
+                match e {
+                    HxError::Io(e) => HxError::Io(memory::explain(e, pid, range)),
+                    e => e,
                 }
+            })?;
+        } else {
+            // cases:
+            //  $ cat Cargo.toml | target/debug/hx
+            //  $ cat Cargo.toml | target/debug/hx -a r
+            //  $ target/debug/hx Cargo.toml
+            //  $ target/debug/hx Cargo.toml -a r
+            //  $ target/debug/hx Cargo.toml Cargo.lock
+            // clap skips `requires` when the required arg conflicts with a
+            // given one, as --serial does with input files
+            if matches.contains_id(ARG_BAU) && !matches.contains_id(ARG_SER) {
+                return Err(HxError::Usage("--baud requires --serial".to_string()));
             }
-        }
-
-        if let Some(length) = matches.get_one::<String>(ARG_LEN) {
-            truncate_len = match length.parse::<u64>() {
-                Ok(truncate_len) => truncate_len,
-                Err(e) => {
-                    eprintln!("-l, --len <integer> expected. {:?}", e);
-                    return Err(Box::new(e));
+            // an input file is only live when followed
+            if matches.contains_id(ARG_IFL)
+                && matches.contains_id(ARG_INP)
+                && !matches.get_flag(ARG_FOL)
+            {
+                return Err(HxError::Usage(
+                    "--idle-flush needs a live input: stdin, --listen, --connect, --serial or a --follow file"
+                        .to_string(),
+                ));
+            }
+            let inputs: Vec<&String> = match matches.get_many::<String>(ARG_INP) {
+                Some(inputs) => inputs.collect(),
+                None if is_stdin(&matches) => Vec::new(),
+                // bytes arriving over a socket
+                None if [ARG_LSN, ARG_CON, ARG_SER]
+                    .iter()
+                    .any(|name| matches.contains_id(name)) =>
+                {
+                    Vec::new()
+                }
+                None => {
+                    return Err(HxError::Usage(
+                        "no input, pass a file path or pipe data into hx, see hx --help"
+                            .to_string(),
+                    ))
                 }
+            };
+            if inputs.is_empty() {
+                output_input(&matches, &mut out, &options, truncate_len, None)?;
             }
-        }
-
-        if let Some(format) = matches.get_one::<String>(ARG_FMT) {
-            // o, x, X, p, b, e, E
-            match format.as_str() {
-                "o" => format_out = Format::Octal,
-                "x" => format_out = Format::LowerHex,
-                "X" => format_out = Format::UpperHex,
-                "p" => format_out = Format::Pointer,
-                "b" => format_out = Format::Binary,
-                "e" => format_out = Format::LowerExp,
-                "E" => format_out = Format::UpperExp,
-                _ => format_out = Format::Unknown,
+            // like head, name each input when there are several and keep
+            // going past inputs that fail
+            if inputs.len() > 1 && matches.get_flag(ARG_FOL) {
+                return Err(HxError::Usage(
+                    "--follow accepts a single input file".to_string(),
+                ));
             }
-        }
-
-        // check no_color here
-        // override via ARG_CLR below
-        if is_no_color() {
-            colorize = false;
-        }
-
-        // prevent term color codes being sent to stdout
-        // test: cat Cargo.toml | target/debug/hx | more
-        // override via ARG_CLR below
-        if !io::stdout().is_terminal() {
-            colorize = false;
-        }
-
-        if let Some(color) = matches.get_one::<String>(ARG_CLR) {
-            colorize = color.parse::<u8>().unwrap() == 1;
-        }
-
-        if let Some(prefix_flag) = matches.get_one::<String>(ARG_PFX) {
-            prefix = prefix_flag.parse::<u8>().unwrap() == 1;
-        }
-
-        // array output mode is mutually exclusive
-        if let Some(array) = matches.get_one::<String>(ARG_ARR) {
-            output_array(array, buf, truncate_len, column_width)?;
-        } else {
-            // Transforms this Read instance to an Iterator over its bytes.
-            // The returned type implements Iterator where the Item is
-            // Result<u8, R::Err>. The yielded item is Ok if a byte was
-            // successfully read and Err otherwise for I/O errors. EOF is
-            // mapped to returning None from this iterator.
-            // (https://doc.rust-lang.org/1.16.0/std/io/trait.Read.html#method.bytes)
-            let mut ascii_line: Line = Line::new();
-            let mut offset_counter: u64 = 0x0;
-            let mut byte_column: u64 = 0x0;
-            let page = buf_to_array(&mut buf, truncate_len, column_width)?;
-
-            let stdout = io::stdout();
-            let mut locked = stdout.lock();
-
-            for line in page.body.iter() {
-                print_offset(&mut locked, offset_counter)?;
-
-                for hex in line.hex_body.iter() {
-                    offset_counter += 1;
-                    byte_column += 1;
-                    print_byte(&mut locked, *hex, format_out, colorize, prefix)?;
-                    append_ascii(&mut ascii_line.ascii, *hex, colorize);
+            let headers = inputs.len() > 1
+                && !matches.get_flag(ARG_REV)
+                && !matches.get_flag(ARG_FRA)
+                && !matches.contains_id(ARG_EXT);
+            let mut failed: usize = 0;
+            for (i, input) in inputs.iter().enumerate() {
+                if headers {
+                    if i > 0 {
+                        writeln!(out)?;
+                    }
+                    writeln!(out, "==> {} <==", input)?;
                 }
-
-                if byte_column < column_width {
-                    write!(
-                        locked,
-                        "{:<1$}",
-                        "",
-                        5 * (column_width - byte_column) as usize
-                    )?;
+                if let Err(e) =
+                    output_input(&matches, &mut out, &options, truncate_len, Some(input))
+                {
+                    // a closed pipe ends the output for every input
+                    if inputs.len() == 1 || e.is_broken_pipe() {
+                        return Err(e);
+                    }
+                    eprintln!("hx: {}: {}", input, e);
+                    failed += 1;
                 }
-
-                locked.write_all(ascii_line.ascii.as_slice())?;
-                writeln!(locked)?;
-
-                byte_column = 0x0;
-                ascii_line = Line::new();
             }
-            if true {
-                writeln!(locked, "   bytes: {}", page.bytes)?;
+            if failed > 0 {
+                drop(out);
+                if let Some(file) = file {
+                    file.commit()?;
+                }
+                return Err(HxError::Io(io::Error::other(format!(
+                    "{} of {} inputs failed",
+                    failed,
+                    inputs.len()
+                ))));
             }
         }
     }
+    drop(out);
+    if let Some(file) = file {
+        file.commit()?;
+    }
     Ok(())
 }
 
-/// Detect stdin, file path and/or parameters.
+/// Make the `--patch`, `--fill`, `--insert` and `--delete` edits to the
+/// input, in place with `--in-place`, or else to a copy in the output.
+///
 /// # Arguments
 ///
-/// * `matches` - argument matches.
-#[allow(clippy::absurd_extreme_comparisons)]
-pub fn is_stdin(matches: ArgMatches) -> Result<bool, Box<dyn Error>> {
-    let mut is_stdin = false;
-    if let Some(file) = matches.get_one::<String>(ARG_INP) {
-        if DBG > 0 {
-            dbg!(file);
-        }
-        is_stdin = false;
-    } else if let Some(nth1) = env::args().nth(1) {
-        if DBG > 0 {
-            dbg!(nth1);
-        }
-        is_stdin = ARGS.iter().any(|arg| matches.index_of(arg) == Some(2));
-    } else if !matches.args_present() {
-        is_stdin = true;
-    }
-    if DBG > 0 {
-        dbg!(is_stdin);
-    }
-    Ok(is_stdin)
+/// * `matches` - Argument matches from command line.
+/// * `out` - output sink for the edited copy.
+fn output_edits(matches: &ArgMatches, out: &mut impl Write) -> Result<(), HxError> {
+    // edits are applied in command line order, so later overwrites win
+    let mut edits: Vec<(usize, patch::Edit)> = Vec::new();
+    let indexed = |name| matches.indices_of(name).into_iter().flatten();
+    let patches = |name| matches.get_many::<patch::Patch>(name).into_iter().flatten();
+    let overwrites = patches(ARG_PCH).cloned().map(patch::Edit::Overwrite);
+    edits.extend(indexed(ARG_PCH).zip(overwrites));
+    let fills = matches
+        .get_many::<patch::Edit>(ARG_FIL)
+        .into_iter()
+        .flatten();
+    edits.extend(indexed(ARG_FIL).zip(fills.cloned()));
+    let inserts = patches(ARG_INS).cloned().map(patch::Edit::Insert);
+    edits.extend(indexed(ARG_INS).zip(inserts));
+    let deletes = matches
+        .get_many::<args::ByteRange>(ARG_DEL)
+        .into_iter()
+        .flatten();
+    edits.extend(indexed(ARG_DEL).zip(deletes.copied().map(patch::Edit::Delete)));
+    edits.sort_by_key(|(index, _)| *index);
+    let edits: Vec<patch::Edit> = edits.into_iter().map(|(_, edit)| edit).collect();
+    let allow_grow = matches.get_flag(ARG_AGR);
+    let inputs: Vec<&String> = matches
+        .get_many::<String>(ARG_INP)
+        .into_iter()
+        .flatten()
+        .collect();
+    if inputs.len() > 1 {
+        return Err(HxError::Usage(
+            "--patch, --fill, --insert and --delete accept a single input".to_string(),
+        ));
+    }
+    match (inputs.first(), matches.get_flag(ARG_IPL)) {
+        (Some(input), true) => {
+            // --in-place only goes with --patch, which leaves the length alone
+            let patches: Vec<patch::Patch> = edits
+                .into_iter()
+                .filter_map(|edit| match edit {
+                    patch::Edit::Overwrite(patch) => Some(patch),
+                    _ => None,
+                })
+                .collect();
+            patch::patch_in_place(input, &patches, allow_grow)
+        }
+        (None, true) => Err(HxError::Usage(
+            "--in-place requires an input file".to_string(),
+        )),
+        (Some(input), false) => {
+            let (mut file, seekable) = open_input(input)?;
+            let len = match seekable {
+                true => Some(file.metadata()?.len()),
+                false => None,
+            };
+            patch::edit_copy(&mut file, out, &edits, len, allow_grow)
+        }
+        (None, false) => patch::edit_copy(&mut io::stdin().lock(), out, &edits, None, allow_grow),
+    }
 }
 
-/// Output source code array format.
+/// Value of option `name` from the command line, or else parsed with
+/// `parse` from the first of `defaults` that sets it.
+///
 /// # Arguments
 ///
-/// * `array_format` - array format, rust (r), C (c), golang (g).
-/// * `buf` - BufRead.
-/// * `truncate_len` - truncate to length.
-/// * `column_width` - column width.
-pub fn output_array(
-    array_format: &str,
-    mut buf: Box<dyn BufRead>,
-    truncate_len: u64,
-    column_width: u64,
-) -> io::Result<()> {
-    let stdout = io::stdout();
-    let mut locked = stdout.lock();
-
-    let page = buf_to_array(&mut buf, truncate_len, column_width).unwrap();
-    match array_format {
-        "r" => writeln!(locked, "let ARRAY: [u8; {}] = [", page.bytes)?,
-        "c" => writeln!(locked, "unsigned char ARRAY[{}] = {{", page.bytes)?,
-        "g" => writeln!(locked, "a := [{}]byte{{", page.bytes)?,
-        "p" => writeln!(locked, "a = [")?,
-        "k" => writeln!(locked, "val a = byteArrayOf(")?,
-        "j" => writeln!(locked, "byte[] a = new byte[]{{")?,
-        "s" => writeln!(locked, "let a: [UInt8] = [")?,
-        "f" => writeln!(locked, "let a = [|")?,
-        _ => writeln!(locked, "unknown array format")?,
-    }
-    let mut i: u64 = 0x0;
-    for line in page.body.iter() {
-        write!(locked, "    ")?;
-        for hex in line.hex_body.iter() {
-            i += 1;
-            if i == page.bytes && array_format != "g" {
-                if array_format != "f" {
-                    write!(locked, "{}", Format::LowerHex.format(*hex, true))?;
-                } else {
-                    write!(locked, "{}uy", Format::LowerHex.format(*hex, true))?;
-                }
-            } else if array_format != "f" {
-                write!(locked, "{}, ", Format::LowerHex.format(*hex, true))?;
-            } else {
-                write!(locked, "{}uy; ", Format::LowerHex.format(*hex, true))?;
-            }
-        }
-        writeln!(locked)?;
+/// * `matches` - Argument matches from command line.
+/// * `defaults` - environment and config file defaults, in precedence order.
+/// * `name` - long option name.
+/// * `parse` - parser of a default, returning the error message.
+fn setting<T: Clone + Send + Sync + 'static>(
+    matches: &ArgMatches,
+    defaults: &[ConfigFile],
+    name: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<Option<T>, HxError> {
+    match matches.get_one::<T>(name) {
+        Some(value) => Ok(Some(value.clone())),
+        None => defaults
+            .iter()
+            .find_map(|defaults| defaults.get(name))
+            .map(|value| parse(value).map_err(HxError::Usage))
+            .transpose(),
     }
+}
 
-    writeln!(
-        locked,
-        "{}",
-        match array_format {
-            "r" => "];",
-            "c" | "j" => "};",
-            "g" => "}",
-            "p" => "]",
-            "k" => ")",
-            "s" => "]",
-            "f" => "|]",
-            _ => "unknown array format",
-        }
-    )
+/// Parser of a default named after a `T`, for `setting`.
+///
+/// # Arguments
+///
+/// * `what` - what the name is of, for the error message.
+/// * `by_name` - lookup of a value by name.
+fn named<T>(
+    what: &'static str,
+    by_name: fn(&str) -> Option<T>,
+) -> impl Fn(&str) -> Result<T, String> {
+    move |name| by_name(name).ok_or_else(|| format!("unknown {}: {}", what, name))
 }
 
-/// Function wave out.
+/// Returns true if flag `name` is given on the command line, or else set
+/// by the first of `defaults` that sets it.
+///
 /// # Arguments
 ///
-/// * `len` - Wave length.
-/// * `places` - Number of decimal places for function wave floats.
-pub fn output_function(len: u64, places: usize) {
-    for y in 0..len {
-        let y_float: f64 = y as f64;
-        let len_float: f64 = len as f64;
-        let x: f64 = (((y_float / len_float) * f64::consts::PI) / 2.0).sin();
-        let formatted_number = format!("{:.*}", places, x);
-        print!("{}", formatted_number);
-        print!(",");
-        if (y % 10) == 9 {
-            println!();
-        }
-    }
-    println!();
+/// * `matches` - Argument matches from command line.
+/// * `defaults` - environment and config file defaults, in precedence order.
+/// * `name` - long flag name.
+fn flag_setting(matches: &ArgMatches, defaults: &[ConfigFile], name: &str) -> bool {
+    matches.get_flag(name)
+        || defaults
+            .iter()
+            .find_map(|defaults| defaults.get(name))
+            .is_some_and(|value| value == "true")
 }
 
-/// Buffer to array.
+/// Build the dump configuration from command line arguments, with
+/// defaults from `HX_*` environment variables and the config file.
 ///
 /// # Arguments
 ///
-/// * `buf` - Buffer to be read.
-/// * `buf_len` - force buffer length.
-/// * `column_width` - column width for output.
-pub fn buf_to_array(
-    buf: &mut dyn Read,
-    buf_len: u64,
-    column_width: u64,
-) -> Result<Page, Box<dyn ::std::error::Error>> {
-    let mut column_count: u64 = 0x0;
-    let max_array_size: u16 = <u16>::max_value(); // 2^16;
-    let mut page: Page = Page::new();
-    let mut line: Line = Line::new();
-    for b in buf.bytes() {
-        let b1: u8 = b?;
-        line.bytes += 1;
-        page.bytes += 1;
-        line.hex_body.push(b1);
-        column_count += 1;
-
-        if column_count >= column_width {
-            page.body.push(line);
-            line = Line::new();
-            column_count = 0;
-        }
-
-        if buf_len > 0 && (page.bytes == buf_len || u64::from(max_array_size) == buf_len) {
-            break;
+/// * `matches` - Argument matches from command line.
+fn hex_config(matches: &ArgMatches) -> Result<HexConfig, HxError> {
+    // env::vars panics on variables that are not unicode
+    let vars = env::vars_os()
+        .filter_map(|(var, value)| Some((var.into_string().ok()?, value.into_string().ok()?)));
+    // a --style preset is chosen for this run, so it takes precedence
+    // over the environment and the config file
+    let style = matches
+        .get_one::<ConfigFile>(ARG_STY)
+        .cloned()
+        .unwrap_or_default();
+    let style = match matches.get_flag(ARG_BIT) {
+        true => style.bits(),
+        false => style,
+    };
+    let defaults = [style, ConfigFile::from_env(vars)?, ConfigFile::load()?];
+    let mut format_out = Format::LowerHex;
+    let mut colorize = true;
+
+    // on a terminal, columns fit its width unless set
+    let columns = setting(matches, &defaults, ARG_COL, |value| {
+        args::cols_value(value).map_err(|e| format!("-c, --cols <integer> expected: {}", e))
+    })?;
+    let fit_columns = match columns {
+        Some(columns) => columns == args::Columns::Auto,
+        None => {
+            io::stdout().is_terminal()
+                && !matches.contains_id(ARG_OUT)
+                && ![ARG_ARR, ARG_STR, ARG_FRX]
+                    .iter()
+                    .any(|name| matches.contains_id(name))
+                && !matches.get_flag(ARG_PLN)
         }
-    }
-    page.body.push(line);
-    Ok(page)
-}
+    };
+    // 0 reads to the end of input
+    let mut truncate_len = matches.get_one::<u64>(ARG_LEN).copied().unwrap_or(0);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    /// @see (https://users.rust-lang.org/t/how-to-test-output-to-stdout/4877/6)
-    #[test]
-    fn test_offset() {
-        let b: u64 = 0x6;
-        assert_eq!(offset(b), "0x000006");
-        assert_eq!(offset(b), format!("{:#08x}", b));
+    if let Some(range) = matches.get_one::<args::ByteRange>(ARG_RNG) {
+        truncate_len = range.length().unwrap_or(0);
     }
 
-    /// hex octal, takes u8
-    #[test]
-    pub fn test_hex_octal() {
-        let b: u8 = 0x6;
+    // -f given more than once stacks rows in the further formats below
+    // each line
+    let mut stacked: Vec<Format> = Vec::new();
+    let formats: Vec<String> = match matches.get_many::<String>(ARG_FMT) {
+        Some(formats) => formats.cloned().collect(),
+        None => setting(matches, &defaults, ARG_FMT, |format| Ok(format.to_string()))?
+            .into_iter()
+            .collect(),
+    };
+    for (i, format) in formats.into_iter().enumerate() {
+        let parsed = Format::by_name(&format);
+        if !parsed.is_implemented() {
+            return Err(HxError::Format(format!(
+                "-f, --format {} is not supported, expected o, x, X, b, n, d, i or r<radix> from r2 to r36",
+                format
+            )));
+        }
+        match i {
+            0 => format_out = parsed,
+            _ => stacked.push(parsed),
+        }
+    }
 
-        //with prefix
-        assert_eq!(Format::Octal.format(b, true), "0o0006");
-        assert_eq!(Format::Octal.format(b, true), format!("{:#06o}", b));
+    // check no_color here
+    // override via ARG_CLR below
+    if is_no_color() {
+        colorize = false;
+    }
 
-        //without prefix
-        assert_eq!(Format::Octal.format(b, false), "0006");
-        assert_eq!(Format::Octal.format(b, false), format!("{:04o}", b));
+    // prevent term color codes being sent to stdout
+    // test: cat Cargo.toml | target/debug/hx | more
+    // override via ARG_CLR below
+    if !io::stdout().is_terminal() {
+        colorize = false;
     }
 
-    /// hex lower hex, takes u8
-    #[test]
-    fn test_hex_lower_hex() {
-        let b: u8 = <u8>::max_value(); // 255
+    // a file written with -o is not a terminal
+    // override via ARG_CLR below
+    if matches.contains_id(ARG_OUT) {
+        colorize = false;
+    }
 
-        //with prefix
-        assert_eq!(Format::LowerHex.format(b, true), "0xff");
-        assert_eq!(Format::LowerHex.format(b, true), format!("{:#04x}", b));
+    // HX_COLOR and styles force color like --color, the config file can turn
+    // color off, but not force it on for pipes
+    let when = |color: &str| {
+        args::parse_when(color)
+            .ok_or_else(|| format!("--color <always|auto|never> expected: {}", color))
+    };
+    let forced = match matches.get_one::<args::When>(ARG_TNT) {
+        Some(&when) => Some(when),
+        None if matches.get_flag(ARG_CLZ) => Some(args::When::Always),
+        None => setting(matches, &defaults[..2], ARG_CLR, when)?,
+    };
+    match (forced, setting(matches, &defaults[2..], ARG_CLR, when)?) {
+        (Some(args::When::Always), _) => colorize = true,
+        (Some(args::When::Never), _) => colorize = false,
+        (Some(args::When::Auto), _) => {}
+        (None, Some(args::When::Never)) => colorize = false,
+        (None, _) => {}
+    }
 
-        //without prefix
-        assert_eq!(Format::LowerHex.format(b, false), "ff");
-        assert_eq!(Format::LowerHex.format(b, false), format!("{:02x}", b));
+    // older Windows consoles print ANSI codes as they are unless told to
+    // interpret them
+    #[cfg(windows)]
+    if colorize && io::stdout().is_terminal() && ansi_term::enable_ansi_support().is_err() {
+        colorize = false;
     }
 
-    /// hex upper hex, takes u8
-    #[test]
-    fn test_hex_upper_hex() {
-        let b: u8 = <u8>::max_value();
+    let color_mode = setting(
+        matches,
+        &defaults,
+        ARG_CMD,
+        named("color mode", ColorMode::by_name),
+    )?;
+    let charset = setting(
+        matches,
+        &defaults,
+        ARG_CHS,
+        named("charset", Charset::by_name),
+    )?;
+    let ascii_style = setting(
+        matches,
+        &defaults,
+        ARG_ASY,
+        named("ascii style", AsciiStyle::by_name),
+    )?;
+    let nonprintable = setting(matches, &defaults, ARG_NPC, |value| {
+        args::char_value(value)
+            .map_err(|_| format!("--nonprintable-char <char> expected: {}", value))
+    })?;
+    let nul = setting(matches, &defaults, ARG_NUL, |value| {
+        args::char_value(value).map_err(|_| format!("--nul-char <char> expected: {}", value))
+    })?;
 
-        //with prefix
-        assert_eq!(Format::UpperHex.format(b, true), "0xFF");
-        assert_eq!(Format::UpperHex.format(b, true), format!("{:#04X}", b));
+    let frame = flag_setting(matches, &defaults, ARG_FRM);
+    // a pane chosen on the command line overrides the other one set as a
+    // default
+    let panes = if matches.get_flag(ARG_AOL) {
+        Panes::Text
+    } else if matches.get_flag(ARG_NAS) {
+        Panes::Hex
+    } else if flag_setting(matches, &defaults, ARG_AOL) {
+        Panes::Text
+    } else if flag_setting(matches, &defaults, ARG_NAS) {
+        Panes::Hex
+    } else {
+        Panes::Both
+    };
+    let text = |value: &str| Ok(value.to_string());
+    let separator = setting(matches, &defaults, ARG_SEP, text)?;
+    let separator = match separator {
+        Some(separator) => separator,
+        None if frame => " | ".to_string(),
+        None => " ".to_string(),
+    };
+    let offset_separator =
+        setting(matches, &defaults, ARG_OSP, text)?.unwrap_or_else(|| ": ".to_string());
 
-        // without prefix
-        assert_eq!(Format::UpperHex.format(b, false), "FF");
-        assert_eq!(Format::UpperHex.format(b, false), format!("{:02X}", b));
+    let mut theme = setting(matches, &defaults, ARG_THM, named("theme", Theme::by_name))?
+        .unwrap_or(Theme::DEFAULT);
+    for (name, color) in [
+        (ARG_OCL, &mut theme.offset),
+        (ARG_SCL, &mut theme.separator),
+        (ARG_TCL, &mut theme.trailer),
+    ] {
+        if let Some(value) = setting(matches, &defaults, name, args::color_value)? {
+            *color = value;
+        }
     }
 
-    /// hex binary, takes u8
-    #[test]
-    fn test_hex_binary() {
-        let b: u8 = <u8>::max_value();
+    let prefix = match matches.get_flag(ARG_NPX) {
+        true => false,
+        false => setting(matches, &defaults, ARG_PFX, |value| {
+            args::parse_switch(value).ok_or_else(|| format!("--prefix <0|1> expected: {}", value))
+        })?
+        .unwrap_or(true),
+    };
 
-        // with prefix
-        assert_eq!(Format::Binary.format(b, true), "0b11111111");
-        assert_eq!(Format::Binary.format(b, true), format!("{:#010b}", b));
+    // xxd -e swaps 4 byte groups unless told otherwise, whatever the style
+    let little_endian_groups = flag_setting(matches, &defaults, ARG_LEG);
+    let group_defaults = match little_endian_groups {
+        true => &defaults[1..],
+        false => &defaults[..],
+    };
+    let group_size = setting(matches, group_defaults, ARG_GRP, |value| {
+        args::group_size_value(value)
+            .map_err(|e| format!("-g, --group-size <integer> expected: {}", e))
+    })?
+    .unwrap_or(if little_endian_groups { 4 } else { 1 });
 
-        // without prefix
-        assert_eq!(Format::Binary.format(b, false), "11111111");
-        assert_eq!(Format::Binary.format(b, false), format!("{:08b}", b));
-    }
+    let block_size = setting(matches, &defaults, ARG_BLK, |value| {
+        args::parse_size(value).map_err(|e| format!("--block-size <integer> expected: {}", e))
+    })?
+    .unwrap_or(0);
 
-    #[test]
-    fn test_line_struct() {
-        let mut ascii_line: Line = Line::new();
-        ascii_line.ascii.push(b'.');
-        assert_eq!(ascii_line.ascii[0], b'.');
-        assert_eq!(ascii_line.offset, 0x0);
-    }
+    let header = setting(matches, &defaults, ARG_HDR, |value| {
+        value
+            .parse::<u64>()
+            .map_err(|e| format!("--header <lines> expected: {}", e))
+    })?;
 
-    use assert_cmd::Command;
+    let mut offset_format = match setting(
+        matches,
+        &defaults,
+        ARG_OFF,
+        named("offset format", args::offset_format_by_name),
+    )? {
+        Some(OffsetFormat::Sector(_)) => match matches.get_one::<u64>(ARG_SSZ) {
+            Some(&sector_size) => OffsetFormat::Sector(sector_size),
+            None => {
+                return Err(HxError::Usage(
+                    "--offset-format sector needs --sector-size".to_string(),
+                ))
+            }
+        },
+        Some(offset_format) => offset_format,
+        None => OffsetFormat::Hex,
+    };
 
-    /// target/debug/hx -ar tests/files/tiny.txt
-    /// assert may have unexpected results depending on terminal:
-    ///     .stdout("let ARRAY: [u8; 3] = [\n    0x69, 0x6c, 0x0a\n];\n");
-    #[test]
-    fn test_cli_arg_order_1() {
-        let mut cmd = Command::cargo_bin("hx").unwrap();
-        let assert = cmd.arg("-ar").arg("tests/files/tiny.txt").assert();
-        assert.success().code(0);
+    let upper = match setting(
+        matches,
+        &defaults,
+        ARG_OCS,
+        named("offset case", args::OffsetCase::by_name),
+    )? {
+        Some(args::OffsetCase::Upper) => true,
+        Some(args::OffsetCase::MatchFormat) => matches!(format_out, Format::UpperHex),
+        _ => false,
+    };
+    if upper && offset_format == OffsetFormat::Hex {
+        offset_format = OffsetFormat::UpperHex;
     }
 
-    /// target/debug/hx tests/files/tiny.txt -ar
-    /// assert may have unexpected results depending on terminal:
-    ///     .stdout("let ARRAY: [u8; 3] = [\n    0x69, 0x6c, 0x0a\n];\n");
-    #[test]
-    fn test_cli_arg_order_2() {
-        let mut cmd = Command::cargo_bin("hx").unwrap();
-        let assert = cmd.arg("tests/files/tiny.txt").arg("-ar").assert();
-        assert.success().code(0);
-    }
+    let offset_width = setting(matches, &defaults, ARG_OFW, |value| {
+        value
+            .parse::<usize>()
+            .map_err(|e| format!("--offset-width <integer> expected: {}", e))
+    })?
+    .unwrap_or(6);
 
-    /// target/debug/hx --len tests/files/tiny.txt
-    ///     error: invalid digit found in string
-    #[test]
-    fn test_cli_missing_param_value() {
-        let mut cmd = Command::cargo_bin("hx").unwrap();
-        let assert = cmd.arg("--len").arg("tests/files/tiny.txt").assert();
-        assert.failure().code(1);
-    }
+    let base_address = matches.get_one::<u64>(ARG_BAS).copied().unwrap_or(0);
 
-    #[test]
-    fn test_cli_input_missing_file() {
-        let mut cmd = Command::cargo_bin("hx").unwrap();
-        let assert = cmd.arg("missing-file").assert();
-        assert.failure().code(1);
-    }
+    let word_size = setting(matches, &defaults, ARG_WSZ, |value| {
+        args::parse_word_size(value)
+            .ok_or_else(|| format!("--word-size <1|2|4|8> expected: {}", value))
+    })?
+    .unwrap_or(1);
+    // a line holds whole words
+    let column_width = match columns {
+        Some(args::Columns::Count(count)) if count % word_size != 0 => {
+            return Err(HxError::Usage(format!(
+                "-c, --cols {} is not a multiple of --word-size {}",
+                count, word_size
+            )))
+        }
+        Some(args::Columns::Count(count)) => count,
+        _ => 10u64.next_multiple_of(word_size),
+    };
 
-    #[test]
-    fn test_cli_input_directory() {
-        let mut cmd = Command::cargo_bin("hx").unwrap();
-        let assert = cmd.arg("src").assert();
-        assert.failure().code(1);
+    let endian = setting(
+        matches,
+        &defaults,
+        ARG_END,
+        named("endianness", Endian::by_name),
+    )?;
+
+    let summary = match matches.get_flag(ARG_NSM) {
+        true => Summary::None,
+        false => setting(
+            matches,
+            &defaults,
+            ARG_SUM,
+            named("summary", Summary::by_name),
+        )?
+        .unwrap_or(Summary::Bytes),
+    };
+
+    let config = HexConfig::new()
+        .columns(column_width)
+        .group_size(group_size)
+        .little_endian_groups(little_endian_groups)
+        .word_size(word_size)
+        .block_size(block_size)
+        .endian(endian.unwrap_or(Endian::Little))
+        .format(format_out)
+        .stacked(stacked)
+        .color(colorize)
+        .color_mode(color_mode.unwrap_or(ColorMode::Value))
+        .theme(theme)
+        .prefix(prefix)
+        .offset_format(offset_format)
+        .offset_width(offset_width)
+        .offset_separator(&offset_separator)
+        .squeeze(!flag_setting(matches, &defaults, ARG_NSQ))
+        .base_address(base_address)
+        .charset(charset.unwrap_or(Charset::Ascii))
+        .ascii_style(ascii_style.unwrap_or(AsciiStyle::Dots))
+        .nonprintable(nonprintable.unwrap_or('.'))
+        .nul(nul)
+        .separator(&separator)
+        .frame(frame)
+        .gutter(flag_setting(matches, &defaults, ARG_GUT))
+        .panes(panes)
+        .line_numbers(flag_setting(matches, &defaults, ARG_LNO))
+        .end_offsets(flag_setting(matches, &defaults, ARG_EOS))
+        .header(header)
+        .summary(summary)
+        .length(truncate_len);
+    if fit_columns {
+        let columns = output::fit_columns(&config.dump_options(), output::terminal_size().0);
+        return Ok(config.columns(columns));
     }
+    Ok(config)
+}
 
-    #[test]
-    fn test_cli_input_stdin() {
-        let mut cmd = Command::cargo_bin("hx").unwrap();
-        let assert = cmd.arg("-t0").write_stdin("012").assert();
-        assert.success().code(0).stdout(
-            "0x000000: 0x30 0x31 0x32                                    012\n   bytes: 3\n",
+/// Write a hex dump of `reader` to `writer` as configured by `config`,
+/// without going through command line parsing. Returns the number of
+/// bytes dumped.
+///
+/// # Arguments
+///
+/// * `reader` - input to be dumped.
+/// * `writer` - output sink.
+/// * `config` - dump configuration.
+pub fn dump(reader: &mut dyn Read, writer: &mut impl Write, config: &HexConfig) -> io::Result<u64> {
+    let start_offset = buffer::skip(reader, SeekFrom::Start(config.start_offset()))?;
+    output::dump(
+        writer,
+        reader,
+        start_offset,
+        config.truncate_len(),
+        &config.dump_options(),
+        &[],
+        None,
+        None,
+    )
+}
+
+/// Open the input, honoring seek, tail and mmap, and write it to `out` in
+/// the selected output mode.
+///
+/// # Arguments
+///
+/// * `matches` - Argument matches from command line.
+/// * `out` - output sink.
+/// * `options` - dump options.
+/// * `truncate_len` - truncate to length, 0 reads to the end of input.
+/// * `input` - input file path, `None` reads stdin.
+fn output_input(
+    matches: &ArgMatches,
+    out: &mut impl Write,
+    options: &DumpOptions,
+    truncate_len: u64,
+    input: Option<&str>,
+) -> Result<(), HxError> {
+    let started = Instant::now();
+    let decode = matches.get_one::<u64>(ARG_DEC).copied();
+    let extract = matches.get_one::<args::ByteRange>(ARG_EXT).copied();
+    let seek = match matches.get_one::<SeekFrom>(ARG_SKP) {
+        Some(&seek) => Some(seek),
+        None if decode.is_some() => decode.map(SeekFrom::Start),
+        None if extract.is_some() => extract.map(|range| SeekFrom::Start(range.start)),
+        None => matches
+            .get_one::<args::ByteRange>(ARG_RNG)
+            .map(|range| SeekFrom::Start(range.start)),
+    };
+    let tail = matches.get_one::<u64>(ARG_TAL).copied();
+    // a live input is stamped with when each line arrived, and serial
+    // input is by default
+    let untimed = ARGS_UNTIMED
+        .iter()
+        .any(|name| matches.value_source(name) == Some(ValueSource::CommandLine));
+    let timestamps = match matches.get_one::<String>(ARG_TSP) {
+        Some(style) => Some(style.as_str()),
+        None if matches.contains_id(ARG_SER) && !untimed => Some("relative"),
+        None => None,
+    }
+    .filter(|_| input.is_none())
+    .map(|style| output::Timestamps {
+        origin: (style == "relative").then(SystemTime::now),
+        arrivals: Arc::new(Mutex::new(buffer::Arrivals::new())),
+    });
+    let stamped: DumpOptions;
+    let options = match &timestamps {
+        Some(timestamps) => {
+            stamped = DumpOptions {
+                timestamps: Some(timestamps.clone()),
+                ..options.clone()
+            };
+            &stamped
+        }
+        None => options,
+    };
+    let mut start_offset: u64 = 0x0;
+    let file = match input {
+        Some(input) => Some(open_input(input)?),
+        None => None,
+    };
+    let seekable = matches!(file, Some((_, true)));
+    // size of the input left to read, for the progress time estimate
+    let mut size: Option<u64> = None;
+    let encoding = matches.get_one::<encoding::Encoding>(ARG_IEN).copied();
+    // --sector-size widens the dump out to whole sectors, from the sector
+    // of the requested offset
+    let sector_size = matches.get_one::<u64>(ARG_SSZ).copied();
+    let mut requested_offset: Option<u64> = None;
+    let mut buf: Box<dyn BufRead> = match file {
+        // decoded input is a stream, even when the encoded one is a file
+        Some((mut file, true)) if encoding.is_none() => {
+            let len = input_len(&mut file)?;
+            if let Some(seek) = seek {
+                start_offset = match seek {
+                    // like --tail, more than the whole input starts at its start
+                    SeekFrom::End(from_end) => {
+                        file.seek(SeekFrom::Start(len.saturating_sub(from_end.unsigned_abs())))?
+                    }
+                    seek => file.seek(seek)?,
+                };
+            }
+            if let Some(tail) = tail {
+                start_offset = file.seek(SeekFrom::Start(len.saturating_sub(tail)))?;
+            }
+            if let Some(sector_size) = sector_size {
+                requested_offset = Some(start_offset);
+                start_offset =
+                    file.seek(SeekFrom::Start(start_offset - start_offset % sector_size))?;
+            }
+            size = Some(len.saturating_sub(start_offset));
+            if matches.get_flag(ARG_MAP) {
+                open_mmap(&file, start_offset)?
+            } else {
+                Box::new(BufReader::new(file))
+            }
+        }
+        Some((_, false)) if matches.get_flag(ARG_MAP) => {
+            return Err(HxError::Usage(
+                "--mmap needs a regular file, not a pipe or device".to_string(),
+            ))
+        }
+        Some((file, _)) => open_stream(
+            encoding::decode(encoding, BufReader::new(file)),
+            snap_seek(seek, sector_size, &mut requested_offset),
+            tail,
+            &mut start_offset,
+        )?,
+        None => {
+            let source: Box<dyn Read + Send> =
+                if let Some(address) = matches.get_one::<String>(ARG_LSN) {
+                    net::listen(&net::Address::parse(address))?
+                } else if let Some(address) = matches.get_one::<String>(ARG_CON) {
+                    net::connect(&net::Address::parse(address))?
+                } else if let Some(device) = matches.get_one::<String>(ARG_SER) {
+                    let baud = matches.get_one::<u32>(ARG_BAU).copied();
+                    serial::open(device, baud.unwrap_or(serial::DEFAULT_BAUD))?
+                } else {
+                    Box::new(io::stdin())
+                };
+            let idle_flush = matches.get_one::<u64>(ARG_IFL).copied();
+            let source: Box<dyn Read> = match (&timestamps, idle_flush) {
+                (None, None) => source,
+                (timestamps, idle_flush) => Box::new(buffer::Timed::new(
+                    source,
+                    idle_flush.map_or(STREAM_FLUSH, Duration::from_millis),
+                    timestamps
+                        .as_ref()
+                        .map(|timestamps| timestamps.arrivals.clone()),
+                )),
+            };
+            open_stream(
+                encoding::decode(encoding, BufReader::new(source)),
+                snap_seek(seek, sector_size, &mut requested_offset),
+                tail,
+                &mut start_offset,
+            )?
+        }
+    };
+    let truncate_len = match (sector_size, requested_offset) {
+        (Some(sector_size), Some(requested)) if truncate_len > 0 => {
+            (requested + truncate_len).div_ceil(sector_size) * sector_size - start_offset
+        }
+        (Some(sector_size), None) if truncate_len > 0 => {
+            truncate_len.div_ceil(sector_size) * sector_size
+        }
+        _ => truncate_len,
+    };
+    // a length past the end of a file would silently read short
+    let truncate_len = match (input, size) {
+        (Some(input), Some(size))
+            if truncate_len > size
+                && matches.contains_id(ARG_LEN)
+                && !matches.get_flag(ARG_FOL) =>
+        {
+            eprintln!(
+                "hx: {}: warning: --len {} runs past the end of the file, reading {} bytes from offset {}",
+                input,
+                truncate_len,
+                size,
+                start_offset
+            );
+            size
+        }
+        _ => truncate_len,
+    };
+    if matches.get_flag(ARG_FOL) && input.is_some() {
+        let idle_flush = matches.get_one::<u64>(ARG_IFL).copied();
+        buf = Box::new(BufReader::new(buffer::Follow::new(
+            buf,
+            FOLLOW_POLL,
+            idle_flush.map(Duration::from_millis),
+        )));
+    }
+    // simple obfuscation is peeled off before anything reads the input,
+    // in command line order
+    let mut ops: Vec<(usize, transform::Op)> = Vec::new();
+    for name in [ARG_XOR, ARG_ROT, ARG_ADD] {
+        let values = matches
+            .get_many::<transform::Op>(name)
+            .into_iter()
+            .flatten();
+        let indices = matches.indices_of(name).into_iter().flatten();
+        ops.extend(indices.zip(values.cloned()));
+    }
+    if !ops.is_empty() {
+        ops.sort_by_key(|(index, _)| *index);
+        let ops = ops.into_iter().map(|(_, op)| op).collect();
+        buf = Box::new(BufReader::new(transform::Transformed::new(
+            buf,
+            ops,
+            start_offset,
+        )));
+    }
+    // progress only makes sense when the dump goes somewhere else
+    if matches.get_flag(ARG_PRG) && io::stderr().is_terminal() && !matches.get_flag(ARG_FOL) {
+        let total = match (size, truncate_len) {
+            (_, 0) => size,
+            (Some(size), len) => Some(size.min(len)),
+            (None, len) => Some(len),
+        };
+        buf = Box::new(BufReader::new(progress::Progress::new(buf, total)));
+    }
+    // the detected type is printed first, and the bytes it was detected
+    // from are put back in front of the input
+    if matches.get_flag(ARG_DET) {
+        let mut head: Vec<u8> = Vec::with_capacity(magic::HEAD_LEN);
+        buf.by_ref()
+            .take(magic::HEAD_LEN as u64)
+            .read_to_end(&mut head)?;
+        let name = magic::detect(&head).map_or("unknown", |signature| signature.name);
+        writeln!(out, "    type: {}", name)?;
+        buf = Box::new(io::Cursor::new(head).chain(buf));
+    }
+    // statistics and digests cover exactly the dumped range, so the
+    // input is truncated before it is measured
+    let stats_mode = matches.get_one::<String>(ARG_STA).map(String::as_str);
+    let stats = Rc::new(RefCell::new(stats::Stats::new()));
+    let mut algorithms: Vec<digest::Algorithm> = matches
+        .get_many::<digest::Algorithm>(ARG_DGS)
+        .into_iter()
+        .flatten()
+        .copied()
+        .collect();
+    // a verbose summary of a hex dump includes a digest, except with
+    // --threads which reads the file by itself
+    let hex_dump = stats_mode != Some("only")
+        && ![ARG_REV, ARG_FRA, ARG_PLN, ARG_CNT]
+            .iter()
+            .any(|flag| matches.get_flag(flag))
+        && ![ARG_ARR, ARG_STR, ARG_FRX, ARG_CTX, ARG_THR]
+            .iter()
+            .any(|name| matches.contains_id(name))
+        && matches
+            .get_one::<String>(ARG_OFM)
+            .is_none_or(|format| format != "json");
+    if algorithms.is_empty() && options.summary == Summary::Verbose && hex_dump {
+        algorithms.push(digest::Algorithm::Sha256);
+    }
+    let digests: Rc<RefCell<Vec<digest::Digest>>> = Rc::new(RefCell::new(
+        algorithms.into_iter().map(digest::Digest::new).collect(),
+    ));
+    if stats_mode.is_some() || !digests.borrow().is_empty() {
+        let mut measured: Box<dyn Read> = if truncate_len > 0 {
+            Box::new(buf.take(truncate_len))
+        } else {
+            Box::new(buf)
+        };
+        if stats_mode.is_some() {
+            measured = Box::new(stats::Counted::new(measured, stats.clone()));
+        }
+        if !digests.borrow().is_empty() {
+            measured = Box::new(digest::Hashed::new(measured, digests.clone()));
+        }
+        buf = Box::new(BufReader::new(measured));
+    }
+
+    // array, strings and reverse output modes are mutually exclusive
+    if decode.is_some() {
+        let mut bytes: Vec<u8> = Vec::with_capacity(decode::DECODE_LEN);
+        buf.take(decode::DECODE_LEN as u64)
+            .read_to_end(&mut bytes)?;
+        if bytes.is_empty() {
+            return Err(HxError::Usage(format!(
+                "--decode offset {} is past the end of the input",
+                start_offset
+            )));
+        }
+        let offset = options.offset_format.format(
+            options.base_address.wrapping_add(start_offset),
+            options.offset_width,
+        );
+        decode::write_report(out, &offset, &bytes)?;
+    } else if let Some(range) = extract {
+        // the range is copied as is, streaming, so huge inputs can be carved
+        let copied = match range.length() {
+            Some(len) => io::copy(&mut buf.take(len), out)?,
+            None => io::copy(&mut buf, out)?,
+        };
+        if range.length().is_some_and(|len| copied < len) {
+            return Err(HxError::Usage(format!(
+                "--extract range ends past the end of the input at {:#x}",
+                start_offset + copied
+            )));
+        }
+    } else if stats_mode == Some("only") {
+        io::copy(&mut buf, &mut io::sink())?;
+    } else if matches.get_flag(ARG_FRA) {
+        reverse::reverse_array(&mut buf, out)?;
+    } else if matches.get_flag(ARG_REV) {
+        let plain = matches.get_flag(ARG_PLN);
+        if plain {
+            reverse::reverse_plain(&mut buf, out)?;
+        } else if options.panes == Panes::Text {
+            return Err(HxError::Usage(
+                "-R cannot reverse an --ascii-only dump, which has no octets".to_string(),
+            ));
+        } else {
+            reverse::reverse(
+                &mut buf,
+                out,
+                options.format,
+                options.offset_format,
+                options.base_address,
+                &reverse::Layout::from(options),
+            )?;
+        }
+    } else if let Some(array) = matches.get_one::<String>(ARG_ARR) {
+        // xxd -i names the array after the input file
+        let name = match (matches.get_one::<String>(ARG_NAM), input) {
+            (Some(name), _) => Some(name.to_string()),
+            (None, Some(input)) if array == "i" => Some(output::c_identifier(input)),
+            _ => None,
+        };
+        let binding = if matches.get_flag(ARG_ACN) {
+            ArrayBinding::Const
+        } else if matches.get_flag(ARG_AST) {
+            ArrayBinding::Static
+        } else {
+            ArrayBinding::Let
+        };
+        let element = matches
+            .get_one::<ArrayElement>(ARG_ATY)
+            .copied()
+            .unwrap_or(ArrayElement::U8);
+        let array_options = ArrayOptions {
+            name,
+            binding,
+            element,
+            rustfmt_skip: matches.get_flag(ARG_RFS),
+            module: matches.get_one::<String>(ARG_MOD).cloned(),
+        };
+        output_array_to_writer(
+            out,
+            array,
+            &array_options,
+            buf,
+            truncate_len,
+            options.column_width,
+        )?;
+    } else if matches.get_flag(ARG_CNT) || matches.contains_id(ARG_CTX) {
+        let mut matcher = match search_matcher(matches, start_offset) {
+            Some(matcher) => matcher,
+            None => {
+                return Err(HxError::Usage(
+                    "--count and --context need --find or --find-regex".to_string(),
+                ))
+            }
+        };
+        match matches.get_one::<u64>(ARG_CTX) {
+            Some(&context) => {
+                search::output_context(
+                    out,
+                    &mut buf,
+                    truncate_len,
+                    &mut matcher,
+                    start_offset,
+                    context,
+                    options,
+                )?;
+            }
+            None => {
+                let count = search::count_matches(&mut buf, truncate_len, &mut matcher)?;
+                writeln!(out, "{}", count)?;
+            }
+        }
+    } else if let Some(search::Matcher::Regex(mut finder)) = search_matcher(matches, start_offset) {
+        search::output_regex(out, &mut buf, truncate_len, &mut finder, options)?;
+    } else if let Some(&min_len) = matches.get_one::<u64>(ARG_STR) {
+        let encoding = matches
+            .get_one::<strings::Encoding>(ARG_SEN)
+            .copied()
+            .unwrap_or(strings::Encoding::Ascii);
+        let mut scanner = strings::StringScanner::new(encoding, min_len as usize, start_offset);
+        strings::output_strings(out, &mut buf, truncate_len, &mut scanner, options)?;
+    } else if matches.get_flag(ARG_PLN) {
+        // xxd -p wraps at 30 bytes unless told otherwise
+        let bytes_per_line = if matches.contains_id(ARG_COL) {
+            options.column_width
+        } else {
+            30
+        };
+        output::output_plain(out, &mut buf, truncate_len, bytes_per_line)?;
+    } else if matches
+        .get_one::<String>(ARG_OFM)
+        .is_some_and(|format| format == "json")
+    {
+        serialize::output_json(
+            out,
+            &mut buf,
+            start_offset,
+            truncate_len,
+            options.column_width,
+        )?;
+    } else if let Some(&arch) = matches.get_one::<disasm::Arch>(ARG_DAS) {
+        disasm::output_disasm(out, &mut buf, start_offset, truncate_len, arch, options)?;
+    } else {
+        let mut marks: Vec<Highlight> = matches
+            .get_many::<args::Mark>(ARG_HIL)
+            .into_iter()
+            .flatten()
+            .map(|mark| mark.highlight(options.theme.highlight))
+            .collect();
+        let mut annotator = match matches.get_one::<String>(ARG_TPL) {
+            Some(path) => {
+                let text = fs::read_to_string(path)
+                    .map_err(|e| io::Error::new(e.kind(), format!("template {}: {}", path, e)))?;
+                let template = template::Template::parse(&text)
+                    .map_err(|e| HxError::Parse(format!("template {}: {}", path, e)))?;
+                marks.extend(template.highlights());
+                Some(template::Annotator::new(template))
+            }
+            None => None,
+        };
+        let mut finder = match search_matcher(matches, start_offset) {
+            Some(search::Matcher::Pattern(finder)) => Some(finder),
+            _ => None,
+        };
+        match (matches.get_one::<u64>(ARG_THR), input) {
+            (Some(&threads), Some(input)) if seekable => {
+                let threads = match threads {
+                    0 => thread::available_parallelism().map_or(1, usize::from),
+                    threads => threads as usize,
+                };
+                let bytes = output::dump_chunked(
+                    out,
+                    Path::new(input),
+                    start_offset,
+                    truncate_len,
+                    options,
+                    &marks,
+                    threads,
+                )?;
+                output::print_summary(out, start_offset, bytes, started.elapsed(), options)?;
+            }
+            (Some(_), _) => {
+                return Err(HxError::Usage(
+                    "--threads needs a regular file, not stdin, a pipe or device".to_string(),
+                ))
+            }
+            (None, _) => {
+                let bytes = output::dump(
+                    out,
+                    &mut buf,
+                    start_offset,
+                    truncate_len,
+                    options,
+                    &marks,
+                    finder.as_mut(),
+                    annotator.as_mut(),
+                )?;
+                output::print_summary(out, start_offset, bytes, started.elapsed(), options)?;
+            }
+        }
+    }
+    if stats_mode == Some("only") {
+        output::print_trailer(out, stats.borrow().total(), options)?;
+    }
+    for digest in digests.borrow().iter() {
+        writeln!(out, "{:>8}: {}", digest.algorithm().name(), digest.hex())?;
+    }
+    if stats_mode.is_some() {
+        stats.borrow().write_report(out)?;
+    }
+    Ok(())
+}
+
+/// Open input file `input`. Returns the file and whether it can seek:
+/// pipes and character devices such as /dev/urandom are read as streams,
+/// while directories and sockets are rejected with a hint to pipe their
+/// data in instead. Errors name `input`, since `--diff` and `--cmp` open
+/// two of them.
+///
+/// # Arguments
+///
+/// * `input` - input file path.
+fn open_input(input: &str) -> io::Result<(fs::File, bool)> {
+    let named = |e: io::Error| io::Error::new(e.kind(), format!("{}: {}", input, e));
+    let file_type = fs::metadata(input).map_err(named)?.file_type();
+    let expected = |kind: &str, hint: String| {
+        io::Error::other(format!(
+            "expected a file, got a {}, pipe its data into hx instead: {} | hx",
+            kind, hint
+        ))
+    };
+    if file_type.is_dir() {
+        return Err(expected("directory", format!("tar -cf - {}", input)));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_socket() {
+            return Err(expected("socket", format!("nc -U {}", input)));
+        }
+        if file_type.is_fifo() || file_type.is_char_device() {
+            return Ok((fs::File::open(input).map_err(named)?, false));
+        }
+    }
+    Ok((fs::File::open(input).map_err(named)?, true))
+}
+
+/// Length of a seekable input. Block devices report a length of 0, so
+/// theirs is found by seeking to the end.
+///
+/// # Arguments
+///
+/// * `file` - input, left at the position it was at.
+fn input_len(file: &mut fs::File) -> io::Result<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file.metadata()?.file_type().is_block_device() {
+            let position = file.stream_position()?;
+            let len = file.seek(SeekFrom::End(0))?;
+            file.seek(SeekFrom::Start(position))?;
+            return Ok(len);
+        }
+    }
+    Ok(file.metadata()?.len())
+}
+
+/// An absolute `seek` moved back to the start of its sector, for an
+/// input that cannot seek back once positioned. The offset asked for is
+/// kept in `requested_offset`.
+///
+/// # Arguments
+///
+/// * `seek` - seek selected on the command line.
+/// * `sector_size` - sector size, if the dump is aligned to sectors.
+/// * `requested_offset` - set to the offset asked for when it is moved.
+fn snap_seek(
+    seek: Option<SeekFrom>,
+    sector_size: Option<u64>,
+    requested_offset: &mut Option<u64>,
+) -> Option<SeekFrom> {
+    match (seek, sector_size) {
+        (Some(SeekFrom::Start(offset)), Some(sector_size)) => {
+            *requested_offset = Some(offset);
+            Some(SeekFrom::Start(offset - offset % sector_size))
+        }
+        (seek, _) => seek,
+    }
+}
+
+/// Position a reader that cannot seek, such as stdin, by reading through
+/// it. `start_offset` is set to the offset of the first byte returned.
+///
+/// # Arguments
+///
+/// * `reader` - input stream.
+/// * `seek` - position to skip to.
+/// * `tail` - keep only the last `tail` bytes.
+/// * `start_offset` - offset of the returned reader in the input.
+fn open_stream(
+    mut reader: impl BufRead + 'static,
+    seek: Option<SeekFrom>,
+    tail: Option<u64>,
+    start_offset: &mut u64,
+) -> io::Result<Box<dyn BufRead>> {
+    if let Some(seek) = seek {
+        *start_offset = buffer::skip(&mut reader, seek)?;
+    }
+    match tail {
+        Some(tail) => {
+            let (tail_offset, bytes) = buffer::tail(&mut reader, tail)?;
+            *start_offset += tail_offset;
+            Ok(Box::new(io::Cursor::new(bytes)))
+        }
+        None => Ok(Box::new(reader)),
+    }
+}
+
+/// Search selected by `--find` or `--find-regex`, if any.
+///
+/// # Arguments
+///
+/// * `matches` - argument matches from command line.
+/// * `start_offset` - offset of the first byte searched.
+fn search_matcher(matches: &ArgMatches, start_offset: u64) -> Option<search::Matcher> {
+    if let Some(pattern) = matches.get_one::<search::Pattern>(ARG_FND) {
+        return Some(search::Matcher::Pattern(search::Finder::new(
+            pattern.clone(),
+            start_offset,
+        )));
+    }
+    matches
+        .get_one::<regex::bytes::Regex>(ARG_FRX)
+        .map(|regex| search::Matcher::Regex(search::RegexFinder::new(regex.clone(), start_offset)))
+}
+
+/// Memory-map `file` and position the returned reader at `start_offset`.
+#[cfg(all(unix, feature = "mmap"))]
+fn open_mmap(file: &fs::File, start_offset: u64) -> io::Result<Box<dyn BufRead>> {
+    let mut cursor = io::Cursor::new(buffer::Mmap::open(file)?);
+    cursor.set_position(start_offset);
+    Ok(Box::new(cursor))
+}
+
+/// Memory-map `file` and position the returned reader at `start_offset`.
+#[cfg(not(all(unix, feature = "mmap")))]
+fn open_mmap(_file: &fs::File, _start_offset: u64) -> io::Result<Box<dyn BufRead>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "--mmap requires hx to be built with the mmap feature on a unix platform",
+    ))
+}
+
+/// Returns true if the input is read from stdin: no input file is given
+/// and stdin is not a terminal.
+///
+/// # Arguments
+///
+/// * `matches` - argument matches.
+pub fn is_stdin(matches: &ArgMatches) -> bool {
+    stdin_input(matches, io::stdin().is_terminal())
+}
+
+/// `is_stdin` for a stdin that is or is not a `terminal`.
+fn stdin_input(matches: &ArgMatches, terminal: bool) -> bool {
+    !terminal && !matches.contains_id(ARG_INP)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    /// @see (https://users.rust-lang.org/t/how-to-test-output-to-stdout/4877/6)
+    #[test]
+    fn test_offset() {
+        let b: u64 = 0x6;
+        assert_eq!(offset(b), "0x000006");
+        assert_eq!(offset(b), format!("{:#08x}", b));
+    }
+
+    /// hex octal, takes u8
+    #[test]
+    pub fn test_hex_octal() {
+        let b: u8 = 0x6;
+
+        //with prefix
+        assert_eq!(Format::Octal.format(b, true), "0o0006");
+        assert_eq!(Format::Octal.format(b, true), format!("{:#06o}", b));
+
+        //without prefix
+        assert_eq!(Format::Octal.format(b, false), "0006");
+        assert_eq!(Format::Octal.format(b, false), format!("{:04o}", b));
+    }
+
+    /// hex lower hex, takes u8
+    #[test]
+    #[allow(clippy::legacy_numeric_constants)]
+    fn test_hex_lower_hex() {
+        let b: u8 = <u8>::max_value(); // 255
+
+        //with prefix
+        assert_eq!(Format::LowerHex.format(b, true), "0xff");
+        assert_eq!(Format::LowerHex.format(b, true), format!("{:#04x}", b));
+
+        //without prefix
+        assert_eq!(Format::LowerHex.format(b, false), "ff");
+        assert_eq!(Format::LowerHex.format(b, false), format!("{:02x}", b));
+    }
+
+    /// hex upper hex, takes u8
+    #[test]
+    #[allow(clippy::legacy_numeric_constants)]
+    fn test_hex_upper_hex() {
+        let b: u8 = <u8>::max_value();
+
+        //with prefix
+        assert_eq!(Format::UpperHex.format(b, true), "0xFF");
+        assert_eq!(Format::UpperHex.format(b, true), format!("{:#04X}", b));
+
+        // without prefix
+        assert_eq!(Format::UpperHex.format(b, false), "FF");
+        assert_eq!(Format::UpperHex.format(b, false), format!("{:02X}", b));
+    }
+
+    /// hex binary, takes u8
+    #[test]
+    #[allow(clippy::legacy_numeric_constants)]
+    fn test_hex_binary() {
+        let b: u8 = <u8>::max_value();
+
+        // with prefix
+        assert_eq!(Format::Binary.format(b, true), "0b11111111");
+        assert_eq!(Format::Binary.format(b, true), format!("{:#010b}", b));
+
+        // without prefix
+        assert_eq!(Format::Binary.format(b, false), "11111111");
+        assert_eq!(Format::Binary.format(b, false), format!("{:08b}", b));
+    }
+
+    /// pointer and exponential formats are rejected before dumping
+    #[test]
+    fn test_format_implemented() {
+        assert!(!Format::Pointer.is_implemented());
+        assert!(!Format::UpperExp.is_implemented());
+        assert!(Format::SignedDecimal.is_implemented());
+    }
+
+    /// decimal and signed decimal, take u8
+    #[test]
+    fn test_hex_decimal() {
+        let b: u8 = u8::MAX;
+        assert_eq!(Format::Decimal.format(b, true), "255");
+        assert_eq!(Format::Decimal.format(0x7, false), "007");
+        assert_eq!(Format::SignedDecimal.format(b, true), "-001");
+        assert_eq!(Format::SignedDecimal.format(0x7f, false), "0127");
+        assert_eq!(
+            Format::SignedDecimal.format_word(0xff80, 2, false),
+            "-00128"
+        );
+    }
+
+    /// nibbles and other radixes, take u8
+    #[test]
+    fn test_hex_radix() {
+        assert_eq!(Format::Nibble.format(0x6c, true), "0b0110_1100");
+        assert_eq!(Format::Nibble.format(0x6c, false), "0110_1100");
+        assert_eq!(
+            Format::Nibble.format_word(0x6c69, 2, false),
+            "0110_1100_0110_1001"
+        );
+        assert_eq!(Format::Radix(4).format(0x6c, true), "1230");
+        assert_eq!(Format::Radix(4).format(0x03, false), "0003");
+        assert_eq!(Format::Radix(32).format(u8::MAX, false), "7v");
+        assert_eq!(Format::Radix(32).format_word(0xffff, 2, false), "1vvv");
+        assert_eq!(Format::Radix(3).digits(1), 6);
+        assert!(matches!(Format::by_name("r36"), Format::Radix(36)));
+        assert!(matches!(Format::by_name("r37"), Format::Unknown));
+        assert!(matches!(Format::by_name("r"), Format::Unknown));
+        assert!(!Format::Radix(1).is_implemented());
+    }
+
+    #[test]
+    fn test_octets_table() {
+        let formats = [
+            Format::Octal,
+            Format::LowerHex,
+            Format::UpperHex,
+            Format::Binary,
+            Format::Decimal,
+            Format::SignedDecimal,
+            Format::Nibble,
+            Format::Radix(4),
+            Format::Radix(32),
+        ];
+        for format in formats {
+            for prefix in [false, true] {
+                for b in 0..=u8::MAX {
+                    assert_eq!(format.octets(prefix)[b as usize], format.format(b, prefix));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dump_with_config() {
+        let config = HexConfig::new()
+            .columns(4)
+            .format(Format::UpperHex)
+            .prefix(false)
+            .seek(2)
+            .length(5);
+        let mut out: Vec<u8> = Vec::new();
+        let bytes = dump(&mut &b"0123456789"[..], &mut out, &config).unwrap();
+        assert_eq!(bytes, 5);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x000002: 32 33 34 35 2345\n0x000006: 36          6\n   bytes: 5\n"
+        );
+    }
+
+    #[test]
+    fn test_line_struct() {
+        let mut ascii_line: Line = Line::new();
+        ascii_line.hex_body = b"a\x01".to_vec();
+        ascii_line.fill_ascii();
+        assert_eq!(ascii_line.ascii, b"a.");
+        assert_eq!(ascii_line.offset, 0x0);
+    }
+
+    #[test]
+    fn test_stdin_input() {
+        let app = clap::Command::new("hx")
+            .arg(clap::Arg::new(ARG_COL).short('c').long(ARG_COL))
+            .arg(clap::Arg::new(ARG_PFX).short('p').long(ARG_PFX))
+            .arg(
+                clap::Arg::new(ARG_INP)
+                    .action(clap::ArgAction::Append)
+                    .num_args(1..),
+            );
+        let matches = |args: &[&str]| app.clone().get_matches_from(args);
+        // flags and option values only, in any order
+        assert!(stdin_input(&matches(&["hx"]), false));
+        assert!(stdin_input(&matches(&["hx", "-c", "4"]), false));
+        assert!(stdin_input(&matches(&["hx", "-c4", "-p", "0"]), false));
+        assert!(stdin_input(
+            &matches(&["hx", "--prefix=0", "--cols", "4"]),
+            false
+        ));
+        // an input file
+        assert!(!stdin_input(&matches(&["hx", "tiny.txt"]), false));
+        assert!(!stdin_input(
+            &matches(&["hx", "-c", "4", "tiny.txt"]),
+            false
+        ));
+        assert!(!stdin_input(&matches(&["hx", "tiny.txt", "-c4"]), false));
+        // nothing piped in
+        assert!(!stdin_input(&matches(&["hx"]), true));
+        assert!(!stdin_input(&matches(&["hx", "-c", "4"]), true));
+    }
+
+    use assert_cmd::Command;
+
+    /// The hx binary, run without the `HX_*` variables and config file of
+    /// whoever runs the tests.
+    fn hx_process() -> std::process::Command {
+        static EMPTY_CONFIG: OnceLock<std::path::PathBuf> = OnceLock::new();
+        let config = EMPTY_CONFIG.get_or_init(|| {
+            let path = env::temp_dir().join(format!("hx-config-{}.toml", std::process::id()));
+            fs::write(&path, "").unwrap();
+            path
+        });
+        let mut cmd = std::process::Command::new(assert_cmd::cargo::cargo_bin("hx"));
+        for (name, _) in env::vars_os() {
+            if name.to_string_lossy().starts_with("HX_") {
+                cmd.env_remove(name);
+            }
+        }
+        cmd.env("HX_CONFIG", config);
+        cmd
+    }
+
+    /// `hx_process` for assertions.
+    fn hx() -> Command {
+        Command::from_std(hx_process())
+    }
+
+    /// target/debug/hx -ar tests/files/tiny.txt
+    /// assert may have unexpected results depending on terminal:
+    ///     .stdout("let ARRAY: [u8; 3] = [\n    0x69, 0x6c, 0x0a\n];\n");
+    #[test]
+    fn test_cli_arg_order_1() {
+        let mut cmd = hx();
+        let assert = cmd.arg("-ar").arg("tests/files/tiny.txt").assert();
+        assert.success().code(0);
+    }
+
+    /// target/debug/hx tests/files/tiny.txt -ar
+    /// assert may have unexpected results depending on terminal:
+    ///     .stdout("let ARRAY: [u8; 3] = [\n    0x69, 0x6c, 0x0a\n];\n");
+    #[test]
+    fn test_cli_arg_order_2() {
+        let mut cmd = hx();
+        let assert = cmd.arg("tests/files/tiny.txt").arg("-ar").assert();
+        assert.success().code(0);
+    }
+
+    #[test]
+    fn test_cli_rust_module() {
+        let mut cmd = hx();
+        cmd.args([
+            "-a",
+            "rmod",
+            "--rustfmt-skip",
+            "--module-name",
+            "tiny",
+            "-c8",
+        ])
+        .arg("tests/files/tiny.txt")
+        .assert()
+        .success()
+        .stdout(
+            "pub mod tiny {\n    #[rustfmt::skip]\n    pub static DATA: &[u8] = &[\n        \
+                 0x69, 0x6c, 0x0a, \n    ];\n}\n",
+        );
+        let mut cmd = hx();
+        cmd.args(["-ar", "--module-name", "tiny", "tests/files/tiny.txt"])
+            .assert()
+            .failure();
+    }
+
+    /// target/debug/hx --len tests/files/tiny.txt
+    ///     error: invalid digit found in string
+    #[test]
+    fn test_cli_missing_param_value() {
+        let mut cmd = hx();
+        let assert = cmd.arg("--len").arg("tests/files/tiny.txt").assert();
+        assert.failure().code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_exit_codes() {
+        let mut cmd = hx();
+        cmd.arg("--bogus")
+            .assert()
+            .failure()
+            .code(error::EXIT_USAGE);
+        let mut cmd = hx();
+        cmd.arg("-R")
+            .write_stdin("0x000010: 41 A\n0x000000: 41 A\n")
+            .assert()
+            .failure()
+            .code(error::EXIT_PARSE)
+            .stderr("error: line 2: offset 0x0 overlaps previous data ending at 0x11\n");
+        let mut cmd = hx();
+        cmd.arg("--template")
+            .arg("tests/files/tiny.txt")
+            .arg("tests/files/tiny.txt")
+            .assert()
+            .failure()
+            .code(error::EXIT_PARSE);
+        let mut cmd = hx();
+        cmd.env("HX_FORMAT", "p")
+            .arg("tests/files/tiny.txt")
+            .assert()
+            .failure()
+            .code(error::EXIT_FORMAT);
+        let mut cmd = hx();
+        cmd.arg("--help").assert().success();
+    }
+
+    #[test]
+    fn test_cli_unsupported_format() {
+        let mut cmd = hx();
+        let assert = cmd.arg("-fp").arg("tests/files/tiny.txt").assert();
+        assert.failure().stdout("");
+    }
+
+    #[test]
+    fn test_cli_invalid_option_exit_codes() {
+        let tiny = "tests/files/tiny.txt";
+        for args in [
+            vec!["-a", "g", "--array-const", tiny],
+            vec!["-a", "hexstr", "--name", "x", tiny],
+            vec!["-a", "c", "--name", "1 bad-name", tiny],
+            vec!["-a", "rmod", "--module-name", "bad-module", tiny],
+            // rejected before the input is opened
+            vec!["--highlight", "1:2:mauve", "missing-file"],
+            vec!["--find", "zz", "missing-file"],
+            vec!["--find-regex", "(", "missing-file"],
+            vec!["-a", "g", "--rustfmt-skip", tiny],
+            vec!["--listen", "127.0.0.1:notaport"],
+            vec!["--connect", "127.0.0.1:notaport"],
+        ] {
+            let mut cmd = hx();
+            let assert = cmd.args(&args).assert();
+            assert.failure().code(error::EXIT_USAGE);
+        }
+        // a stream cannot seek from its end
+        let mut cmd = hx();
+        let assert = cmd.args(["-s", "-1"]).write_stdin("abc").assert();
+        assert.failure().code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_input_missing_file() {
+        let mut cmd = hx();
+        let assert = cmd.arg("missing-file").assert();
+        assert.failure().code(error::EXIT_IO);
+    }
+
+    #[test]
+    fn test_cli_input_directory() {
+        let mut cmd = hx();
+        let assert = cmd.arg("src").assert();
+        assert.failure().code(error::EXIT_IO).stderr(
+            "error: expected a file, got a directory, pipe its data into hx instead: tar -cf - src | hx\n",
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cli_input_character_device() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("-c4")
+            .arg("-l6")
+            .arg("-s2")
+            .arg("/dev/zero")
+            .assert();
+        assert.success().stdout(
+            "0x000002: 0x00 0x00 0x00 0x00 ....\n0x000006: 0x00 0x00           ..\n   bytes: 6\n",
+        );
+        let mut cmd = hx();
+        cmd.arg("--mmap")
+            .arg("/dev/zero")
+            .assert()
+            .failure()
+            .code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_input_stdin() {
+        let mut cmd = hx();
+        let assert = cmd.arg("-t0").write_stdin("012").assert();
+        assert.success().code(0).stdout(
+            "0x000000: 0x30 0x31 0x32                                    012\n   bytes: 3\n",
+        );
+    }
+
+    /// target/debug/hx -t0 -s 0x3c tests/files/alphanumeric.txt
+    #[test]
+    fn test_cli_seek_file() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("-s")
+            .arg("0x3c")
+            .arg("tests/files/alphanumeric.txt")
+            .assert();
+        assert.success().code(0).stdout(
+            "0x00003c: 0x32 0x33 0x34 0x35 0x36 0x37 0x38 0x39           23456789\n   bytes: 8\n",
+        );
+    }
+
+    #[test]
+    fn test_cli_seek_before_start() {
+        // like --tail, more than the whole file dumps all of it
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "-s", "-1000", "tests/files/tiny.txt"])
+            .assert();
+        assert.success().stdout(
+            "0x000000: 0x69 0x6c 0x0a                                    il.\n   bytes: 3\n",
+        );
+    }
+
+    #[test]
+    fn test_cli_mmap_changing_file() {
+        // a mapped file that shrinks faults, so files still being written
+        // are read instead
+        for args in [["--mmap", "--follow"], ["--mmap", "--tail=1"]] {
+            let mut cmd = hx();
+            let assert = cmd.args(args).arg("tests/files/tiny.txt").assert();
+            assert.failure().code(error::EXIT_USAGE);
+        }
+    }
+
+    #[cfg(not(all(unix, feature = "mmap")))]
+    #[test]
+    fn test_cli_mmap_unsupported() {
+        let mut cmd = hx();
+        let assert = cmd.args(["--mmap", "tests/files/tiny.txt"]).assert();
+        assert.failure().code(error::EXIT_USAGE);
+    }
+
+    #[cfg(all(unix, feature = "disasm"))]
+    #[test]
+    fn test_cli_disasm() {
+        // stands in for objdump, whose machines vary between builds
+        let objdump = env::temp_dir().join(format!("hx-objdump-{}.sh", std::process::id()));
+        fs::write(
+            &objdump,
+            "printf '   0:\\t69 6c\\tinsb\\n   2:\\t0a\\tor\\n'\n",
+        )
+        .unwrap();
+        let mut cmd = hx();
+        let assert = cmd
+            .env("HX_OBJDUMP", format!("sh {}", objdump.display()))
+            .args(["-t0", "-c2", "--disasm", "x86_64", "tests/files/tiny.txt"])
+            .assert();
+        let _ = fs::remove_file(&objdump);
+        assert
+            .success()
+            .stdout("0x000000: 0x69 0x6c il  insb\n0x000002: 0x0a      .   or\n");
+        let mut cmd = hx();
+        cmd.env("HX_OBJDUMP", "/nonexistent/objdump")
+            .args(["--disasm", "x86_64", "tests/files/tiny.txt"])
+            .assert()
+            .failure()
+            .code(error::EXIT_IO);
+    }
+
+    #[cfg(not(feature = "disasm"))]
+    #[test]
+    fn test_cli_disasm_unsupported() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--disasm", "x86_64", "tests/files/tiny.txt"])
+            .assert();
+        assert.failure().code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_len_past_end() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("-s")
+            .arg("0x3c")
+            .arg("-l")
+            .arg("1KiB")
+            .arg("tests/files/alphanumeric.txt")
+            .assert();
+        assert
+            .success()
+            .stdout(
+                "0x00003c: 0x32 0x33 0x34 0x35 0x36 0x37 0x38 0x39           23456789\n   bytes: 8\n",
+            )
+            .stderr(
+                "hx: tests/files/alphanumeric.txt: warning: --len 1024 runs past the end of the file, reading 8 bytes from offset 60\n",
+            );
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("-l")
+            .arg("all")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert.success().stderr("").stdout(
+            "0x000000: 0x69 0x6c 0x0a                                    il.\n   bytes: 3\n",
+        );
+    }
+
+    #[test]
+    fn test_cli_tail_file() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("-T4")
+            .arg("tests/files/alphanumeric.txt")
+            .assert();
+        assert.success().code(0).stdout(
+            "0x000040: 0x36 0x37 0x38 0x39                               6789\n   bytes: 4\n",
+        );
+    }
+
+    #[test]
+    fn test_cli_tail_stdin() {
+        let mut cmd = hx();
+        let assert = cmd.arg("-t0").arg("--tail=2").write_stdin("012").assert();
+        assert.success().code(0).stdout(
+            "0x000001: 0x31 0x32                                         12\n   bytes: 2\n",
+        );
+    }
+
+    #[test]
+    fn test_cli_find() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("--find")
+            .arg("3031")
+            .arg("tests/files/alphanumeric.txt")
+            .assert();
+        assert.success().code(0).stdout(predicates::str::ends_with(
+            "   bytes: 68\n   match: 0x00001b\n   match: 0x000026\n   match: 0x000030\n   match: 0x00003a\n",
+        ));
+    }
+
+    #[test]
+    fn test_cli_find_masked() {
+        // the digit after any byte whose low nibble is 1
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "--find", "?1 32", "tests/files/alphanumeric.txt"])
+            .assert();
+        assert.success().stdout(predicates::str::ends_with(
+            "   bytes: 68\n   match: 0x00001c\n   match: 0x000027\n   match: 0x000031\n   match: 0x00003b\n",
+        ));
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "--find", "3132&ff", "tests/files/alphanumeric.txt"])
+            .assert();
+        assert
+            .code(error::EXIT_USAGE)
+            .stderr(predicates::str::contains(
+            "for '--find <hexstring>': mask length 1 does not match the search pattern length 2",
+        ));
+    }
+
+    #[test]
+    fn test_cli_find_regex() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args([
+                "--find-regex",
+                "[0-9]{3}",
+                "-s",
+                "0x20",
+                "tests/files/alphanumeric.txt",
+            ])
+            .assert();
+        assert.success().stdout(
+            "0x000020: 567\n0x000026: 012\n0x000029: 345\n0x00002c: 678\n0x00002f: 901\n0x000032: 234\n0x000035: 567\n0x000038: 890\n0x00003b: 123\n0x00003e: 456\n0x000041: 789\n",
+        );
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--find-regex", "(", "tests/files/alphanumeric.txt"])
+            .assert();
+        assert
+            .code(error::EXIT_USAGE)
+            .stderr(predicates::str::contains("for '--find-regex <re>': "));
+    }
+
+    #[test]
+    fn test_cli_find_count_context() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--find", "3031", "--count", "tests/files/alphanumeric.txt"])
+            .assert();
+        assert.success().stdout("4\n");
+        let mut cmd = hx();
+        let assert = cmd
+            .args([
+                "--find-regex",
+                "xyz",
+                "--context",
+                "1",
+                "-c8",
+                "tests/files/alphanumeric.txt",
+            ])
+            .assert();
+        assert.success().stdout(
+            "0x000010: 0x70 0x71 0x72 0x73 0x74 0x75 0x76 0x77 pqrstuvw\n\
+             0x000018: 0x78 0x79 0x7a 0x30 0x31 0x32 0x33 0x34 xyz01234\n\
+             0x000020: 0x35 0x36 0x37 0x38 0x39 0x0a 0x30 0x31 56789.01\n",
+        );
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--count", "tests/files/alphanumeric.txt"])
+            .assert();
+        assert
+            .code(error::EXIT_USAGE)
+            .stderr(predicates::str::contains(
+                "--count and --context need --find or --find-regex",
+            ));
+    }
+
+    #[test]
+    fn test_cli_strings() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("--strings")
+            .write_stdin(&b"\x00\x01hello\x00hi\x00\xffworld!"[..])
+            .assert();
+        assert
+            .success()
+            .code(0)
+            .stdout("0x000002: hello\n0x00000c: world!\n");
+    }
+
+    #[test]
+    fn test_cli_diff() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("--diff")
+            .arg("tests/files/alphanumeric.txt")
+            .arg("tests/files/alphanumeric.txt")
+            .assert();
+        assert.success().code(0).stdout("   bytes: 68 | 68\n");
+    }
+
+    #[test]
+    fn test_cli_diff_missing() {
+        for (left, right) in [
+            ("tests/files/missing.txt", "tests/files/tiny.txt"),
+            ("tests/files/tiny.txt", "tests/files/missing.txt"),
+        ] {
+            let mut cmd = hx();
+            cmd.args(["--diff", left, right])
+                .assert()
+                .failure()
+                .code(error::EXIT_IO)
+                .stderr(predicates::str::starts_with(
+                    "error: tests/files/missing.txt: ",
+                ));
+        }
+    }
+
+    #[test]
+    fn test_cli_cmp() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args([
+                "--cmp",
+                "tests/files/alphanumeric.txt",
+                "tests/files/alphanumeric.txt",
+            ])
+            .assert();
+        assert.success().stdout("");
+        let mut cmd = hx();
+        let assert = cmd
+            .args([
+                "--cmp",
+                "tests/files/tiny.txt",
+                "tests/files/alphanumeric.txt",
+            ])
+            .assert();
+        assert.code(error::EXIT_DIFFER).stderr("").stdout(
+            "0x000000..0x000003: 3 bytes\n0x000003..0x000044: 65 bytes only in tests/files/alphanumeric.txt\n",
+        );
+    }
+
+    #[test]
+    fn test_cli_from_array() {
+        for language in ["r", "rmod", "c", "g", "p", "f", "nim"] {
+            let mut cmd = hx();
+            let array = cmd
+                .args(["-a", language, "tests/files/alphanumeric.txt"])
+                .output()
+                .unwrap();
+            let mut cmd = hx();
+            let assert = cmd.arg("--from-array").write_stdin(array.stdout).assert();
+            assert
+                .success()
+                .stdout(fs::read("tests/files/alphanumeric.txt").unwrap());
+        }
+        let mut cmd = hx();
+        cmd.arg("--from-array")
+            .write_stdin("let a = [0x100];")
+            .assert()
+            .failure()
+            .code(error::EXIT_PARSE);
+    }
+
+    #[test]
+    fn test_cli_reverse_not_a_dump() {
+        let mut cmd = hx();
+        cmd.arg("--reverse")
+            .write_stdin("not a dump\n")
+            .assert()
+            .failure()
+            .code(error::EXIT_PARSE)
+            .stderr(predicates::str::contains(
+                "line 1: not a line of hx dump output",
+            ));
+    }
+
+    #[test]
+    fn test_cli_reverse_squeezed_no_summary() {
+        let zeros = vec![0u8; 100000];
+        let mut cmd = hx();
+        let dump = cmd
+            .args(["-t0", "--no-summary"])
+            .write_stdin(zeros.clone())
+            .output()
+            .unwrap();
+        let mut cmd = hx();
+        let assert = cmd.arg("--reverse").write_stdin(dump.stdout).assert();
+        assert.success().stdout(zeros);
+    }
+
+    #[test]
+    fn test_cli_reverse() {
+        let mut cmd = hx();
+        let dump = cmd
+            .arg("-t0")
+            .arg("tests/files/alphanumeric.txt")
+            .output()
+            .unwrap();
+        let mut cmd = hx();
+        let assert = cmd.arg("--reverse").write_stdin(dump.stdout).assert();
+        assert
+            .success()
+            .code(0)
+            .stdout(fs::read("tests/files/alphanumeric.txt").unwrap());
+    }
+
+    #[test]
+    fn test_cli_reverse_layouts() {
+        // reversed with the options the dump was printed with
+        for layout in [
+            &["--no-ascii"][..],
+            &["--line-numbers"],
+            &["--end-offsets"],
+            &["--style", "canonical"],
+            &["--no-ascii", "--frame", "--end-offsets"],
+            &["--line-numbers", "--end-offsets", "--gutter"],
+        ] {
+            let mut cmd = hx();
+            let dump = cmd
+                .arg("-t0")
+                .args(layout)
+                .arg("tests/files/alphanumeric.txt")
+                .output()
+                .unwrap();
+            let mut cmd = hx();
+            let assert = cmd
+                .arg("--reverse")
+                .args(layout)
+                .write_stdin(dump.stdout)
+                .assert();
+            assert
+                .success()
+                .stdout(fs::read("tests/files/alphanumeric.txt").unwrap());
+        }
+        let mut cmd = hx();
+        cmd.args(["--reverse", "--ascii-only"])
+            .write_stdin("0x000000: il.\n")
+            .assert()
+            .failure()
+            .code(error::EXIT_USAGE)
+            .stderr(predicates::str::contains("--ascii-only"));
+    }
+
+    #[test]
+    fn test_cli_plain_round_trip() {
+        let mut cmd = hx();
+        let plain = cmd
+            .arg("--plain")
+            .arg("-c8")
+            .arg("tests/files/tiny.txt")
+            .output()
+            .unwrap();
+        assert_eq!(plain.stdout, b"696c0a\n");
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("--reverse")
+            .arg("--plain")
+            .write_stdin(plain.stdout)
+            .assert();
+        assert.success().code(0).stdout("il\n");
+    }
+
+    #[test]
+    fn test_cli_broken_pipe() {
+        let path = env::temp_dir().join(format!("hx-pipe-{}.bin", std::process::id()));
+        fs::write(&path, vec![0x41; 0x100000]).unwrap();
+        for mode in [
+            &[][..],
+            &["-ar"],
+            &["--plain"],
+            &["--output-format", "json"],
+        ] {
+            let mut child = hx_process()
+                .args(mode)
+                .arg("--no-squeeze")
+                .arg(&path)
+                .arg(&path)
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::piped())
+                .spawn()
+                .unwrap();
+            let mut stdout = child.stdout.take().unwrap();
+            stdout.read_exact(&mut [0u8; 16]).unwrap();
+            drop(stdout);
+            let output = child.wait_with_output().unwrap();
+            assert!(output.status.success(), "{:?}", mode);
+            assert_eq!(String::from_utf8_lossy(&output.stderr), "", "{:?}", mode);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_cli_output_file() {
+        let path = env::temp_dir().join(format!("hx-output-{}.txt", std::process::id()));
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-o")
+            .arg(&path)
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert.success().stdout("");
+        let dump = fs::read_to_string(&path).unwrap();
+        assert!(dump.starts_with("0x000000: 0x69 0x6c 0x0a"));
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-ar")
+            .arg("-o")
+            .arg(&path)
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert.success().stdout("");
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "let ARRAY: [u8; 3] = [\n    0x69, 0x6c, 0x0a\n];\n"
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_cli_multiple_inputs() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("tests/files/tiny.txt")
+            .arg("missing-file")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert
+            .failure()
+            .code(error::EXIT_IO)
+            .stdout(
+                "==> tests/files/tiny.txt <==\n\
+                 0x000000: 0x69 0x6c 0x0a                                    il.\n   bytes: 3\n\n\
+                 ==> missing-file <==\n\n\
+                 ==> tests/files/tiny.txt <==\n\
+                 0x000000: 0x69 0x6c 0x0a                                    il.\n   bytes: 3\n",
+            )
+            .stderr(predicates::str::contains("hx: missing-file:"));
+    }
+
+    #[test]
+    fn test_cli_output_format_json() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("--output-format")
+            .arg("json")
+            .arg("-s1")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert
+            .success()
+            .stdout("{\"offset\":1,\"bytes\":[108,10],\"ascii\":\"l.\"}\n");
+    }
+
+    #[test]
+    fn test_cli_size_suffix() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("-l0x2")
+            .arg("-c0b10")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert
+            .success()
+            .stdout("0x000000: 0x69 0x6c il\n   bytes: 2\n");
+    }
+
+    #[test]
+    fn test_cli_stats_only() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("--stats=only")
+            .arg("-s1")
+            .arg("-l1")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert.success().stdout(format!(
+            "   bytes: 1\n  entropy: 0.0000 bits/byte\nprintable: 100.00%\n      nul: 0.00%\n     0x6c:          1 100.00% {}\n",
+            "#".repeat(40)
+        ));
+    }
+
+    #[test]
+    fn test_cli_digest_range() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("--digest")
+            .arg("md5,crc32")
+            .arg("-s1")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert.success().stdout(
+            "0x000001: 0x6c 0x0a                                         l.\n   bytes: 2\n     md5: 12f54a96f64443246930da001cafda8b\n   crc32: 6844df4a\n",
+        );
+    }
+
+    #[test]
+    fn test_cli_range() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("--range")
+            .arg("1..=1")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert
+            .success()
+            .stdout("0x000001: 0x6c                                              l\n   bytes: 1\n");
+    }
+
+    #[test]
+    fn test_cli_base_address() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("--base-address")
+            .arg("0x08000000")
+            .arg("-s1")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert.success().stdout(
+            "0x8000001: 0x6c 0x0a                                         l.\n   bytes: 2\n",
+        );
+    }
+
+    #[test]
+    fn test_cli_template() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("--template")
+            .arg("tests/files/tiny.toml")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert.success().stdout(
+            "0x000000: 0x69 0x6c 0x0a                                    il.\n          text: \"il\"\n          newline: 10 (0xa)\n   bytes: 3\n",
+        );
+    }
+
+    #[test]
+    fn test_cli_template_missing() {
+        let mut cmd = hx();
+        cmd.args([
+            "--template",
+            "tests/files/missing.toml",
+            "tests/files/tiny.txt",
+        ])
+        .assert()
+        .failure()
+        .code(error::EXIT_IO)
+        .stderr(predicates::str::contains(
+            "template tests/files/missing.toml: ",
+        ));
+    }
+
+    #[test]
+    fn test_cli_detect() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("--detect")
+            .write_stdin(b"\x7fELF\x02".to_vec())
+            .assert();
+        assert.success().stdout(
+            "    type: ELF executable\n0x000000: 0x7f 0x45 0x4c 0x46 0x02                          .ELF.\n   bytes: 5\n",
+        );
+    }
+
+    #[test]
+    fn test_cli_charset_utf8() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("-c4")
+            .arg("-l8")
+            .arg("--charset")
+            .arg("utf8")
+            .arg("tests/files/emoji.txt")
+            .assert();
+        assert.success().stdout(
+            "0x000000: 0xf0 0x9f 0x98 0x80 \u{1f600}\u{b7}\u{b7}\u{b7}\n0x000004: 0xf0 0x9f 0x98 0x83 \u{1f603}\u{b7}\u{b7}\u{b7}\n   bytes: 8\n",
+        );
+    }
+
+    #[test]
+    fn test_cli_ascii_style() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("--ascii-style")
+            .arg("caret")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert.success().stdout(
+            "0x000000: 0x69 0x6c 0x0a                                    il^J\n   bytes: 3\n",
+        );
+    }
+
+    #[test]
+    fn test_cli_nonprintable_and_nul_char() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("--nonprintable-char")
+            .arg("_")
+            .arg("--nul-char")
+            .arg(" ")
+            .write_stdin(b"a\x00\x01".to_vec())
+            .assert();
+        assert.success().stdout(
+            "0x000000: 0x61 0x00 0x01                                    a _\n   bytes: 3\n",
+        );
+        let mut cmd = hx();
+        cmd.arg("--nul-char")
+            .arg("ab")
+            .arg("tests/files/tiny.txt")
+            .assert()
+            .failure();
+    }
+
+    #[test]
+    fn test_cli_frame() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("-c4")
+            .arg("--frame")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert
+            .success()
+            .stdout("0x000000: | 0x69 0x6c 0x0a      | il.  |\n   bytes: 3\n");
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("--separator")
+            .arg("|")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert.success().stdout(
+            "0x000000: 0x69 0x6c 0x0a                                   |il.\n   bytes: 3\n",
+        );
+    }
+
+    #[test]
+    fn test_cli_panes() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("-c4")
+            .arg("--no-ascii")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert
+            .success()
+            .stdout("0x000000: 0x69 0x6c 0x0a\n   bytes: 3\n");
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("-c4")
+            .arg("--ascii-only")
+            .arg("--frame")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert.success().stdout("0x000000: | il.  |\n   bytes: 3\n");
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("--no-ascii")
+            .arg("--ascii-only")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert.failure().code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_color_choice() {
+        let plain =
+            "0x000000: 0x69 0x6c 0x0a                                    il.\n   bytes: 3\n";
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--color", "auto", "tests/files/tiny.txt"])
+            .assert();
+        assert.success().stdout(plain);
+        let mut cmd = hx();
+        let assert = cmd.args(["--color", "0", "tests/files/tiny.txt"]).assert();
+        assert.success().stdout(plain);
+        let mut cmd = hx();
+        let assert = cmd.args(["--color=auto", "tests/files/tiny.txt"]).assert();
+        assert.success().stdout(plain);
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--color=never", "tests/files/tiny.txt"])
+            .env("HX_COLOR", "always")
+            .assert();
+        assert.success().stdout(plain);
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--color=always", "tests/files/tiny.txt"])
+            .assert();
+        let dump = assert.success().get_output().stdout.clone();
+        assert!(String::from_utf8(dump).unwrap().contains("\x1b["));
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--color", "always", "tests/files/tiny.txt"])
+            .assert();
+        let dump = assert.success().get_output().stdout.clone();
+        assert!(String::from_utf8(dump).unwrap().contains("\x1b["));
+        let mut cmd = hx();
+        let assert = cmd.args(["--color", "1", "tests/files/tiny.txt"]).assert();
+        let dump = assert.success().get_output().stdout.clone();
+        assert!(String::from_utf8(dump).unwrap().contains("\x1b["));
+        let mut cmd = hx();
+        let assert = cmd.args(["--colorize", "tests/files/tiny.txt"]).assert();
+        let dump = assert.success().get_output().stdout.clone();
+        assert!(String::from_utf8(dump).unwrap().contains("\x1b["));
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "--no-prefix", "tests/files/tiny.txt"])
+            .assert();
+        assert
+            .success()
+            .stdout("0x000000: 69 6c 0a                      il.\n   bytes: 3\n");
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("tests/files/tiny.txt")
+            .env("HX_COLOR", "sometimes")
+            .assert();
+        assert.failure().code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_connect() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let sender = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"il\n").unwrap();
+        });
+        let mut cmd = hx();
+        let assert = cmd.args(["-t0", "--connect", &address]).assert();
+        sender.join().unwrap();
+        assert.success().stdout(
+            "0x000000: 0x69 0x6c 0x0a                                    il.\n   bytes: 3\n",
+        );
+    }
+
+    #[test]
+    fn test_cli_timestamps() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "--timestamps"])
+            .write_stdin("012")
+            .assert();
+        assert.success().stdout(
+            predicates::str::is_match(
+                r"^\[ +\d+\.\d{6}\] 0x000000: 0x30 0x31 0x32 +012\n   bytes: 3\n$",
+            )
+            .unwrap(),
+        );
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "--timestamps=absolute"])
+            .write_stdin("012")
+            .assert();
+        assert.success().stdout(
+            predicates::str::is_match(r"^\[\d{4}-\d\d-\d\d \d\d:\d\d:\d\d\.\d{6}\] 0x000000: ")
+                .unwrap(),
+        );
+        let mut cmd = hx();
+        cmd.args(["--timestamps", "--strings"])
+            .write_stdin("012")
+            .assert()
+            .failure()
+            .code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_idle_flush() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "-c4", "--idle-flush", "50"])
+            .write_stdin("abcdef")
+            .assert();
+        assert.success().stdout(
+            "0x000000: 0x61 0x62 0x63 0x64 abcd\n0x000004: 0x65 0x66           ef\n   bytes: 6\n",
+        );
+        let mut cmd = hx();
+        cmd.args(["--idle-flush", "0"])
+            .write_stdin("abc")
+            .assert()
+            .failure()
+            .code(error::EXIT_USAGE);
+        let mut cmd = hx();
+        cmd.args(["--idle-flush", "50", "tests/files/tiny.txt"])
+            .assert()
+            .failure()
+            .code(error::EXIT_USAGE)
+            .stderr(predicates::str::contains("--idle-flush needs a live input"));
+        let mut cmd = hx();
+        cmd.args([
+            "--idle-flush",
+            "50",
+            "--follow",
+            "-ar",
+            "tests/files/tiny.txt",
+        ])
+        .assert()
+        .failure()
+        .code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_follow_idle_flush() {
+        let path = env::temp_dir().join(format!("hx-follow-{}.txt", std::process::id()));
+        fs::write(&path, "abcdef").unwrap();
+        let mut child = hx_process()
+            .args(["-t0", "-c4", "--follow", "--idle-flush", "50"])
+            .arg(&path)
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                if sender.send(line.unwrap()).is_err() {
+                    break;
+                }
+            }
+        });
+        let line = || receiver.recv_timeout(Duration::from_secs(10));
+        assert_eq!(line().unwrap(), "0x000000: 0x61 0x62 0x63 0x64 abcd");
+        // the file does not grow, so only the idle flush prints the rest
+        let partial = line();
+        child.kill().unwrap();
+        child.wait().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(partial.unwrap(), "0x000004: 0x65 0x66           ef");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_cli_pid() {
+        static MARKER: [u8; 8] = *b"hxmarker";
+        let address = MARKER.as_ptr() as u64;
+        let range = format!("{:#x}..{:#x}", address, address + 8);
+        let pid = std::process::id().to_string();
+        let mut cmd = hx();
+        let output = cmd
+            .args(["-t0", "-c8", "--pid", &pid, "--range", &range])
+            .output()
+            .unwrap();
+        // reading another process' memory may not be allowed here
+        if output.status.success() {
+            let stdout = String::from_utf8(output.stdout).unwrap();
+            assert_eq!(
+                stdout,
+                format!(
+                    "{:#08x}: 0x68 0x78 0x6d 0x61 0x72 0x6b 0x65 0x72 hxmarker\n   bytes: 8\n",
+                    address
+                )
+            );
+        } else {
+            let stderr = String::from_utf8(output.stderr).unwrap();
+            assert!(stderr.contains("permission denied"), "{}", stderr);
+        }
+        let mut cmd = hx();
+        cmd.args(["--pid", &pid, "--range", "0x10.."])
+            .assert()
+            .failure()
+            .code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_sector_size() {
+        // bytes 10..18 widen out to the sectors at 8 and 16
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "-c8", "--sector-size", "8", "-s", "10", "-l", "8"])
+            .args(["--offset-format", "sector", "tests/files/alphanumeric.txt"])
+            .assert();
+        assert.success().stdout(
+            "000001:0: 0x69 0x6a 0x6b 0x69 0x6c 0x6d 0x6e 0x6f ijkilmno\n\
+             000002:0: 0x70 0x71 0x72 0x73 0x74 0x75 0x76 0x77 pqrstuvw\n   bytes: 16\n",
+        );
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "-c8", "--sector-size", "8", "--range", "3..5"])
+            .write_stdin("abcdefghijkl")
+            .assert();
+        assert
+            .success()
+            .stdout("0x000000: 0x61 0x62 0x63 0x64 0x65 0x66 0x67 0x68 abcdefgh\n   bytes: 8\n");
+        let mut cmd = hx();
+        cmd.args(["--offset-format", "sector", "tests/files/tiny.txt"])
+            .assert()
+            .failure()
+            .code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_serial() {
+        // /dev/null is not a serial device, and without the serial
+        // feature there are none
+        let mut cmd = hx();
+        cmd.args(["--serial", "/dev/null"]).assert().failure();
+        let mut cmd = hx();
+        cmd.args(["--baud", "9600", "tests/files/tiny.txt"])
+            .assert()
+            .failure()
+            .code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_structure_colors() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t1", "--frame", "--offset-color", "blue"])
+            .arg("tests/files/tiny.txt")
+            .assert();
+        let dump = assert.success().get_output().stdout.clone();
+        let dump = String::from_utf8(dump).unwrap();
+        let offset = ansi_term::Color::Blue.paint("0x000000").to_string();
+        let border = Theme::DEFAULT.separator.paint("|").to_string();
+        let trailer = Theme::DEFAULT.trailer.paint("bytes: 3").to_string();
+        assert!(dump.starts_with(&format!("{}: {} ", offset, border)));
+        assert!(dump.ends_with(&format!(" {}\n   {}\n", border, trailer)));
+        // colored dumps read back like plain ones
+        let mut cmd = hx();
+        let assert = cmd.arg("-R").write_stdin(dump).assert();
+        assert.success().stdout("il\n");
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--trailer-color", "mauve", "tests/files/tiny.txt"])
+            .assert();
+        assert
+            .failure()
+            .code(error::EXIT_USAGE)
+            .stderr(predicates::str::contains("unknown color: mauve"));
+    }
+
+    #[test]
+    fn test_cli_word_size_cols() {
+        // the default columns round up to whole words
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("--word-size=4")
+            .write_stdin("abcdefghijklmnop")
+            .assert();
+        assert.success().stdout(
+            "0x000000: 0x64636261 0x68676665 0x6c6b6a69 abcdefghijkl\n\
+             0x00000c: 0x706f6e6d                       mnop\n   bytes: 16\n",
+        );
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--word-size", "8", "-c", "3"])
+            .write_stdin("abcdefghijklmnop")
+            .assert();
+        assert
+            .failure()
+            .code(error::EXIT_USAGE)
+            .stderr("error: -c, --cols 3 is not a multiple of --word-size 8\n");
+    }
+
+    #[test]
+    fn test_cli_offset_case() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "-fX", "--offset-case", "match-format"])
+            .args(["--base-address", "0xab", "tests/files/tiny.txt"])
+            .assert();
+        assert
+            .success()
+            .stdout(predicates::str::starts_with("0x0000AB: 0x69 0x6C 0x0A"));
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "-fx", "--offset-case", "match-format"])
+            .args(["--base-address", "0xab", "tests/files/tiny.txt"])
+            .assert();
+        assert
+            .success()
+            .stdout(predicates::str::starts_with("0x0000ab: "));
+    }
+
+    #[test]
+    fn test_cli_summary() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "--no-summary", "tests/files/tiny.txt"])
+            .assert();
+        assert
+            .success()
+            .stdout("0x000000: 0x69 0x6c 0x0a                                    il.\n");
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "--summary", "verbose", "-s1", "tests/files/tiny.txt"])
+            .assert();
+        assert.success().stdout(
+            predicates::str::is_match(
+                "^0x000001: .*\n   bytes: 2\n   range: 0x000001\\.\\.0x000003\n \
+             elapsed: \\d+\\.\\d{3}s\n  sha256: [0-9a-f]{64}\n$",
+            )
+            .unwrap(),
+        );
+        let mut cmd = hx();
+        let assert = cmd
+            .args([
+                "-t0",
+                "--no-summary",
+                "--summary",
+                "bytes",
+                "tests/files/tiny.txt",
+            ])
+            .assert();
+        assert.failure().code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_style_xxd() {
+        let xxd = "00000000: 6162 6364 6566 6768 696a 6b69 6c6d 6e6f  abcdefghijkilmno\n\
+                   00000010: 7071 7273 7475 7677 7879 7a30 3132 3334  pqrstuvwxyz01234\n\
+                   00000020: 3536 3738 390a 3031 3233 3435 3637 3839  56789.0123456789\n\
+                   00000030: 3031 3233 3435 3637 3839 3031 3233 3435  0123456789012345\n\
+                   00000040: 3637 3839                                6789\n";
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--style", "xxd", "tests/files/alphanumeric.txt"])
+            .assert();
+        assert.success().stdout(xxd);
+        let mut cmd = hx();
+        let assert = cmd.args(["--style", "xxd", "-R"]).write_stdin(xxd).assert();
+        assert
+            .success()
+            .stdout(fs::read_to_string("tests/files/alphanumeric.txt").unwrap());
+        // options on the command line override the style
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--style", "xxd", "-c4", "tests/files/tiny.txt"])
+            .assert();
+        assert.success().stdout("00000000: 696c 0a    il.\n");
+    }
+
+    #[test]
+    fn test_cli_style_canonical() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--style", "canonical", "tests/files/alphanumeric.txt"])
+            .assert();
+        assert.success().stdout(
+            "00000000  61 62 63 64 65 66 67 68  69 6a 6b 69 6c 6d 6e 6f  |abcdefghijkilmno|\n\
+             00000010  70 71 72 73 74 75 76 77  78 79 7a 30 31 32 33 34  |pqrstuvwxyz01234|\n\
+             00000020  35 36 37 38 39 0a 30 31  32 33 34 35 36 37 38 39  |56789.0123456789|\n\
+             00000030  30 31 32 33 34 35 36 37  38 39 30 31 32 33 34 35  |0123456789012345|\n\
+             00000040  36 37 38 39                                       |6789|\n\
+             00000044\n",
+        );
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--style", "canonical"])
+            .write_stdin(vec![0u8; 40])
+            .assert();
+        assert.success().stdout(
+            "00000000  00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00  |................|\n\
+             *\n\
+             00000020  00 00 00 00 00 00 00 00                           |........|\n\
+             00000028\n",
+        );
+        let mut cmd = hx();
+        let assert = cmd.args(["--style", "canonical"]).write_stdin("").assert();
+        assert.success().stdout("");
+    }
+
+    #[test]
+    fn test_cli_stacked_formats() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "-r0", "-fx", "-fo", "tests/files/tiny.txt"])
+            .assert();
+        assert.success().stdout(
+            "0x000000:   69   6c   0a                                    il.\n          \
+             0151 0154 0012\n   bytes: 3\n",
+        );
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "-fx", "-fo", "-c2", "tests/files/tiny.txt"])
+            .assert();
+        let dump = assert.success().get_output().stdout.clone();
+        let mut cmd = hx();
+        let assert = cmd.arg("-R").write_stdin(dump).assert();
+        assert.success().stdout("il\n");
+    }
+
+    #[test]
+    fn test_cli_little_endian_groups() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args([
+                "--style",
+                "xxd",
+                "--little-endian-groups",
+                "tests/files/alphanumeric.txt",
+            ])
+            .assert();
+        assert.success().stdout(
+            "00000000: 64636261 68676665 696b6a69 6f6e6d6c  abcdefghijkilmno\n\
+             00000010: 73727170 77767574 307a7978 34333231  pqrstuvwxyz01234\n\
+             00000020: 38373635 31300a39 35343332 39383736  56789.0123456789\n\
+             00000030: 33323130 37363534 31303938 35343332  0123456789012345\n\
+             00000040: 39383736                             6789\n",
+        );
+        let mut cmd = hx();
+        let assert = cmd
+            .args([
+                "--style",
+                "xxd",
+                "--little-endian-groups",
+                "-g2",
+                "tests/files/tiny.txt",
+            ])
+            .assert();
+        assert
+            .success()
+            .stdout("00000000: 6c69   0a                                il.\n");
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--little-endian-groups", "-R", "tests/files/tiny.txt"])
+            .assert();
+        assert.failure().code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_bits() {
+        let bits = "00000000: 01100001 01100010 01100011 01100100 01100101 01100110 01100111 01101000  abcdefgh\n\
+                    00000008: 01101001 01101010 01101011 01101001 01101100 01101101 01101110 01101111  ijkilmno\n";
+        let mut cmd = hx();
+        let assert = cmd
+            .args([
+                "--bits",
+                "--style",
+                "xxd",
+                "-l16",
+                "tests/files/alphanumeric.txt",
+            ])
+            .assert();
+        assert.success().stdout(bits);
+        let mut cmd = hx();
+        let assert = cmd.args(["--bits", "-R"]).write_stdin(bits).assert();
+        assert.success().stdout("abcdefghijkilmno");
+        // the padding of a short last line keeps the text column aligned
+        let mut cmd = hx();
+        let assert = cmd.args(["--bits", "tests/files/tiny.txt"]).assert();
+        assert.success().stdout(
+            "0x000000: 01101001 01101100 00001010                                               il.\n   bytes: 3\n",
+        );
+        let mut cmd = hx();
+        let assert = cmd.args(["--bits", "-fx", "tests/files/tiny.txt"]).assert();
+        assert.failure();
+    }
+
+    #[test]
+    fn test_cli_radix_formats() {
+        let mut cmd = hx();
+        let assert = cmd.args(["-fn", "-c4", "tests/files/tiny.txt"]).assert();
+        assert
+            .success()
+            .stdout("0x000000: 0b0110_1001 0b0110_1100 0b0000_1010             il.\n   bytes: 3\n");
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-fr4", "-g2", "-c4", "tests/files/tiny.txt"])
+            .assert();
+        assert
+            .success()
+            .stdout("0x000000: 12211230 0022     il.\n   bytes: 3\n");
+        // radix 32 octets such as 0o and 0b are not read as prefixes
+        let input = b"\x18\x0b\xff\x00";
+        let mut cmd = hx();
+        let assert = cmd.args(["-fr32"]).write_stdin(&input[..]).assert();
+        let dump = assert.get_output().stdout.clone();
+        assert!(String::from_utf8_lossy(&dump).starts_with("0x000000: 0o 0b 7v 00 "));
+        let mut cmd = hx();
+        let assert = cmd.args(["-R", "-fr32"]).write_stdin(dump).assert();
+        assert.success().stdout(&input[..]);
+        let mut cmd = hx();
+        let assert = cmd.args(["-fr37", "tests/files/tiny.txt"]).assert();
+        assert.failure().code(error::EXIT_FORMAT);
+    }
+
+    #[test]
+    fn test_cli_decode() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--decode", "0x10", "tests/files/alphanumeric.txt"])
+            .assert();
+        assert
+            .success()
+            .stdout(predicates::str::starts_with(
+                "  offset: 0x000010\n  endian: le                        be\n      u8: 112\n",
+            ))
+            .stdout(predicates::str::contains(
+                "     u32: 1936879984                1886483059\n",
+            ))
+            .stdout(predicates::str::contains(
+                "  unix32: 2031-05-18 14:13:04 UTC   2029-10-12 07:04:19 UTC\n",
+            ));
+        let mut cmd = hx();
+        let assert = cmd.args(["--decode", "1"]).write_stdin("il\n").assert();
+        // types longer than the rest of the input are left out
+        assert.success().stdout(
+            "  offset: 0x000001\n  endian: le                        be\n      u8: 108\n      i8: 108\n     \
+             u16: 2668                      27658\n     i16: 2668                      27658\n \
+             uleb128: 108 (1 byte)\n sleb128: -20 (1 byte)\n",
+        );
+        let mut cmd = hx();
+        let assert = cmd.args(["--decode", "3", "tests/files/tiny.txt"]).assert();
+        assert.failure().code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_patch() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--patch", "0=49", "--patch", "2=21", "tests/files/tiny.txt"])
+            .assert();
+        assert.success().stdout("Il!");
+        // the input is not extended unless asked to
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--patch", "3=21", "tests/files/tiny.txt"])
+            .assert();
+        assert.failure().code(error::EXIT_USAGE).stdout("");
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--patch", "4=21", "--allow-grow"])
+            .write_stdin("il\n")
+            .assert();
+        assert.success().stdout("il\n\0!");
+        let path = env::temp_dir().join(format!("hx-patch-{}.bin", std::process::id()));
+        fs::write(&path, "il\n").unwrap();
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--patch", "0x1=4c", "--in-place"])
+            .arg(&path)
+            .assert();
+        assert.success().stdout("");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "iL\n");
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--patch", "2=0a21", "--in-place"])
+            .arg(&path)
+            .assert();
+        assert.failure().code(error::EXIT_USAGE);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "iL\n");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_cli_fill_insert_delete() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args([
+                "--delete",
+                "1..=1",
+                "--insert",
+                "3=21",
+                "--fill",
+                "0..1=41",
+                "tests/files/tiny.txt",
+            ])
+            .assert();
+        assert.success().stdout("A\n!");
+        // later edits win where they overlap
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--fill", "0..=2=2e", "--patch", "1=21"])
+            .write_stdin("il\n")
+            .assert();
+        assert.success().stdout(".!.");
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--patch", "1=21", "--fill", "0..=2=2e"])
+            .write_stdin("il\n")
+            .assert();
+        assert.success().stdout("...");
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--insert", "5=21", "tests/files/tiny.txt"])
+            .assert();
+        assert.failure().code(error::EXIT_USAGE).stdout("");
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--insert", "5=21", "--allow-grow", "tests/files/tiny.txt"])
+            .assert();
+        assert.success().stdout("il\n\0\0!");
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--fill", "0..1=4142", "tests/files/tiny.txt"])
+            .assert();
+        assert
+            .failure()
+            .code(error::EXIT_USAGE)
+            .stderr(predicates::str::contains("fill must be a single byte"));
+    }
+
+    #[test]
+    fn test_cli_extract() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--extract", "2..5", "tests/files/alphanumeric.txt"])
+            .assert();
+        assert.success().stdout("cde");
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--extract", "0x40.."])
+            .write_stdin(fs::read("tests/files/alphanumeric.txt").unwrap())
+            .assert();
+        assert.success().stdout("6789");
+        let path = env::temp_dir().join(format!("hx-extract-{}.bin", std::process::id()));
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--extract", "1..=2", "-o"])
+            .arg(&path)
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert.success().stdout("");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "l\n");
+        // a short range leaves the output file alone
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--extract", "0..4", "-o"])
+            .arg(&path)
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert.failure().code(error::EXIT_USAGE);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "l\n");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_cli_transforms() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "--xor", "20", "tests/files/tiny.txt"])
+            .assert();
+        assert
+            .success()
+            .stdout(predicates::str::starts_with("0x000000: 0x49 0x4c 0x2a "));
+        // the key lines up with the offset in the input after a seek
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--xor", "0102", "--extract", "1..3", "tests/files/tiny.txt"])
+            .assert();
+        assert.success().stdout("n\x0b");
+        let mut cmd = hx();
+        let assert = cmd
+            .args([
+                "--add",
+                "-1",
+                "--rot",
+                "1",
+                "--plain",
+                "tests/files/tiny.txt",
+            ])
+            .assert();
+        assert.success().stdout("d0d612\n");
+        let mut cmd = hx();
+        let assert = cmd
+            .args([
+                "--rot",
+                "1",
+                "--add",
+                "-1",
+                "--plain",
+                "tests/files/tiny.txt",
+            ])
+            .assert();
+        assert.success().stdout("d1d713\n");
+        let mut cmd = hx();
+        let assert = cmd.args(["--xor", "zz", "tests/files/tiny.txt"]).assert();
+        assert.failure().code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_cli_input_encoding() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--input-encoding", "base64", "--plain"])
+            .write_stdin("aHgK\n")
+            .assert();
+        assert.success().stdout("68780a\n");
+        // offsets count decoded bytes
+        let gzip = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0xa8, 0xe0, 0x02,
+            0x00, 0x6a, 0x59, 0xb6, 0xbe, 0x03, 0x00, 0x00, 0x00,
+        ];
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--input-encoding", "gzip", "-s1", "--plain"])
+            .write_stdin(gzip)
+            .assert();
+        assert.success().stdout("780a\n");
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--input-encoding", "zlib", "tests/files/tiny.txt"])
+            .assert();
+        assert
+            .code(error::EXIT_PARSE)
+            .stderr(predicates::str::contains("invalid zlib data: bad header"));
+    }
+
+    #[test]
+    fn test_cli_pager() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--pager", "always", "--plain", "tests/files/tiny.txt"])
+            .env("PAGER", "sed s/^/paged:/")
+            .assert();
+        assert.success().stdout("paged:696c0a\n");
+        // auto leaves output that is not a terminal alone
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--pager", "auto", "--plain", "tests/files/tiny.txt"])
+            .env("PAGER", "sed s/^/paged:/")
+            .assert();
+        assert.success().stdout("696c0a\n");
+    }
+
+    #[test]
+    fn test_cli_cols_auto() {
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["--cols", "auto", "-t0", "tests/files/alphanumeric.txt"])
+            .env("COLUMNS", "40")
+            .assert();
+        assert.success().stdout(predicates::str::starts_with(
+            "0x000000: 0x61 0x62 0x63 0x64 0x65 abcde\n",
+        ));
+        // not a terminal, so the default stays
+        let mut cmd = hx();
+        let assert = cmd
+            .args(["-t0", "tests/files/alphanumeric.txt"])
+            .env("COLUMNS", "40")
+            .assert();
+        assert.success().stdout(predicates::str::starts_with(
+            "0x000000: 0x61 0x62 0x63 0x64 0x65 0x66 0x67 0x68 0x69 0x6a abcdefghij\n",
+        ));
+    }
+
+    #[test]
+    fn test_cli_wide_cols() {
+        // the widest lines --cols allows, padded past the last octet
+        for args in [
+            &["-c4096", "-fb"][..],
+            &["-c4096", "-fb", "--frame", "--end-offsets"],
+        ] {
+            let mut cmd = hx();
+            let output = cmd
+                .args(["-t0"])
+                .args(args)
+                .arg("tests/files/tiny.txt")
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            let dump = String::from_utf8(output.stdout).unwrap();
+            assert!(dump.lines().next().unwrap().len() > 40000);
+        }
+        let mut cmd = hx();
+        cmd.args(["-t0", "-c4096", "--diff"])
+            .args(["tests/files/tiny.txt", "tests/files/alphanumeric.txt"])
+            .assert()
+            .success();
+        for cols in ["4097", "0x100000000000"] {
+            let mut cmd = hx();
+            cmd.args(["-t0", "-c", cols, "tests/files/tiny.txt"])
+                .assert()
+                .failure()
+                .code(error::EXIT_USAGE);
+        }
+    }
+
+    #[test]
+    fn test_cli_header() {
+        let mut cmd = hx();
+        let assert = cmd
+            .arg("-t0")
+            .arg("-c4")
+            .arg("--header")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert.success().stdout(
+            "            00   01   02   03\n0x000000: 0x69 0x6c 0x0a      il.\n   bytes: 3\n",
+        );
+        let mut cmd = hx();
+        cmd.arg("--header=x")
+            .arg("tests/files/tiny.txt")
+            .assert()
+            .failure();
+    }
+
+    #[test]
+    fn test_cli_config_file() {
+        let mut cmd = hx();
+        let assert = cmd
+            .env("HX_CONFIG", "tests/files/config.toml")
+            .arg("-t0")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert
+            .success()
+            .stdout("0x000000: | 69 6C 0A    | il.  |\n   bytes: 3\n");
+        // command line options take precedence
+        let mut cmd = hx();
+        let assert = cmd
+            .env("HX_CONFIG", "tests/files/config.toml")
+            .arg("-t0")
+            .arg("-c2")
+            .arg("-fx")
+            .arg("tests/files/tiny.txt")
+            .assert();
+        assert
+            .success()
+            .stdout("0x000000: | 69 6c | il |\n0x000002: | 0a    | .  |\n   bytes: 3\n");
+        let mut cmd = hx();
+        cmd.env("HX_CONFIG", "tests/files/tiny.txt")
+            .arg("tests/files/tiny.txt")
+            .assert()
+            .failure();
+    }
+
+    #[test]
+    fn test_cli_env_precedence() {
+        let dump = |args: &[&str], env: &[(&str, &str)]| {
+            let mut cmd = hx();
+            cmd.env("HX_CONFIG", "tests/files/config.toml")
+                .env_remove("HX_COLS")
+                .env_remove("HX_FORMAT")
+                .envs(env.iter().copied())
+                .arg("-t0")
+                .args(args)
+                .arg("tests/files/tiny.txt")
+                .output()
+                .unwrap()
+        };
+        // config file
+        let out = dump(&[], &[]);
+        assert!(String::from_utf8(out.stdout)
+            .unwrap()
+            .starts_with("0x000000: | 69 6C 0A    |"));
+        // environment over config file
+        let out = dump(&[], &[("HX_COLS", "2"), ("HX_FORMAT", "x")]);
+        assert!(String::from_utf8(out.stdout)
+            .unwrap()
+            .starts_with("0x000000: | 69 6c | il |\n"));
+        // command line over environment
+        let out = dump(&["-c3", "-fX"], &[("HX_COLS", "2"), ("HX_FORMAT", "x")]);
+        assert!(String::from_utf8(out.stdout)
+            .unwrap()
+            .starts_with("0x000000: | 69 6C 0A | il. |\n"));
+        let out = dump(&[], &[("HX_FRAME", "maybe")]);
+        assert!(!out.status.success());
+    }
+
+    #[test]
+    fn test_cli_completions() {
+        for shell in ["bash", "zsh", "fish", "powershell"] {
+            let mut cmd = hx();
+            let assert = cmd.arg("--completions").arg(shell).assert();
+            assert
+                .success()
+                .stdout(predicates::str::contains("ascii-style"));
+        }
+        let mut cmd = hx();
+        cmd.arg("--completions").arg("tcsh").assert().failure();
+    }
+
+    #[test]
+    fn test_cli_seek_stdin() {
+        let mut cmd = hx();
+        let assert = cmd.arg("-t0").arg("--seek=1").write_stdin("012").assert();
+        assert.success().code(0).stdout(
+            "0x000001: 0x31 0x32                                         12\n   bytes: 2\n",
         );
     }
 }
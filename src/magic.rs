@@ -0,0 +1,114 @@
+//! file type detection by magic number
+
+/// Magic number identifying a file type.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    /// file type description
+    pub name: &'static str,
+    /// offset of the magic number from the start of the input
+    pub offset: usize,
+    /// magic number bytes
+    pub magic: &'static [u8],
+}
+
+impl Signature {
+    /// Returns true if `head` has the magic number at the signature offset.
+    ///
+    /// # Arguments
+    ///
+    /// * `head` - first bytes of the input.
+    pub fn matches(&self, head: &[u8]) -> bool {
+        head.get(self.offset..self.offset + self.magic.len()) == Some(self.magic)
+    }
+}
+
+/// Built in signatures, checked in order, so more specific signatures
+/// come before shorter ones they share a prefix with.
+#[rustfmt::skip]
+pub const SIGNATURES: &[Signature] = &[
+    Signature { name: "ELF executable", offset: 0, magic: b"\x7fELF" },
+    Signature { name: "Mach-O executable, 32 bit", offset: 0, magic: b"\xfe\xed\xfa\xce" },
+    Signature { name: "Mach-O executable, 64 bit", offset: 0, magic: b"\xfe\xed\xfa\xcf" },
+    Signature { name: "Mach-O executable, 32 bit", offset: 0, magic: b"\xce\xfa\xed\xfe" },
+    Signature { name: "Mach-O executable, 64 bit", offset: 0, magic: b"\xcf\xfa\xed\xfe" },
+    Signature { name: "Mach-O universal binary or Java class", offset: 0, magic: b"\xca\xfe\xba\xbe" },
+    Signature { name: "PE/DOS executable", offset: 0, magic: b"MZ" },
+    Signature { name: "WebAssembly module", offset: 0, magic: b"\0asm" },
+    Signature { name: "PNG image", offset: 0, magic: b"\x89PNG\r\n\x1a\n" },
+    Signature { name: "JPEG image", offset: 0, magic: b"\xff\xd8\xff" },
+    Signature { name: "GIF image", offset: 0, magic: b"GIF87a" },
+    Signature { name: "GIF image", offset: 0, magic: b"GIF89a" },
+    Signature { name: "BMP image", offset: 0, magic: b"BM" },
+    Signature { name: "RIFF container (WAV, AVI, WebP)", offset: 0, magic: b"RIFF" },
+    Signature { name: "PDF document", offset: 0, magic: b"%PDF-" },
+    Signature { name: "SQLite database", offset: 0, magic: b"SQLite format 3\0" },
+    Signature { name: "ZIP archive", offset: 0, magic: b"PK\x03\x04" },
+    Signature { name: "ZIP archive, empty", offset: 0, magic: b"PK\x05\x06" },
+    Signature { name: "gzip compressed data", offset: 0, magic: b"\x1f\x8b" },
+    Signature { name: "bzip2 compressed data", offset: 0, magic: b"BZh" },
+    Signature { name: "xz compressed data", offset: 0, magic: b"\xfd7zXZ\0" },
+    Signature { name: "zstd compressed data", offset: 0, magic: b"\x28\xb5\x2f\xfd" },
+    Signature { name: "7-zip archive", offset: 0, magic: b"7z\xbc\xaf\x27\x1c" },
+    Signature { name: "tar archive", offset: 257, magic: b"ustar" },
+];
+
+/// Number of bytes needed to check every built in signature.
+pub const HEAD_LEN: usize = 262;
+
+/// Find the first of `signatures` matching the start of the input.
+///
+/// # Arguments
+///
+/// * `head` - first bytes of the input.
+/// * `signatures` - signatures to check, in order.
+pub fn detect_with<'a>(head: &[u8], signatures: &'a [Signature]) -> Option<&'a Signature> {
+    signatures.iter().find(|signature| signature.matches(head))
+}
+
+/// Find the built in signature matching the start of the input.
+///
+/// # Arguments
+///
+/// * `head` - first bytes of the input, at least `HEAD_LEN` unless the
+///   input is shorter.
+pub fn detect(head: &[u8]) -> Option<&'static Signature> {
+    detect_with(head, SIGNATURES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_head_len_covers_signatures() {
+        for signature in SIGNATURES {
+            assert!(signature.offset + signature.magic.len() <= HEAD_LEN);
+        }
+    }
+
+    #[test]
+    fn test_detect() {
+        let name = |head: &[u8]| detect(head).map(|signature| signature.name);
+        assert_eq!(name(b"\x7fELF\x02\x01\x01"), Some("ELF executable"));
+        assert_eq!(name(b"\x89PNG\r\n\x1a\n\0\0"), Some("PNG image"));
+        assert_eq!(name(b"MZ\x90\0"), Some("PE/DOS executable"));
+        assert_eq!(name(b"%PDF-1.7"), Some("PDF document"));
+        let mut tar = vec![0u8; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert_eq!(name(&tar), Some("tar archive"));
+        assert_eq!(name(b"\x7fEL"), None);
+        assert_eq!(name(b"il\n"), None);
+        assert_eq!(name(b""), None);
+    }
+
+    #[test]
+    fn test_detect_with() {
+        let custom = [Signature {
+            name: "hx test",
+            offset: 1,
+            magic: b"l\n",
+        }];
+        assert_eq!(detect_with(b"il\n", &custom), Some(&custom[0]));
+        assert_eq!(detect_with(b"l\n", &custom), None);
+    }
+}
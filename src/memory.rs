@@ -0,0 +1,82 @@
+//! live process memory input, read through `/proc/<pid>/mem` on Linux
+use crate::args::ByteRange;
+use std::io;
+
+/// Path of the memory of process `pid`, which reads at virtual
+/// addresses.
+///
+/// # Arguments
+///
+/// * `pid` - process id.
+#[cfg(target_os = "linux")]
+pub fn mem_path(pid: u32) -> io::Result<String> {
+    Ok(format!("/proc/{}/mem", pid))
+}
+
+/// Path of the memory of process `pid`, which reads at virtual
+/// addresses.
+///
+/// # Arguments
+///
+/// * `pid` - process id.
+#[cfg(not(target_os = "linux"))]
+pub fn mem_path(_pid: u32) -> io::Result<String> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "--pid is only supported on Linux",
+    ))
+}
+
+/// Explain a failure to read `range` of the memory of process `pid`,
+/// which the kernel reports as bare `ENOENT`, `EACCES` or `EIO`.
+///
+/// # Arguments
+///
+/// * `e` - error opening or reading the memory.
+/// * `pid` - process id.
+/// * `range` - dumped address range.
+pub fn explain(e: io::Error, pid: u32, range: &ByteRange) -> io::Error {
+    let message = match e.kind() {
+        io::ErrorKind::NotFound => format!("no process {}", pid),
+        io::ErrorKind::PermissionDenied => format!(
+            "permission denied reading the memory of process {}, which takes the same user and \
+             ptrace permission, see /proc/sys/kernel/yama/ptrace_scope, or root",
+            pid
+        ),
+        // EIO, reading an address that is not mapped
+        _ if e.raw_os_error() == Some(5) => format!(
+            "{:#x}..{:#x} is not all mapped in process {}, see /proc/{}/maps",
+            range.start,
+            range.end.unwrap_or(range.start),
+            pid,
+            pid
+        ),
+        _ => return e,
+    };
+    io::Error::new(e.kind(), message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain() {
+        let range = ByteRange {
+            start: 0x1000,
+            end: Some(0x2000),
+        };
+        let explained = |e: io::Error| explain(e, 42, &range).to_string();
+        assert_eq!(
+            explained(io::Error::from(io::ErrorKind::NotFound)),
+            "no process 42"
+        );
+        assert!(explained(io::Error::from(io::ErrorKind::PermissionDenied))
+            .starts_with("permission denied reading the memory of process 42"));
+        assert_eq!(
+            explained(io::Error::from_raw_os_error(5)),
+            "0x1000..0x2000 is not all mapped in process 42, see /proc/42/maps"
+        );
+        assert_eq!(explained(io::Error::other("closed")), "closed");
+    }
+}
@@ -0,0 +1,117 @@
+//! network input, the bytes arriving over a TCP or Unix socket
+use std::io::{self, Read};
+use std::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+
+/// Socket address given to `--listen` or `--connect`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Address {
+    /// TCP `host:port`
+    Tcp(String),
+    /// Unix socket path, given as `unix:<path>`
+    Unix(PathBuf),
+}
+
+impl Address {
+    /// Parse an address, `unix:<path>` for a Unix socket and `host:port`
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - argument value.
+    pub fn parse(value: &str) -> Address {
+        match value.strip_prefix("unix:") {
+            Some(path) => Address::Unix(PathBuf::from(path)),
+            None => Address::Tcp(value.to_string()),
+        }
+    }
+}
+
+/// Error for Unix sockets on platforms without them.
+#[cfg(not(unix))]
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "unix sockets are not supported on this platform",
+    )
+}
+
+/// Listen on `address` and return the first connection made to it. The
+/// address listened on is reported on stderr, which names the port picked
+/// for port 0. A Unix socket file is removed once connected.
+///
+/// # Arguments
+///
+/// * `address` - address to listen on.
+pub fn listen(address: &Address) -> io::Result<Box<dyn Read + Send>> {
+    match address {
+        Address::Tcp(address) => {
+            let listener = TcpListener::bind(address)?;
+            eprintln!("hx: listening on {}", listener.local_addr()?);
+            Ok(Box::new(listener.accept()?.0))
+        }
+        #[cfg(unix)]
+        Address::Unix(path) => {
+            let listener = UnixListener::bind(path)?;
+            eprintln!("hx: listening on unix:{}", path.display());
+            let accepted = listener.accept();
+            let _ = std::fs::remove_file(path);
+            Ok(Box::new(accepted?.0))
+        }
+        #[cfg(not(unix))]
+        Address::Unix(_) => Err(unsupported()),
+    }
+}
+
+/// Connect to `address` and return the connection.
+///
+/// # Arguments
+///
+/// * `address` - address to connect to.
+pub fn connect(address: &Address) -> io::Result<Box<dyn Read + Send>> {
+    match address {
+        Address::Tcp(address) => Ok(Box::new(TcpStream::connect(address)?)),
+        #[cfg(unix)]
+        Address::Unix(path) => Ok(Box::new(UnixStream::connect(path)?)),
+        #[cfg(not(unix))]
+        Address::Unix(_) => Err(unsupported()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::thread;
+
+    #[test]
+    fn test_address_parse() {
+        assert_eq!(
+            Address::parse("127.0.0.1:4000"),
+            Address::Tcp("127.0.0.1:4000".to_string())
+        );
+        assert_eq!(
+            Address::parse("unix:/tmp/hx.sock"),
+            Address::Unix(PathBuf::from("/tmp/hx.sock"))
+        );
+    }
+
+    #[test]
+    fn test_connect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = Address::Tcp(listener.local_addr().unwrap().to_string());
+        let sender = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"hx over tcp").unwrap();
+        });
+        let mut received: Vec<u8> = Vec::new();
+        connect(&address)
+            .unwrap()
+            .read_to_end(&mut received)
+            .unwrap();
+        sender.join().unwrap();
+        assert_eq!(received, b"hx over tcp");
+    }
+}
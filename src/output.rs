@@ -0,0 +1,3589 @@
+//! output rendering
+use crate::buffer::{buf_to_array, read_line, Arrivals, Line, Page, PageReader};
+use crate::decode::date_time;
+use crate::search::Finder;
+use crate::stats::EntropyWindow;
+use crate::template::Annotator;
+use crate::Format;
+use ansi_term::{Color, Style};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::f64;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Options controlling how a hex dump is rendered.
+#[derive(Clone, Debug)]
+pub struct DumpOptions {
+    /// number of bytes per line
+    pub column_width: u64,
+    /// number of bytes printed together without separating spaces
+    pub group_size: u64,
+    /// number of bytes printed as a single word value
+    pub word_size: u64,
+    /// print the octets of each group in reverse, as little endian
+    /// values, like `xxd -e`
+    pub little_endian_groups: bool,
+    /// number of bytes between the extra spaces splitting a line into
+    /// blocks, 0 for no blocks
+    pub block_size: u64,
+    /// byte order of words
+    pub endian: Endian,
+    /// octet format
+    pub format: Format,
+    /// formats of further rows printed below each line, like od given
+    /// several `-t` types
+    pub stacked: Vec<Format>,
+    /// colorize output
+    pub colorize: bool,
+    /// how colors are assigned to bytes
+    pub color_mode: ColorMode,
+    /// color theme
+    pub theme: Theme,
+    /// include format prefix
+    pub prefix: bool,
+    /// radix of the offset column
+    pub offset_format: OffsetFormat,
+    /// number of zero padded digits in the offset column
+    pub offset_width: usize,
+    /// text between the offset column and the octets
+    pub offset_separator: String,
+    /// collapse runs of identical lines into a single `*` line
+    pub squeeze: bool,
+    /// address added to offsets in the offset column
+    pub base_address: u64,
+    /// character set of the text column
+    pub charset: Charset,
+    /// how unprintable characters are shown in the text column
+    pub ascii_style: AsciiStyle,
+    /// text column placeholder for unprintable characters
+    pub nonprintable: char,
+    /// text column character for NUL, `None` shows the placeholder
+    pub nul: Option<char>,
+    /// text between the octets and the text column
+    pub separator: String,
+    /// draw `|` borders around the octets and the text column
+    pub frame: bool,
+    /// wrap the text column in `|`, without padding, like `hexdump -C`
+    pub gutter: bool,
+    /// which of the octets and the text column are printed
+    pub panes: Panes,
+    /// number lines from 1 in a column before the offset, counting from
+    /// the line at `Some(offset)`; `dump` counts from its start offset
+    pub line_numbers: Option<u64>,
+    /// repeat the offset of the last byte of each line after the line
+    pub end_offsets: bool,
+    /// print when the first byte of each line of a live input arrived
+    /// in a column before the line numbers
+    pub timestamps: Option<Timestamps>,
+    /// print a column index ruler above the dump, repeated every `n`
+    /// lines for `Some(n)`, or only once for `Some(0)`
+    pub header: Option<u64>,
+    /// what is printed after the dump
+    pub summary: Summary,
+}
+
+impl DumpOptions {
+    /// Style for a byte, or `None` when colorization is disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `b` - byte to be styled.
+    /// * `highlight` - background color, if the byte is highlighted.
+    pub fn style(&self, b: u8, highlight: Option<Color>) -> Option<Style> {
+        if !self.colorize {
+            return None;
+        }
+        let style = Style::new().fg(self.theme.color(b, self.color_mode));
+        Some(match highlight {
+            Some(background) => style.on(background),
+            None => style,
+        })
+    }
+
+    /// `text` painted in a theme color of the dump structure, such as
+    /// `theme.offset`, or `text` as is when colorization is disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - theme color.
+    /// * `text` - offset, separator or trailer text.
+    pub fn paint<'a>(&self, color: Color, text: &'a str) -> Cow<'a, str> {
+        match self.colorize {
+            true => Cow::Owned(color.paint(text).to_string()),
+            false => Cow::Borrowed(text),
+        }
+    }
+
+    /// Text column cell for a character decoded by `Charset::decode`.
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - decoded character.
+    pub fn text_cell(&self, c: Option<char>) -> String {
+        match (c, self.nul) {
+            (Some('\0'), Some(nul)) => nul.to_string(),
+            _ => self.ascii_style.render(c, self.nonprintable),
+        }
+    }
+
+    /// Text column cell for a character decoded by `Charset::decode`, if
+    /// it is a single character. Same as `text_cell` without allocating.
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - decoded character.
+    pub fn text_char(&self, c: Option<char>) -> Option<char> {
+        match (c, self.nul, self.ascii_style) {
+            (Some('\0'), Some(nul), _) => Some(nul),
+            (Some(c), _, _) if !c.is_control() => Some(c),
+            (None, _, _) | (_, _, AsciiStyle::Dots) => Some(self.nonprintable),
+            _ => None,
+        }
+    }
+
+    /// Width in characters of `bytes` octets in the hex column, including
+    /// the space after each group and between blocks.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - number of octets.
+    pub fn octets_width(&self, bytes: u64) -> u64 {
+        let blocks = match self.block_size {
+            0 => 0,
+            block => bytes.saturating_sub(1) / block,
+        };
+        let cell = if self.word_size > 1 {
+            self.word_size
+        } else {
+            self.group_size.max(1)
+        };
+        let rest = match bytes % cell {
+            0 => 0,
+            _ if self.little_endian_groups && self.word_size <= 1 => self.cell_width(cell) + 1,
+            rest => self.cell_width(rest) + 1,
+        };
+        bytes / cell * (self.cell_width(cell) + 1) + rest + blocks
+    }
+
+    /// Width in characters of a word or group of `bytes` octets printed
+    /// in `format`, without the space after it.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - octet format.
+    /// * `bytes` - number of octets in the word or group.
+    fn format_cell_width(&self, format: Format, bytes: u64) -> u64 {
+        let prefix = if self.prefix {
+            format.prefix().len() as u64
+        } else {
+            0
+        };
+        match self.word_size {
+            0 | 1 => prefix + bytes * format.digits(1) as u64,
+            _ => prefix + format.digits(bytes as usize) as u64,
+        }
+    }
+
+    /// Width in characters of a word or group of `bytes` octets, the
+    /// widest of the octet format and the stacked formats, so that the
+    /// stacked rows line up with the line.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - number of octets in the word or group.
+    fn cell_width(&self, bytes: u64) -> u64 {
+        self.stacked.iter().fold(
+            self.format_cell_width(self.format, bytes),
+            |width, format| width.max(self.format_cell_width(*format, bytes)),
+        )
+    }
+
+    /// Spaces printed before a word or group of `bytes` octets to right
+    /// align it to the widest of the stacked formats.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - number of octets in the word or group.
+    fn cell_padding(&self, bytes: u64) -> usize {
+        (self.cell_width(bytes) - self.format_cell_width(self.format, bytes)) as usize
+    }
+
+    /// Returns true if an extra space splits the line after its first
+    /// `bytes` bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - number of bytes printed so far on the line.
+    /// * `line_bytes` - number of bytes on the line.
+    fn block_ends(&self, bytes: u64, line_bytes: u64) -> bool {
+        self.block_size > 0 && bytes.is_multiple_of(self.block_size) && bytes < line_bytes
+    }
+}
+
+/// Range of input offsets rendered with a background color.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Highlight {
+    /// highlighted offsets
+    pub range: Range<u64>,
+    /// background color
+    pub color: Color,
+}
+
+/// Byte order of words printed with `--word-size`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Endian {
+    /// least significant byte first
+    Little,
+    /// most significant byte first
+    Big,
+}
+
+impl Endian {
+    /// Look up a byte order by its `--endian` name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - byte order name.
+    pub fn by_name(name: &str) -> Option<Endian> {
+        match name {
+            "le" => Some(Self::Little),
+            "be" => Some(Self::Big),
+            _ => None,
+        }
+    }
+
+    /// Value of a word stored in `bytes`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - at most 8 bytes of the word.
+    pub fn word(&self, bytes: &[u8]) -> u64 {
+        let fold = |word: u64, b: &u8| word << 8 | u64::from(*b);
+        match self {
+            Self::Little => bytes.iter().rev().fold(0, fold),
+            Self::Big => bytes.iter().fold(0, fold),
+        }
+    }
+}
+
+/// Source code language of `--array` output.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArrayLanguage {
+    /// rust
+    Rust,
+    /// rust module item, a `pub static` slice
+    RustModule,
+    /// C
+    C,
+    /// C include file, as printed by `xxd -i`
+    CInclude,
+    /// golang
+    Go,
+    /// python
+    Python,
+    /// kotlin
+    Kotlin,
+    /// java
+    Java,
+    /// swift
+    Swift,
+    /// fsharp
+    FSharp,
+    /// javascript
+    JavaScript,
+    /// typescript
+    TypeScript,
+    /// C#
+    CSharp,
+    /// zig
+    Zig,
+    /// nim
+    Nim,
+    /// ruby
+    Ruby,
+}
+
+/// Syntax of an array literal. `{name}` and `{len}` in the header and
+/// footer are replaced by the identifier and number of bytes, `{binding}`
+/// and `{type}` by the declaration keywords and element type of
+/// `ArrayOptions`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ArraySyntax {
+    /// line opening the array
+    pub header: &'static str,
+    /// separator between elements
+    pub separator: &'static str,
+    /// suffix of each element, such as a type suffix
+    pub element_suffix: &'static str,
+    /// whether the last element is followed by a separator
+    pub trailing_separator: bool,
+    /// line closing the array
+    pub footer: &'static str,
+    /// identifier used when none is given
+    pub default_name: &'static str,
+}
+
+impl ArrayLanguage {
+    /// Every language, in `--array` help order.
+    pub const ALL: [ArrayLanguage; 16] = [
+        Self::Rust,
+        Self::RustModule,
+        Self::C,
+        Self::CInclude,
+        Self::Go,
+        Self::Python,
+        Self::Kotlin,
+        Self::Java,
+        Self::Swift,
+        Self::FSharp,
+        Self::JavaScript,
+        Self::TypeScript,
+        Self::CSharp,
+        Self::Zig,
+        Self::Nim,
+        Self::Ruby,
+    ];
+
+    /// `--array` name of the language.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rust => "r",
+            Self::RustModule => "rmod",
+            Self::C => "c",
+            Self::CInclude => "i",
+            Self::Go => "g",
+            Self::Python => "p",
+            Self::Kotlin => "k",
+            Self::Java => "j",
+            Self::Swift => "s",
+            Self::FSharp => "f",
+            Self::JavaScript => "js",
+            Self::TypeScript => "ts",
+            Self::CSharp => "cs",
+            Self::Zig => "zig",
+            Self::Nim => "nim",
+            Self::Ruby => "rb",
+        }
+    }
+
+    /// Look up a language by its `--array` name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - language name.
+    pub fn by_name(name: &str) -> Option<ArrayLanguage> {
+        Self::ALL
+            .into_iter()
+            .find(|language| language.name() == name)
+    }
+
+    /// Returns true if `ArrayOptions` binding and element type apply to
+    /// this language.
+    pub fn is_typed(&self) -> bool {
+        matches!(
+            self,
+            Self::Rust | Self::RustModule | Self::C | Self::CInclude
+        )
+    }
+
+    /// Returns true if this language is rust, where `#[rustfmt::skip]`
+    /// applies.
+    pub fn is_rust(&self) -> bool {
+        matches!(self, Self::Rust | Self::RustModule)
+    }
+
+    /// Syntax of an array literal in this language.
+    pub fn syntax(&self) -> ArraySyntax {
+        let syntax = ArraySyntax {
+            header: "",
+            separator: ", ",
+            element_suffix: "",
+            trailing_separator: false,
+            footer: "",
+            default_name: "a",
+        };
+        match self {
+            Self::Rust => ArraySyntax {
+                header: "{binding} {name}: [{type}; {len}] = [",
+                footer: "];",
+                default_name: "ARRAY",
+                ..syntax
+            },
+            Self::RustModule => ArraySyntax {
+                header: "{binding} {name}: &[{type}] = &[",
+                trailing_separator: true,
+                footer: "];",
+                default_name: "DATA",
+                ..syntax
+            },
+            Self::C => ArraySyntax {
+                header: "{binding}{type} {name}[{len}] = {",
+                footer: "};",
+                default_name: "ARRAY",
+                ..syntax
+            },
+            Self::CInclude => ArraySyntax {
+                header: "{binding}{type} {name}[] = {",
+                footer: "};\nunsigned int {name}_len = {len};",
+                ..syntax
+            },
+            Self::Go => ArraySyntax {
+                header: "{name} := [{len}]byte{",
+                trailing_separator: true,
+                footer: "}",
+                ..syntax
+            },
+            Self::Python => ArraySyntax {
+                header: "{name} = [",
+                footer: "]",
+                ..syntax
+            },
+            Self::Kotlin => ArraySyntax {
+                header: "val {name} = byteArrayOf(",
+                footer: ")",
+                ..syntax
+            },
+            Self::Java => ArraySyntax {
+                header: "byte[] {name} = new byte[]{",
+                footer: "};",
+                ..syntax
+            },
+            Self::Swift => ArraySyntax {
+                header: "let {name}: [UInt8] = [",
+                footer: "]",
+                ..syntax
+            },
+            Self::FSharp => ArraySyntax {
+                header: "let {name} = [|",
+                separator: "; ",
+                element_suffix: "uy",
+                footer: "|]",
+                ..syntax
+            },
+            Self::JavaScript => ArraySyntax {
+                header: "const {name} = new Uint8Array([",
+                footer: "]);",
+                ..syntax
+            },
+            Self::TypeScript => ArraySyntax {
+                header: "const {name}: Uint8Array = new Uint8Array([",
+                footer: "]);",
+                ..syntax
+            },
+            Self::CSharp => ArraySyntax {
+                header: "byte[] {name} = new byte[] {",
+                footer: "};",
+                ..syntax
+            },
+            Self::Zig => ArraySyntax {
+                header: "const {name} = [_]u8{",
+                footer: "};",
+                ..syntax
+            },
+            Self::Nim => ArraySyntax {
+                header: "let {name} = @[",
+                element_suffix: "'u8",
+                footer: "]",
+                ..syntax
+            },
+            Self::Ruby => ArraySyntax {
+                header: "{name} = [",
+                footer: "]",
+                ..syntax
+            },
+        }
+    }
+}
+
+/// Declaration of a generated rust or C array.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ArrayBinding {
+    /// rust `let`, plain C declaration
+    #[default]
+    Let,
+    /// rust `pub const`, C `const`
+    Const,
+    /// rust `pub static`, C `static`
+    Static,
+}
+
+/// Element type of a generated rust or C array.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ArrayElement {
+    /// unsigned byte
+    #[default]
+    U8,
+    /// signed byte, elements are printed as signed decimals
+    I8,
+    /// unsigned 32 bit integer holding one byte each
+    U32,
+}
+
+impl ArrayElement {
+    /// Look up an element type by its `--array-type` name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - element type name.
+    pub fn by_name(name: &str) -> Option<ArrayElement> {
+        match name {
+            "u8" => Some(Self::U8),
+            "i8" => Some(Self::I8),
+            "u32" => Some(Self::U32),
+            _ => None,
+        }
+    }
+}
+
+/// Options of `--array` output.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArrayOptions {
+    /// array identifier, defaults to `ARRAY` or `a`
+    pub name: Option<String>,
+    /// declaration keywords, rust and C only
+    pub binding: ArrayBinding,
+    /// element type, rust and C only
+    pub element: ArrayElement,
+    /// precede rust arrays with `#[rustfmt::skip]`
+    pub rustfmt_skip: bool,
+    /// wrap rust module arrays in `pub mod <module>`
+    pub module: Option<String>,
+}
+
+/// Character set used to decode the text column.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Charset {
+    /// printable ASCII, other bytes are dotted out
+    Ascii,
+    /// UTF-8, multi-byte sequences shown as the decoded character
+    Utf8,
+    /// EBCDIC code page 037, as used on IBM mainframes
+    Ebcdic,
+    /// IBM PC code page 437, as used by DOS
+    Cp437,
+    /// ISO 8859-1
+    Latin1,
+}
+
+/// Text column placeholder for the continuation bytes of a character.
+pub const CONTINUATION: char = '\u{b7}';
+
+/// Characters of EBCDIC code page 037 bytes.
+#[rustfmt::skip]
+const EBCDIC: [char; 256] = [
+    '\u{0}', '\u{1}', '\u{2}', '\u{3}', '\u{9c}', '\u{9}', '\u{86}', '\u{7f}',
+    '\u{97}', '\u{8d}', '\u{8e}', '\u{b}', '\u{c}', '\u{d}', '\u{e}', '\u{f}',
+    '\u{10}', '\u{11}', '\u{12}', '\u{13}', '\u{9d}', '\u{85}', '\u{8}', '\u{87}',
+    '\u{18}', '\u{19}', '\u{92}', '\u{8f}', '\u{1c}', '\u{1d}', '\u{1e}', '\u{1f}',
+    '\u{80}', '\u{81}', '\u{82}', '\u{83}', '\u{84}', '\u{a}', '\u{17}', '\u{1b}',
+    '\u{88}', '\u{89}', '\u{8a}', '\u{8b}', '\u{8c}', '\u{5}', '\u{6}', '\u{7}',
+    '\u{90}', '\u{91}', '\u{16}', '\u{93}', '\u{94}', '\u{95}', '\u{96}', '\u{4}',
+    '\u{98}', '\u{99}', '\u{9a}', '\u{9b}', '\u{14}', '\u{15}', '\u{9e}', '\u{1a}',
+    ' ', '\u{a0}', 'â', 'ä', 'à', 'á', 'ã', 'å',
+    'ç', 'ñ', '¢', '.', '<', '(', '+', '|',
+    '&', 'é', 'ê', 'ë', 'è', 'í', 'î', 'ï',
+    'ì', 'ß', '!', '$', '*', ')', ';', '¬',
+    '-', '/', 'Â', 'Ä', 'À', 'Á', 'Ã', 'Å',
+    'Ç', 'Ñ', '¦', ',', '%', '_', '>', '?',
+    'ø', 'É', 'Ê', 'Ë', 'È', 'Í', 'Î', 'Ï',
+    'Ì', '`', ':', '#', '@', '\'', '=', '"',
+    'Ø', 'a', 'b', 'c', 'd', 'e', 'f', 'g',
+    'h', 'i', '«', '»', 'ð', 'ý', 'þ', '±',
+    '°', 'j', 'k', 'l', 'm', 'n', 'o', 'p',
+    'q', 'r', 'ª', 'º', 'æ', '¸', 'Æ', '¤',
+    'µ', '~', 's', 't', 'u', 'v', 'w', 'x',
+    'y', 'z', '¡', '¿', 'Ð', 'Ý', 'Þ', '®',
+    '^', '£', '¥', '·', '©', '§', '¶', '¼',
+    '½', '¾', '[', ']', '¯', '¨', '´', '×',
+    '{', 'A', 'B', 'C', 'D', 'E', 'F', 'G',
+    'H', 'I', '\u{ad}', 'ô', 'ö', 'ò', 'ó', 'õ',
+    '}', 'J', 'K', 'L', 'M', 'N', 'O', 'P',
+    'Q', 'R', '¹', 'û', 'ü', 'ù', 'ú', 'ÿ',
+    '\\', '÷', 'S', 'T', 'U', 'V', 'W', 'X',
+    'Y', 'Z', '²', 'Ô', 'Ö', 'Ò', 'Ó', 'Õ',
+    '0', '1', '2', '3', '4', '5', '6', '7',
+    '8', '9', '³', 'Û', 'Ü', 'Ù', 'Ú', '\u{9f}',
+];
+
+/// Characters of code page 437 bytes from 0x80, below which it is ASCII.
+#[rustfmt::skip]
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç',
+    'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù',
+    'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º',
+    '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖',
+    '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟',
+    '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫',
+    '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ',
+    'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈',
+    '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+impl Charset {
+    /// Look up a character set by its `--charset` name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - character set name.
+    pub fn by_name(name: &str) -> Option<Charset> {
+        match name {
+            "ascii" => Some(Self::Ascii),
+            "utf8" => Some(Self::Utf8),
+            "ebcdic" => Some(Self::Ebcdic),
+            "cp437" => Some(Self::Cp437),
+            "latin1" => Some(Self::Latin1),
+            _ => None,
+        }
+    }
+
+    /// Characters of `bytes`, one per byte, `None` for bytes that are not
+    /// a character in the character set. Control characters are kept,
+    /// for `AsciiStyle` to render. In UTF-8 a character is given at its
+    /// first byte and `CONTINUATION` at the rest, including continuation
+    /// bytes at the start of `bytes` whose character began on a previous
+    /// line. A character cut off at the end of `bytes` is `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - bytes of a line.
+    pub fn decode(&self, bytes: &[u8]) -> Vec<Option<char>> {
+        match self {
+            Self::Ascii => bytes
+                .iter()
+                .map(|b| b.is_ascii().then(|| char::from(*b)))
+                .collect(),
+            Self::Ebcdic => bytes.iter().map(|b| Some(EBCDIC[*b as usize])).collect(),
+            Self::Cp437 => bytes
+                .iter()
+                .map(|b| match b {
+                    0x80..=0xff => Some(CP437_HIGH[*b as usize - 0x80]),
+                    _ => Some(char::from(*b)),
+                })
+                .collect(),
+            Self::Latin1 => bytes.iter().map(|b| Some(char::from(*b))).collect(),
+            Self::Utf8 => {
+                let mut cells: Vec<Option<char>> = Vec::with_capacity(bytes.len());
+                let continued = bytes.iter().take_while(|b| **b & 0xc0 == 0x80).count();
+                cells.resize(continued, Some(CONTINUATION));
+                let mut i = continued;
+                while i < bytes.len() {
+                    let len = match bytes[i] {
+                        0xc2..=0xdf => 2,
+                        0xe0..=0xef => 3,
+                        0xf0..=0xf4 => 4,
+                        _ => 1,
+                    };
+                    match bytes
+                        .get(i..i + len)
+                        .and_then(|sequence| str::from_utf8(sequence).ok())
+                        .and_then(|text| text.chars().next())
+                    {
+                        Some(c) => {
+                            cells.push(Some(c));
+                            cells.resize(cells.len() + len - 1, Some(CONTINUATION));
+                            i += len;
+                        }
+                        None => {
+                            cells.push(None);
+                            i += 1;
+                        }
+                    }
+                }
+                cells
+            }
+        }
+    }
+}
+
+/// How the text column shows characters that are not printable.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AsciiStyle {
+    /// `.` for every unprintable character
+    Dots,
+    /// caret notation for ASCII control characters, such as `^C`
+    Caret,
+    /// Unicode control pictures for ASCII control characters, such as `␃`
+    Pictures,
+}
+
+impl AsciiStyle {
+    /// Look up a style by its `--ascii-style` name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - style name.
+    pub fn by_name(name: &str) -> Option<AsciiStyle> {
+        match name {
+            "dots" => Some(Self::Dots),
+            "caret" => Some(Self::Caret),
+            "pictures" => Some(Self::Pictures),
+            _ => None,
+        }
+    }
+
+    /// Text column cell for a character decoded by `Charset::decode`.
+    /// Unprintable characters other than ASCII control characters, and
+    /// bytes that are not a character, are `placeholder` in every style.
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - decoded character.
+    /// * `placeholder` - character shown for unprintable characters.
+    pub fn render(&self, c: Option<char>, placeholder: char) -> String {
+        match (self, c) {
+            (_, Some(c)) if !c.is_control() => c.to_string(),
+            (Self::Caret, Some('\u{7f}')) => "^?".to_string(),
+            (Self::Caret, Some(c)) if c < ' ' => format!("^{}", char::from(c as u8 + 0x40)),
+            (Self::Pictures, Some('\u{7f}')) => "\u{2421}".to_string(),
+            (Self::Pictures, Some(c)) if c < ' ' => {
+                char::from_u32(0x2400 + c as u32).map_or(placeholder.to_string(), String::from)
+            }
+            _ => placeholder.to_string(),
+        }
+    }
+}
+
+/// Lines printed after a dump.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Summary {
+    /// nothing, so the dump compares equal to xxd output
+    None,
+    /// the `bytes:` trailer
+    Bytes,
+    /// the `bytes:` trailer, the range shown and the time taken
+    Verbose,
+    /// the offset after the last byte, like `hexdump -C`
+    Offset,
+}
+
+impl Summary {
+    /// Look up a summary by its `--summary` name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - summary name.
+    pub fn by_name(name: &str) -> Option<Summary> {
+        match name {
+            "none" => Some(Self::None),
+            "bytes" => Some(Self::Bytes),
+            "verbose" => Some(Self::Verbose),
+            "offset" => Some(Self::Offset),
+            _ => None,
+        }
+    }
+}
+
+/// Columns of a dump line printed after the offset.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Panes {
+    /// the octets and the text column
+    Both,
+    /// the octets only, `--no-ascii`
+    Hex,
+    /// the text column only, `--ascii-only`
+    Text,
+}
+
+/// Timestamp column of a live input, see `DumpOptions::timestamps`.
+#[derive(Clone, Debug)]
+pub struct Timestamps {
+    /// time relative timestamps count from, `None` for absolute UTC ones
+    pub origin: Option<SystemTime>,
+    /// arrival times of the input bytes, recorded by `buffer::Timed`
+    pub arrivals: Arc<Mutex<Arrivals>>,
+}
+
+impl Timestamps {
+    /// Width of a stamp, without the space after it.
+    fn width(&self) -> usize {
+        match self.origin {
+            // [    0.123456]
+            Some(_) => 14,
+            // [1970-01-01 00:00:00.123456]
+            None => 28,
+        }
+    }
+
+    /// Stamp of the line starting at `offset`, blank if it has not
+    /// arrived.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - offset of the first byte of the line.
+    fn stamp(&self, offset: u64) -> String {
+        let time = self.arrivals.lock().unwrap().at(offset);
+        match (time, self.origin) {
+            (Some(time), Some(origin)) => {
+                let elapsed = time.duration_since(origin).unwrap_or_default();
+                format!("[{:>12.6}]", elapsed.as_secs_f64())
+            }
+            (Some(time), None) => {
+                let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+                format!(
+                    "[{}.{:06}]",
+                    date_time(since.as_secs() as i64),
+                    since.subsec_micros()
+                )
+            }
+            (None, _) => format!("{:1$}", "", self.width()),
+        }
+    }
+}
+
+/// Radix of the offset column.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OffsetFormat {
+    /// hexadecimal with a `0x` prefix
+    Hex,
+    /// hexadecimal with a `0x` prefix and uppercase digits
+    UpperHex,
+    /// hexadecimal without a prefix, as printed by xxd
+    BareHex,
+    /// decimal
+    Dec,
+    /// octal, as printed by od
+    Oct,
+    /// decimal sector number and hexadecimal offset into the sector, like
+    /// `2:1f0` for 512 byte sectors
+    Sector(u64),
+}
+
+impl OffsetFormat {
+    /// Look up an offset format by its `--offset-format` name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - offset format name.
+    pub fn by_name(name: &str) -> Option<OffsetFormat> {
+        match name {
+            "hex" => Some(Self::Hex),
+            "bare-hex" => Some(Self::BareHex),
+            "dec" => Some(Self::Dec),
+            "oct" => Some(Self::Oct),
+            _ => None,
+        }
+    }
+
+    /// Format an offset zero padded to `width` digits.
+    ///
+    /// # Arguments
+    ///
+    /// * `b` - offset value.
+    /// * `width` - minimum number of digits.
+    pub fn format(&self, b: u64, width: usize) -> String {
+        match self {
+            Self::Hex => format!("0x{:01$x}", b, width),
+            Self::UpperHex => format!("0x{:01$X}", b, width),
+            Self::BareHex => format!("{:01$x}", b, width),
+            Self::Dec => format!("{:01$}", b, width),
+            Self::Oct => format!("{:01$o}", b, width),
+            Self::Sector(size) => {
+                let digits = format!("{:x}", size.saturating_sub(1)).len();
+                format!("{:0w$}:{:0d$x}", b / size, b % size, w = width, d = digits)
+            }
+        }
+    }
+
+    /// Parse an offset printed by `format`.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - offset text.
+    pub fn parse(&self, text: &str) -> Option<u64> {
+        match self {
+            Self::Hex | Self::UpperHex | Self::BareHex => {
+                let digits = text
+                    .strip_prefix("0x")
+                    .or_else(|| text.strip_prefix("0X"))
+                    .unwrap_or(text);
+                u64::from_str_radix(digits, 16).ok()
+            }
+            Self::Dec => text.parse::<u64>().ok(),
+            Self::Oct => u64::from_str_radix(text, 8).ok(),
+            Self::Sector(size) => {
+                let (sector, offset) = text.split_once(':')?;
+                let offset = u64::from_str_radix(offset, 16).ok()?;
+                sector
+                    .parse::<u64>()
+                    .ok()?
+                    .checked_mul(*size)?
+                    .checked_add(offset)
+            }
+        }
+    }
+}
+
+/// How colors are assigned to bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    /// one color per byte value
+    Value,
+    /// one color per byte class, see `ByteClass`
+    Semantic,
+    /// bytes colored by value, with the offset column tinted by the
+    /// entropy of the preceding `ENTROPY_WINDOW` bytes
+    Entropy,
+}
+
+impl ColorMode {
+    /// Look up a color mode by its `--color-mode` name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - color mode name.
+    pub fn by_name(name: &str) -> Option<ColorMode> {
+        match name {
+            "value" => Some(Self::Value),
+            "semantic" => Some(Self::Semantic),
+            "entropy" => Some(Self::Entropy),
+            _ => None,
+        }
+    }
+}
+
+/// Number of bytes over which `ColorMode::Entropy` measures entropy.
+pub const ENTROPY_WINDOW: usize = 256;
+
+/// Color for an entropy level in bits per byte, from blue for repetitive
+/// data through green and yellow for text and code to red for compressed
+/// or encrypted data.
+///
+/// # Arguments
+///
+/// * `entropy` - entropy, 0 to 8.
+pub fn entropy_color(entropy: f64) -> Color {
+    match entropy {
+        e if e < 2.0 => Color::Fixed(27),
+        e if e < 4.0 => Color::Fixed(35),
+        e if e < 6.0 => Color::Fixed(178),
+        e if e < 7.0 => Color::Fixed(208),
+        _ => Color::Fixed(196),
+    }
+}
+
+/// Broad classification of a byte, used for semantic colorization.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ByteClass {
+    /// 0x00
+    Null,
+    /// ascii letters a-z and A-Z
+    Letter,
+    /// ascii digits 0-9
+    Digit,
+    /// other printable ascii
+    Punctuation,
+    /// space, tab, newline, vertical tab, form feed, carriage return
+    Whitespace,
+    /// remaining control characters including DEL
+    Control,
+    /// bytes with the high bit set
+    HighBit,
+}
+
+impl ByteClass {
+    /// Classify a byte.
+    ///
+    /// # Arguments
+    ///
+    /// * `b` - byte to be classified.
+    pub fn of(b: u8) -> ByteClass {
+        match b {
+            0x00 => Self::Null,
+            b'a'..=b'z' | b'A'..=b'Z' => Self::Letter,
+            b'0'..=b'9' => Self::Digit,
+            b' ' | b'\t' | b'\n' | 0x0b | 0x0c | b'\r' => Self::Whitespace,
+            0x21..=0x7e => Self::Punctuation,
+            0x80..=0xff => Self::HighBit,
+            _ => Self::Control,
+        }
+    }
+}
+
+/// Color theme used for colorized output.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// theme name, as accepted by `--theme`
+    pub name: &'static str,
+    /// `ByteClass::Null` color
+    pub null: Color,
+    /// `ByteClass::Letter` color
+    pub letter: Color,
+    /// `ByteClass::Digit` color
+    pub digit: Color,
+    /// `ByteClass::Punctuation` color
+    pub punctuation: Color,
+    /// `ByteClass::Whitespace` color
+    pub whitespace: Color,
+    /// `ByteClass::Control` color
+    pub control: Color,
+    /// `ByteClass::HighBit` color
+    pub high_bit: Color,
+    /// background color of highlighted bytes, such as `--find` matches
+    pub highlight: Color,
+    /// offset column and header ruler color
+    pub offset: Color,
+    /// color of the separator and frame borders between the columns
+    pub separator: Color,
+    /// color of the `bytes:` trailer
+    pub trailer: Color,
+    /// colors spread evenly over byte values when coloring by value,
+    /// empty uses the terminal 256 color palette (see `byte_to_color`)
+    pub values: &'static [Color],
+}
+
+impl Theme {
+    /// default theme
+    pub const DEFAULT: Theme = Theme {
+        name: "default",
+        null: Color::Fixed(242),
+        letter: Color::Fixed(114),
+        digit: Color::Fixed(75),
+        punctuation: Color::Fixed(180),
+        whitespace: Color::Fixed(44),
+        control: Color::Fixed(204),
+        high_bit: Color::Fixed(214),
+        highlight: Color::Fixed(89),
+        offset: Color::Fixed(244),
+        separator: Color::Fixed(240),
+        trailer: Color::Fixed(244),
+        values: &[],
+    };
+
+    /// solarized dark theme
+    pub const SOLARIZED_DARK: Theme = Theme {
+        name: "solarized-dark",
+        null: Color::Fixed(240),
+        letter: Color::Fixed(64),
+        digit: Color::Fixed(33),
+        punctuation: Color::Fixed(136),
+        whitespace: Color::Fixed(37),
+        control: Color::Fixed(160),
+        high_bit: Color::Fixed(125),
+        highlight: Color::Fixed(236),
+        offset: Color::Fixed(241),
+        separator: Color::Fixed(239),
+        trailer: Color::Fixed(241),
+        values: &[
+            Color::Fixed(33),
+            Color::Fixed(37),
+            Color::Fixed(64),
+            Color::Fixed(136),
+            Color::Fixed(166),
+            Color::Fixed(160),
+            Color::Fixed(125),
+            Color::Fixed(61),
+        ],
+    };
+
+    /// solarized light theme
+    pub const SOLARIZED_LIGHT: Theme = Theme {
+        name: "solarized-light",
+        null: Color::Fixed(245),
+        letter: Color::Fixed(64),
+        digit: Color::Fixed(33),
+        punctuation: Color::Fixed(136),
+        whitespace: Color::Fixed(37),
+        control: Color::Fixed(160),
+        high_bit: Color::Fixed(61),
+        highlight: Color::Fixed(254),
+        offset: Color::Fixed(245),
+        separator: Color::Fixed(250),
+        trailer: Color::Fixed(245),
+        values: &[
+            Color::Fixed(61),
+            Color::Fixed(33),
+            Color::Fixed(37),
+            Color::Fixed(64),
+            Color::Fixed(136),
+            Color::Fixed(166),
+            Color::Fixed(160),
+            Color::Fixed(125),
+        ],
+    };
+
+    /// tango theme
+    pub const TANGO: Theme = Theme {
+        name: "tango",
+        null: Color::Fixed(102),
+        letter: Color::Fixed(112),
+        digit: Color::Fixed(74),
+        punctuation: Color::Fixed(221),
+        whitespace: Color::Fixed(139),
+        control: Color::Fixed(160),
+        high_bit: Color::Fixed(214),
+        highlight: Color::Fixed(58),
+        offset: Color::Fixed(102),
+        separator: Color::Fixed(240),
+        trailer: Color::Fixed(102),
+        values: &[
+            Color::Fixed(74),
+            Color::Fixed(139),
+            Color::Fixed(112),
+            Color::Fixed(221),
+            Color::Fixed(214),
+            Color::Fixed(137),
+            Color::Fixed(160),
+        ],
+    };
+
+    /// monokai theme
+    pub const MONOKAI: Theme = Theme {
+        name: "monokai",
+        null: Color::Fixed(242),
+        letter: Color::Fixed(148),
+        digit: Color::Fixed(141),
+        punctuation: Color::Fixed(186),
+        whitespace: Color::Fixed(81),
+        control: Color::Fixed(197),
+        high_bit: Color::Fixed(208),
+        highlight: Color::Fixed(59),
+        offset: Color::Fixed(242),
+        separator: Color::Fixed(238),
+        trailer: Color::Fixed(242),
+        values: &[
+            Color::Fixed(81),
+            Color::Fixed(141),
+            Color::Fixed(148),
+            Color::Fixed(186),
+            Color::Fixed(208),
+            Color::Fixed(197),
+        ],
+    };
+
+    /// grayscale theme
+    pub const GRAYSCALE: Theme = Theme {
+        name: "grayscale",
+        null: Color::Fixed(238),
+        letter: Color::Fixed(255),
+        digit: Color::Fixed(250),
+        punctuation: Color::Fixed(246),
+        whitespace: Color::Fixed(244),
+        control: Color::Fixed(242),
+        high_bit: Color::Fixed(248),
+        highlight: Color::Fixed(236),
+        offset: Color::Fixed(244),
+        separator: Color::Fixed(240),
+        trailer: Color::Fixed(244),
+        values: &[
+            Color::Fixed(240),
+            Color::Fixed(242),
+            Color::Fixed(244),
+            Color::Fixed(246),
+            Color::Fixed(248),
+            Color::Fixed(250),
+            Color::Fixed(252),
+            Color::Fixed(254),
+        ],
+    };
+
+    /// all themes selectable via `--theme`
+    pub const ALL: [Theme; 6] = [
+        Theme::DEFAULT,
+        Theme::SOLARIZED_DARK,
+        Theme::SOLARIZED_LIGHT,
+        Theme::TANGO,
+        Theme::MONOKAI,
+        Theme::GRAYSCALE,
+    ];
+
+    /// Look up a theme by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - theme name.
+    pub fn by_name(name: &str) -> Option<Theme> {
+        Theme::ALL.iter().find(|theme| theme.name == name).copied()
+    }
+
+    /// Color for a byte class.
+    ///
+    /// # Arguments
+    ///
+    /// * `class` - byte class.
+    pub fn class_color(&self, class: ByteClass) -> Color {
+        match class {
+            ByteClass::Null => self.null,
+            ByteClass::Letter => self.letter,
+            ByteClass::Digit => self.digit,
+            ByteClass::Punctuation => self.punctuation,
+            ByteClass::Whitespace => self.whitespace,
+            ByteClass::Control => self.control,
+            ByteClass::HighBit => self.high_bit,
+        }
+    }
+
+    /// Color for a byte.
+    ///
+    /// # Arguments
+    ///
+    /// * `b` - byte to be colored.
+    /// * `mode` - how colors are assigned to bytes.
+    pub fn color(&self, b: u8, mode: ColorMode) -> Color {
+        match mode {
+            ColorMode::Semantic => self.class_color(ByteClass::of(b)),
+            ColorMode::Value | ColorMode::Entropy if self.values.is_empty() => {
+                Color::Fixed(byte_to_color(b))
+            }
+            ColorMode::Value | ColorMode::Entropy => {
+                self.values[b as usize * self.values.len() / 0x100]
+            }
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::DEFAULT
+    }
+}
+
+/// offset column
+///
+/// # Arguments
+///
+/// * `b` - offset value.
+pub fn offset(b: u64) -> String {
+    format!("{:#08x}", b)
+}
+
+/// print offset to std out, relative to the base address
+pub fn print_offset(w: &mut impl Write, b: u64, options: &DumpOptions) -> io::Result<()> {
+    let offset = options
+        .offset_format
+        .format(options.base_address.wrapping_add(b), options.offset_width);
+    write!(
+        w,
+        "{}{}",
+        options.paint(options.theme.offset, &offset),
+        options.offset_separator
+    )
+}
+
+/// Print the `bytes:` trailer after a dump, for summaries that include it.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `bytes` - number of bytes, or numbers of the inputs of a diff.
+/// * `options` - dump options.
+pub fn print_trailer(
+    w: &mut impl Write,
+    bytes: impl fmt::Display,
+    options: &DumpOptions,
+) -> io::Result<()> {
+    if matches!(options.summary, Summary::None | Summary::Offset) {
+        return Ok(());
+    }
+    let trailer = format!("bytes: {}", bytes);
+    writeln!(w, "   {}", options.paint(options.theme.trailer, &trailer))
+}
+
+/// Print the range shown and the time taken for a verbose summary, or
+/// the offset after the last byte for an offset summary of a non-empty
+/// dump.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `start_offset` - offset of the first byte shown.
+/// * `bytes` - number of bytes shown.
+/// * `elapsed` - time taken by the dump.
+/// * `options` - dump options.
+pub fn print_summary(
+    w: &mut impl Write,
+    start_offset: u64,
+    bytes: u64,
+    elapsed: Duration,
+    options: &DumpOptions,
+) -> io::Result<()> {
+    let address = |offset: u64| {
+        options.offset_format.format(
+            options.base_address.wrapping_add(offset),
+            options.offset_width,
+        )
+    };
+    match options.summary {
+        Summary::Verbose => {}
+        Summary::Offset if bytes > 0 => {
+            let end = address(start_offset.saturating_add(bytes));
+            return writeln!(w, "{}", options.paint(options.theme.offset, &end));
+        }
+        _ => return Ok(()),
+    }
+    let range = format!(
+        "range: {}..{}",
+        address(start_offset),
+        address(start_offset.saturating_add(bytes))
+    );
+    let elapsed = format!("elapsed: {:.3}s", elapsed.as_secs_f64());
+    writeln!(w, "   {}", options.paint(options.theme.trailer, &range))?;
+    writeln!(w, " {}", options.paint(options.theme.trailer, &elapsed))
+}
+
+/// Print a ruler with the index of the first byte of each octet cell,
+/// in the radix of the offset column, lined up with the line at `offset`.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `offset` - offset of the line printed below the ruler.
+/// * `options` - dump options.
+pub fn print_header(w: &mut impl Write, offset: u64, options: &DumpOptions) -> io::Result<()> {
+    // the ruler indexes octet cells
+    if options.panes == Panes::Text {
+        return Ok(());
+    }
+    let offset = options.offset_format.format(
+        options.base_address.wrapping_add(offset),
+        options.offset_width,
+    );
+    let mut ruler = format!(
+        "{:<1$}",
+        "",
+        margin_width(options) + offset.len() + options.offset_separator.chars().count()
+    );
+    if options.frame {
+        ruler.push_str("| ");
+    }
+    let cell = if options.word_size > 1 {
+        options.word_size
+    } else {
+        options.group_size.max(1)
+    };
+    let mut index: u64 = 0x0;
+    while index < options.column_width {
+        let bytes = cell.min(options.column_width - index);
+        let width = options.octets_width(bytes) as usize - 1;
+        let label = options.offset_format.format(index, 2);
+        let label = label.trim_start_matches("0x");
+        let label = match options.format {
+            Format::UpperHex => label.to_uppercase(),
+            _ => label.to_string(),
+        };
+        let label = &label[label.len().saturating_sub(width)..];
+        ruler.push_str(&format!("{:>1$} ", label, width));
+        index += bytes;
+        if options.block_ends(index, options.column_width) {
+            ruler.push(' ');
+        }
+    }
+    writeln!(
+        w,
+        "{}",
+        options.paint(options.theme.offset, ruler.trim_end())
+    )
+}
+
+/// Returns true if a header ruler is printed before the line following
+/// `printed` dump lines.
+fn header_due(options: &DumpOptions, printed: u64) -> bool {
+    match options.header {
+        Some(0) => printed == 0,
+        Some(every) => printed.is_multiple_of(every),
+        None => false,
+    }
+}
+
+/// print byte to std out
+pub fn print_byte(
+    w: &mut impl Write,
+    b: u8,
+    format: Format,
+    style: Option<Style>,
+    prefix: bool,
+) -> io::Result<()> {
+    if let Some(style) = style {
+        // note, for color testing: for (( i = 0; i < 256; i++ )); do echo "$(tput setaf $i)This is ($i) $(tput sgr0)"; done
+        write!(w, "{} ", style.paint(&format.octets(prefix)[b as usize]))
+    } else {
+        w.write_all(format.octets(prefix)[b as usize].as_bytes())?;
+        w.write_all(b" ")
+    }
+}
+
+/// get the color for a specific byte
+pub fn byte_to_color(b: u8) -> u8 {
+    let mut color: u8 = b;
+    if color < 1 {
+        color = 0x16;
+    }
+    color
+}
+
+/// append char representation of a byte to a buffer, with
+/// unprintable bytes shown in `ascii_style`
+pub fn append_ascii(target: &mut Vec<u8>, b: u8, style: Option<Style>, ascii_style: AsciiStyle) {
+    let cell = ascii_style.render(Charset::Ascii.decode(&[b])[0], '.');
+    append_cell(target, &cell, style);
+}
+
+/// append a text column cell to a buffer
+fn append_cell(target: &mut Vec<u8>, cell: &str, style: Option<Style>) {
+    if let Some(style) = style {
+        // writing to a Vec cannot fail
+        let _ = write!(target, "{}", style.paint(cell));
+    } else {
+        target.extend(cell.as_bytes());
+    }
+}
+
+/// Minimum width of the `--line-numbers` column.
+const LINE_NUMBER_WIDTH: usize = 6;
+
+/// Width of the timestamp and line number columns before the offset,
+/// with the spaces after them.
+fn margin_width(options: &DumpOptions) -> usize {
+    let timestamps = options
+        .timestamps
+        .as_ref()
+        .map_or(0, |timestamps| timestamps.width() + 1);
+    match options.line_numbers {
+        Some(_) => timestamps + LINE_NUMBER_WIDTH + 1,
+        None => timestamps,
+    }
+}
+
+/// Print a single line of the dump: offset, octets and ascii text.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `line` - line to be printed, at `line.offset`.
+/// * `options` - dump options.
+/// * `highlights` - highlighted offset ranges.
+pub fn print_line(
+    w: &mut impl Write,
+    line: &Line,
+    options: &DumpOptions,
+    highlights: &[Highlight],
+) -> io::Result<()> {
+    if let Some(timestamps) = &options.timestamps {
+        let stamp = timestamps.stamp(line.offset);
+        write!(w, "{} ", options.paint(options.theme.offset, &stamp))?;
+    }
+    if let Some(origin) = options.line_numbers {
+        let number = line.offset.saturating_sub(origin) / options.column_width.max(1) + 1;
+        let number = format!("{:>1$}", number, LINE_NUMBER_WIDTH);
+        write!(w, "{} ", options.paint(options.theme.offset, &number))?;
+    }
+    print_offset(w, line.offset, options)?;
+    print_octets(w, line, options, highlights)?;
+    writeln!(w)?;
+    print_stacked(w, line, options, highlights)
+}
+
+/// A single line of the dump as a string, like `print_line` writes it,
+/// ending with a newline.
+///
+/// # Arguments
+///
+/// * `line` - line to be rendered, at `line.offset`.
+/// * `options` - dump options.
+pub fn render_line(line: &Line, options: &DumpOptions) -> String {
+    let mut rendered: Vec<u8> = Vec::new();
+    // writing to a Vec cannot fail
+    let _ = print_line(&mut rendered, line, options, &[]);
+    String::from_utf8_lossy(&rendered).into_owned()
+}
+
+/// The lines of a page as a string, like `render_line` renders them.
+/// Empty lines, such as the one ending a page read by `buf_to_array`,
+/// are left out.
+///
+/// # Arguments
+///
+/// * `page` - page to be rendered.
+/// * `options` - dump options.
+pub fn render_page(page: &Page, options: &DumpOptions) -> String {
+    page.body
+        .iter()
+        .filter(|line| line.bytes > 0)
+        .map(|line| render_line(line, options))
+        .collect()
+}
+
+/// Largest number of columns whose dump lines fit in `width` characters,
+/// a multiple of the word or group size and at least one of them. Lines are
+/// rendered to measure them, so every option that changes the cell
+/// width, such as the format, prefix, grouping or frame, is accounted for.
+///
+/// # Arguments
+///
+/// * `options` - dump options, the column width is ignored.
+/// * `width` - terminal width in characters.
+pub fn fit_columns(options: &DumpOptions, width: u64) -> u64 {
+    let step = if options.word_size > 1 {
+        options.word_size
+    } else {
+        options.group_size.max(1)
+    };
+    let mut options = options.clone();
+    options.colorize = false;
+    let mut fitting = step;
+    // far wider than any terminal
+    for columns in (step..=1024).step_by(step as usize) {
+        options.column_width = columns;
+        let mut line = Line::new();
+        line.hex_body = vec![0; columns as usize];
+        line.bytes = columns;
+        let len = render_line(&line, &options)
+            .lines()
+            .map(|l| l.chars().count())
+            .max()
+            .unwrap_or(0);
+        if len as u64 > width {
+            break;
+        }
+        fitting = columns;
+    }
+    fitting
+}
+
+/// Size of the terminal as columns and rows, from the terminal, else
+/// `COLUMNS` and `LINES`, else 80 by 24.
+pub fn terminal_size() -> (u64, u64) {
+    #[cfg(unix)]
+    if let Some(size) = crate::sys::window_size(libc::STDOUT_FILENO) {
+        return size;
+    }
+    let var = |name: &str, default: u64| {
+        std::env::var(name)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .filter(|&value| value > 0)
+            .unwrap_or(default)
+    };
+    (var("COLUMNS", 80), var("LINES", 24))
+}
+
+/// Print the octets of a line again in each of the stacked formats, one
+/// row per format, indented to the octets of the line.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `line` - line to be printed, at `line.offset`.
+/// * `options` - dump options.
+/// * `highlights` - highlighted offset ranges.
+fn print_stacked(
+    w: &mut impl Write,
+    line: &Line,
+    options: &DumpOptions,
+    highlights: &[Highlight],
+) -> io::Result<()> {
+    if options.stacked.is_empty() || options.panes == Panes::Text {
+        return Ok(());
+    }
+    let offset = options.offset_format.format(
+        options.base_address.wrapping_add(line.offset),
+        options.offset_width,
+    );
+    let mut indent =
+        margin_width(options) + offset.len() + options.offset_separator.chars().count();
+    if options.frame {
+        indent += 2;
+    }
+    for format in &options.stacked {
+        // every format stays stacked, so cells are as wide as on the line
+        let options = DumpOptions {
+            format: *format,
+            stacked: [options.format]
+                .iter()
+                .chain(&options.stacked)
+                .copied()
+                .collect(),
+            ..options.clone()
+        };
+        let hex = hex_column(line, &options, highlights)?;
+        write!(w, "{:indent$}", "", indent = indent)?;
+        w.write_all(hex.trim_ascii_end())?;
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+/// Render the hex column of a line, with a space after each cell.
+///
+/// # Arguments
+///
+/// * `line` - line to be rendered, at `line.offset`.
+/// * `options` - dump options.
+/// * `highlights` - highlighted offset ranges.
+fn hex_column(line: &Line, options: &DumpOptions, highlights: &[Highlight]) -> io::Result<Vec<u8>> {
+    let offset = line.offset;
+    let mut hex: Vec<u8> = Vec::with_capacity(options.octets_width(line.bytes) as usize);
+    let group = options.group_size.max(1) as usize;
+    let word = options.word_size.max(1) as usize;
+    let highlight_at = |position: u64| {
+        highlights
+            .iter()
+            .rev()
+            .find(|highlight| highlight.range.contains(&position))
+            .map(|highlight| highlight.color)
+    };
+    if word > 1 {
+        for (i, bytes) in line.hex_body.chunks(word).enumerate() {
+            let position = offset + (i * word) as u64;
+            let highlight = (position..position + bytes.len() as u64).find_map(highlight_at);
+            let value = options.endian.word(bytes);
+            let digits = options
+                .format
+                .format_word(value, bytes.len(), options.prefix);
+            let padding = options.cell_padding(bytes.len() as u64);
+            hex.resize(hex.len() + padding, b' ');
+            match options.style(bytes[0], highlight) {
+                Some(style) => write!(hex, "{} ", style.paint(digits))?,
+                None => write!(hex, "{} ", digits)?,
+            }
+            if options.block_ends((i * word + bytes.len()) as u64, line.bytes) {
+                hex.push(b' ');
+            }
+        }
+        return Ok(hex);
+    }
+    if group > 1 {
+        // groups are prefixed once, not every octet
+        let octets = options.format.octets(false);
+        let digits = options.format.digits(1);
+        for (g, bytes) in line.hex_body.chunks(group).enumerate() {
+            let start = g * group;
+            // swapped groups are right aligned, with the missing high
+            // order octets left blank
+            let padding = match options.little_endian_groups {
+                true => options.cell_padding(group as u64) + (group - bytes.len()) * digits,
+                false => options.cell_padding(bytes.len() as u64),
+            };
+            hex.resize(hex.len() + padding, b' ');
+            if options.prefix {
+                write!(hex, "{}", options.format.prefix())?;
+            }
+            for j in 0..bytes.len() {
+                let j = match options.little_endian_groups {
+                    true => bytes.len() - 1 - j,
+                    false => j,
+                };
+                let b = bytes[j];
+                let style = match options.colorize {
+                    true => options.style(b, highlight_at(offset + (start + j) as u64)),
+                    false => None,
+                };
+                match style {
+                    Some(style) => write!(hex, "{}", style.paint(&octets[b as usize]))?,
+                    None => hex.extend_from_slice(octets[b as usize].as_bytes()),
+                }
+            }
+            hex.push(b' ');
+            if options.block_ends((start + bytes.len()) as u64, line.bytes) {
+                hex.push(b' ');
+            }
+        }
+        return Ok(hex);
+    }
+    let padding = options.cell_padding(1);
+    let octets = options.format.octets(options.prefix);
+    for (i, b) in line.hex_body.iter().enumerate() {
+        let style = match options.colorize {
+            true => options.style(*b, highlight_at(offset + i as u64)),
+            false => None,
+        };
+        hex.resize(hex.len() + padding, b' ');
+        match style {
+            Some(_) => print_byte(&mut hex, *b, options.format, style, options.prefix)?,
+            None => {
+                hex.extend_from_slice(octets[*b as usize].as_bytes());
+                hex.push(b' ');
+            }
+        }
+        if options.block_ends(i as u64 + 1, line.bytes) {
+            hex.push(b' ');
+        }
+    }
+    Ok(hex)
+}
+
+/// Print the octets and ascii text of a line, padded to the column width.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `line` - line to be printed, at `line.offset`.
+/// * `options` - dump options.
+/// * `highlights` - highlighted offset ranges.
+pub fn print_octets(
+    w: &mut impl Write,
+    line: &Line,
+    options: &DumpOptions,
+    highlights: &[Highlight],
+) -> io::Result<()> {
+    let border = options.paint(options.theme.separator, "|");
+    if options.frame {
+        write!(w, "{} ", border)?;
+    }
+    if options.panes != Panes::Text {
+        let mut hex = hex_column(line, options, highlights)?;
+        // the space after the last octet is replaced by the separator
+        let mut padding = options
+            .octets_width(options.column_width)
+            .saturating_sub(options.octets_width(line.bytes));
+        match hex.last() {
+            Some(b' ') => {
+                hex.pop();
+            }
+            _ => padding = padding.saturating_sub(1),
+        }
+        w.write_all(&hex)?;
+        if options.panes == Panes::Hex {
+            if options.frame {
+                print_padding(w, padding)?;
+                write!(w, " {}", border)?;
+            } else if options.end_offsets {
+                print_padding(w, padding)?;
+            }
+            return print_end_offset(w, line, options);
+        }
+        let separator = options.paint(options.theme.separator, &options.separator);
+        print_padding(w, padding)?;
+        write!(w, "{}", separator)?;
+        if options.gutter {
+            write!(w, "{}", border)?;
+        }
+    }
+    print_text(w, line, options, highlights)?;
+    if options.gutter {
+        write!(w, "{}", border)?;
+    }
+    if options.frame || options.end_offsets {
+        let cells: usize = options
+            .charset
+            .decode(&line.hex_body)
+            .iter()
+            .map(|c| options.text_cell(*c).chars().count())
+            .sum();
+        print_padding(w, options.column_width.saturating_sub(cells as u64))?;
+        if options.frame {
+            write!(w, " {}", border)?;
+        }
+    }
+    print_end_offset(w, line, options)
+}
+
+/// Print `width` spaces. Padding of wide `--cols` lines is wider than
+/// the formatter's width argument allows.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `width` - number of spaces.
+pub fn print_padding(w: &mut impl Write, width: u64) -> io::Result<()> {
+    io::copy(&mut io::repeat(b' ').take(width), w).map(|_| ())
+}
+
+/// Print the offset of the last byte of a line after it, for
+/// `--end-offsets`.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `line` - line printed, at `line.offset`.
+/// * `options` - dump options.
+fn print_end_offset(w: &mut impl Write, line: &Line, options: &DumpOptions) -> io::Result<()> {
+    if !options.end_offsets {
+        return Ok(());
+    }
+    let end = line.offset + line.bytes.saturating_sub(1);
+    let end = options
+        .offset_format
+        .format(options.base_address.wrapping_add(end), options.offset_width);
+    write!(w, " {}", options.paint(options.theme.offset, &end))
+}
+
+/// Print the text column of a line, without padding.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `line` - line to be printed, at `line.offset`.
+/// * `options` - dump options.
+/// * `highlights` - highlighted offset ranges.
+fn print_text(
+    w: &mut impl Write,
+    line: &Line,
+    options: &DumpOptions,
+    highlights: &[Highlight],
+) -> io::Result<()> {
+    let mut ascii: Vec<u8> = Vec::with_capacity(line.hex_body.len());
+    let text = options.charset.decode(&line.hex_body);
+    let highlight_at = |position: u64| {
+        highlights
+            .iter()
+            .rev()
+            .find(|highlight| highlight.range.contains(&position))
+            .map(|highlight| highlight.color)
+    };
+    for (i, b) in line.hex_body.iter().enumerate() {
+        let style = match options.colorize {
+            true => options.style(*b, highlight_at(line.offset + i as u64)),
+            false => None,
+        };
+        match (options.text_char(text[i]), style) {
+            (Some(c), None) => ascii.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            (_, style) => append_cell(&mut ascii, &options.text_cell(text[i]), style),
+        }
+    }
+    w.write_all(&ascii)
+}
+
+/// Collapses runs of identical full lines, like `hexdump -C`: the first
+/// line of a run is printed, the rest are replaced by a single `*`.
+#[derive(Debug, Default)]
+struct Squeeze {
+    previous: Option<Vec<u8>>,
+    squeezing: bool,
+}
+
+impl Squeeze {
+    /// Returns true if `line` repeats the previous line and is skipped,
+    /// writing `*` for the first skipped line of a run.
+    fn skip(
+        &mut self,
+        w: &mut impl Write,
+        line: &Line,
+        options: &DumpOptions,
+        highlights: &[Highlight],
+    ) -> io::Result<bool> {
+        let full = line.bytes == options.column_width;
+        let end = line.offset + line.bytes;
+        let highlighted = highlights
+            .iter()
+            .any(|highlight| highlight.range.start < end && highlight.range.end > line.offset);
+        if full && !highlighted && self.previous.as_deref() == Some(&line.hex_body[..]) {
+            if !self.squeezing {
+                writeln!(w, "*")?;
+                self.squeezing = true;
+            }
+            return Ok(true);
+        }
+        self.squeezing = false;
+        self.previous = Some(line.hex_body.clone());
+        Ok(false)
+    }
+}
+
+/// Print the offset after the last byte when the dump ends in a run of
+/// squeezed lines, as `hexdump -C` does, so the length of the run is
+/// known without a summary. The offset summary prints the same line.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `end` - offset after the last byte.
+/// * `options` - dump options.
+fn print_squeezed_end(w: &mut impl Write, end: u64, options: &DumpOptions) -> io::Result<()> {
+    if options.summary == Summary::Offset {
+        return Ok(());
+    }
+    let end = options
+        .offset_format
+        .format(options.base_address.wrapping_add(end), options.offset_width);
+    writeln!(w, "{}", options.paint(options.theme.offset, &end))
+}
+
+/// Print a line with the offset column painted in `tint`, if given.
+fn print_tinted_line(
+    w: &mut impl Write,
+    line: &Line,
+    options: &DumpOptions,
+    highlights: &[Highlight],
+    tint: Option<Color>,
+) -> io::Result<()> {
+    match tint {
+        Some(color) => {
+            let offset_text = options.offset_format.format(
+                options.base_address.wrapping_add(line.offset),
+                options.offset_width,
+            );
+            write!(
+                w,
+                "{}{}",
+                color.paint(offset_text),
+                options.offset_separator
+            )?;
+            print_octets(w, line, options, highlights)?;
+            writeln!(w)?;
+            print_stacked(w, line, options, highlights)
+        }
+        None => print_line(w, line, options, highlights),
+    }
+}
+
+/// Feed `line` to the annotator, if any, and print the template fields
+/// ending on it, indented to the octets.
+fn print_annotations(
+    w: &mut impl Write,
+    line: &Line,
+    options: &DumpOptions,
+    annotator: Option<&mut Annotator>,
+) -> io::Result<()> {
+    let annotator = match annotator {
+        Some(annotator) => annotator,
+        None => return Ok(()),
+    };
+    let indent = options
+        .offset_format
+        .format(
+            options.base_address.wrapping_add(line.offset),
+            options.offset_width,
+        )
+        .len()
+        + options.offset_separator.chars().count();
+    for annotation in annotator.feed(line) {
+        let name = match options.colorize {
+            true => Style::new()
+                .on(annotation.color)
+                .paint(annotation.name)
+                .to_string(),
+            false => annotation.name,
+        };
+        writeln!(
+            w,
+            "{:indent$}{}: {}",
+            "",
+            name,
+            annotation.value,
+            indent = indent
+        )?;
+    }
+    Ok(())
+}
+
+/// Stream a hex dump of `buf` to `w` one line at a time, so memory use
+/// stays constant regardless of input size. Returns the number of bytes
+/// dumped.
+///
+/// Bytes within `marks` are highlighted with the mark's color. When a
+/// `finder` is given, matches are highlighted and their offsets listed
+/// after the dump. Lines are held back only until every match that could
+/// overlap them is known. When an `annotator` is given, decoded template
+/// fields are printed below the line they end on.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `buf` - input to be dumped.
+/// * `start_offset` - offset of the first byte of `buf` in the input.
+/// * `truncate_len` - truncate to length, 0 reads to the end of input.
+/// * `options` - dump options.
+/// * `marks` - highlighted offset ranges, such as `--highlight` fields.
+/// * `finder` - optional pattern search.
+/// * `annotator` - optional template field decoding.
+#[allow(clippy::too_many_arguments)]
+pub fn dump(
+    w: &mut impl Write,
+    buf: &mut dyn Read,
+    start_offset: u64,
+    truncate_len: u64,
+    options: &DumpOptions,
+    marks: &[Highlight],
+    mut finder: Option<&mut Finder>,
+    mut annotator: Option<&mut Annotator>,
+) -> io::Result<u64> {
+    if let Some(format) = std::iter::once(&options.format)
+        .chain(&options.stacked)
+        .find(|format| !format.is_implemented())
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("format is not implemented: {:?}", format),
+        ));
+    }
+    // lines are numbered from the first one dumped
+    let options = &DumpOptions {
+        line_numbers: options.line_numbers.map(|_| start_offset),
+        ..options.clone()
+    };
+    let mut lines = PageReader::new(buf, options.column_width, start_offset, truncate_len);
+    let lookahead = finder
+        .as_ref()
+        .map_or(0, |finder| finder.pattern().len() as u64 - 1);
+    let mut entropy = match (options.colorize, options.color_mode) {
+        (true, ColorMode::Entropy) => Some(EntropyWindow::new(ENTROPY_WINDOW)),
+        _ => None,
+    };
+    let mut squeeze: Option<Squeeze> = options.squeeze.then(Squeeze::default);
+    let mut pending: VecDeque<(Line, Option<Color>)> = VecDeque::new();
+    let mut highlights: Vec<Highlight> = marks.to_vec();
+    let mut offset_counter: u64 = start_offset;
+    let mut printed: u64 = 0x0;
+    loop {
+        let next = lines.next().transpose()?;
+        let tint = match (next.as_ref(), entropy.as_mut()) {
+            (Some(line), Some(entropy)) => Some(entropy_color(entropy.feed(&line.hex_body))),
+            _ => None,
+        };
+        if let Some(line) = next.as_ref() {
+            if let Some(finder) = finder.as_mut() {
+                let len = finder.pattern().len() as u64;
+                for found in finder.feed(&line.hex_body) {
+                    highlights.push(Highlight {
+                        range: *found..*found + len,
+                        color: options.theme.highlight,
+                    });
+                }
+            }
+            offset_counter += line.bytes;
+        }
+        while let Some((line, tint)) = pending.front() {
+            if next.is_some() && line.offset + line.bytes + lookahead > offset_counter {
+                break;
+            }
+            if !squeeze.as_mut().map_or(Ok(false), |squeeze| {
+                squeeze.skip(w, line, options, &highlights)
+            })? {
+                if header_due(options, printed) {
+                    print_header(w, line.offset, options)?;
+                }
+                print_tinted_line(w, line, options, &highlights, *tint)?;
+                printed += 1;
+            }
+            print_annotations(w, line, options, annotator.as_deref_mut())?;
+            let printed = line.offset + line.bytes;
+            highlights.retain(|highlight| highlight.range.end > printed);
+            pending.pop_front();
+        }
+        match next {
+            Some(line) => pending.push_back((line, tint)),
+            None => break,
+        }
+        if lookahead == 0 {
+            if let Some((line, tint)) = pending.pop_front() {
+                if !squeeze.as_mut().map_or(Ok(false), |squeeze| {
+                    squeeze.skip(w, &line, options, &highlights)
+                })? {
+                    if header_due(options, printed) {
+                        print_header(w, line.offset, options)?;
+                    }
+                    print_tinted_line(w, &line, options, &highlights, tint)?;
+                    printed += 1;
+                }
+                print_annotations(w, &line, options, annotator.as_deref_mut())?;
+                let printed = line.offset + line.bytes;
+                highlights.retain(|highlight| highlight.range.end > printed);
+            }
+        }
+    }
+    if squeeze.is_some_and(|squeeze| squeeze.squeezing) {
+        print_squeezed_end(w, offset_counter, options)?;
+    }
+    let bytes = offset_counter - start_offset;
+    print_trailer(w, bytes, options)?;
+    if let Some(finder) = finder {
+        for found in finder.matches() {
+            writeln!(
+                w,
+                "   match: {}",
+                options.offset_format.format(*found, options.offset_width)
+            )?;
+        }
+    }
+    Ok(bytes)
+}
+
+/// Number of lines a thread renders at a time in `dump_chunked`.
+const CHUNK_LINES: u64 = 1 << 14;
+
+/// Hex dump the file at `path` like `dump`, with the lines split into
+/// chunks that are rendered on `threads` threads and written in order.
+/// Entropy colors and repeated headers depend on every line before, so
+/// those dumps are rendered by `dump` on the calling thread.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `path` - file to be dumped.
+/// * `start_offset` - offset of the first dumped byte in the file.
+/// * `truncate_len` - truncate to length, 0 reads to the end of the file.
+/// * `options` - dump options.
+/// * `marks` - highlighted offset ranges, such as `--highlight` fields.
+/// * `threads` - number of chunks rendered at the same time.
+pub fn dump_chunked(
+    w: &mut impl Write,
+    path: &Path,
+    start_offset: u64,
+    truncate_len: u64,
+    options: &DumpOptions,
+    marks: &[Highlight],
+    threads: usize,
+) -> io::Result<u64> {
+    let mut file = fs::File::open(path)?;
+    let sequential = matches!(options.header, Some(every) if every > 0)
+        || (options.colorize && options.color_mode == ColorMode::Entropy);
+    if sequential || !options.format.is_implemented() {
+        file.seek(SeekFrom::Start(start_offset))?;
+        let mut buf = BufReader::new(file);
+        return dump(
+            w,
+            &mut buf,
+            start_offset,
+            truncate_len,
+            options,
+            marks,
+            None,
+            None,
+        );
+    }
+    // lines are numbered from the first one dumped, as by `dump`
+    let options = &DumpOptions {
+        line_numbers: options.line_numbers.map(|_| start_offset),
+        ..options.clone()
+    };
+    let mut end = file.metadata()?.len().max(start_offset);
+    if truncate_len > 0 {
+        end = end.min(start_offset.saturating_add(truncate_len));
+    }
+    let chunk = CHUNK_LINES * options.column_width.max(1);
+    let starts: Vec<u64> = (start_offset..end).step_by(chunk as usize).collect();
+    if options.header.is_some() && !starts.is_empty() {
+        print_header(w, start_offset, options)?;
+    }
+    let mut squeezing = false;
+    for batch in starts.chunks(threads.max(1)) {
+        let rendered: Vec<io::Result<(Vec<u8>, bool)>> = thread::scope(|scope| {
+            let handles: Vec<_> = batch
+                .iter()
+                .map(|start| {
+                    let range = *start..end.min(start + chunk);
+                    scope.spawn(move || render_chunk(path, range, start_offset, options, marks))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|e| std::panic::resume_unwind(e))
+                })
+                .collect()
+        });
+        for chunk in rendered {
+            let chunk = chunk?;
+            w.write_all(&chunk.0)?;
+            squeezing = chunk.1;
+        }
+    }
+    if squeezing {
+        print_squeezed_end(w, end, options)?;
+    }
+    let bytes = end - start_offset;
+    print_trailer(w, bytes, options)?;
+    Ok(bytes)
+}
+
+/// Render the dump lines of `range` of the file at `path` into a buffer,
+/// and whether it ends in a run of squeezed lines. The two lines before
+/// the range are read too, so repeated lines are squeezed across chunks
+/// as if the file was dumped in one go.
+///
+/// # Arguments
+///
+/// * `path` - file to be dumped.
+/// * `range` - offsets of the lines to render, starting on a line.
+/// * `start_offset` - offset of the first dumped byte in the file.
+/// * `options` - dump options.
+/// * `marks` - highlighted offset ranges.
+fn render_chunk(
+    path: &Path,
+    range: Range<u64>,
+    start_offset: u64,
+    options: &DumpOptions,
+    marks: &[Highlight],
+) -> io::Result<(Vec<u8>, bool)> {
+    let context = range
+        .start
+        .saturating_sub(2 * options.column_width)
+        .max(start_offset);
+    let mut file = fs::File::open(path)?;
+    file.seek(SeekFrom::Start(context))?;
+    let mut buf = BufReader::new(file).take(range.end - context);
+    let mut squeeze: Option<Squeeze> = options.squeeze.then(Squeeze::default);
+    let mut out: Vec<u8> = Vec::new();
+    let mut offset = context;
+    while let Some(line) = read_line(&mut buf, options.column_width, offset)? {
+        offset += line.bytes;
+        if line.offset < range.start {
+            if let Some(squeeze) = squeeze.as_mut() {
+                squeeze.skip(&mut io::sink(), &line, options, marks)?;
+            }
+            continue;
+        }
+        let skipped = match squeeze.as_mut() {
+            Some(squeeze) => squeeze.skip(&mut out, &line, options, marks)?,
+            None => false,
+        };
+        if !skipped {
+            print_line(&mut out, &line, options, marks)?;
+        }
+    }
+    let squeezing = squeeze.is_some_and(|squeeze| squeeze.squeezing);
+    Ok((out, squeezing))
+}
+
+/// Output a continuous lowercase hex string without offsets or ascii
+/// text, wrapped after `bytes_per_line` bytes. Returns the number of
+/// bytes output.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `buf` - input to be dumped.
+/// * `truncate_len` - truncate to length, 0 reads to the end of input.
+/// * `bytes_per_line` - bytes per output line, 0 disables wrapping.
+pub fn output_plain(
+    w: &mut impl Write,
+    buf: &mut dyn Read,
+    truncate_len: u64,
+    bytes_per_line: u64,
+) -> io::Result<u64> {
+    let mut limited: Box<dyn Read + '_> = if truncate_len > 0 {
+        Box::new(buf.take(truncate_len))
+    } else {
+        Box::new(buf)
+    };
+    let chunk = if bytes_per_line > 0 {
+        bytes_per_line
+    } else {
+        0x1000
+    };
+    let mut bytes: u64 = 0x0;
+    while let Some(line) = read_line(&mut limited, chunk, bytes)? {
+        for hex in line.hex_body.iter() {
+            write!(w, "{}", Format::LowerHex.format(*hex, false))?;
+        }
+        if bytes_per_line > 0 {
+            writeln!(w)?;
+        }
+        bytes += line.bytes;
+    }
+    if bytes_per_line == 0 && bytes > 0 {
+        writeln!(w)?;
+    }
+    Ok(bytes)
+}
+
+/// Output file written through a temporary file in the same directory,
+/// which is renamed over `path` by `commit`. Readers never see a partially
+/// written file, and the temporary file is removed if `commit` is never
+/// reached.
+#[derive(Debug)]
+pub struct AtomicFile {
+    path: PathBuf,
+    tmp_path: PathBuf,
+    writer: Option<io::BufWriter<fs::File>>,
+}
+
+impl AtomicFile {
+    /// Create the temporary file for `path`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - final output path, created or truncated on `commit`.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<AtomicFile> {
+        let path = path.as_ref().to_path_buf();
+        let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(format!(".hx-{}.tmp", process::id()));
+        let tmp_path = path.with_file_name(file_name);
+        let file = fs::File::create(&tmp_path)?;
+        Ok(AtomicFile {
+            path,
+            tmp_path,
+            writer: Some(io::BufWriter::new(file)),
+        })
+    }
+
+    /// Flush the temporary file and rename it over the output path.
+    pub fn commit(mut self) -> io::Result<()> {
+        if let Some(writer) = self.writer.take() {
+            let file = writer.into_inner().map_err(|e| e.into_error())?;
+            file.sync_all()?;
+        }
+        fs::rename(&self.tmp_path, &self.path)
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.writer.as_mut() {
+            Some(writer) => writer.write(buf),
+            None => Err(io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.writer.as_mut() {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.writer.take().is_some() {
+            let _ = fs::remove_file(&self.tmp_path);
+        }
+    }
+}
+
+/// Output source code array format.
+/// # Arguments
+///
+/// * `array_format` - array format, rust (r), C (c), golang (g).
+/// * `buf` - BufRead.
+/// * `truncate_len` - truncate to length.
+/// * `column_width` - column width.
+pub fn output_array(
+    array_format: &str,
+    buf: Box<dyn BufRead>,
+    truncate_len: u64,
+    column_width: u64,
+) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut locked = stdout.lock();
+    output_array_to_writer(
+        &mut locked,
+        array_format,
+        &ArrayOptions::default(),
+        buf,
+        truncate_len,
+        column_width,
+    )
+}
+
+/// Output source code array format to `w`.
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `array_format` - `ArrayLanguage` name, e.g. rust (r), C (c), golang (g).
+/// * `options` - array identifier, declaration and element type.
+/// * `buf` - BufRead.
+/// * `truncate_len` - truncate to length.
+/// * `column_width` - column width.
+pub fn output_array_to_writer(
+    w: &mut impl Write,
+    array_format: &str,
+    options: &ArrayOptions,
+    mut buf: Box<dyn BufRead>,
+    truncate_len: u64,
+    column_width: u64,
+) -> io::Result<()> {
+    if matches!(array_format, "hexstr" | "base64") {
+        if *options != ArrayOptions::default() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "array name, declaration and type options do not apply to string literals",
+            ));
+        }
+        let bytes = output_literal_to_writer(w, array_format, &mut buf, truncate_len, column_width);
+        return bytes.map(|_| ());
+    }
+    let language = match ArrayLanguage::by_name(array_format) {
+        Some(language) => language,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown array format: {}", array_format),
+            ))
+        }
+    };
+    let typed = options.binding != ArrayBinding::Let || options.element != ArrayElement::U8;
+    if typed && !language.is_typed() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "array declaration and type options apply to rust and C arrays only",
+        ));
+    }
+    if options.rustfmt_skip && !language.is_rust() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "#[rustfmt::skip] applies to rust arrays only",
+        ));
+    }
+    if options.module.is_some() && language != ArrayLanguage::RustModule {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "module name applies to rust module (rmod) arrays only",
+        ));
+    }
+    let syntax = language.syntax();
+    let rust = language.is_rust();
+    let binding = match (options.binding, rust) {
+        // `let` is not an item, so module arrays default to a static
+        (ArrayBinding::Let, true) if language == ArrayLanguage::RustModule => "pub static",
+        (ArrayBinding::Let, true) => "let",
+        (ArrayBinding::Const, true) => "pub const",
+        (ArrayBinding::Static, true) => "pub static",
+        (ArrayBinding::Let, false) => "",
+        (ArrayBinding::Const, false) => "const ",
+        (ArrayBinding::Static, false) => "static ",
+    };
+    let element_type = match (options.element, rust) {
+        (ArrayElement::U8, true) => "u8",
+        (ArrayElement::I8, true) => "i8",
+        (ArrayElement::U32, true) => "u32",
+        (ArrayElement::U8, false) => "unsigned char",
+        (ArrayElement::I8, false) => "signed char",
+        (ArrayElement::U32, false) => "unsigned int",
+    };
+    let page = buf_to_array(&mut buf, truncate_len, column_width, 0).unwrap();
+    let name = options.name.as_deref().unwrap_or(syntax.default_name);
+    let fill = |template: &str| {
+        template
+            .replace("{binding}", binding)
+            .replace("{type}", element_type)
+            .replace("{name}", name)
+            .replace("{len}", &page.bytes.to_string())
+    };
+    let indent = match &options.module {
+        Some(module) => {
+            writeln!(w, "pub mod {} {{", module)?;
+            "    "
+        }
+        None => "",
+    };
+    if options.rustfmt_skip {
+        writeln!(w, "{}#[rustfmt::skip]", indent)?;
+    }
+    writeln!(w, "{}{}", indent, fill(syntax.header))?;
+    let mut i: u64 = 0x0;
+    for line in page.body.iter() {
+        write!(w, "{}    ", indent)?;
+        for hex in line.hex_body.iter() {
+            i += 1;
+            let element = match options.element {
+                // hex literals above 0x7f overflow signed bytes
+                ArrayElement::I8 => (*hex as i8).to_string(),
+                _ => Format::LowerHex.format(*hex, true),
+            };
+            write!(w, "{}{}", element, syntax.element_suffix)?;
+            if i < page.bytes || syntax.trailing_separator {
+                write!(w, "{}", syntax.separator)?;
+            }
+        }
+        writeln!(w)?;
+    }
+    writeln!(w, "{}{}", indent, fill(syntax.footer))?;
+    if options.module.is_some() {
+        writeln!(w, "}}")?;
+    }
+    Ok(())
+}
+
+/// Encode `bytes` as standard base64 with padding.
+///
+/// # Arguments
+///
+/// * `bytes` - bytes to be encoded.
+pub fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (i, b)| group | u32::from(*b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Output the bytes as quoted string literals, one per line of
+/// `column_width` bytes, either as a hex string (`hexstr`) or base64
+/// (`base64`). Base64 lines are rounded up to a multiple of 3 bytes so
+/// the lines can be joined. Returns the number of bytes output.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `literal` - literal format, `hexstr` or `base64`.
+/// * `buf` - input to be output.
+/// * `truncate_len` - truncate to length, 0 reads to the end of input.
+/// * `column_width` - bytes per line, 0 disables wrapping.
+pub fn output_literal_to_writer(
+    w: &mut impl Write,
+    literal: &str,
+    buf: &mut dyn Read,
+    truncate_len: u64,
+    column_width: u64,
+) -> io::Result<u64> {
+    let mut limited: Box<dyn Read + '_> = if truncate_len > 0 {
+        Box::new(buf.take(truncate_len))
+    } else {
+        Box::new(buf)
+    };
+    let base64 = match literal {
+        "hexstr" => false,
+        "base64" => true,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown literal format: {}", literal),
+            ))
+        }
+    };
+    let width = match (column_width, base64) {
+        (0, _) => u64::MAX,
+        (width, true) => width.div_ceil(3) * 3,
+        (width, false) => width,
+    };
+    let wrap = width != u64::MAX;
+    // unwrapped output is still read in chunks, and joined on one line
+    let chunk = width.min(0x1000 * 3);
+    let mut bytes: u64 = 0x0;
+    if !wrap {
+        write!(w, "\"")?;
+    }
+    while let Some(line) = read_line(&mut limited, chunk, bytes)? {
+        if wrap {
+            write!(w, "\"")?;
+        }
+        if base64 {
+            write!(w, "{}", base64_encode(&line.hex_body))?;
+        } else {
+            for hex in line.hex_body.iter() {
+                write!(w, "{}", Format::LowerHex.format(*hex, false))?;
+            }
+        }
+        if wrap {
+            writeln!(w, "\"")?;
+        }
+        bytes += line.bytes;
+    }
+    if !wrap {
+        writeln!(w, "\"")?;
+    } else if bytes == 0 {
+        writeln!(w, "\"\"")?;
+    }
+    Ok(bytes)
+}
+
+/// Derive a C identifier from a file path the way `xxd -i` does: the file
+/// name with every character that is not alphanumeric replaced by `_`.
+///
+/// # Arguments
+///
+/// * `path` - input file path.
+pub fn c_identifier(path: &str) -> String {
+    let file_name = Path::new(path)
+        .file_name()
+        .map_or(path.into(), |name| name.to_string_lossy());
+    let ident: String = file_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    match ident.chars().next() {
+        Some(c) if c.is_ascii_digit() => format!("__{}", ident),
+        _ => ident,
+    }
+}
+
+/// Function wave out.
+/// # Arguments
+///
+/// * `len` - Wave length.
+/// * `places` - Number of decimal places for function wave floats.
+pub fn output_function(len: u64, places: usize) {
+    let stdout = io::stdout();
+    let mut locked = stdout.lock();
+    output_function_to_writer(&mut locked, len, places).unwrap();
+}
+
+/// Function wave out to `w`.
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `len` - Wave length.
+/// * `places` - Number of decimal places for function wave floats.
+pub fn output_function_to_writer(w: &mut impl Write, len: u64, places: usize) -> io::Result<()> {
+    for y in 0..len {
+        let y_float: f64 = y as f64;
+        let len_float: f64 = len as f64;
+        let x: f64 = (((y_float / len_float) * f64::consts::PI) / 2.0).sin();
+        let formatted_number = format!("{:.*}", places, x);
+        write!(w, "{}", formatted_number)?;
+        write!(w, ",")?;
+        if (y % 10) == 9 {
+            writeln!(w)?;
+        }
+    }
+    writeln!(w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HexConfig;
+
+    #[test]
+    fn test_fit_columns() {
+        let options = HexConfig::new().dump_options();
+        // "0x000000: " and "0x61 " and "a" per column
+        assert_eq!(fit_columns(&options, 40), 5);
+        assert_eq!(fit_columns(&options, 39), 4);
+        assert_eq!(fit_columns(&options, 1), 1);
+        // the widest line fits the terminal
+        let columns = fit_columns(&options, 60);
+        assert_eq!(columns, 8);
+        let plain = HexConfig::new()
+            .color(false)
+            .columns(columns)
+            .dump_options();
+        let page = buf_to_array(&mut &[b'a'; 8][..], 0, columns, 0).unwrap();
+        assert!(render_line(&page.body[0], &plain).trim_end().len() <= 60);
+        let options = HexConfig::new().group_size(4).prefix(false).dump_options();
+        assert_eq!(fit_columns(&options, 80), 20);
+    }
+
+    #[test]
+    fn test_render_page() {
+        let options = HexConfig::new().columns(4).dump_options();
+        let page = buf_to_array(&mut &b"abcdef"[..], 0, 4, 0x10).unwrap();
+        assert_eq!(
+            render_line(&page.body[0], &options),
+            "0x000010: 0x61 0x62 0x63 0x64 abcd\n"
+        );
+        assert_eq!(
+            render_page(&page, &options),
+            "0x000010: 0x61 0x62 0x63 0x64 abcd\n0x000014: 0x65 0x66           ef\n"
+        );
+    }
+
+    #[test]
+    fn test_dump_streams_lines() {
+        let options = DumpOptions {
+            column_width: 4,
+            ..HexConfig::new().dump_options()
+        };
+        let mut input: &[u8] = b"abcdef";
+        let mut out: Vec<u8> = Vec::new();
+        let bytes = dump(&mut out, &mut input, 0, 0, &options, &[], None, None).unwrap();
+        assert_eq!(bytes, 6);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x000000: 0x61 0x62 0x63 0x64 abcd\n\
+             0x000004: 0x65 0x66           ef\n   bytes: 6\n"
+        );
+    }
+
+    #[test]
+    fn test_dump_find_spanning_lines() {
+        let options = DumpOptions {
+            column_width: 4,
+            colorize: true,
+            prefix: false,
+            ..HexConfig::new().dump_options()
+        };
+        let mut finder = Finder::new(crate::search::Pattern::parse("6465").unwrap(), 0);
+        let mut input: &[u8] = b"abcdef";
+        let mut out: Vec<u8> = Vec::new();
+        dump(
+            &mut out,
+            &mut input,
+            0,
+            0,
+            &options,
+            &[],
+            Some(&mut finder),
+            None,
+        )
+        .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let highlighted = options.style(b'd', Some(Theme::DEFAULT.highlight)).unwrap();
+        assert!(out.contains(&highlighted.paint("64").to_string()));
+        let highlighted = options.style(b'e', Some(Theme::DEFAULT.highlight)).unwrap();
+        assert!(out.contains(&highlighted.paint("65").to_string()));
+        assert!(out.ends_with("   match: 0x000003\n"));
+    }
+
+    #[test]
+    fn test_charset_decode() {
+        let text = |charset: Charset, bytes: &[u8]| -> String {
+            charset
+                .decode(bytes)
+                .into_iter()
+                .map(|c| AsciiStyle::Dots.render(c, '.'))
+                .collect()
+        };
+        assert_eq!(text(Charset::Ascii, b"a\x00\xc3\xa9"), "a...");
+        assert_eq!(text(Charset::Utf8, "aé€\n".as_bytes()), "aé·€··.");
+        // continued from the previous line, and cut off by the next
+        assert_eq!(text(Charset::Utf8, b"\xa9b\xe2\x82"), "·b..");
+        assert_eq!(text(Charset::Utf8, b"\xffa\xa9"), ".a.");
+        assert_eq!(text(Charset::Utf8, "\u{85}".as_bytes()), ".·");
+        assert_eq!(
+            text(Charset::Ebcdic, b"\xc8\x85\x93\x93\x96\x40\xf1\x25"),
+            "Hello 1."
+        );
+        assert_eq!(text(Charset::Cp437, b"a\x01\x80\xb0\xdb"), "a.Ç░█");
+        assert_eq!(text(Charset::Latin1, b"a\x85\xe9\xff"), "a.éÿ");
+    }
+
+    #[test]
+    fn test_ascii_style() {
+        let cells = |style: AsciiStyle| -> Vec<String> {
+            Charset::Ascii
+                .decode(b"a\x00\x03\n\x7f\x80")
+                .into_iter()
+                .map(|c| style.render(c, '.'))
+                .collect()
+        };
+        assert_eq!(cells(AsciiStyle::Dots), vec!["a", ".", ".", ".", ".", "."]);
+        assert_eq!(
+            cells(AsciiStyle::Caret),
+            vec!["a", "^@", "^C", "^J", "^?", "."]
+        );
+        assert_eq!(
+            cells(AsciiStyle::Pictures),
+            vec!["a", "\u{2400}", "\u{2403}", "\u{240a}", "\u{2421}", "."]
+        );
+        assert_eq!(AsciiStyle::Pictures.render(Some('\u{85}'), '.'), ".");
+        assert_eq!(AsciiStyle::Caret.render(None, '_'), "_");
+    }
+
+    #[test]
+    fn test_text_char() {
+        let mut options = HexConfig::new().dump_options();
+        let mut text: Vec<Option<char>> = Charset::Utf8.decode("a\0\x03\x7f é\u{85}".as_bytes());
+        text.push(None);
+        for ascii_style in [AsciiStyle::Dots, AsciiStyle::Caret, AsciiStyle::Pictures] {
+            for nul in [None, Some('0')] {
+                options.ascii_style = ascii_style;
+                options.nul = nul;
+                for c in &text {
+                    if let Some(cell) = options.text_char(*c) {
+                        assert_eq!(cell.to_string(), options.text_cell(*c));
+                    }
+                }
+            }
+        }
+        assert_eq!(options.text_char(Some('\x03')), None);
+    }
+
+    #[test]
+    fn test_dump_marks() {
+        let options = DumpOptions {
+            column_width: 2,
+            colorize: true,
+            prefix: false,
+            ..HexConfig::new().dump_options()
+        };
+        let marks = [Highlight {
+            range: 1..3,
+            color: Color::Red,
+        }];
+        let mut out: Vec<u8> = Vec::new();
+        dump(
+            &mut out,
+            &mut &b"abcd"[..],
+            0,
+            0,
+            &options,
+            &marks,
+            None,
+            None,
+        )
+        .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        for (b, hex) in [(b'b', "62"), (b'c', "63")] {
+            let marked = options.style(b, Some(Color::Red)).unwrap();
+            assert!(out.contains(&marked.paint(hex).to_string()));
+        }
+        let unmarked = options.style(b'd', None).unwrap();
+        assert!(out.contains(&unmarked.paint("64").to_string()));
+    }
+
+    #[test]
+    fn test_dump_squeeze() {
+        let mut options = DumpOptions {
+            column_width: 2,
+            prefix: false,
+            offset_width: 2,
+            ..HexConfig::new().dump_options()
+        };
+        let input: &[u8] = b"aaaaaaabbbbbbba";
+        let mut out: Vec<u8> = Vec::new();
+        dump(&mut out, &mut &input[..], 0, 0, &options, &[], None, None).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x00: 61 61 aa\n*\n0x06: 61 62 ab\n0x08: 62 62 bb\n*\n0x0e: 61    a\n   bytes: 15\n"
+        );
+
+        // a match inside a repeated line keeps it visible
+        let mut finder = Finder::new(crate::search::Pattern::parse("6262").unwrap(), 0);
+        let mut out: Vec<u8> = Vec::new();
+        dump(
+            &mut out,
+            &mut &input[..],
+            0,
+            0,
+            &options,
+            &[],
+            Some(&mut finder),
+            None,
+        )
+        .unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("0x0a: 62 62 bb\n"));
+
+        // a dump ending in a squeezed run ends with the offset after it
+        options.summary = Summary::None;
+        let mut out: Vec<u8> = Vec::new();
+        dump(
+            &mut out,
+            &mut &b"aaaaaa"[..],
+            0,
+            0,
+            &options,
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "0x00: 61 61 aa\n*\n0x06\n");
+
+        options.summary = Summary::Bytes;
+        options.squeeze = false;
+        let mut out: Vec<u8> = Vec::new();
+        dump(&mut out, &mut &input[..], 0, 0, &options, &[], None, None).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 9);
+    }
+
+    #[test]
+    fn test_dump_chunked() {
+        let options = DumpOptions {
+            column_width: 1,
+            prefix: false,
+            header: Some(0),
+            ..HexConfig::new().dump_options()
+        };
+        // runs of repeated lines cross the chunk boundaries
+        let len = 3 * CHUNK_LINES as usize + 5;
+        let input: Vec<u8> = (0..len).map(|i| (i / 7000 % 3) as u8).collect();
+        let path = std::env::temp_dir().join(format!("hx-chunked-{}.bin", process::id()));
+        fs::write(&path, &input).unwrap();
+        for (start, len) in [(0, 0), (3, 0), (100, CHUNK_LINES + 1), (len as u64, 0)] {
+            let mut expected: Vec<u8> = Vec::new();
+            let mut buf = &input[start as usize..];
+            dump(
+                &mut expected,
+                &mut buf,
+                start,
+                len,
+                &options,
+                &[],
+                None,
+                None,
+            )
+            .unwrap();
+            for threads in [1, 2, 3] {
+                let mut out: Vec<u8> = Vec::new();
+                dump_chunked(&mut out, &path, start, len, &options, &[], threads).unwrap();
+                assert_eq!(out, expected);
+            }
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_dump_entropy_tint() {
+        let options = DumpOptions {
+            column_width: 4,
+            colorize: true,
+            color_mode: ColorMode::Entropy,
+            ..HexConfig::new().dump_options()
+        };
+        // a repeated byte, then 16 distinct bytes raising the window to 3.9 bits
+        let mut input: Vec<u8> = vec![0x41; 4];
+        input.extend(0x00..0x10);
+        let mut out: Vec<u8> = Vec::new();
+        dump(&mut out, &mut &input[..], 0, 0, &options, &[], None, None).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].starts_with(&format!("{}: ", Color::Fixed(27).paint("0x000000"))));
+        assert!(lines[4].starts_with(&format!("{}: ", Color::Fixed(35).paint("0x000010"))));
+        assert_eq!(entropy_color(8.0), Color::Fixed(196));
+    }
+
+    #[test]
+    fn test_dump_group_size() {
+        let options = DumpOptions {
+            column_width: 6,
+            group_size: 4,
+            ..HexConfig::new().dump_options()
+        };
+        assert_eq!(options.octets_width(6), 18);
+        let mut out: Vec<u8> = Vec::new();
+        dump(
+            &mut out,
+            &mut &b"abcdefgh"[..],
+            0,
+            0,
+            &options,
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x000000: 0x61626364 0x6566 abcdef\n\
+             0x000006: 0x6768            gh\n   bytes: 8\n"
+        );
+    }
+
+    #[test]
+    fn test_dump_last_line_alignment() {
+        let formats = [
+            Format::Octal,
+            Format::LowerHex,
+            Format::UpperHex,
+            Format::Binary,
+            Format::Decimal,
+            Format::SignedDecimal,
+        ];
+        for format in formats {
+            for prefix in [true, false] {
+                for (group_size, word_size) in [(1, 1), (2, 1), (3, 1), (1, 2)] {
+                    let options = DumpOptions {
+                        column_width: 4,
+                        group_size,
+                        word_size,
+                        format,
+                        prefix,
+                        ..HexConfig::new().dump_options()
+                    };
+                    let mut out: Vec<u8> = Vec::new();
+                    dump(
+                        &mut out,
+                        &mut &b"\x80bcdef"[..],
+                        0,
+                        0,
+                        &options,
+                        &[],
+                        None,
+                        None,
+                    )
+                    .unwrap();
+                    let out = String::from_utf8(out).unwrap();
+                    let lines: Vec<&str> = out.lines().collect();
+                    assert_eq!(lines[0].find(".bcd"), lines[1].find("ef"), "{}", out);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dump_header() {
+        let options = DumpOptions {
+            column_width: 3,
+            format: Format::UpperHex,
+            prefix: false,
+            header: Some(2),
+            ..HexConfig::new().dump_options()
+        };
+        let mut out: Vec<u8> = Vec::new();
+        dump(
+            &mut out,
+            &mut &b"abcdefghij"[..],
+            0,
+            0,
+            &options,
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "          00 01 02\n\
+             0x000000: 61 62 63 abc\n\
+             0x000003: 64 65 66 def\n          00 01 02\n\
+             0x000006: 67 68 69 ghi\n\
+             0x000009: 6A       j\n   bytes: 10\n"
+        );
+        assert!(header_due(&options, 4));
+        assert!(!header_due(&options, 3));
+        assert!(!header_due(
+            &DumpOptions {
+                header: Some(0),
+                ..options.clone()
+            },
+            4
+        ));
+        let mut out: Vec<u8> = Vec::new();
+        print_header(
+            &mut out,
+            0,
+            &DumpOptions {
+                column_width: 5,
+                group_size: 2,
+                prefix: true,
+                offset_format: OffsetFormat::Dec,
+                frame: true,
+                ..options
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "        |     00     02   04\n"
+        );
+    }
+
+    #[test]
+    fn test_dump_frame() {
+        let mut options = DumpOptions {
+            column_width: 4,
+            prefix: false,
+            ascii_style: AsciiStyle::Caret,
+            separator: "|".to_string(),
+            ..HexConfig::new().dump_options()
+        };
+        let render = |options: &DumpOptions| {
+            let mut out: Vec<u8> = Vec::new();
+            dump(
+                &mut out,
+                &mut &b"abcd\n"[..],
+                0,
+                0,
+                options,
+                &[],
+                None,
+                None,
+            )
+            .unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(
+            render(&options),
+            "0x000000: 61 62 63 64|abcd\n0x000004: 0a         |^J\n   bytes: 5\n"
+        );
+        options.separator = " | ".to_string();
+        options.frame = true;
+        assert_eq!(
+            render(&options),
+            "0x000000: | 61 62 63 64 | abcd |\n0x000004: | 0a          | ^J   |\n   bytes: 5\n"
+        );
+    }
+
+    #[test]
+    fn test_dump_rejects_unimplemented_format() {
+        let options = DumpOptions {
+            column_width: 4,
+            format: Format::LowerExp,
+            ..HexConfig::new().dump_options()
+        };
+        let mut out: Vec<u8> = Vec::new();
+        assert!(dump(&mut out, &mut &b"abc"[..], 0, 0, &options, &[], None, None).is_err());
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_dump_word_size() {
+        let mut options = DumpOptions {
+            column_width: 6,
+            word_size: 4,
+            ..HexConfig::new().dump_options()
+        };
+        let mut out: Vec<u8> = Vec::new();
+        dump(
+            &mut out,
+            &mut &b"abcdefgh"[..],
+            0,
+            0,
+            &options,
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x000000: 0x64636261 0x6665 abcdef\n\
+             0x000006: 0x6867            gh\n   bytes: 8\n"
+        );
+        options.endian = Endian::Big;
+        options.format = Format::Octal;
+        options.prefix = false;
+        let mut out: Vec<u8> = Vec::new();
+        dump(
+            &mut out,
+            &mut &b"\x00\x01\x02\x03"[..],
+            0,
+            0,
+            &options,
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x000000: 00000201003        ....\n   bytes: 4\n"
+        );
+    }
+
+    #[test]
+    fn test_panes() {
+        let input: &[u8] = b"hex dump il\n";
+        let mut out: Vec<u8> = Vec::new();
+        let options = HexConfig::new()
+            .columns(8)
+            .panes(Panes::Hex)
+            .header(Some(0))
+            .summary(Summary::None)
+            .dump_options();
+        dump(&mut out, &mut &input[..], 0, 0, &options, &[], None, None).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "            00   01   02   03   04   05   06   07\n\
+             0x000000: 0x68 0x65 0x78 0x20 0x64 0x75 0x6d 0x70\n\
+             0x000008: 0x20 0x69 0x6c 0x0a\n"
+        );
+        let mut out: Vec<u8> = Vec::new();
+        let options = HexConfig::new()
+            .columns(8)
+            .panes(Panes::Text)
+            .header(Some(0))
+            .summary(Summary::None)
+            .dump_options();
+        dump(&mut out, &mut &input[..], 0, 0, &options, &[], None, None).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x000000: hex dump\n0x000008:  il.\n"
+        );
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let mut arrivals = Arrivals::new();
+        arrivals.record(0, UNIX_EPOCH + Duration::from_millis(1500));
+        arrivals.record(4, UNIX_EPOCH + Duration::from_millis(86401250));
+        let arrivals = Arc::new(Mutex::new(arrivals));
+        let mut options = HexConfig::new()
+            .columns(4)
+            .prefix(false)
+            .summary(Summary::None)
+            .dump_options();
+        options.timestamps = Some(Timestamps {
+            origin: Some(UNIX_EPOCH + Duration::from_secs(1)),
+            arrivals: arrivals.clone(),
+        });
+        let mut out: Vec<u8> = Vec::new();
+        dump(
+            &mut out,
+            &mut &b"abcdef"[..],
+            0,
+            0,
+            &options,
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[    0.500000] 0x000000: 61 62 63 64 abcd\n\
+             [86400.250000] 0x000004: 65 66       ef\n"
+        );
+        options.timestamps = Some(Timestamps {
+            origin: None,
+            arrivals,
+        });
+        let line = Line {
+            offset: 4,
+            hex_body: b"ef".to_vec(),
+            ascii: b"ef".to_vec(),
+            bytes: 2,
+        };
+        assert_eq!(
+            render_line(&line, &options),
+            "[1970-01-02 00:00:01.250000] 0x000004: 65 66       ef\n"
+        );
+    }
+
+    #[test]
+    fn test_line_numbers_and_end_offsets() {
+        let input: &[u8] = b"hex dump il\n";
+        let mut out: Vec<u8> = Vec::new();
+        let options = HexConfig::new()
+            .columns(8)
+            .prefix(false)
+            .line_numbers(true)
+            .end_offsets(true)
+            .header(Some(0))
+            .summary(Summary::None)
+            .dump_options();
+        dump(
+            &mut out,
+            &mut &input[..],
+            0x10,
+            0,
+            &options,
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "                 00 01 02 03 04 05 06 07\n\
+             \x20    1 0x000010: 68 65 78 20 64 75 6d 70 hex dump 0x000017\n\
+             \x20    2 0x000018: 20 69 6c 0a              il.     0x00001b\n"
+        );
+    }
+
+    #[test]
+    fn test_blocks_and_gutter() {
+        let options = HexConfig::new()
+            .columns(8)
+            .prefix(false)
+            .block_size(4)
+            .offset_separator("  ")
+            .separator("  ")
+            .gutter(true)
+            .dump_options();
+        assert_eq!(options.octets_width(8), 25);
+        assert_eq!(options.octets_width(4), 12);
+        let input: &[u8] = b"hex dump il\n";
+        let mut out: Vec<u8> = Vec::new();
+        dump(&mut out, &mut &input[..], 0, 0, &options, &[], None, None).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x000000  68 65 78 20  64 75 6d 70  |hex dump|\n\
+             0x000008  20 69 6c 0a               | il.|\n   bytes: 12\n"
+        );
+        let mut out: Vec<u8> = Vec::new();
+        let options = HexConfig::new()
+            .columns(8)
+            .word_size(2)
+            .prefix(false)
+            .block_size(4)
+            .header(Some(0))
+            .summary(Summary::Offset)
+            .dump_options();
+        let bytes = dump(&mut out, &mut &input[..], 0, 0, &options, &[], None, None).unwrap();
+        print_summary(&mut out, 0, bytes, Duration::ZERO, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "            00   02    04   06\n\
+             0x000000: 6568 2078  7564 706d hex dump\n\
+             0x000008: 6920 0a6c             il.\n\
+             0x00000c\n"
+        );
+    }
+
+    #[test]
+    fn test_little_endian_groups() {
+        let options = HexConfig::new()
+            .columns(8)
+            .group_size(4)
+            .little_endian_groups(true)
+            .prefix(false)
+            .dump_options();
+        // a partial group is as wide as a full one
+        assert_eq!(options.octets_width(1), 9);
+        assert_eq!(options.octets_width(5), 18);
+        let input: &[u8] = b"hex dump\n";
+        let mut out: Vec<u8> = Vec::new();
+        dump(&mut out, &mut &input[..], 0, 0, &options, &[], None, None).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x000000: 20786568 706d7564 hex dump\n\
+             0x000008:       0a          .\n   bytes: 9\n"
+        );
+    }
+
+    #[test]
+    fn test_stacked_formats() {
+        let options = HexConfig::new()
+            .columns(4)
+            .stacked(vec![Format::Octal, Format::Decimal])
+            .dump_options();
+        assert_eq!(options.octets_width(4), 28);
+        let input: &[u8] = b"il\n";
+        let mut out: Vec<u8> = Vec::new();
+        dump(&mut out, &mut &input[..], 0, 0, &options, &[], None, None).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x000000:   0x69   0x6c   0x0a        il.\n\
+             \x20         0o0151 0o0154 0o0012\n\
+             \x20            105    108    010\n   bytes: 3\n"
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        let input: &[u8] = b"il\n";
+        let render = |summary: Summary| {
+            let options = HexConfig::new().summary(summary).dump_options();
+            let mut out: Vec<u8> = Vec::new();
+            let bytes = dump(&mut out, &mut &input[..], 1, 0, &options, &[], None, None).unwrap();
+            print_summary(&mut out, 1, bytes, Duration::from_millis(1500), &options).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let lines = "0x000001: 0x69 0x6c 0x0a                                    il.\n";
+        assert_eq!(render(Summary::None), lines);
+        assert_eq!(render(Summary::Bytes), format!("{}   bytes: 3\n", lines));
+        assert_eq!(
+            render(Summary::Verbose),
+            format!(
+                "{}   bytes: 3\n   range: 0x000001..0x000004\n elapsed: 1.500s\n",
+                lines
+            )
+        );
+        assert_eq!(Summary::by_name("verbose"), Some(Summary::Verbose));
+        assert_eq!(Summary::by_name("all"), None);
+    }
+
+    #[test]
+    fn test_offset_format() {
+        assert_eq!(OffsetFormat::Hex.format(0x2a, 6), offset(0x2a));
+        assert_eq!(OffsetFormat::Dec.format(42, 8), "00000042");
+        assert_eq!(OffsetFormat::Oct.format(42, 7), "0000052");
+        assert_eq!(OffsetFormat::UpperHex.format(0x2a, 6), "0x00002A");
+        assert_eq!(OffsetFormat::Sector(512).format(0x3f0, 1), "1:1f0");
+        assert_eq!(OffsetFormat::Sector(4096).format(8192, 4), "0002:000");
+        for format in [
+            OffsetFormat::Hex,
+            OffsetFormat::UpperHex,
+            OffsetFormat::Dec,
+            OffsetFormat::Oct,
+            OffsetFormat::Sector(512),
+        ] {
+            assert_eq!(format.parse(&format.format(1234, 4)), Some(1234));
+        }
+    }
+
+    #[test]
+    fn test_atomic_file() {
+        let dir = std::env::temp_dir().join(format!("hx-atomic-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.bin");
+        fs::write(&path, b"old").unwrap();
+        let mut file = AtomicFile::create(&path).unwrap();
+        file.write_all(b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"old");
+        file.commit().unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        // dropped without commit leaves the output and no temporary file
+        let mut file = AtomicFile::create(&path).unwrap();
+        file.write_all(b"partial").unwrap();
+        drop(file);
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_output_array_to_writer() {
+        let mut out: Vec<u8> = Vec::new();
+        let options = ArrayOptions::default();
+        output_array_to_writer(&mut out, "c", &options, Box::new(&b"il\n"[..]), 0, 10).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "unsigned char ARRAY[3] = {\n    0x69, 0x6c, 0x0a\n};\n"
+        );
+    }
+
+    #[test]
+    fn test_output_array_languages() {
+        let expected = [
+            ("js", "const a = new Uint8Array([\n    0x69, 0x6c\n]);\n"),
+            ("cs", "byte[] a = new byte[] {\n    0x69, 0x6c\n};\n"),
+            ("zig", "const a = [_]u8{\n    0x69, 0x6c\n};\n"),
+            ("nim", "let a = @[\n    0x69'u8, 0x6c'u8\n]\n"),
+            ("rb", "a = [\n    0x69, 0x6c\n]\n"),
+            ("g", "a := [2]byte{\n    0x69, 0x6c, \n}\n"),
+            ("f", "let a = [|\n    0x69uy; 0x6cuy\n|]\n"),
+        ];
+        for (language, array) in expected {
+            let mut out: Vec<u8> = Vec::new();
+            let options = ArrayOptions::default();
+            output_array_to_writer(&mut out, language, &options, Box::new(&b"il"[..]), 0, 10)
+                .unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), array);
+        }
+        let mut sink = io::sink();
+        let options = ArrayOptions::default();
+        assert!(
+            output_array_to_writer(&mut sink, "x", &options, Box::new(&b""[..]), 0, 10).is_err()
+        );
+    }
+
+    #[test]
+    fn test_output_array_binding_and_type() {
+        let mut options = ArrayOptions {
+            name: Some("DATA".to_string()),
+            binding: ArrayBinding::Const,
+            element: ArrayElement::I8,
+            ..ArrayOptions::default()
+        };
+        let mut out: Vec<u8> = Vec::new();
+        output_array_to_writer(&mut out, "r", &options, Box::new(&b"i\xff"[..]), 0, 10).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "pub const DATA: [i8; 2] = [\n    105, -1\n];\n"
+        );
+        options.binding = ArrayBinding::Static;
+        options.element = ArrayElement::U32;
+        let mut out: Vec<u8> = Vec::new();
+        output_array_to_writer(&mut out, "c", &options, Box::new(&b"i"[..]), 0, 10).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "static unsigned int DATA[1] = {\n    0x69\n};\n"
+        );
+        let mut sink = io::sink();
+        assert!(
+            output_array_to_writer(&mut sink, "p", &options, Box::new(&b"i"[..]), 0, 10).is_err()
+        );
+    }
+
+    #[test]
+    fn test_output_array_rust_module() {
+        let mut out: Vec<u8> = Vec::new();
+        let mut options = ArrayOptions::default();
+        output_array_to_writer(&mut out, "rmod", &options, Box::new(&b"il"[..]), 0, 10).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "pub static DATA: &[u8] = &[\n    0x69, 0x6c, \n];\n"
+        );
+        options.binding = ArrayBinding::Const;
+        options.rustfmt_skip = true;
+        options.module = Some("tiny".to_string());
+        let mut out: Vec<u8> = Vec::new();
+        output_array_to_writer(&mut out, "rmod", &options, Box::new(&b"i"[..]), 0, 10).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "pub mod tiny {\n    #[rustfmt::skip]\n    pub const DATA: &[u8] = &[\n        0x69, \n    ];\n}\n"
+        );
+        let mut sink = io::sink();
+        assert!(
+            output_array_to_writer(&mut sink, "r", &options, Box::new(&b"i"[..]), 0, 10).is_err()
+        );
+        options.module = None;
+        assert!(
+            output_array_to_writer(&mut sink, "c", &options, Box::new(&b"i"[..]), 0, 10).is_err()
+        );
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar\xff"), "Zm9vYmFy/w==");
+    }
+
+    #[test]
+    fn test_output_literal() {
+        let mut out: Vec<u8> = Vec::new();
+        output_literal_to_writer(&mut out, "hexstr", &mut &b"hello"[..], 0, 2).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\"6865\"\n\"6c6c\"\n\"6f\"\n"
+        );
+        let mut out: Vec<u8> = Vec::new();
+        output_literal_to_writer(&mut out, "base64", &mut &b"foobar!"[..], 0, 2).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\"Zm9v\"\n\"YmFy\"\n\"IQ==\"\n"
+        );
+        let mut out: Vec<u8> = Vec::new();
+        output_literal_to_writer(&mut out, "base64", &mut &b"foobar!"[..], 0, 0).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\"Zm9vYmFyIQ==\"\n");
+        let mut out: Vec<u8> = Vec::new();
+        output_literal_to_writer(&mut out, "hexstr", &mut &b""[..], 0, 4).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\"\"\n");
+    }
+
+    #[test]
+    fn test_output_array_c_include() {
+        let mut out: Vec<u8> = Vec::new();
+        let name = c_identifier("tests/files/tiny.txt");
+        assert_eq!(name, "tiny_txt");
+        assert_eq!(c_identifier("1.bin"), "__1_bin");
+        let options = ArrayOptions {
+            name: Some(name),
+            ..ArrayOptions::default()
+        };
+        output_array_to_writer(&mut out, "i", &options, Box::new(&b"il\n"[..]), 0, 10).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "unsigned char tiny_txt[] = {\n    0x69, 0x6c, 0x0a\n};\nunsigned int tiny_txt_len = 3;\n"
+        );
+    }
+
+    #[test]
+    fn test_output_function_to_writer() {
+        let mut out: Vec<u8> = Vec::new();
+        output_function_to_writer(&mut out, 3, 2).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "0.00,0.50,0.87,\n");
+    }
+
+    #[test]
+    fn test_output_plain() {
+        let mut out: Vec<u8> = Vec::new();
+        let bytes = output_plain(&mut out, &mut &b"hello"[..], 0, 2).unwrap();
+        assert_eq!(bytes, 5);
+        assert_eq!(String::from_utf8(out).unwrap(), "6865\n6c6c\n6f\n");
+        let mut out: Vec<u8> = Vec::new();
+        output_plain(&mut out, &mut &b"hello"[..], 4, 0).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "68656c6c\n");
+    }
+
+    #[test]
+    fn test_byte_class() {
+        assert_eq!(ByteClass::of(0x00), ByteClass::Null);
+        assert_eq!(ByteClass::of(b'q'), ByteClass::Letter);
+        assert_eq!(ByteClass::of(b'7'), ByteClass::Digit);
+        assert_eq!(ByteClass::of(b'~'), ByteClass::Punctuation);
+        assert_eq!(ByteClass::of(b'\n'), ByteClass::Whitespace);
+        assert_eq!(ByteClass::of(0x7f), ByteClass::Control);
+        assert_eq!(ByteClass::of(0x80), ByteClass::HighBit);
+    }
+
+    #[test]
+    fn test_theme_colors() {
+        let theme = Theme::by_name("monokai").unwrap();
+        assert_eq!(theme.color(b'a', ColorMode::Semantic), theme.letter);
+        assert_eq!(theme.color(0x00, ColorMode::Value), Color::Fixed(81));
+        assert_eq!(theme.color(0x00, ColorMode::Entropy), Color::Fixed(81));
+        assert_eq!(theme.color(0xff, ColorMode::Value), Color::Fixed(197));
+        assert_eq!(
+            Theme::DEFAULT.color(b'a', ColorMode::Value),
+            Color::Fixed(b'a')
+        );
+        assert!(Theme::by_name("missing").is_none());
+    }
+}
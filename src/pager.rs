@@ -0,0 +1,190 @@
+//! paging of long terminal output through $PAGER
+use std::env;
+use std::io::{self, Write};
+use std::process::{Child, Command, Stdio};
+
+/// Pager run when `PAGER` is not set.
+const DEFAULT_PAGER: &str = "less";
+
+/// When output goes through a pager, selected by `--pager`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PagerMode {
+    /// page output to a terminal that is longer than the terminal
+    Auto,
+    /// page all output
+    Always,
+    /// never page
+    Never,
+}
+
+impl PagerMode {
+    /// Pager mode with the given name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name as given to `--pager`.
+    pub fn by_name(name: &str) -> Option<PagerMode> {
+        match name {
+            "auto" => Some(PagerMode::Auto),
+            "always" => Some(PagerMode::Always),
+            "never" => Some(PagerMode::Never),
+            _ => None,
+        }
+    }
+}
+
+/// Start `PAGER`, or `less`, reading from a pipe. Colors pass through
+/// `less` unless `LESS` says otherwise.
+fn spawn_pager() -> io::Result<Child> {
+    let pager = env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    let mut words = pager.split_whitespace();
+    let mut command = Command::new(words.next().unwrap_or(DEFAULT_PAGER));
+    command.args(words).stdin(Stdio::piped());
+    if env::var_os("LESS").is_none() {
+        command.env("LESS", "R");
+    }
+    command.spawn()
+}
+
+/// Where paged output currently goes.
+#[derive(Debug)]
+enum Sink {
+    /// holding output until it is known to fill the terminal
+    Holding(Vec<u8>, u64),
+    Direct,
+    Pager(Child),
+}
+
+/// Writer that sends output through a pager. In auto mode output is held
+/// until it has more lines than the terminal, and written directly if it
+/// ends before that. If the pager cannot be started, output is written
+/// directly too. The pager is waited for when the writer is dropped.
+#[derive(Debug)]
+pub struct Paged<W: Write> {
+    inner: W,
+    rows: u64,
+    sink: Sink,
+}
+
+impl<W: Write> Paged<W> {
+    /// Paged constructor.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - output when not paging, usually stdout.
+    /// * `mode` - when to page, `Auto` or `Always`.
+    /// * `rows` - height of the terminal in auto mode.
+    pub fn new(inner: W, mode: PagerMode, rows: u64) -> Paged<W> {
+        let mut paged = Paged {
+            inner,
+            rows,
+            sink: Sink::Holding(Vec::new(), 0),
+        };
+        match mode {
+            PagerMode::Auto => {}
+            PagerMode::Always => paged.start(),
+            PagerMode::Never => paged.sink = Sink::Direct,
+        }
+        paged
+    }
+
+    /// Switch to the pager, falling back to writing directly.
+    fn start(&mut self) {
+        self.sink = match spawn_pager() {
+            Ok(child) => Sink::Pager(child),
+            Err(_) => Sink::Direct,
+        };
+    }
+
+    /// Write held output to wherever output now goes.
+    fn release(&mut self, held: &[u8]) -> io::Result<()> {
+        match &mut self.sink {
+            Sink::Pager(child) => child.stdin.as_mut().unwrap().write_all(held),
+            _ => self.inner.write_all(held),
+        }
+    }
+}
+
+impl<W: Write> Write for Paged<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.sink {
+            Sink::Holding(held, lines) => {
+                held.extend_from_slice(buf);
+                *lines += buf.iter().filter(|&&b| b == b'\n').count() as u64;
+                if *lines >= self.rows {
+                    let held = std::mem::take(held);
+                    self.start();
+                    self.release(&held)?;
+                }
+                Ok(buf.len())
+            }
+            Sink::Direct => self.inner.write(buf),
+            Sink::Pager(child) => child.stdin.as_mut().unwrap().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.sink {
+            // held output is flushed once it is known where it goes
+            Sink::Holding(..) => Ok(()),
+            Sink::Direct => self.inner.flush(),
+            Sink::Pager(child) => child.stdin.as_mut().unwrap().flush(),
+        }
+    }
+}
+
+impl<W: Write> Drop for Paged<W> {
+    fn drop(&mut self) {
+        match &mut self.sink {
+            Sink::Holding(held, _) => {
+                let held = std::mem::take(held);
+                // errors such as a closed pipe cannot be reported here
+                let _ = self.inner.write_all(&held);
+                let _ = self.inner.flush();
+            }
+            Sink::Direct => {}
+            Sink::Pager(child) => {
+                // closing the pipe tells the pager the output is complete
+                drop(child.stdin.take());
+                let _ = child.wait();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pager_mode_by_name() {
+        assert_eq!(PagerMode::by_name("auto"), Some(PagerMode::Auto));
+        assert_eq!(PagerMode::by_name("never"), Some(PagerMode::Never));
+        assert_eq!(PagerMode::by_name("more"), None);
+    }
+
+    #[test]
+    fn test_paged_short_output_is_written_directly() {
+        let mut out: Vec<u8> = Vec::new();
+        {
+            let mut paged = Paged::new(&mut out, PagerMode::Auto, 3);
+            writeln!(paged, "one").unwrap();
+            writeln!(paged, "two").unwrap();
+            paged.flush().unwrap();
+        }
+        assert_eq!(out, b"one\ntwo\n");
+    }
+
+    #[test]
+    fn test_paged_never() {
+        let mut out: Vec<u8> = Vec::new();
+        let mut paged = Paged::new(&mut out, PagerMode::Never, 1);
+        writeln!(paged, "one").unwrap();
+        writeln!(paged, "two").unwrap();
+        drop(paged);
+        assert_eq!(out, b"one\ntwo\n");
+    }
+}
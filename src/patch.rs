@@ -0,0 +1,357 @@
+//! byte patches, fills, inserts and deletes made to a copy of the input
+use crate::args::{parse_range, parse_size, ByteRange};
+use crate::search::Pattern;
+use crate::HxError;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Size of the chunks a patched copy is streamed in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Bytes to write at an offset, parsed from a `--patch` spec such as
+/// `0x10=deadbeef`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Patch {
+    /// offset of the first byte written
+    pub offset: u64,
+    /// bytes written
+    pub bytes: Vec<u8>,
+}
+
+impl Patch {
+    /// Parse an `<offset>=<hexbytes>` spec. The offset is a size as
+    /// accepted by `parse_size`, and the bytes a hex string as accepted by
+    /// `Pattern::parse`.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - patch spec.
+    pub fn parse(spec: &str) -> Result<Patch, Box<dyn Error>> {
+        let (offset, hex) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("missing = in {}", spec))?;
+        Ok(Patch {
+            offset: parse_size(offset.trim())?,
+            bytes: Pattern::parse(hex)
+                .map_err(|e| e.to_string().replace("search pattern", "patch"))?
+                .bytes,
+        })
+    }
+
+    /// Offset one past the last byte written.
+    pub fn end(&self) -> u64 {
+        self.offset.saturating_add(self.bytes.len() as u64)
+    }
+
+    /// Write the part of the patch that falls in `chunk`, which holds the
+    /// bytes from `chunk_offset`.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - bytes to patch.
+    /// * `chunk_offset` - offset of the first byte of `chunk`.
+    fn apply(&self, chunk: &mut [u8], chunk_offset: u64) {
+        let chunk_end = chunk_offset + chunk.len() as u64;
+        let start = self.offset.max(chunk_offset);
+        let end = self.end().min(chunk_end);
+        if start >= end {
+            return;
+        }
+        let from = (start - self.offset) as usize;
+        let to = (start - chunk_offset) as usize;
+        let len = (end - start) as usize;
+        chunk[to..to + len].copy_from_slice(&self.bytes[from..from + len]);
+    }
+}
+
+/// Change made to the input by one of the edit options. Offsets and
+/// ranges are offsets in the original input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Edit {
+    /// write bytes over the input, from `--patch`
+    Overwrite(Patch),
+    /// set every byte of a range to one value, from `--fill`; a range
+    /// without an end runs to the end of the input
+    Fill(ByteRange, u8),
+    /// insert bytes before an offset, from `--insert`
+    Insert(Patch),
+    /// remove a range, from `--delete`
+    Delete(ByteRange),
+}
+
+impl Edit {
+    /// Parse a `--fill` spec, `<range>=<byte>`, such as `0x10..0x20=ff`.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - fill spec.
+    pub fn parse_fill(spec: &str) -> Result<Edit, Box<dyn Error>> {
+        let (range, hex) = spec
+            .rsplit_once('=')
+            .ok_or_else(|| format!("missing = in {}", spec))?;
+        let byte = match Pattern::parse(hex)?.bytes[..] {
+            [byte] => byte,
+            _ => return Err(format!("fill must be a single byte: {}", hex).into()),
+        };
+        Ok(Edit::Fill(parse_range(range)?, byte))
+    }
+
+    /// Length the input needs for the edit to apply within it.
+    fn end(&self) -> u64 {
+        match self {
+            Self::Overwrite(patch) => patch.end(),
+            Self::Fill(range, _) => range.end.unwrap_or(range.start),
+            Self::Insert(patch) => patch.offset,
+            Self::Delete(_) => 0,
+        }
+    }
+
+    /// Write an overwrite or fill over the part of `chunk` it covers.
+    /// Fills without an end stop at the end of the input, so they skip
+    /// chunks `past_end`.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - bytes to edit.
+    /// * `chunk_offset` - offset of the first byte of `chunk`.
+    /// * `past_end` - whether `chunk` extends the input.
+    fn overwrite(&self, chunk: &mut [u8], chunk_offset: u64, past_end: bool) {
+        let chunk_end = chunk_offset + chunk.len() as u64;
+        match self {
+            Self::Overwrite(patch) => patch.apply(chunk, chunk_offset),
+            Self::Fill(range, byte) if range.end.is_some() || !past_end => {
+                let start = range.start.clamp(chunk_offset, chunk_end);
+                let end = range.end.unwrap_or(u64::MAX).clamp(chunk_offset, chunk_end);
+                chunk[(start - chunk_offset) as usize..(end - chunk_offset) as usize].fill(*byte);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Refuse edits that end past `len` bytes, unless the input may grow.
+///
+/// # Arguments
+///
+/// * `edits` - edits to check.
+/// * `len` - length of the input.
+/// * `allow_grow` - whether the input may be extended.
+fn check_bounds(edits: &[Edit], len: u64, allow_grow: bool) -> Result<(), HxError> {
+    match edits.iter().find(|edit| edit.end() > len) {
+        Some(edit) if !allow_grow => Err(HxError::Usage(format!(
+            "{} ends past the end of the input at {:#x}, use --allow-grow to extend it",
+            match edit {
+                Edit::Overwrite(patch) => format!("--patch at offset {:#x}", patch.offset),
+                Edit::Fill(range, _) => format!("--fill of {:#x}..", range.start),
+                Edit::Insert(patch) => format!("--insert at offset {:#x}", patch.offset),
+                Edit::Delete(_) => unreachable!("deletes never extend the input"),
+            },
+            len
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Write `patches` over a file in place. Every patch is checked before
+/// the first one is written. A file extended by a patch past its end is
+/// zero filled up to the patch.
+///
+/// # Arguments
+///
+/// * `path` - file to patch.
+/// * `patches` - patches, applied in order so later ones win.
+/// * `allow_grow` - whether the file may be extended.
+pub fn patch_in_place(path: &str, patches: &[Patch], allow_grow: bool) -> Result<(), HxError> {
+    let mut file = fs::OpenOptions::new().write(true).open(path)?;
+    let edits: Vec<Edit> = patches.iter().cloned().map(Edit::Overwrite).collect();
+    check_bounds(&edits, file.metadata()?.len(), allow_grow)?;
+    for patch in patches {
+        file.seek(SeekFrom::Start(patch.offset))?;
+        file.write_all(&patch.bytes)?;
+    }
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Copy edited bytes to the output: the bytes of `chunk`, which starts
+/// at `chunk_offset`, that are not deleted, with the inserts at offsets
+/// within it written before the byte they precede.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `chunk` - bytes of the input, overwrites already applied.
+/// * `chunk_offset` - offset of the first byte of `chunk`.
+/// * `edits` - edits, for the inserts and deletes.
+fn write_chunk(
+    w: &mut impl Write,
+    chunk: &[u8],
+    chunk_offset: u64,
+    edits: &[Edit],
+) -> io::Result<()> {
+    let chunk_end = chunk_offset + chunk.len() as u64;
+    let kept = |w: &mut dyn Write, from: u64, to: u64| -> io::Result<()> {
+        let mut from = from;
+        while from < to {
+            // skip the deletes covering `from`, then write up to the next
+            let deleted = edits.iter().find_map(|edit| match edit {
+                Edit::Delete(range)
+                    if range.start <= from && range.end.is_none_or(|end| from < end) =>
+                {
+                    Some(range.end.unwrap_or(u64::MAX).min(to))
+                }
+                _ => None,
+            });
+            if let Some(end) = deleted {
+                from = end;
+                continue;
+            }
+            let next = edits
+                .iter()
+                .filter_map(|edit| match edit {
+                    Edit::Delete(range) if range.start > from => Some(range.start),
+                    _ => None,
+                })
+                .fold(to, u64::min);
+            w.write_all(&chunk[(from - chunk_offset) as usize..(next - chunk_offset) as usize])?;
+            from = next;
+        }
+        Ok(())
+    };
+    let mut inserts: Vec<&Patch> = edits
+        .iter()
+        .filter_map(|edit| match edit {
+            Edit::Insert(patch) if (chunk_offset..chunk_end).contains(&patch.offset) => Some(patch),
+            _ => None,
+        })
+        .collect();
+    // a stable sort keeps inserts at the same offset in the order given
+    inserts.sort_by_key(|patch| patch.offset);
+    let mut from = chunk_offset;
+    for insert in inserts {
+        kept(w, from, insert.offset)?;
+        w.write_all(&insert.bytes)?;
+        from = insert.offset;
+    }
+    kept(w, from, chunk_end)
+}
+
+/// Copy `r` to `w` with `edits` made to it. `len`, the length of the
+/// input if it is known, lets edits past the end be refused before
+/// anything is written; otherwise they are refused at the end of the
+/// input.
+///
+/// # Arguments
+///
+/// * `r` - input.
+/// * `w` - output sink.
+/// * `edits` - edits, applied in order so later overwrites win.
+/// * `len` - length of the input, if known.
+/// * `allow_grow` - whether the output may extend past the input.
+pub fn edit_copy(
+    r: &mut impl Read,
+    w: &mut impl Write,
+    edits: &[Edit],
+    len: Option<u64>,
+    allow_grow: bool,
+) -> Result<(), HxError> {
+    if let Some(len) = len {
+        check_bounds(edits, len, allow_grow)?;
+    }
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    let mut offset: u64 = 0;
+    loop {
+        let n = match r.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        for edit in edits {
+            edit.overwrite(&mut chunk[..n], offset, false);
+        }
+        write_chunk(w, &chunk[..n], offset, edits)?;
+        offset += n as u64;
+    }
+    check_bounds(edits, offset, allow_grow)?;
+    // edits past the end extend the output, zero filled up to them
+    let end = edits.iter().map(Edit::end).max().unwrap_or(0).max(offset);
+    let mut tail = vec![0u8; (end - offset) as usize];
+    for edit in edits {
+        edit.overwrite(&mut tail, offset, true);
+    }
+    write_chunk(w, &tail, offset, edits)?;
+    // inserts at the very end follow the last byte
+    for edit in edits {
+        if let Edit::Insert(patch) = edit {
+            if patch.offset == end {
+                w.write_all(&patch.bytes)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patch_parse() {
+        assert_eq!(
+            Patch::parse("0x10=dead beef").unwrap(),
+            Patch {
+                offset: 0x10,
+                bytes: vec![0xde, 0xad, 0xbe, 0xef]
+            }
+        );
+        assert_eq!(Patch::parse("2=0x01").unwrap().end(), 3);
+        assert!(Patch::parse("2").is_err());
+        assert!(Patch::parse("x=01").is_err());
+        assert_eq!(
+            Patch::parse("2=").unwrap_err().to_string(),
+            "patch is empty"
+        );
+    }
+
+    fn edited(input: &[u8], edits: &[Edit], allow_grow: bool) -> Result<Vec<u8>, HxError> {
+        let mut out: Vec<u8> = Vec::new();
+        edit_copy(&mut &input[..], &mut out, edits, None, allow_grow)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_edit_copy() {
+        let patch = |spec| Edit::Overwrite(Patch::parse(spec).unwrap());
+        let insert = |spec| Edit::Insert(Patch::parse(spec).unwrap());
+        let delete = |spec| Edit::Delete(parse_range(spec).unwrap());
+        let edits = [patch("1=4142"), patch("2=43")];
+        assert_eq!(edited(b"hex", &edits, false).unwrap(), b"hAC");
+        let edits = [patch("4=2121")];
+        let mut out: Vec<u8> = Vec::new();
+        assert!(edit_copy(&mut &b"hex"[..], &mut out, &edits, Some(3), false).is_err());
+        assert!(out.is_empty());
+        assert_eq!(edited(b"hex", &edits, true).unwrap(), b"hex\0!!");
+        let edits = [Edit::parse_fill("1..=2=2e").unwrap(), patch("2=21")];
+        assert_eq!(edited(b"hex dump", &edits, false).unwrap(), b"h.! dump");
+        let edits = [Edit::parse_fill("4..=2e").unwrap()];
+        assert_eq!(edited(b"hex dump", &edits, true).unwrap(), b"hex ....");
+        let edits = [Edit::parse_fill("6..8=2e").unwrap()];
+        assert!(edited(b"hex", &edits, false).is_err());
+        assert_eq!(edited(b"hex", &edits, true).unwrap(), b"hex\0\0\0..");
+        let edits = [
+            insert("3=21"),
+            insert("0=3e"),
+            insert("3=3f"),
+            delete("1..=1"),
+        ];
+        assert_eq!(edited(b"hex", &edits, false).unwrap(), b">hx!?");
+        let edits = [delete("1..3"), delete("6.."), insert("7=21")];
+        assert_eq!(edited(b"hex dump", &edits, false).unwrap(), b"h du!");
+        assert_eq!(
+            Edit::parse_fill("0..1=0102").unwrap_err().to_string(),
+            "fill must be a single byte: 0102"
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! progress of long reads on stderr
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+/// Time between redraws of the progress line.
+const REDRAW: Duration = Duration::from_millis(100);
+
+/// Reader that reports the bytes read through it on stderr, with the
+/// throughput and, when the input size is known, the time left.
+#[derive(Debug)]
+pub struct Progress<R: Read> {
+    inner: R,
+    total: Option<u64>,
+    read: u64,
+    started: Instant,
+    drawn: Option<Instant>,
+}
+
+impl<R: Read> Progress<R> {
+    /// Progress constructor.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - reader to be reported on.
+    /// * `total` - number of bytes that will be read, if known.
+    pub fn new(inner: R, total: Option<u64>) -> Progress<R> {
+        Progress {
+            inner,
+            total,
+            read: 0,
+            started: Instant::now(),
+            drawn: None,
+        }
+    }
+
+    /// Redraw the progress line in place.
+    fn draw(&mut self) {
+        let line = format_progress(self.read, self.total, self.started.elapsed());
+        // progress is best effort, a failing stderr must not end the dump
+        let _ = write!(io::stderr(), "\r{}\x1b[K", line);
+        self.drawn = Some(Instant::now());
+    }
+}
+
+impl<R: Read> Read for Progress<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        if self.drawn.is_none_or(|drawn| drawn.elapsed() >= REDRAW) {
+            self.draw();
+        }
+        Ok(n)
+    }
+}
+
+impl<R: Read> Drop for Progress<R> {
+    /// The final count is left on its own line.
+    fn drop(&mut self) {
+        if self.drawn.is_some() {
+            self.draw();
+            let _ = writeln!(io::stderr());
+        }
+    }
+}
+
+/// Format a byte count with a binary unit, e.g. `1.5 MiB`.
+///
+/// # Arguments
+///
+/// * `bytes` - number of bytes.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Format the progress line: bytes read, throughput and, when `total`
+/// is known, the percentage done and the estimated time left.
+///
+/// # Arguments
+///
+/// * `read` - bytes read so far.
+/// * `total` - number of bytes that will be read, if known.
+/// * `elapsed` - time since reading started.
+pub fn format_progress(read: u64, total: Option<u64>, elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs_f64();
+    let rate = if seconds > 0.0 {
+        read as f64 / seconds
+    } else {
+        0.0
+    };
+    let throughput = format!("{}/s", format_bytes(rate as u64));
+    match total {
+        Some(total) => {
+            let percent = (read.min(total) * 100).checked_div(total).unwrap_or(100);
+            let eta = if rate > 0.0 {
+                let left = (total.saturating_sub(read) as f64 / rate).ceil() as u64;
+                format!("{}:{:02}", left / 60, left % 60)
+            } else {
+                "-:--".to_string()
+            };
+            format!(
+                "{} / {} {:>3}% {} ETA {}",
+                format_bytes(read),
+                format_bytes(total),
+                percent,
+                throughput,
+                eta
+            )
+        }
+        None => format!("{} {}", format_bytes(read), throughput),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(1536 * 1024), "1.5 MiB");
+        assert_eq!(format_bytes(u64::MAX), "16384.0 PiB");
+    }
+
+    #[test]
+    fn test_format_progress() {
+        let second = Duration::from_secs(1);
+        assert_eq!(
+            format_progress(1 << 20, Some(4 << 20), second),
+            "1.0 MiB / 4.0 MiB  25% 1.0 MiB/s ETA 0:03"
+        );
+        assert_eq!(
+            format_progress(0, Some(4 << 20), Duration::ZERO),
+            "0 B / 4.0 MiB   0% 0 B/s ETA -:--"
+        );
+        assert_eq!(
+            format_progress(0, Some(0), second),
+            "0 B / 0 B 100% 0 B/s ETA -:--"
+        );
+        assert_eq!(format_progress(2048, None, second * 2), "2.0 KiB 1.0 KiB/s");
+    }
+}
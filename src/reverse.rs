@@ -0,0 +1,1026 @@
+//! convert a hex dump back into binary
+use crate::output::{AsciiStyle, Charset, DumpOptions, OffsetFormat, Panes};
+use crate::Format;
+use std::io::{self, BufRead, Read, Write};
+
+/// Columns around the offset and octets of the dump lines being reversed,
+/// as printed with the options the dump was made with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// line numbers before the offsets, `--line-numbers`
+    pub line_numbers: bool,
+    /// text between the offset and the octets, `--offset-separator`
+    pub offset_separator: String,
+    /// the octets are followed by a text column, unless `--no-ascii`
+    pub text: bool,
+    /// the text column is wrapped in `|`, `--gutter`
+    pub gutter: bool,
+    /// offset of the last byte at the end of each line, `--end-offsets`
+    pub end_offsets: bool,
+}
+
+impl Default for Layout {
+    fn default() -> Layout {
+        Layout {
+            line_numbers: false,
+            offset_separator: ": ".to_string(),
+            text: true,
+            gutter: false,
+            end_offsets: false,
+        }
+    }
+}
+
+impl From<&DumpOptions> for Layout {
+    fn from(options: &DumpOptions) -> Layout {
+        Layout {
+            line_numbers: options.line_numbers.is_some(),
+            offset_separator: options.offset_separator.clone(),
+            text: options.panes != Panes::Hex,
+            gutter: options.gutter,
+            end_offsets: options.end_offsets,
+        }
+    }
+}
+
+/// Remove ANSI escape sequences, so colorized dumps can be reversed.
+///
+/// # Arguments
+///
+/// * `line` - line of text.
+pub fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // skip up to and including the final byte of the sequence
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// Split the radix prefix off an octet or group token. In hex formats
+/// without a prefix `0b` starts an octet such as `0b` or `0b00`, so it is
+/// only a binary prefix when whole binary octets follow. In radixes whose
+/// digits include the letter of a prefix, it is never a prefix.
+///
+/// # Arguments
+///
+/// * `token` - octet or group text.
+/// * `format` - octet format used when there is no prefix.
+fn split_prefix(token: &str, format: Format) -> (&str, &str) {
+    let letter_is_digit = |prefix: &str| {
+        let letter = prefix.chars().nth(1).unwrap();
+        letter.is_digit(format.radix())
+    };
+    match token.get(..2) {
+        Some(prefix) if format.radix() > 16 && letter_is_digit(prefix) => ("", token),
+        Some(prefix @ ("0x" | "0X" | "0o")) => (prefix, &token[2..]),
+        Some("0b") => {
+            let digits = &token[2..];
+            let binary = !digits.is_empty()
+                && digits.len().is_multiple_of(8)
+                && digits.bytes().all(|c| c == b'0' || c == b'1');
+            if format.radix() != 16 || binary {
+                ("0b", digits)
+            } else {
+                ("", token)
+            }
+        }
+        _ => ("", token),
+    }
+}
+
+/// Parse a single octet, as printed in the dump. A `0x`, `0o` or `0b`
+/// prefix selects the radix, otherwise the radix of `format` is used.
+///
+/// # Arguments
+///
+/// * `token` - octet text.
+/// * `format` - octet format used when there is no prefix.
+pub fn parse_octet(token: &str, format: Format) -> Option<u8> {
+    if let Format::Nibble = format {
+        return parse_octet(&token.replace('_', ""), Format::Binary);
+    }
+    let (digits, radix) = match split_prefix(token, format) {
+        ("0x" | "0X", digits) => (digits, 16),
+        ("0o", digits) => (digits, 8),
+        ("0b", digits) => (digits, 2),
+        (_, digits) => (digits, format.radix()),
+    };
+    if digits.is_empty() || digits.starts_with('+') {
+        return None;
+    }
+    match format {
+        Format::SignedDecimal if radix == 10 => {
+            i8::from_str_radix(digits, radix).ok().map(|b| b as u8)
+        }
+        _ => u8::from_str_radix(digits, radix).ok(),
+    }
+}
+
+/// Parse a token of the hex column, which holds a single octet or, with
+/// `--group-size`, several fixed width octets sharing one prefix.
+///
+/// # Arguments
+///
+/// * `token` - octet or group text.
+/// * `format` - octet format used when there is no prefix.
+pub fn parse_group(token: &str, format: Format) -> Option<Vec<u8>> {
+    if let Format::Nibble = format {
+        return parse_group(&token.replace('_', ""), Format::Binary);
+    }
+    let (prefix, digits) = split_prefix(token, format);
+    // octets are zero padded to a fixed width per radix
+    let radix = match prefix {
+        "0o" => 8,
+        "0b" => 2,
+        "" => format.radix(),
+        _ => 16,
+    };
+    let width = match prefix {
+        "0o" => 4,
+        "0b" => 8,
+        "" => format.digits(1),
+        _ => 2,
+    };
+    // a group of zero octets, such as `0045`, would also parse as one
+    // octet, so only tokens no wider than an octet are single octets
+    if digits.len() <= width || digits.starts_with('-') {
+        return parse_octet(token, format).map(|b| vec![b]);
+    }
+    if !digits.len().is_multiple_of(width) || !digits.is_ascii() {
+        return None;
+    }
+    // octets are prefixed by their radix, so that `0b` in a hex group
+    // is not read as a binary prefix
+    let prefix = match radix {
+        16 => "0x",
+        8 => "0o",
+        2 => "0b",
+        _ => prefix,
+    };
+    (0..digits.len())
+        .step_by(width)
+        .map(|i| parse_octet(&format!("{}{}", prefix, &digits[i..i + width]), format))
+        .collect()
+}
+
+/// Parse a line of dump output into its offset and bytes. Returns `None`
+/// for lines that are not dump lines, such as the `bytes:` trailer.
+///
+/// The ascii column holds one character per byte, so the octets are
+/// found by splitting that many characters off the end of the line.
+/// A column matching the `--ascii-style caret` text of the octets, where
+/// control characters take two, is split off first. A `--frame` line
+/// is split at the `|` of the separator instead. Line numbers and end
+/// offsets given by `layout` are split off before either.
+///
+/// # Arguments
+///
+/// * `line` - line of dump output.
+/// * `format` - octet format used when octets have no prefix.
+/// * `offset_format` - radix of the offset column.
+/// * `layout` - columns printed besides the offset and octets.
+pub fn parse_line(
+    line: &str,
+    format: Format,
+    offset_format: OffsetFormat,
+    layout: &Layout,
+) -> Option<(u64, Vec<u8>)> {
+    let line = strip_ansi(line);
+    let mut line = line.trim_end_matches(['\r', '\n']);
+    if layout.line_numbers {
+        let (number, rest) = line.trim_start().split_once(' ')?;
+        if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        line = rest;
+    }
+    let (offset, mut rest) = line.split_once(layout.offset_separator.as_str())?;
+    let offset = offset_format.parse(offset.trim())?;
+    if layout.end_offsets {
+        let (columns, end) = rest.trim_end().rsplit_once(' ')?;
+        offset_format.parse(end)?;
+        rest = columns.trim_end();
+    }
+    if !layout.text {
+        // every token is an octet or group, except the `|` of a frame
+        let groups: Option<Vec<Vec<u8>>> = rest
+            .split_whitespace()
+            .filter(|token| *token != "|")
+            .map(|token| parse_group(token, format))
+            .collect();
+        return groups.map(|groups| (offset, groups.concat()));
+    }
+    let framed = rest
+        .strip_prefix("| ")
+        .and_then(|rest| rest.strip_suffix(" |"));
+    if let Some((hex_area, _)) = framed.and_then(|inner| inner.split_once('|')) {
+        // octets never contain `|`, so the first one ends the hex column
+        let groups: Option<Vec<Vec<u8>>> = hex_area
+            .split_whitespace()
+            .map(|token| parse_group(token, format))
+            .collect();
+        if let Some(groups) = groups {
+            return Some((offset, groups.concat()));
+        }
+    }
+    if layout.gutter {
+        rest = rest.trim_end().strip_suffix('|')?;
+    }
+    let chars: Vec<char> = rest.chars().collect();
+    let octets = |n: usize| -> Option<Vec<u8>> {
+        let hex_area: String = chars[..chars.len() - n].iter().collect();
+        // a `--separator` of `|` may be left at the end of the hex column
+        let groups: Option<Vec<Vec<u8>>> = hex_area
+            .split_whitespace()
+            .map(|token| token.trim_end_matches('|'))
+            .filter(|token| !token.is_empty())
+            .map(|token| parse_group(token, format))
+            .collect();
+        groups.map(|groups| groups.concat())
+    };
+    let caret_text = |bytes: &[u8]| -> String {
+        Charset::Ascii
+            .decode(bytes)
+            .into_iter()
+            .map(|c| AsciiStyle::Caret.render(c, '.'))
+            .collect()
+    };
+    let text = |n: usize| -> String { chars[chars.len() - n..].iter().collect() };
+    let bytes = (1..=chars.len())
+        .rev()
+        .find_map(|n| octets(n).filter(|bytes| caret_text(bytes) == text(n)))
+        .or_else(|| {
+            (1..=chars.len())
+                .rev()
+                .find_map(|n| octets(n).filter(|bytes| bytes.len() == n))
+        })?;
+    Some((offset, bytes))
+}
+
+/// Read dump output from `buf` and write the reconstructed binary to `w`.
+/// Gaps between lines are filled with zeros, except after a `*` line,
+/// where the line before it repeats up to the next line, the offset
+/// after the last byte, or the `bytes:` total at the end of the dump.
+/// Returns the number of bytes written. Lines that are not dump lines
+/// are passed over, but input without any is an `InvalidData` error.
+///
+/// # Arguments
+///
+/// * `buf` - dump output.
+/// * `w` - binary output sink.
+/// * `format` - octet format used when octets have no prefix.
+/// * `offset_format` - radix of the offset column.
+/// * `base_address` - address the dump's offset column started at.
+/// * `layout` - columns printed besides the offset and octets.
+pub fn reverse(
+    buf: &mut dyn BufRead,
+    w: &mut impl Write,
+    format: Format,
+    offset_format: OffsetFormat,
+    base_address: u64,
+    layout: &Layout,
+) -> io::Result<u64> {
+    let mut written: u64 = 0x0;
+    let mut text = String::new();
+    let mut line_number: u64 = 0x0;
+    let mut previous: Vec<u8> = Vec::new();
+    let mut squeezed = false;
+    let mut dump_start: Option<u64> = None;
+    let mut parsed = false;
+    let mut unparsed: Option<u64> = None;
+    loop {
+        text.clear();
+        if buf.read_line(&mut text)? == 0 {
+            break;
+        }
+        line_number += 1;
+        let stripped = strip_ansi(&text);
+        if stripped.trim() == "*" {
+            squeezed = true;
+            continue;
+        }
+        if let Some(total) = stripped.trim().strip_prefix("bytes:") {
+            if let (true, Some(start), Ok(total)) =
+                (squeezed, dump_start, total.trim().parse::<u64>())
+            {
+                let end = start.saturating_add(total);
+                written += repeat_line(w, &previous, end.saturating_sub(written))?;
+            }
+            squeezed = false;
+            dump_start = None;
+            continue;
+        }
+        // the offset after the last byte, ending a squeezed run
+        if let Some(end) = offset_format.parse(stripped.trim()) {
+            if squeezed {
+                let end = end.saturating_sub(base_address);
+                written += repeat_line(w, &previous, end.saturating_sub(written))?;
+                squeezed = false;
+            }
+            continue;
+        }
+        let (offset, bytes) = match parse_line(&text, format, offset_format, layout) {
+            Some(parsed) => parsed,
+            None => {
+                // headers, annotations and summary lines are passed over
+                if !stripped.trim().is_empty() && !is_summary(&stripped) {
+                    unparsed.get_or_insert(line_number);
+                }
+                continue;
+            }
+        };
+        parsed = true;
+        let offset = match offset.checked_sub(base_address) {
+            Some(offset) => offset,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "line {}: offset {:#x} is below base address {:#x}",
+                        line_number, offset, base_address
+                    ),
+                ))
+            }
+        };
+        dump_start.get_or_insert(offset);
+        if squeezed && offset > written {
+            written += repeat_line(w, &previous, offset - written)?;
+        }
+        squeezed = false;
+        if offset < written {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "line {}: offset {:#x} overlaps previous data ending at {:#x}",
+                    line_number, offset, written
+                ),
+            ));
+        }
+        io::copy(&mut io::repeat(0x0).take(offset - written), w)?;
+        w.write_all(&bytes)?;
+        written = offset.checked_add(bytes.len() as u64).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: offset {:#x} is out of range", line_number, offset),
+            )
+        })?;
+        previous = bytes;
+    }
+    if let (false, Some(line_number)) = (parsed, unparsed) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("line {}: not a line of hx dump output", line_number),
+        ));
+    }
+    w.flush()?;
+    Ok(written)
+}
+
+/// Returns true if `line` is an indented `label: value` line printed
+/// after a dump, such as `   bytes: 3` or `  sha256: ...`.
+fn is_summary(line: &str) -> bool {
+    line.starts_with(' ')
+        && line.trim().split_once(": ").is_some_and(|(label, _)| {
+            !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+/// Write `len` bytes repeating `line`, for the lines collapsed into `*`.
+fn repeat_line(w: &mut impl Write, line: &[u8], len: u64) -> io::Result<u64> {
+    if line.is_empty() {
+        return io::copy(&mut io::repeat(0x0).take(len), w);
+    }
+    // written in blocks of whole lines, so a long run takes no memory
+    let block: Vec<u8> = line.repeat(8192_usize.div_ceil(line.len()));
+    let mut left = len;
+    while left > 0 {
+        let n = left.min(block.len() as u64);
+        w.write_all(&block[..n as usize])?;
+        left -= n;
+    }
+    Ok(len)
+}
+
+/// Read a plain hex string, as printed by `--plain`, from `buf` and write
+/// the decoded binary to `w`. Whitespace is ignored. Returns the number
+/// of bytes written.
+///
+/// # Arguments
+///
+/// * `buf` - plain hex string.
+/// * `w` - binary output sink.
+pub fn reverse_plain(buf: &mut dyn BufRead, w: &mut impl Write) -> io::Result<u64> {
+    let mut written: u64 = 0x0;
+    let mut high: Option<u8> = None;
+    let mut text = String::new();
+    let mut decoded: Vec<u8> = Vec::new();
+    loop {
+        text.clear();
+        if buf.read_line(&mut text)? == 0 {
+            break;
+        }
+        decoded.clear();
+        for c in text.chars().filter(|c| !c.is_whitespace()) {
+            let nibble = c.to_digit(16).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid hex digit in plain input: {:?}", c),
+                )
+            })? as u8;
+            match high.take() {
+                Some(high) => decoded.push(high << 4 | nibble),
+                None => high = Some(nibble),
+            }
+        }
+        w.write_all(&decoded)?;
+        written += decoded.len() as u64;
+    }
+    if high.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "plain input has an odd number of hex digits",
+        ));
+    }
+    w.flush()?;
+    Ok(written)
+}
+
+/// Source text without its `//`, `/* */` and `#` comments, which are
+/// replaced by a space, or a newline for line comments. Comments are not
+/// looked for inside string and character literals.
+///
+/// # Arguments
+///
+/// * `source` - array source code.
+fn strip_comments(source: &str) -> String {
+    let mut stripped = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('/', Some('/')) | ('#', _) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                stripped.push('\n');
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+                stripped.push(' ');
+            }
+            ('"', _) => {
+                stripped.push(c);
+                while let Some(c) = chars.next() {
+                    stripped.push(c);
+                    match c {
+                        '\\' => stripped.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            _ => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+/// The elements of the first array literal in `source`: the text of the
+/// last bracketed group of the statement after its first `=`, which
+/// skips the size in types such as `[3]byte{...}`. The statement ends at
+/// a `;`, or at the end of a line once a group has closed.
+///
+/// # Arguments
+///
+/// * `source` - array source code without comments.
+fn array_body(source: &str) -> Option<&str> {
+    let assigned = &source[source.find('=')? + 1..];
+    let mut depth: usize = 0;
+    let mut start: usize = 0;
+    let mut body: Option<&str> = None;
+    for (i, c) in assigned.char_indices() {
+        match c {
+            '[' | '{' | '(' => {
+                if depth == 0 {
+                    start = i + 1;
+                }
+                depth += 1;
+            }
+            ']' | '}' | ')' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    body = Some(&assigned[start..i]);
+                }
+            }
+            ';' if depth == 0 => break,
+            '\n' if depth == 0 && body.is_some() => break,
+            _ => {}
+        }
+    }
+    body
+}
+
+/// Parse an integer literal of an array element, as printed by `--array`
+/// in any of its languages: `0x`, `0o` and `0b` prefixes, decimals that
+/// may be negative for signed bytes, `_` separators and type suffixes
+/// such as `u8`, `'u8` or `uy`. Returns `None` for tokens that are not
+/// numbers, such as the names in casts.
+///
+/// # Arguments
+///
+/// * `token` - element text.
+fn parse_element(token: &str) -> Option<io::Result<u8>> {
+    let (negative, unsigned) = match token.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, token.strip_prefix('+').unwrap_or(token)),
+    };
+    if !unsigned.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid array element: {}", token),
+        )
+    };
+    let (digits, radix) = match unsigned.get(..2) {
+        Some("0x" | "0X") => (&unsigned[2..], 16),
+        Some("0o" | "0O") => (&unsigned[2..], 8),
+        Some("0b" | "0B") if unsigned[2..].starts_with(['0', '1']) => (&unsigned[2..], 2),
+        _ => (unsigned, 10),
+    };
+    let end = digits
+        .find(|c: char| !(c.is_digit(radix) || c == '_'))
+        .unwrap_or(digits.len());
+    let suffix = digits[end..].trim_start_matches('\'');
+    let known_suffix = [
+        "", "u8", "i8", "uy", "y", "u", "U", "l", "L", "ul", "UL", "u32", ".toByte",
+    ];
+    if !known_suffix.contains(&suffix) {
+        return Some(Err(invalid()));
+    }
+    let value = match i64::from_str_radix(&digits[..end].replace('_', ""), radix) {
+        Ok(value) if negative => -value,
+        Ok(value) => value,
+        Err(_) => return Some(Err(invalid())),
+    };
+    match u8::try_from(value) {
+        Ok(byte) => Some(Ok(byte)),
+        // signed bytes, as printed with --array-type i8
+        Err(_) if (-128..0).contains(&value) => Some(Ok(value as u8)),
+        Err(_) => Some(Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("array element is not a byte: {}", token),
+        ))),
+    }
+}
+
+/// Read an array literal, as printed by `--array` in any of its
+/// languages, from `buf` and write its bytes to `w`. Comments and
+/// whitespace are ignored. Returns the number of bytes written.
+///
+/// # Arguments
+///
+/// * `buf` - array source code.
+/// * `w` - binary output sink.
+pub fn reverse_array(buf: &mut dyn BufRead, w: &mut impl Write) -> io::Result<u64> {
+    let mut source = String::new();
+    buf.read_to_string(&mut source)?;
+    let source = strip_comments(&source);
+    let body = array_body(&source).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "no array literal found, expected one like those printed by --array",
+        )
+    })?;
+    let separators = |c: char| c.is_whitespace() || ",;|[](){}@".contains(c);
+    let mut bytes: Vec<u8> = Vec::new();
+    for token in body.split(separators).filter(|token| !token.is_empty()) {
+        if let Some(byte) = parse_element(token) {
+            bytes.push(byte?);
+        }
+    }
+    w.write_all(&bytes)?;
+    w.flush()?;
+    Ok(bytes.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_octet() {
+        assert_eq!(parse_octet("0x6c", Format::LowerHex), Some(0x6c));
+        assert_eq!(parse_octet("0o0154", Format::LowerHex), Some(0x6c));
+        assert_eq!(parse_octet("0b01101100", Format::LowerHex), Some(0x6c));
+        assert_eq!(parse_octet("6C", Format::UpperHex), Some(0x6c));
+        assert_eq!(parse_octet("0154", Format::Octal), Some(0x6c));
+        assert_eq!(parse_octet("il", Format::LowerHex), None);
+        assert_eq!(parse_octet("0x", Format::LowerHex), None);
+        assert_eq!(parse_octet("108", Format::Decimal), Some(0x6c));
+        assert_eq!(parse_octet("-001", Format::SignedDecimal), Some(0xff));
+        assert_eq!(parse_octet("0108", Format::SignedDecimal), Some(0x6c));
+    }
+
+    #[test]
+    fn test_parse_group() {
+        assert_eq!(
+            parse_group("0x6c6d", Format::LowerHex),
+            Some(vec![0x6c, 0x6d])
+        );
+        assert_eq!(parse_group("6c", Format::LowerHex), Some(vec![0x6c]));
+        // xxd groups of octets that would also parse as a single octet
+        assert_eq!(
+            parse_group("0045", Format::LowerHex),
+            Some(vec![0x00, 0x45])
+        );
+        assert_eq!(
+            parse_group("0b00", Format::LowerHex),
+            Some(vec![0x0b, 0x00])
+        );
+        assert_eq!(parse_group("0b", Format::LowerHex), Some(vec![0x0b]));
+        assert_eq!(
+            parse_group("0b0110110001101101", Format::LowerHex),
+            Some(vec![0x6c, 0x6d])
+        );
+        assert_eq!(parse_group("6c6", Format::LowerHex), None);
+        assert_eq!(
+            parse_group("0b0110_11000110_1101", Format::Nibble),
+            Some(vec![0x6c, 0x6d])
+        );
+        assert_eq!(
+            parse_group("12301231", Format::Radix(4)),
+            Some(vec![0x6c, 0x6d])
+        );
+        assert_eq!(
+            parse_group("0o0b", Format::Radix(32)),
+            Some(vec![0x18, 0x0b])
+        );
+        assert_eq!(parse_group("377", Format::Radix(8)), Some(vec![0xff]));
+    }
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line(
+                "0x00003c: 0x32 0x33 0x34 0x35 0x36 0x37 0x38 0x39           23456789\n",
+                Format::LowerHex,
+                OffsetFormat::Hex,
+                &Layout::default()
+            ),
+            Some((0x3c, b"23456789".to_vec()))
+        );
+        // ascii text that also parses as octets
+        assert_eq!(
+            parse_line(
+                "0x000000: 61 62 ab",
+                Format::LowerHex,
+                OffsetFormat::Hex,
+                &Layout::default()
+            ),
+            Some((0, b"ab".to_vec()))
+        );
+        // caret notation takes two characters
+        assert_eq!(
+            parse_line(
+                "0x000000: 0x39 0x0a 0x30 9^J0",
+                Format::LowerHex,
+                OffsetFormat::Hex,
+                &Layout::default()
+            ),
+            Some((0, b"9\n0".to_vec()))
+        );
+        assert_eq!(
+            parse_line(
+                "0x000000: 0x38 0x03 0x00 8^C^@",
+                Format::LowerHex,
+                OffsetFormat::Hex,
+                &Layout::default()
+            ),
+            Some((0, b"8\x03\0".to_vec()))
+        );
+        // ascii text starting with a space
+        assert_eq!(
+            parse_line(
+                "0x000000: 0x20 0x41  A",
+                Format::LowerHex,
+                OffsetFormat::Hex,
+                &Layout::default()
+            ),
+            Some((0, b" A".to_vec()))
+        );
+        assert_eq!(
+            parse_line(
+                "0x000000: \u{1b}[38;5;105m0x69\u{1b}[0m \u{1b}[38;5;105mi\u{1b}[0m",
+                Format::LowerHex,
+                OffsetFormat::Hex,
+                &Layout::default()
+            ),
+            Some((0, b"i".to_vec()))
+        );
+        assert_eq!(
+            parse_line(
+                "0x000000: 0x61626364 0x6566 abcdef",
+                Format::LowerHex,
+                OffsetFormat::Hex,
+                &Layout::default()
+            ),
+            Some((0, b"abcdef".to_vec()))
+        );
+        assert_eq!(
+            parse_line(
+                "0x000000: 01540155 lm",
+                Format::Octal,
+                OffsetFormat::Hex,
+                &Layout::default()
+            ),
+            Some((0, b"lm".to_vec()))
+        );
+        assert_eq!(
+            parse_line(
+                "0000012: 41 A",
+                Format::LowerHex,
+                OffsetFormat::Dec,
+                &Layout::default()
+            ),
+            Some((12, b"A".to_vec()))
+        );
+        // --separator and --frame
+        assert_eq!(
+            parse_line(
+                "0x000000: 0x61 0x7c      |a|",
+                Format::LowerHex,
+                OffsetFormat::Hex,
+                &Layout::default()
+            ),
+            Some((0, b"a|".to_vec()))
+        );
+        assert_eq!(
+            parse_line(
+                "0x000000: | 0x61 0x7c      | a|   |",
+                Format::LowerHex,
+                OffsetFormat::Hex,
+                &Layout::default()
+            ),
+            Some((0, b"a|".to_vec()))
+        );
+        assert_eq!(
+            parse_line(
+                "   bytes: 3",
+                Format::LowerHex,
+                OffsetFormat::Hex,
+                &Layout::default()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_line_layout() {
+        let layout = Layout {
+            line_numbers: true,
+            end_offsets: true,
+            ..Layout::default()
+        };
+        assert_eq!(
+            parse_line(
+                "     2 0x000002: 0x33 0x34 34 0x000003",
+                Format::LowerHex,
+                OffsetFormat::Hex,
+                &layout
+            ),
+            Some((2, b"34".to_vec()))
+        );
+        // end offsets would otherwise parse as a group of octets
+        let layout = Layout {
+            text: false,
+            end_offsets: true,
+            ..Layout::default()
+        };
+        assert_eq!(
+            parse_line(
+                "0x000000: | 0x69 0x6c |  0x000001",
+                Format::LowerHex,
+                OffsetFormat::Hex,
+                &layout
+            ),
+            Some((0, b"il".to_vec()))
+        );
+        let layout = Layout {
+            offset_separator: "  ".to_string(),
+            gutter: true,
+            ..Layout::default()
+        };
+        assert_eq!(
+            parse_line(
+                "00000000  69 6c 0a              |il.|",
+                Format::LowerHex,
+                OffsetFormat::BareHex,
+                &layout
+            ),
+            Some((0, b"il\n".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_reverse_round_trip() {
+        let dump = "0x000000: 0x69 0x6c 0x0a      il.\n   bytes: 3\n\
+                    0x000005: 0x21                 !\n";
+        let mut out: Vec<u8> = Vec::new();
+        let written = reverse(
+            &mut dump.as_bytes(),
+            &mut out,
+            Format::LowerHex,
+            OffsetFormat::Hex,
+            0,
+            &Layout::default(),
+        )
+        .unwrap();
+        assert_eq!(written, 6);
+        assert_eq!(out, b"il\n\0\0!");
+        let mut overlapping = "0x000002: 0x41 A\n0x000000: 0x41 A\n".as_bytes();
+        let mut sink = io::sink();
+        assert!(reverse(
+            &mut overlapping,
+            &mut sink,
+            Format::LowerHex,
+            OffsetFormat::Hex,
+            0,
+            &Layout::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_reverse_not_a_dump() {
+        let mut sink = io::sink();
+        let reversed = |dump: &str, sink: &mut io::Sink| {
+            reverse(
+                &mut dump.as_bytes(),
+                sink,
+                Format::LowerHex,
+                OffsetFormat::Hex,
+                0,
+                &Layout::default(),
+            )
+        };
+        let e = reversed("hello\nworld\n", &mut sink).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(e.to_string(), "line 1: not a line of hx dump output");
+        let e = reversed("\n00000000  69 6c 0a  |il.|\n", &mut sink).unwrap_err();
+        assert_eq!(e.to_string(), "line 2: not a line of hx dump output");
+        // an empty dump has only its summary
+        assert_eq!(reversed("   bytes: 0\n", &mut sink).unwrap(), 0);
+        assert_eq!(reversed("", &mut sink).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_reverse_base_address() {
+        let dump = "0x8000000: 0x69 0x6c il\n0x8000003: 0x21 !\n";
+        let mut out: Vec<u8> = Vec::new();
+        let written = reverse(
+            &mut dump.as_bytes(),
+            &mut out,
+            Format::LowerHex,
+            OffsetFormat::Hex,
+            0x0800_0000,
+            &Layout::default(),
+        )
+        .unwrap();
+        assert_eq!(written, 4);
+        assert_eq!(out, b"il\0!");
+        let mut sink = io::sink();
+        assert!(reverse(
+            &mut dump.as_bytes(),
+            &mut sink,
+            Format::LowerHex,
+            OffsetFormat::Hex,
+            0x0800_0001,
+            &Layout::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_reverse_squeezed() {
+        let dump = "0x000000: 0x61 0x61 aa\n*\n0x000006: 0x61 0x62 ab\n\
+                    0x000008: 0x62 0x62 bb\n*\n   bytes: 14\n";
+        let mut out: Vec<u8> = Vec::new();
+        let written = reverse(
+            &mut dump.as_bytes(),
+            &mut out,
+            Format::LowerHex,
+            OffsetFormat::Hex,
+            0,
+            &Layout::default(),
+        )
+        .unwrap();
+        assert_eq!(written, 14);
+        assert_eq!(out, b"aaaaaaabbbbbbb");
+    }
+
+    #[test]
+    fn test_reverse_squeezed_end_offset() {
+        let dump = "0x000000: 0x61 0x62 ab\n*\n0x000008\n";
+        let mut out: Vec<u8> = Vec::new();
+        let written = reverse(
+            &mut dump.as_bytes(),
+            &mut out,
+            Format::LowerHex,
+            OffsetFormat::Hex,
+            0,
+            &Layout::default(),
+        )
+        .unwrap();
+        assert_eq!(written, 8);
+        assert_eq!(out, b"abababab");
+    }
+
+    #[test]
+    fn test_reverse_squeezed_long_run() {
+        // the run is streamed, not collected in memory
+        let dump = "0x000000: 0x61 0x62 ab\n*\n   bytes: 1073741824\n";
+        let written = reverse(
+            &mut dump.as_bytes(),
+            &mut io::sink(),
+            Format::LowerHex,
+            OffsetFormat::Hex,
+            0,
+            &Layout::default(),
+        )
+        .unwrap();
+        assert_eq!(written, 1 << 30);
+        let mut out: Vec<u8> = Vec::new();
+        let dump = "0x000000: 0x61 0x62 0x63 abc\n*\n   bytes: 8\n";
+        reverse(
+            &mut dump.as_bytes(),
+            &mut out,
+            Format::LowerHex,
+            OffsetFormat::Hex,
+            0,
+            &Layout::default(),
+        )
+        .unwrap();
+        assert_eq!(out, b"abcabcab");
+    }
+
+    #[test]
+    fn test_reverse_plain() {
+        let mut out: Vec<u8> = Vec::new();
+        let written = reverse_plain(&mut "6865\n6c6C\n6f\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(written, 5);
+        assert_eq!(out, b"hello");
+        let mut sink = io::sink();
+        assert!(reverse_plain(&mut "686".as_bytes(), &mut sink).is_err());
+        assert!(reverse_plain(&mut "0x68".as_bytes(), &mut sink).is_err());
+    }
+
+    #[test]
+    fn test_reverse_array() {
+        let reversed = |source: &str| -> io::Result<Vec<u8>> {
+            let mut out: Vec<u8> = Vec::new();
+            reverse_array(&mut source.as_bytes(), &mut out)?;
+            Ok(out)
+        };
+        let rust = "// generated by hx\nlet ARRAY: [u8; 3] = [\n    0x69, 0x6c, /* l */ 0x0a\n];\n";
+        assert_eq!(reversed(rust).unwrap(), b"il\n");
+        let go = "a := [3]byte{\n    0x69, 0x6c, 0x0a, \n}\n";
+        assert_eq!(reversed(go).unwrap(), b"il\n");
+        let c = "unsigned char f[] = {\n  0x69, 0x6c\n};\nunsigned int f_len = 2;\n";
+        assert_eq!(reversed(c).unwrap(), b"il");
+        let fsharp = "let a = [|\n    0x69uy; 0xffuy\n|]\n";
+        assert_eq!(reversed(fsharp).unwrap(), [0x69, 0xff]);
+        let signed = "let ARRAY: [i8; 2] = [\n    1, -1\n];\n";
+        assert_eq!(reversed(signed).unwrap(), [0x01, 0xff]);
+        let cast = "byte[] a = { (byte)0xff, 0b1010, 0o17 }; # done";
+        assert_eq!(reversed(cast).unwrap(), [0xff, 0x0a, 0x0f]);
+        assert_eq!(
+            reversed("a = [256]").unwrap_err().to_string(),
+            "array element is not a byte: 256"
+        );
+        assert_eq!(
+            reversed("a = [0x6g]").unwrap_err().to_string(),
+            "invalid array element: 0x6g"
+        );
+        assert!(reversed("0x69, 0x6c").is_err());
+    }
+}
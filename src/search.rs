@@ -0,0 +1,625 @@
+//! byte pattern search
+use crate::buffer::{read_line, Line};
+use crate::output::{print_line, DumpOptions, Highlight};
+use crate::strings::{print_string, Found};
+use regex::bytes::Regex;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::{self, Read, Write};
+use std::ops::Range;
+
+/// Bytes of input a regex searches past a match before it is printed, so
+/// matches spanning reads are found. Longer matches are cut short.
+const REGEX_WINDOW: usize = 1 << 16;
+
+/// Byte sequence to search for, parsed from a hex string such as
+/// `deadbeef` or `de ad be ef`, optionally with wildcards.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    /// bytes to match, with the bits outside `mask` cleared
+    pub bytes: Vec<u8>,
+    /// bits of each byte that must match, 0xff unless the pattern has
+    /// wildcards
+    pub mask: Vec<u8>,
+}
+
+impl Pattern {
+    /// Pattern matching exactly `bytes`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - bytes to match.
+    pub fn exact(bytes: Vec<u8>) -> Pattern {
+        let mask = vec![0xff; bytes.len()];
+        Pattern { bytes, mask }
+    }
+
+    /// Parse a hex string into a pattern. Whitespace and an optional `0x`
+    /// prefix are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `hex` - pattern as a hex string.
+    pub fn parse(hex: &str) -> Result<Pattern, Box<dyn Error>> {
+        let mut bytes: Vec<u8> = Vec::new();
+        for pair in hex_digits(hex)?.chunks(2) {
+            let pair: String = pair.iter().collect();
+            bytes.push(u8::from_str_radix(&pair, 16)?);
+        }
+        Ok(Pattern::exact(bytes))
+    }
+
+    /// Parse a hex string with wildcards into a pattern, for signatures
+    /// where some bytes vary. A `?` digit matches any nibble, as in
+    /// `DE??BE?F`, and a mask after `&`, as in `deadbeef&ffff00ff`,
+    /// selects the bits of each byte that must match. Otherwise it is
+    /// parsed like `parse`.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - pattern as a hex string.
+    pub fn parse_masked(text: &str) -> Result<Pattern, Box<dyn Error>> {
+        if let Some((hex, mask)) = text.split_once('&') {
+            let pattern = Pattern::parse_masked(hex)?;
+            let mask = Pattern::parse(mask)
+                .map_err(|e| e.to_string().replace("search pattern", "mask"))?
+                .bytes;
+            if mask.len() != pattern.len() {
+                return Err(format!(
+                    "mask length {} does not match the search pattern length {}",
+                    mask.len(),
+                    pattern.len()
+                )
+                .into());
+            }
+            let mask: Vec<u8> = mask.iter().zip(&pattern.mask).map(|(a, b)| a & b).collect();
+            let bytes = pattern
+                .bytes
+                .iter()
+                .zip(&mask)
+                .map(|(b, m)| b & m)
+                .collect();
+            return Ok(Pattern { bytes, mask });
+        }
+        let mut pattern = Pattern::exact(Vec::new());
+        for pair in hex_digits(text)?.chunks(2) {
+            let value: String = pair
+                .iter()
+                .map(|&c| if c == '?' { '0' } else { c })
+                .collect();
+            let mask: String = pair
+                .iter()
+                .map(|&c| if c == '?' { '0' } else { 'f' })
+                .collect();
+            pattern.bytes.push(u8::from_str_radix(&value, 16)?);
+            pattern.mask.push(u8::from_str_radix(&mask, 16)?);
+        }
+        Ok(pattern)
+    }
+
+    /// Returns true if `bytes`, as long as the pattern, match it.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - bytes to test.
+    pub fn matches<'a>(&self, bytes: impl IntoIterator<Item = &'a u8>) -> bool {
+        bytes
+            .into_iter()
+            .zip(self.bytes.iter().zip(&self.mask))
+            .all(|(b, (p, m))| b & m == *p)
+    }
+
+    /// Number of bytes in the pattern.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns true if the pattern has no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+/// Streaming search for a `Pattern`. Input is fed in order, in chunks of
+/// any size, and matches spanning chunk boundaries are found.
+#[derive(Clone, Debug)]
+pub struct Finder {
+    pattern: Pattern,
+    window: VecDeque<u8>,
+    position: u64,
+    matches: Vec<u64>,
+}
+
+impl Finder {
+    /// Finder constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - pattern to search for.
+    /// * `start_offset` - offset of the first byte fed.
+    pub fn new(pattern: Pattern, start_offset: u64) -> Finder {
+        Finder {
+            window: VecDeque::with_capacity(pattern.len()),
+            pattern,
+            position: start_offset,
+            matches: Vec::new(),
+        }
+    }
+
+    /// Pattern being searched for.
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    /// Feed the next bytes of input. Returns the offsets of matches
+    /// completed by these bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - next bytes of input.
+    pub fn feed(&mut self, bytes: &[u8]) -> &[u64] {
+        let found = self.matches.len();
+        let len = self.pattern.len();
+        for b in bytes {
+            if self.window.len() == len {
+                self.window.pop_front();
+            }
+            self.window.push_back(*b);
+            self.position += 1;
+            if self.window.len() == len && self.pattern.matches(&self.window) {
+                self.matches.push(self.position - len as u64);
+            }
+        }
+        &self.matches[found..]
+    }
+
+    /// Offsets of all matches found so far.
+    pub fn matches(&self) -> &[u64] {
+        &self.matches
+    }
+
+    /// Offset before which every match has been found.
+    pub fn settled(&self) -> u64 {
+        (self.position + 1).saturating_sub(self.pattern.len() as u64)
+    }
+}
+
+/// Streaming search for a regex over the input as text. Input is fed in
+/// order and searched once a window of it has built up, so matches
+/// spanning chunks are found as long as they are shorter than the
+/// window.
+#[derive(Clone, Debug)]
+pub struct RegexFinder {
+    regex: Regex,
+    window: Vec<u8>,
+    window_offset: u64,
+}
+
+impl RegexFinder {
+    /// RegexFinder constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `regex` - regex to search for.
+    /// * `start_offset` - offset of the first byte fed.
+    pub fn new(regex: Regex, start_offset: u64) -> RegexFinder {
+        RegexFinder {
+            regex,
+            window: Vec::new(),
+            window_offset: start_offset,
+        }
+    }
+
+    /// Feed the next bytes of input. The offsets and bytes of matches
+    /// that further input cannot change are appended to `found`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - next bytes of input.
+    /// * `found` - matches found.
+    pub fn feed(&mut self, bytes: &[u8], found: &mut Vec<(u64, Vec<u8>)>) {
+        self.window.extend_from_slice(bytes);
+        if self.window.len() >= 2 * REGEX_WINDOW {
+            self.search(self.window.len() - REGEX_WINDOW, found);
+        }
+    }
+
+    /// Search the rest of the input once it has all been fed.
+    ///
+    /// # Arguments
+    ///
+    /// * `found` - matches found.
+    pub fn finish(&mut self, found: &mut Vec<(u64, Vec<u8>)>) {
+        self.search(self.window.len(), found);
+    }
+
+    /// Offset before which every match has been found.
+    pub fn settled(&self) -> u64 {
+        self.window_offset
+    }
+
+    /// Report the matches ending by `settled` in the window and drop the
+    /// input before them.
+    fn search(&mut self, settled: usize, found: &mut Vec<(u64, Vec<u8>)>) {
+        // a match ending past the settled part of the window may grow
+        // with more input, so it is searched again
+        let mut cut = settled;
+        for m in self.regex.find_iter(&self.window) {
+            if m.end() > settled {
+                cut = m.start().min(settled);
+                break;
+            }
+            if !m.is_empty() {
+                found.push((self.window_offset + m.start() as u64, m.as_bytes().to_vec()));
+            }
+        }
+        self.window.drain(..cut);
+        self.window_offset += cut as u64;
+    }
+}
+
+/// Search selected by `--find` or `--find-regex`.
+#[derive(Clone, Debug)]
+pub enum Matcher {
+    /// hex pattern search
+    Pattern(Finder),
+    /// regex search
+    Regex(RegexFinder),
+}
+
+impl Matcher {
+    /// Feed the next bytes of input, appending the offset ranges of the
+    /// matches found to `found`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - next bytes of input.
+    /// * `found` - matches found.
+    pub fn feed(&mut self, bytes: &[u8], found: &mut Vec<Range<u64>>) {
+        match self {
+            Matcher::Pattern(finder) => {
+                let len = finder.pattern().len() as u64;
+                found.extend(finder.feed(bytes).iter().map(|&at| at..at + len));
+            }
+            Matcher::Regex(finder) => {
+                let mut matched = Vec::new();
+                finder.feed(bytes, &mut matched);
+                found.extend(matched_ranges(matched));
+            }
+        }
+    }
+
+    /// Finish the search once all input has been fed.
+    ///
+    /// # Arguments
+    ///
+    /// * `found` - matches found.
+    pub fn finish(&mut self, found: &mut Vec<Range<u64>>) {
+        if let Matcher::Regex(finder) = self {
+            let mut matched = Vec::new();
+            finder.finish(&mut matched);
+            found.extend(matched_ranges(matched));
+        }
+    }
+
+    /// Offset before which every match has been found.
+    pub fn settled(&self) -> u64 {
+        match self {
+            Matcher::Pattern(finder) => finder.settled(),
+            Matcher::Regex(finder) => finder.settled(),
+        }
+    }
+}
+
+/// Offset ranges of regex matches.
+fn matched_ranges(matched: Vec<(u64, Vec<u8>)>) -> impl Iterator<Item = Range<u64>> {
+    matched
+        .into_iter()
+        .map(|(at, bytes)| at..at + bytes.len() as u64)
+}
+
+/// Run a regex search over the input and print each match with its
+/// byte offset, like `--strings`. Invalid UTF-8 never matches `.` or
+/// other character classes, but can be matched as bytes with
+/// `(?-u:\xff)`. Returns the number of matches.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `buf` - input to be searched.
+/// * `truncate_len` - truncate to length, 0 reads to the end of input.
+/// * `finder` - regex search.
+/// * `options` - dump options.
+pub fn output_regex(
+    w: &mut impl Write,
+    buf: &mut dyn Read,
+    truncate_len: u64,
+    finder: &mut RegexFinder,
+    options: &DumpOptions,
+) -> io::Result<u64> {
+    let mut limited: Box<dyn Read + '_> = if truncate_len > 0 {
+        Box::new(buf.take(truncate_len))
+    } else {
+        Box::new(buf)
+    };
+    let mut found: Vec<(u64, Vec<u8>)> = Vec::new();
+    let mut count: u64 = 0x0;
+    loop {
+        let next = read_line(&mut limited, REGEX_WINDOW as u64, 0)?;
+        match next.as_ref() {
+            Some(line) => finder.feed(&line.hex_body, &mut found),
+            None => finder.finish(&mut found),
+        }
+        for (offset, bytes) in found.drain(..) {
+            let text = String::from_utf8_lossy(&bytes)
+                .chars()
+                .map(|c| {
+                    if c.is_control() {
+                        options.nonprintable
+                    } else {
+                        c
+                    }
+                })
+                .collect();
+            print_string(w, &Found { offset, text }, options)?;
+            count += 1;
+        }
+        if next.is_none() {
+            return Ok(count);
+        }
+    }
+}
+
+/// Print only the lines of the dump within `context` lines of a match,
+/// like `grep -C`, with `--` between runs of lines that are not
+/// adjacent. Returns the number of matches.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `buf` - input to be searched.
+/// * `truncate_len` - truncate to length, 0 reads to the end of input.
+/// * `matcher` - search.
+/// * `start_offset` - offset of the first byte of `buf`.
+/// * `context` - number of lines printed before and after a match.
+/// * `options` - dump options.
+pub fn output_context(
+    w: &mut impl Write,
+    buf: &mut dyn Read,
+    truncate_len: u64,
+    matcher: &mut Matcher,
+    start_offset: u64,
+    context: u64,
+    options: &DumpOptions,
+) -> io::Result<u64> {
+    let mut limited: Box<dyn Read + '_> = if truncate_len > 0 {
+        Box::new(buf.take(truncate_len))
+    } else {
+        Box::new(buf)
+    };
+    let reach = context.saturating_mul(options.column_width.max(1));
+    let mut pending: VecDeque<Line> = VecDeque::new();
+    let mut ranges: VecDeque<Range<u64>> = VecDeque::new();
+    let mut found: Vec<Range<u64>> = Vec::new();
+    let mut offset_counter: u64 = start_offset;
+    let mut last_printed: Option<u64> = None;
+    let mut count: u64 = 0x0;
+    loop {
+        let next = read_line(&mut limited, options.column_width, offset_counter)?;
+        let settled = match next {
+            Some(line) => {
+                matcher.feed(&line.hex_body, &mut found);
+                offset_counter += line.bytes;
+                pending.push_back(line);
+                matcher.settled()
+            }
+            None => {
+                matcher.finish(&mut found);
+                u64::MAX
+            }
+        };
+        count += found.len() as u64;
+        ranges.extend(found.drain(..));
+        while let Some(line) = pending.front() {
+            let end = line.offset + line.bytes;
+            // a match yet to be found could still bring the line into
+            // context
+            if end.saturating_add(reach) > settled {
+                break;
+            }
+            while ranges
+                .front()
+                .is_some_and(|range| range.end.saturating_add(reach) <= line.offset)
+            {
+                ranges.pop_front();
+            }
+            let nearby: Vec<Highlight> = ranges
+                .iter()
+                .take_while(|range| range.start < end.saturating_add(reach))
+                .map(|range| Highlight {
+                    range: range.clone(),
+                    color: options.theme.highlight,
+                })
+                .collect();
+            if !nearby.is_empty() {
+                if last_printed.is_some_and(|last| last != line.offset) {
+                    writeln!(w, "--")?;
+                }
+                print_line(w, line, options, &nearby)?;
+                last_printed = Some(end);
+            }
+            pending.pop_front();
+        }
+        if settled == u64::MAX {
+            return Ok(count);
+        }
+    }
+}
+
+/// Run a search over the input without printing it. Returns the number
+/// of matches.
+///
+/// # Arguments
+///
+/// * `buf` - input to be searched.
+/// * `truncate_len` - truncate to length, 0 reads to the end of input.
+/// * `matcher` - search.
+pub fn count_matches(
+    buf: &mut dyn Read,
+    truncate_len: u64,
+    matcher: &mut Matcher,
+) -> io::Result<u64> {
+    let mut limited: Box<dyn Read + '_> = if truncate_len > 0 {
+        Box::new(buf.take(truncate_len))
+    } else {
+        Box::new(buf)
+    };
+    let mut found: Vec<Range<u64>> = Vec::new();
+    let mut count: u64 = 0x0;
+    while let Some(line) = read_line(&mut limited, REGEX_WINDOW as u64, 0)? {
+        matcher.feed(&line.hex_body, &mut found);
+        count += found.drain(..).count() as u64;
+    }
+    matcher.finish(&mut found);
+    Ok(count + found.len() as u64)
+}
+
+/// Digits of a hex string with whitespace and an optional `0x` prefix
+/// removed, checked to be an even, non-zero number.
+///
+/// # Arguments
+///
+/// * `hex` - pattern as a hex string.
+fn hex_digits(hex: &str) -> Result<Vec<char>, Box<dyn Error>> {
+    let hex = hex.trim();
+    let hex = hex
+        .strip_prefix("0x")
+        .or_else(|| hex.strip_prefix("0X"))
+        .unwrap_or(hex);
+    let digits: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+    if digits.is_empty() {
+        return Err("search pattern is empty".into());
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("search pattern has an odd number of hex digits: {}", hex).into());
+    }
+    Ok(digits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pattern() {
+        assert_eq!(
+            Pattern::parse("deadBEEF").unwrap().bytes,
+            vec![0xde, 0xad, 0xbe, 0xef]
+        );
+        assert_eq!(Pattern::parse("0x0a 0d").unwrap().bytes, vec![0x0a, 0x0d]);
+        assert!(Pattern::parse("abc").is_err());
+        assert!(Pattern::parse("zz").is_err());
+        assert!(Pattern::parse("").is_err());
+    }
+
+    #[test]
+    fn test_parse_masked_pattern() {
+        let pattern = Pattern::parse_masked("DE??BE?F").unwrap();
+        assert_eq!(pattern.bytes, vec![0xde, 0x00, 0xbe, 0x0f]);
+        assert_eq!(pattern.mask, vec![0xff, 0x00, 0xff, 0x0f]);
+        let pattern = Pattern::parse_masked("deadbeef&ffff00ff").unwrap();
+        assert_eq!(pattern.bytes, vec![0xde, 0xad, 0x00, 0xef]);
+        assert_eq!(pattern.mask, vec![0xff, 0xff, 0x00, 0xff]);
+        assert!(pattern.matches(&[0xde, 0xad, 0x42, 0xef]));
+        assert!(!pattern.matches(&[0xde, 0xad, 0x42, 0xee]));
+        assert_eq!(
+            Pattern::parse_masked("dead").unwrap(),
+            Pattern::parse("dead").unwrap()
+        );
+        assert_eq!(
+            Pattern::parse_masked("dead&ff").unwrap_err().to_string(),
+            "mask length 1 does not match the search pattern length 2"
+        );
+        assert_eq!(
+            Pattern::parse_masked("dead&f").unwrap_err().to_string(),
+            "mask has an odd number of hex digits: f"
+        );
+        assert!(Pattern::parse("de??").is_err());
+    }
+
+    #[test]
+    fn test_finder_masked() {
+        let mut finder = Finder::new(Pattern::parse_masked("61??63").unwrap(), 0);
+        assert_eq!(finder.feed(b"abcaxcab"), &[0, 3]);
+        assert_eq!(finder.feed(b"c"), &[6]);
+    }
+
+    #[test]
+    fn test_finder_spans_chunks() {
+        let mut finder = Finder::new(Pattern::parse("6162").unwrap(), 0x10);
+        assert!(finder.feed(b"xxa").is_empty());
+        assert_eq!(finder.feed(b"bxab"), &[0x12, 0x15]);
+        assert_eq!(finder.matches(), &[0x12, 0x15]);
+    }
+
+    #[test]
+    fn test_finder_overlapping() {
+        let mut finder = Finder::new(Pattern::parse("6161").unwrap(), 0);
+        assert_eq!(finder.feed(b"aaa"), &[0, 1]);
+    }
+
+    #[test]
+    fn test_output_regex() {
+        let options = crate::HexConfig::new().dump_options();
+        let mut out: Vec<u8> = Vec::new();
+        let input = b"\x00\x01GET / HTTP/1.1\r\nUser-Agent: hx\r\n\xffUser-Agent: curl\n";
+        let mut finder = RegexFinder::new(Regex::new("User-Agent:.*").unwrap(), 0x10);
+        let count = output_regex(&mut out, &mut &input[..], 0, &mut finder, &options).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x000022: User-Agent: hx.\n0x000033: User-Agent: curl\n"
+        );
+    }
+
+    #[test]
+    fn test_output_regex_spans_reads() {
+        let options = crate::HexConfig::new().dump_options();
+        let mut input = vec![b'x'; REGEX_WINDOW * 2 - 3];
+        input.extend_from_slice(b"needle");
+        let mut out: Vec<u8> = Vec::new();
+        let mut finder = RegexFinder::new(Regex::new("x{2}needle").unwrap(), 0);
+        output_regex(&mut out, &mut &input[..], 0, &mut finder, &options).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "0x01fffb: xxneedle\n");
+        // a match straddling the part of the window searched first
+        let mut input = vec![b'x'; REGEX_WINDOW - 4];
+        input.extend_from_slice(b"needle");
+        input.extend_from_slice(&[b'x'; REGEX_WINDOW + 10]);
+        let mut out: Vec<u8> = Vec::new();
+        let mut finder = RegexFinder::new(Regex::new("needle").unwrap(), 0);
+        output_regex(&mut out, &mut &input[..], 0, &mut finder, &options).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "0x00fffc: needle\n");
+    }
+
+    #[test]
+    fn test_output_context() {
+        let options = crate::HexConfig::new().columns(4).dump_options();
+        let input = b"aaaabbbbccccddddXXeeffffgggghhhhXiii";
+        let mut matcher = Matcher::Pattern(Finder::new(Pattern::parse("58").unwrap(), 0));
+        let mut out: Vec<u8> = Vec::new();
+        let count =
+            output_context(&mut out, &mut &input[..], 0, &mut matcher, 0, 1, &options).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x00000c: 0x64 0x64 0x64 0x64 dddd\n\
+                   0x000010: 0x58 0x58 0x65 0x65 XXee\n\
+                   0x000014: 0x66 0x66 0x66 0x66 ffff\n\
+                   --\n\
+                   0x00001c: 0x68 0x68 0x68 0x68 hhhh\n\
+                   0x000020: 0x58 0x69 0x69 0x69 Xiii\n"
+        );
+        let mut matcher = Matcher::Regex(RegexFinder::new(Regex::new("X+").unwrap(), 0));
+        assert_eq!(count_matches(&mut &input[..], 0, &mut matcher).unwrap(), 2);
+    }
+}
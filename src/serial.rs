@@ -0,0 +1,125 @@
+//! serial port input, the bytes arriving on a UART device
+use std::io::{self, Read};
+
+/// Baud rate used when `--baud` is not given.
+pub const DEFAULT_BAUD: u32 = 115200;
+
+/// Termios speed for a baud rate, or `None` if the platform has no
+/// constant for it.
+#[cfg(all(unix, feature = "serial"))]
+fn speed(baud: u32) -> Option<libc::speed_t> {
+    Some(match baud {
+        1200 => libc::B1200,
+        2400 => libc::B2400,
+        4800 => libc::B4800,
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115200 => libc::B115200,
+        230400 => libc::B230400,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        460800 => libc::B460800,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        921600 => libc::B921600,
+        _ => return None,
+    })
+}
+
+/// Serial device in raw mode. The device settings are restored when
+/// dropped.
+#[cfg(all(unix, feature = "serial"))]
+struct Port {
+    file: std::fs::File,
+    original: libc::termios,
+}
+
+#[cfg(all(unix, feature = "serial"))]
+impl Port {
+    /// Open `path` and switch it to raw 8N1 at `baud`, so bytes are read
+    /// as they arrive and none are translated.
+    fn open(path: &str, baud: u32) -> io::Result<Port> {
+        use std::os::unix::fs::OpenOptionsExt;
+        use std::os::unix::io::AsRawFd;
+
+        let speed = speed(baud).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported baud rate: {}", baud),
+            )
+        })?;
+        // the device must not become the controlling terminal of hx
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY)
+            .open(path)?;
+        let fd = file.as_raw_fd();
+        let original = crate::sys::termios(fd)?;
+        let mut raw = original;
+        crate::sys::make_raw(&mut raw, speed)?;
+        crate::sys::set_termios(fd, &raw)?;
+        Ok(Port { file, original })
+    }
+}
+
+#[cfg(all(unix, feature = "serial"))]
+impl Read for Port {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+#[cfg(all(unix, feature = "serial"))]
+impl Drop for Port {
+    fn drop(&mut self) {
+        use std::os::unix::io::AsRawFd;
+
+        let _ = crate::sys::set_termios(self.file.as_raw_fd(), &self.original);
+    }
+}
+
+/// Open the serial device `path` at `baud` and return a reader of the
+/// bytes arriving on it.
+///
+/// # Arguments
+///
+/// * `path` - serial device, such as `/dev/ttyUSB0`.
+/// * `baud` - baud rate.
+#[cfg(all(unix, feature = "serial"))]
+pub fn open(path: &str, baud: u32) -> io::Result<Box<dyn Read + Send>> {
+    Ok(Box::new(Port::open(path, baud)?))
+}
+
+/// Open the serial device `path` at `baud` and return a reader of the
+/// bytes arriving on it.
+///
+/// # Arguments
+///
+/// * `path` - serial device, such as `/dev/ttyUSB0`.
+/// * `baud` - baud rate.
+#[cfg(not(all(unix, feature = "serial")))]
+pub fn open(_path: &str, _baud: u32) -> io::Result<Box<dyn Read + Send>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "--serial requires hx to be built with the serial feature on a unix platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(all(unix, feature = "serial"))]
+    #[test]
+    fn test_speed() {
+        assert_eq!(speed(9600), Some(libc::B9600));
+        assert_eq!(speed(DEFAULT_BAUD), Some(libc::B115200));
+        assert_eq!(speed(1234), None);
+    }
+
+    #[test]
+    fn test_open_missing_device() {
+        assert!(open("/nonexistent/ttyUSB0", DEFAULT_BAUD).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! structured output for machine consumption
+use crate::buffer::{Line, PageReader};
+use std::io::{self, Read, Write};
+
+/// Escape `text` as the contents of a JSON string.
+///
+/// # Arguments
+///
+/// * `text` - text to be escaped.
+pub fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Write a line as a single JSON record,
+/// `{"offset":0,"bytes":[105,108,10],"ascii":"il."}`.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `line` - line to be written, at `line.offset`, with `ascii` filled.
+pub fn write_json_line(w: &mut impl Write, line: &Line) -> io::Result<()> {
+    let bytes: Vec<String> = line.hex_body.iter().map(u8::to_string).collect();
+    writeln!(
+        w,
+        "{{\"offset\":{},\"bytes\":[{}],\"ascii\":\"{}\"}}",
+        line.offset,
+        bytes.join(","),
+        escape_json(&String::from_utf8_lossy(&line.ascii))
+    )
+}
+
+/// Stream `buf` as JSON lines, one record per line of `column_width`
+/// bytes. Returns the number of bytes written.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `buf` - input to be dumped.
+/// * `start_offset` - offset of the first byte of `buf` in the input.
+/// * `truncate_len` - truncate to length, 0 reads to the end of input.
+/// * `column_width` - number of bytes per record.
+pub fn output_json(
+    w: &mut impl Write,
+    buf: &mut dyn Read,
+    start_offset: u64,
+    truncate_len: u64,
+    column_width: u64,
+) -> io::Result<u64> {
+    let mut lines = PageReader::new(buf, column_width, start_offset, truncate_len);
+    for line in lines.by_ref() {
+        write_json_line(w, &line?)?;
+    }
+    Ok(lines.offset() - start_offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_json() {
+        assert_eq!(escape_json("a\"b\\c\n\u{1}"), "a\\\"b\\\\c\\n\\u0001");
+    }
+
+    #[test]
+    fn test_output_json() {
+        let mut out: Vec<u8> = Vec::new();
+        let bytes = output_json(&mut out, &mut &b"il\n\"\\"[..], 0x10, 0, 3).unwrap();
+        assert_eq!(bytes, 5);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"offset\":16,\"bytes\":[105,108,10],\"ascii\":\"il.\"}\n\
+             {\"offset\":19,\"bytes\":[34,92],\"ascii\":\"\\\"\\\\\"}\n"
+        );
+    }
+}
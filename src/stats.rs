@@ -0,0 +1,242 @@
+//! byte statistics
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+
+/// Width of the longest histogram bar.
+const BAR_WIDTH: u64 = 40;
+
+/// Byte frequencies of an input, fed incrementally as it is read.
+#[derive(Clone, Debug)]
+pub struct Stats {
+    counts: [u64; 256],
+    total: u64,
+}
+
+impl Stats {
+    /// Stats constructor.
+    pub fn new() -> Stats {
+        Stats {
+            counts: [0; 256],
+            total: 0,
+        }
+    }
+
+    /// Count `bytes`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - next bytes of the input.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.counts[*b as usize] += 1;
+        }
+        self.total += bytes.len() as u64;
+    }
+
+    /// Number of bytes counted.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Number of times `b` was counted.
+    pub fn count(&self, b: u8) -> u64 {
+        self.counts[b as usize]
+    }
+
+    /// Shannon entropy in bits per byte, from 0 for a single repeated
+    /// byte to 8 for uniformly random bytes.
+    pub fn entropy(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let total = self.total as f64;
+        self.counts
+            .iter()
+            .filter(|count| **count > 0)
+            .map(|count| {
+                let p = *count as f64 / total;
+                p * (1.0 / p).log2()
+            })
+            .sum()
+    }
+
+    /// Fraction of printable ASCII bytes, space included.
+    pub fn printable_ratio(&self) -> f64 {
+        let printable: u64 = (0x20..=0x7e).map(|b| self.counts[b]).sum();
+        self.ratio(printable)
+    }
+
+    /// Fraction of NUL bytes.
+    pub fn nul_ratio(&self) -> f64 {
+        self.ratio(self.counts[0])
+    }
+
+    fn ratio(&self, count: u64) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            count as f64 / self.total as f64
+        }
+    }
+
+    /// Write the entropy, ratios and a histogram of the bytes that occur,
+    /// with bars scaled to the most frequent byte.
+    ///
+    /// # Arguments
+    ///
+    /// * `w` - output sink.
+    pub fn write_report(&self, w: &mut impl Write) -> io::Result<()> {
+        writeln!(w, "  entropy: {:.4} bits/byte", self.entropy())?;
+        writeln!(w, "printable: {:.2}%", self.printable_ratio() * 100.0)?;
+        writeln!(w, "      nul: {:.2}%", self.nul_ratio() * 100.0)?;
+        let max = self.counts.iter().copied().max().unwrap_or(0);
+        for (b, count) in self.counts.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+            let bar = (count * BAR_WIDTH).div_ceil(max) as usize;
+            writeln!(
+                w,
+                "     0x{:02x}: {:>10} {:>6.2}% {}",
+                b,
+                count,
+                self.ratio(*count) * 100.0,
+                "#".repeat(bar)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Stats {
+        Stats::new()
+    }
+}
+
+/// Entropy of the most recent bytes of a stream, over a window that
+/// slides forward as bytes are fed.
+#[derive(Clone, Debug)]
+pub struct EntropyWindow {
+    window: VecDeque<u8>,
+    size: usize,
+    stats: Stats,
+}
+
+impl EntropyWindow {
+    /// EntropyWindow constructor.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - number of bytes in the window.
+    pub fn new(size: usize) -> EntropyWindow {
+        EntropyWindow {
+            window: VecDeque::with_capacity(size),
+            size: size.max(1),
+            stats: Stats::new(),
+        }
+    }
+
+    /// Slide the window over `bytes` and return the entropy of the window,
+    /// in bits per byte.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - next bytes of the input.
+    pub fn feed(&mut self, bytes: &[u8]) -> f64 {
+        for b in bytes {
+            if self.window.len() == self.size {
+                if let Some(old) = self.window.pop_front() {
+                    self.stats.counts[old as usize] -= 1;
+                    self.stats.total -= 1;
+                }
+            }
+            self.window.push_back(*b);
+            self.stats.feed(&[*b]);
+        }
+        self.stats.entropy()
+    }
+}
+
+/// Reader that counts every byte read through it, so statistics come
+/// from the same stream as the dump. The stats are shared, so they can
+/// be read after the reader has been handed off to an output mode.
+#[derive(Debug)]
+pub struct Counted<R: Read> {
+    inner: R,
+    stats: Rc<RefCell<Stats>>,
+}
+
+impl<R: Read> Counted<R> {
+    /// Counted constructor.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - reader to be counted.
+    /// * `stats` - statistics fed with the bytes read.
+    pub fn new(inner: R, stats: Rc<RefCell<Stats>>) -> Counted<R> {
+        Counted { inner, stats }
+    }
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.stats.borrow_mut().feed(&buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entropy() {
+        let mut stats = Stats::new();
+        assert_eq!(stats.entropy(), 0.0);
+        stats.feed(b"aaaa");
+        assert!(stats.entropy().is_sign_positive());
+        assert_eq!(stats.entropy(), 0.0);
+        stats.feed(b"bbbb");
+        assert_eq!(stats.entropy(), 1.0);
+        let mut stats = Stats::new();
+        stats.feed(&(0..=255).collect::<Vec<u8>>());
+        assert_eq!(stats.entropy(), 8.0);
+    }
+
+    #[test]
+    fn test_entropy_window() {
+        let mut window = EntropyWindow::new(4);
+        assert_eq!(window.feed(b"abab"), 1.0);
+        assert_eq!(window.feed(b"cd"), 2.0);
+        assert_eq!(window.feed(b"zzzzzz"), 0.0);
+    }
+
+    #[test]
+    fn test_counted_ratios_and_report() {
+        let shared = Rc::new(RefCell::new(Stats::new()));
+        let mut sink: Vec<u8> = Vec::new();
+        io::copy(&mut Counted::new(&b"ab\0\0"[..], shared.clone()), &mut sink).unwrap();
+        let stats = shared.borrow();
+        assert_eq!(stats.total(), 4);
+        assert_eq!(stats.count(0), 2);
+        assert_eq!(stats.printable_ratio(), 0.5);
+        assert_eq!(stats.nul_ratio(), 0.5);
+        let mut report: Vec<u8> = Vec::new();
+        stats.write_report(&mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report
+            .starts_with("  entropy: 1.5000 bits/byte\nprintable: 50.00%\n      nul: 50.00%\n"));
+        assert!(report.contains(&format!(
+            "     0x00:          2  50.00% {}\n",
+            "#".repeat(40)
+        )));
+        assert!(report.contains(&format!(
+            "     0x61:          1  25.00% {}\n",
+            "#".repeat(20)
+        )));
+    }
+}
@@ -0,0 +1,311 @@
+//! printable string extraction
+use crate::buffer::read_line;
+use crate::output::{print_offset, DumpOptions};
+use std::io::{self, Read, Write};
+
+/// Character encoding scanned for by `--strings`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// 7 bit ascii
+    Ascii,
+    /// utf-8
+    Utf8,
+    /// utf-16 little endian
+    Utf16Le,
+    /// utf-16 big endian
+    Utf16Be,
+}
+
+impl Encoding {
+    /// Look up an encoding by its `--strings-encoding` name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - encoding name.
+    pub fn by_name(name: &str) -> Option<Encoding> {
+        match name {
+            "ascii" => Some(Self::Ascii),
+            "utf8" => Some(Self::Utf8),
+            "utf16le" => Some(Self::Utf16Le),
+            "utf16be" => Some(Self::Utf16Be),
+            _ => None,
+        }
+    }
+}
+
+/// Printable string found in the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Found {
+    /// offset of the first byte of the string
+    pub offset: u64,
+    /// decoded string
+    pub text: String,
+}
+
+/// Streaming scanner for runs of printable characters. Input is fed in
+/// order, in chunks of any size.
+#[derive(Clone, Debug)]
+pub struct StringScanner {
+    encoding: Encoding,
+    min_len: usize,
+    position: u64,
+    pending: Vec<u8>,
+    unit_start: u64,
+    high_surrogate: Option<u16>,
+    run: String,
+    run_start: u64,
+    run_chars: usize,
+}
+
+impl StringScanner {
+    /// StringScanner constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `encoding` - character encoding to scan for.
+    /// * `min_len` - minimum number of characters in a string.
+    /// * `start_offset` - offset of the first byte fed.
+    pub fn new(encoding: Encoding, min_len: usize, start_offset: u64) -> StringScanner {
+        StringScanner {
+            encoding,
+            min_len: min_len.max(1),
+            position: start_offset,
+            pending: Vec::new(),
+            unit_start: start_offset,
+            high_surrogate: None,
+            run: String::new(),
+            run_start: start_offset,
+            run_chars: 0,
+        }
+    }
+
+    /// Feed the next bytes of input, collecting completed strings.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - next bytes of input.
+    /// * `found` - completed strings are appended here.
+    pub fn feed(&mut self, bytes: &[u8], found: &mut Vec<Found>) {
+        for b in bytes {
+            self.feed_byte(*b, found);
+            self.position += 1;
+        }
+    }
+
+    /// Flush the string in progress at the end of input.
+    ///
+    /// # Arguments
+    ///
+    /// * `found` - completed strings are appended here.
+    pub fn finish(&mut self, found: &mut Vec<Found>) {
+        self.end_run(found);
+    }
+
+    fn feed_byte(&mut self, b: u8, found: &mut Vec<Found>) {
+        if self.pending.is_empty() {
+            self.unit_start = self.position;
+        }
+        match self.encoding {
+            Encoding::Ascii => self.push_char(Some(b as char).filter(char::is_ascii), found),
+            Encoding::Utf8 => self.feed_utf8(b, found),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                self.pending.push(b);
+                if self.pending.len() == 2 {
+                    let pair = [self.pending[0], self.pending[1]];
+                    self.pending.clear();
+                    let unit = match self.encoding {
+                        Encoding::Utf16Le => u16::from_le_bytes(pair),
+                        _ => u16::from_be_bytes(pair),
+                    };
+                    self.feed_utf16(unit, found);
+                }
+            }
+        }
+    }
+
+    fn feed_utf8(&mut self, b: u8, found: &mut Vec<Found>) {
+        if !self.pending.is_empty() {
+            if b & 0xc0 == 0x80 {
+                self.pending.push(b);
+                let expected = match self.pending[0] {
+                    0xc2..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    _ => 4,
+                };
+                if self.pending.len() == expected {
+                    let c = std::str::from_utf8(&self.pending)
+                        .ok()
+                        .and_then(|s| s.chars().next());
+                    self.pending.clear();
+                    self.push_char(c, found);
+                }
+                return;
+            }
+            // truncated sequence, rescan this byte on its own
+            self.pending.clear();
+            self.end_run(found);
+            self.unit_start = self.position;
+        }
+        match b {
+            0x00..=0x7f => self.push_char(Some(b as char), found),
+            0xc2..=0xf4 => self.pending.push(b),
+            _ => self.end_run(found),
+        }
+    }
+
+    fn feed_utf16(&mut self, unit: u16, found: &mut Vec<Found>) {
+        match (self.high_surrogate.take(), unit) {
+            (None, 0xd800..=0xdbff) => self.high_surrogate = Some(unit),
+            (Some(high), 0xdc00..=0xdfff) => {
+                self.unit_start -= 2;
+                let c = char::decode_utf16([high, unit]).next().and_then(Result::ok);
+                self.push_char(c, found);
+            }
+            (high, _) => {
+                if high.is_some() {
+                    self.end_run(found);
+                }
+                self.push_char(char::from_u32(u32::from(unit)), found);
+            }
+        }
+    }
+
+    fn push_char(&mut self, c: Option<char>, found: &mut Vec<Found>) {
+        match c {
+            Some(c) if c == '\t' || !c.is_control() => {
+                if self.run.is_empty() {
+                    self.run_start = self.unit_start;
+                }
+                self.run.push(c);
+                self.run_chars += 1;
+            }
+            _ => self.end_run(found),
+        }
+    }
+
+    fn end_run(&mut self, found: &mut Vec<Found>) {
+        if self.run_chars >= self.min_len {
+            found.push(Found {
+                offset: self.run_start,
+                text: std::mem::take(&mut self.run),
+            });
+        }
+        self.run.clear();
+        self.run_chars = 0;
+    }
+}
+
+/// Print a found string with its offset, colored per the dump options.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `found` - string to be printed.
+/// * `options` - dump options.
+pub fn print_string(w: &mut impl Write, found: &Found, options: &DumpOptions) -> io::Result<()> {
+    print_offset(w, found.offset, options)?;
+    if options.colorize {
+        for c in found.text.chars() {
+            let b = u8::try_from(u32::from(c)).unwrap_or(0xff);
+            let style = options.style(b, None).unwrap_or_default();
+            write!(w, "{}", style.paint(c.to_string()))?;
+        }
+        writeln!(w)
+    } else {
+        writeln!(w, "{}", found.text)
+    }
+}
+
+/// Stream `buf` through a `StringScanner`, printing each string found.
+/// Returns the number of strings found.
+///
+/// # Arguments
+///
+/// * `w` - output sink.
+/// * `buf` - input to be scanned.
+/// * `truncate_len` - truncate to length, 0 reads to the end of input.
+/// * `scanner` - string scanner.
+/// * `options` - dump options.
+pub fn output_strings(
+    w: &mut impl Write,
+    buf: &mut dyn Read,
+    truncate_len: u64,
+    scanner: &mut StringScanner,
+    options: &DumpOptions,
+) -> io::Result<u64> {
+    let mut limited: Box<dyn Read + '_> = if truncate_len > 0 {
+        Box::new(buf.take(truncate_len))
+    } else {
+        Box::new(buf)
+    };
+    let mut found: Vec<Found> = Vec::new();
+    let mut count: u64 = 0x0;
+    while let Some(line) = read_line(&mut limited, 0x1000, 0)? {
+        scanner.feed(&line.hex_body, &mut found);
+        for string in found.drain(..) {
+            print_string(w, &string, options)?;
+            count += 1;
+        }
+    }
+    scanner.finish(&mut found);
+    for string in found.drain(..) {
+        print_string(w, &string, options)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(encoding: Encoding, min_len: usize, bytes: &[u8]) -> Vec<Found> {
+        let mut scanner = StringScanner::new(encoding, min_len, 0);
+        let mut found: Vec<Found> = Vec::new();
+        for chunk in bytes.chunks(3) {
+            scanner.feed(chunk, &mut found);
+        }
+        scanner.finish(&mut found);
+        found
+    }
+
+    #[test]
+    fn test_scan_ascii() {
+        let found = scan(Encoding::Ascii, 4, b"\x00\x01hello\x00hi\x00\xffworld!");
+        assert_eq!(
+            found,
+            vec![
+                Found {
+                    offset: 2,
+                    text: "hello".to_string()
+                },
+                Found {
+                    offset: 12,
+                    text: "world!".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_utf8() {
+        let found = scan(Encoding::Utf8, 4, "\u{0}gr\u{fc}\u{df}e\u{0}".as_bytes());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].offset, 1);
+        assert_eq!(found[0].text, "gr\u{fc}\u{df}e");
+        assert!(scan(Encoding::Utf8, 1, b"\xc3\x00").is_empty());
+    }
+
+    #[test]
+    fn test_scan_utf16() {
+        let le: Vec<u8> = "\u{0}abc\u{1f600}"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let found = scan(Encoding::Utf16Le, 4, &le);
+        assert_eq!(found[0].offset, 2);
+        assert_eq!(found[0].text, "abc\u{1f600}");
+        let be: Vec<u8> = "xyz!".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(scan(Encoding::Utf16Be, 4, &be)[0].text, "xyz!");
+    }
+}
@@ -0,0 +1,173 @@
+//! unix system calls made through libc, each wrapped in a safe function.
+//! This is the only module allowed unsafe code, so every unsafe block of
+//! the crate is here with the reason it is sound.
+#[cfg(any(feature = "mmap", feature = "tui", feature = "serial"))]
+use std::io;
+
+/// Size of the terminal `fd` as columns and rows, or `None` if `fd` is
+/// not a terminal or has no size set.
+///
+/// # Arguments
+///
+/// * `fd` - file descriptor of the terminal.
+pub(crate) fn window_size(fd: libc::c_int) -> Option<(u64, u64)> {
+    // SAFETY: winsize is plain integers, for which all zeroes is valid.
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    // SAFETY: TIOCGWINSZ writes at most one winsize through the pointer,
+    // which is valid for that. On a descriptor that is not a terminal it
+    // fails without writing.
+    if unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) } == 0
+        && size.ws_col > 0
+        && size.ws_row > 0
+    {
+        return Some((u64::from(size.ws_col), u64::from(size.ws_row)));
+    }
+    None
+}
+
+/// Terminal settings of `fd`.
+///
+/// # Arguments
+///
+/// * `fd` - file descriptor of a terminal or serial device.
+#[cfg(any(feature = "tui", feature = "serial"))]
+pub(crate) fn termios(fd: libc::c_int) -> io::Result<libc::termios> {
+    // SAFETY: termios is plain integers and arrays of them, for which all
+    // zeroes is valid.
+    let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+    // SAFETY: tcgetattr writes one termios through the pointer, which is
+    // valid for that, and fails on a descriptor that is not a terminal.
+    if unsafe { libc::tcgetattr(fd, &mut termios) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(termios)
+}
+
+/// Apply `termios` to `fd` at once.
+///
+/// # Arguments
+///
+/// * `fd` - file descriptor of a terminal or serial device.
+/// * `termios` - settings, as read by `termios` and changed.
+#[cfg(any(feature = "tui", feature = "serial"))]
+pub(crate) fn set_termios(fd: libc::c_int, termios: &libc::termios) -> io::Result<()> {
+    // SAFETY: tcsetattr only reads the termios behind the reference.
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, termios) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Settings for raw 8N1 at `speed`, so bytes are read as they arrive and
+/// none are translated, like `cfmakeraw`.
+///
+/// # Arguments
+///
+/// * `termios` - settings to be changed.
+/// * `speed` - termios speed constant, such as `B115200`.
+#[cfg(feature = "serial")]
+pub(crate) fn make_raw(termios: &mut libc::termios, speed: libc::speed_t) -> io::Result<()> {
+    // SAFETY: cfmakeraw only changes the flags of the termios behind the
+    // reference.
+    unsafe { libc::cfmakeraw(termios) };
+    termios.c_cflag |= libc::CLOCAL | libc::CREAD;
+    termios.c_cc[libc::VMIN] = 1;
+    termios.c_cc[libc::VTIME] = 0;
+    // SAFETY: both only set the speed fields of the termios behind the
+    // reference, and fail on a speed that is not a speed constant.
+    if unsafe { libc::cfsetispeed(termios, speed) } != 0
+        || unsafe { libc::cfsetospeed(termios, speed) } != 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Read-only private mapping of a whole file, unmapped when dropped.
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub(crate) struct Mapping {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+#[cfg(feature = "mmap")]
+impl Mapping {
+    /// Map the whole of `file` read-only.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - file to be mapped.
+    pub(crate) fn new(file: &std::fs::File) -> io::Result<Mapping> {
+        use std::os::unix::io::AsRawFd;
+
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            // mmap rejects empty mappings
+            return Ok(Mapping {
+                ptr: std::ptr::null_mut(),
+                len,
+            });
+        }
+        // SAFETY: a null hint lets the kernel pick the address, and the
+        // descriptor is open for reading for the duration of the call. The
+        // result is checked against MAP_FAILED before it is used.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        // dumps read front to back, let the kernel read ahead aggressively
+        // SAFETY: ptr and len are the mapping just made; madvise is only
+        // a hint and does not change its contents.
+        unsafe { libc::madvise(ptr, len, libc::MADV_SEQUENTIAL) };
+        Ok(Mapping { ptr, len })
+    }
+
+    /// The mapped bytes.
+    pub(crate) fn bytes(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        // SAFETY: ptr points at len readable bytes until drop unmaps them,
+        // and the slice borrows self, so it cannot outlive them. The
+        // mapping is private and read-only, so hx never writes through it.
+        // A file truncated by another process while mapped makes reads
+        // past its new end fault with SIGBUS; --mmap documents this and
+        // refuses --follow and --tail, which are meant for files that
+        // change.
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: ptr and len are the mapping made in new, unmapped
+            // only here, and no slice of it outlives self.
+            unsafe { libc::munmap(self.ptr, self.len) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_size() {
+        use std::os::unix::io::AsRawFd;
+
+        let file = std::fs::File::open("Cargo.toml").unwrap();
+        assert_eq!(window_size(file.as_raw_fd()), None);
+    }
+}
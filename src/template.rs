@@ -0,0 +1,488 @@
+//! field annotations from a layout template
+use crate::args::{parse_color, parse_size};
+use crate::buffer::Line;
+use crate::output::{Endian, Highlight};
+use ansi_term::Color;
+use std::error::Error;
+use std::ops::Range;
+use toml::Value;
+
+/// Background colors given in turn to fields without a `color`.
+const FIELD_COLORS: [Color; 6] = [
+    Color::Fixed(24),
+    Color::Fixed(89),
+    Color::Fixed(58),
+    Color::Fixed(30),
+    Color::Fixed(94),
+    Color::Fixed(54),
+];
+
+/// Number of bytes shown when decoding a `bytes` field.
+const BYTES_SHOWN: usize = 16;
+
+/// How the bytes of a template field are decoded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    /// unsigned 8 bit integer
+    U8,
+    /// unsigned 16 bit integer
+    U16,
+    /// unsigned 32 bit integer
+    U32,
+    /// unsigned 64 bit integer
+    U64,
+    /// signed 8 bit integer
+    I8,
+    /// signed 16 bit integer
+    I16,
+    /// signed 32 bit integer
+    I32,
+    /// signed 64 bit integer
+    I64,
+    /// 32 bit float
+    F32,
+    /// 64 bit float
+    F64,
+    /// raw bytes, printed as hex
+    Bytes,
+    /// ASCII text
+    Ascii,
+}
+
+impl FieldType {
+    /// Look up a field type by its template name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - field type name.
+    pub fn by_name(name: &str) -> Option<FieldType> {
+        match name {
+            "u8" => Some(Self::U8),
+            "u16" => Some(Self::U16),
+            "u32" => Some(Self::U32),
+            "u64" => Some(Self::U64),
+            "i8" => Some(Self::I8),
+            "i16" => Some(Self::I16),
+            "i32" => Some(Self::I32),
+            "i64" => Some(Self::I64),
+            "f32" => Some(Self::F32),
+            "f64" => Some(Self::F64),
+            "bytes" => Some(Self::Bytes),
+            "ascii" => Some(Self::Ascii),
+            _ => None,
+        }
+    }
+
+    /// Number of bytes of a value, `None` when the template gives the length.
+    pub fn size(&self) -> Option<u64> {
+        match self {
+            Self::U8 | Self::I8 => Some(1),
+            Self::U16 | Self::I16 => Some(2),
+            Self::U32 | Self::I32 | Self::F32 => Some(4),
+            Self::U64 | Self::I64 | Self::F64 => Some(8),
+            Self::Bytes | Self::Ascii => None,
+        }
+    }
+}
+
+/// Named field of a template, covering `length` bytes from `offset`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    /// field name
+    pub name: String,
+    /// offset of the first byte in the input
+    pub offset: u64,
+    /// number of bytes
+    pub length: u64,
+    /// how the bytes are decoded
+    pub kind: FieldType,
+    /// byte order of numeric fields
+    pub endian: Endian,
+    /// background color of the field's bytes
+    pub color: Color,
+}
+
+impl Field {
+    /// Offsets covered by the field.
+    pub fn range(&self) -> Range<u64> {
+        self.offset..self.offset + self.length
+    }
+
+    /// Decoded value of the field.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - the field's bytes.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        let word = self.endian.word(bytes);
+        let bits = bytes.len() as u32 * 8;
+        let signed = |word: u64| (word << (64 - bits)) as i64 >> (64 - bits);
+        match self.kind {
+            FieldType::U8 | FieldType::U16 | FieldType::U32 | FieldType::U64 => {
+                format!("{} ({:#x})", word, word)
+            }
+            FieldType::I8 | FieldType::I16 | FieldType::I32 | FieldType::I64 => {
+                format!("{}", signed(word))
+            }
+            FieldType::F32 => format!("{}", f32::from_bits(word as u32)),
+            FieldType::F64 => format!("{}", f64::from_bits(word)),
+            FieldType::Bytes => {
+                let hex: Vec<String> = bytes
+                    .iter()
+                    .take(BYTES_SHOWN)
+                    .map(|b| format!("{:02x}", b))
+                    .collect();
+                match bytes.len() > BYTES_SHOWN {
+                    true => format!("{} ...", hex.join(" ")),
+                    false => hex.join(" "),
+                }
+            }
+            FieldType::Ascii => {
+                let text: String = bytes
+                    .iter()
+                    .map(|b| match b {
+                        0x20..=0x7e => char::from(*b),
+                        _ => '.',
+                    })
+                    .collect();
+                format!("\"{}\"", text)
+            }
+        }
+    }
+}
+
+/// Layout of a binary format, read from a TOML file such as:
+///
+/// ```toml
+/// endian = "le"
+///
+/// [[field]]
+/// name = "magic"
+/// offset = 0
+/// type = "ascii"
+/// length = 4
+///
+/// [[field]]
+/// name = "version"
+/// offset = "0x4"
+/// type = "u16"
+/// endian = "be"
+/// color = "blue"
+/// ```
+///
+/// Offsets and lengths are integers or sizes as accepted by `parse_size`.
+/// Numeric types have a fixed length, `bytes` and `ascii` fields need a
+/// `length`. `endian` defaults to `le`, and `color` to a color from a
+/// palette cycled through the fields.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    fields: Vec<Field>,
+}
+
+impl Template {
+    /// Parse a TOML template.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - template source.
+    pub fn parse(text: &str) -> Result<Template, Box<dyn Error>> {
+        let value: Value = text.parse()?;
+        let endian = match value.get("endian") {
+            Some(endian) => parse_endian(endian)?,
+            None => Endian::Little,
+        };
+        let entries = match value.get("field") {
+            Some(Value::Array(entries)) => entries.as_slice(),
+            Some(_) => return Err("template field must be an array of tables, [[field]]".into()),
+            None => &[],
+        };
+        let mut fields: Vec<Field> = Vec::with_capacity(entries.len());
+        for (i, entry) in entries.iter().enumerate() {
+            fields.push(parse_field(entry, i, endian)?);
+        }
+        // annotations are emitted in order of the field ends
+        fields.sort_by_key(|field| (field.range().end, field.offset));
+        Ok(Template { fields })
+    }
+
+    /// Fields, ordered by the offset after their last byte.
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Highlights coloring the bytes of each field.
+    pub fn highlights(&self) -> Vec<Highlight> {
+        self.fields
+            .iter()
+            .map(|field| Highlight {
+                range: field.range(),
+                color: field.color,
+            })
+            .collect()
+    }
+}
+
+/// Parse entry `i` of the `[[field]]` array.
+fn parse_field(entry: &Value, i: usize, endian: Endian) -> Result<Field, Box<dyn Error>> {
+    let name = match entry.get("name") {
+        Some(Value::String(name)) => name.clone(),
+        _ => return Err(format!("template field {} has no name", i + 1).into()),
+    };
+    let kind = match entry.get("type") {
+        Some(Value::String(kind)) => FieldType::by_name(kind)
+            .ok_or_else(|| format!("template field {}: unknown type: {}", name, kind))?,
+        _ => return Err(format!("template field {} has no type", name).into()),
+    };
+    let offset = match entry.get("offset") {
+        Some(offset) => parse_size_value(offset, &name)?,
+        None => return Err(format!("template field {} has no offset", name).into()),
+    };
+    let length = match (entry.get("length"), kind.size()) {
+        (Some(length), size) => {
+            let length = parse_size_value(length, &name)?;
+            if size.is_some_and(|size| size != length) {
+                return Err(format!("template field {}: length does not match type", name).into());
+            }
+            length
+        }
+        (None, Some(size)) => size,
+        (None, None) => return Err(format!("template field {} has no length", name).into()),
+    };
+    if length == 0 {
+        return Err(format!("template field {} is empty", name).into());
+    }
+    if offset.checked_add(length).is_none() {
+        return Err(format!("template field {} ends beyond the largest offset", name).into());
+    }
+    let endian = match entry.get("endian") {
+        Some(endian) => parse_endian(endian)?,
+        None => endian,
+    };
+    let color = match entry.get("color") {
+        Some(Value::String(color)) => {
+            parse_color(color).ok_or_else(|| format!("unknown color: {}", color))?
+        }
+        Some(_) => return Err(format!("template field {}: color must be a string", name).into()),
+        None => FIELD_COLORS[i % FIELD_COLORS.len()],
+    };
+    Ok(Field {
+        name,
+        offset,
+        length,
+        kind,
+        endian,
+        color,
+    })
+}
+
+/// Parse an offset or length given as an integer or a size string.
+fn parse_size_value(value: &Value, name: &str) -> Result<u64, Box<dyn Error>> {
+    match value {
+        Value::Integer(n) => u64::try_from(*n)
+            .map_err(|_| format!("template field {}: negative offset or length", name).into()),
+        Value::String(size) => Ok(parse_size(size)?),
+        _ => Err(format!("template field {}: offset and length must be sizes", name).into()),
+    }
+}
+
+fn parse_endian(value: &Value) -> Result<Endian, Box<dyn Error>> {
+    value
+        .as_str()
+        .and_then(Endian::by_name)
+        .ok_or_else(|| format!("unknown endianness: {}", value).into())
+}
+
+/// Decoded value of a template field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Annotation {
+    /// field name
+    pub name: String,
+    /// decoded value
+    pub value: String,
+    /// field color
+    pub color: Color,
+}
+
+/// Decodes template fields from dumped lines. Bytes are kept only while
+/// a field that needs them has not ended.
+#[derive(Clone, Debug)]
+pub struct Annotator {
+    fields: Vec<Field>,
+    next: usize,
+    window: Vec<u8>,
+    window_start: u64,
+}
+
+impl Annotator {
+    /// Annotator constructor.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - fields to decode.
+    pub fn new(template: Template) -> Annotator {
+        Annotator {
+            fields: template.fields,
+            next: 0,
+            window: Vec::new(),
+            window_start: 0,
+        }
+    }
+
+    /// Feed the next dumped line, and return the fields ending within it.
+    /// Fields starting before the first line fed are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - next line, at `line.offset`.
+    pub fn feed(&mut self, line: &Line) -> Vec<Annotation> {
+        if line.offset != self.window_start + self.window.len() as u64 {
+            self.window.clear();
+            self.window_start = line.offset;
+        }
+        self.window.extend_from_slice(&line.hex_body);
+        let end = line.offset + line.bytes;
+        let mut annotations: Vec<Annotation> = Vec::new();
+        while let Some(field) = self.fields.get(self.next) {
+            if field.range().end > end {
+                break;
+            }
+            if field.offset >= self.window_start {
+                let start = (field.offset - self.window_start) as usize;
+                let bytes = &self.window[start..start + field.length as usize];
+                annotations.push(Annotation {
+                    name: field.name.clone(),
+                    value: field.decode(bytes),
+                    color: field.color,
+                });
+            }
+            self.next += 1;
+        }
+        let keep = self.fields[self.next..]
+            .iter()
+            .map(|field| field.offset)
+            .min()
+            .unwrap_or(end)
+            .clamp(self.window_start, end);
+        self.window.drain(..(keep - self.window_start) as usize);
+        self.window_start = keep;
+        annotations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::read_line;
+
+    const TEMPLATE: &str = r#"
+        [[field]]
+        name = "magic"
+        offset = 0
+        type = "ascii"
+        length = 4
+
+        [[field]]
+        name = "version"
+        offset = "0x4"
+        type = "u16"
+        endian = "be"
+        color = "blue"
+
+        [[field]]
+        name = "delta"
+        offset = 6
+        type = "i8"
+    "#;
+
+    #[test]
+    fn test_parse_template() {
+        let template = Template::parse(TEMPLATE).unwrap();
+        let names: Vec<&str> = template
+            .fields()
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["magic", "version", "delta"]);
+        let version = &template.fields()[1];
+        assert_eq!(version.range(), 4..6);
+        assert_eq!(version.endian, Endian::Big);
+        assert_eq!(version.color, Color::Blue);
+        assert_eq!(template.highlights()[0].range, 0..4);
+        assert_eq!(template.highlights()[0].color, FIELD_COLORS[0]);
+    }
+
+    #[test]
+    fn test_parse_template_errors() {
+        let field = |body: &str| Template::parse(&format!("[[field]]\n{}", body));
+        assert!(field("name = \"a\"\noffset = 0\ntype = \"u128\"").is_err());
+        assert!(field("name = \"a\"\noffset = 0\ntype = \"bytes\"").is_err());
+        assert!(field("name = \"a\"\noffset = 0\ntype = \"u16\"\nlength = 4").is_err());
+        assert!(field("name = \"a\"\noffset = -1\ntype = \"u8\"").is_err());
+        assert!(field("name = \"a\"\ntype = \"u8\"").is_err());
+        assert!(field("offset = 0\ntype = \"u8\"").is_err());
+        assert!(Template::parse("field = 1").is_err());
+        assert!(Template::parse("[[field]").is_err());
+    }
+
+    #[test]
+    fn test_decode() {
+        let field = |kind, endian| Field {
+            name: String::new(),
+            offset: 0,
+            length: 0,
+            kind,
+            endian,
+            color: Color::Red,
+        };
+        assert_eq!(
+            field(FieldType::U16, Endian::Little).decode(&[0x34, 0x12]),
+            "4660 (0x1234)"
+        );
+        assert_eq!(
+            field(FieldType::I16, Endian::Big).decode(&[0xff, 0xfe]),
+            "-2"
+        );
+        assert_eq!(
+            field(FieldType::F32, Endian::Little).decode(&1.5f32.to_le_bytes()),
+            "1.5"
+        );
+        assert_eq!(
+            field(FieldType::Ascii, Endian::Little).decode(b"EL\x7f"),
+            "\"EL.\""
+        );
+        assert_eq!(
+            field(FieldType::Bytes, Endian::Little).decode(&[0xab; 17]),
+            format!("{} ...", vec!["ab"; 16].join(" "))
+        );
+    }
+
+    #[test]
+    fn test_annotator_spans_lines() {
+        let mut annotator = Annotator::new(Template::parse(TEMPLATE).unwrap());
+        let mut input: &[u8] = b"hx\0\x01\x00\x02\xff\x00";
+        let mut values: Vec<Vec<String>> = Vec::new();
+        let mut offset = 0;
+        while let Some(line) = read_line(&mut input, 3, offset).unwrap() {
+            offset += line.bytes;
+            let annotations = annotator.feed(&line);
+            values.push(annotations.into_iter().map(|a| a.value).collect());
+        }
+        assert_eq!(
+            values,
+            vec![
+                vec![],
+                vec!["\"hx..\"".to_string(), "2 (0x2)".to_string()],
+                vec!["-1".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_annotator_skips_fields_before_seek() {
+        let mut annotator = Annotator::new(Template::parse(TEMPLATE).unwrap());
+        let mut input: &[u8] = b"\x00\x02\xff";
+        let line = read_line(&mut input, 3, 4).unwrap().unwrap();
+        let names: Vec<String> = annotator.feed(&line).into_iter().map(|a| a.name).collect();
+        assert_eq!(names, vec!["version", "delta"]);
+    }
+}
@@ -0,0 +1,142 @@
+//! byte transforms applied to the input before it is rendered
+use crate::args::parse_size;
+use crate::search::Pattern;
+use std::error::Error;
+use std::io::{self, Read};
+
+/// Transform applied to every byte, selected by `--xor`, `--rot` or
+/// `--add`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Op {
+    /// xor with a key, repeated from offset 0 of the input
+    Xor(Vec<u8>),
+    /// rotate the bits of the byte left, or right if negative
+    Rot(i64),
+    /// add a value, wrapping, or subtract it if negative
+    Add(i64),
+}
+
+impl Op {
+    /// Parse the value of a `--xor`, `--rot` or `--add` option.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - long option name.
+    /// * `value` - option value, a hex key for `xor` and an integer,
+    ///   which may be negative, otherwise.
+    pub fn parse(name: &str, value: &str) -> Result<Op, Box<dyn Error>> {
+        let signed = |value: &str| -> Result<i64, Box<dyn Error>> {
+            match value.strip_prefix('-') {
+                Some(value) => Ok(-(parse_size(value)? as i64)),
+                None => Ok(parse_size(value)? as i64),
+            }
+        };
+        match name {
+            "xor" => Ok(Op::Xor(
+                Pattern::parse(value)
+                    .map_err(|e| e.to_string().replace("search pattern", "key"))?
+                    .bytes,
+            )),
+            "rot" => Ok(Op::Rot(signed(value)?)),
+            "add" => Ok(Op::Add(signed(value)?)),
+            _ => Err(format!("unknown transform: {}", name).into()),
+        }
+    }
+
+    /// Transform `bytes`, the first of which is at `offset` in the input.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - bytes to transform.
+    /// * `offset` - offset of the first byte, which selects the key byte.
+    pub fn apply(&self, bytes: &mut [u8], offset: u64) {
+        match self {
+            Op::Xor(key) => {
+                let start = (offset % key.len() as u64) as usize;
+                for (b, k) in bytes.iter_mut().zip(key.iter().cycle().skip(start)) {
+                    *b ^= k;
+                }
+            }
+            Op::Rot(n) => {
+                let n = n.rem_euclid(8) as u32;
+                bytes.iter_mut().for_each(|b| *b = b.rotate_left(n));
+            }
+            Op::Add(n) => {
+                let n = n.rem_euclid(256) as u8;
+                bytes.iter_mut().for_each(|b| *b = b.wrapping_add(n));
+            }
+        }
+    }
+}
+
+/// Reader that transforms every byte read through it, so every output
+/// mode renders the transformed input.
+#[derive(Debug)]
+pub struct Transformed<R: Read> {
+    inner: R,
+    ops: Vec<Op>,
+    offset: u64,
+}
+
+impl<R: Read> Transformed<R> {
+    /// Transformed constructor.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - reader to be transformed.
+    /// * `ops` - transforms, applied in order.
+    /// * `start_offset` - offset of the first byte read from `inner`.
+    pub fn new(inner: R, ops: Vec<Op>, start_offset: u64) -> Transformed<R> {
+        Transformed {
+            inner,
+            ops,
+            offset: start_offset,
+        }
+    }
+}
+
+impl<R: Read> Read for Transformed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        for op in &self.ops {
+            op.apply(&mut buf[..n], self.offset);
+        }
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_op_parse() {
+        assert_eq!(
+            Op::parse("xor", "0x2a ff").unwrap(),
+            Op::Xor(vec![0x2a, 0xff])
+        );
+        assert_eq!(Op::parse("rot", "-3").unwrap(), Op::Rot(-3));
+        assert_eq!(Op::parse("add", "0x10").unwrap(), Op::Add(16));
+        assert_eq!(
+            Op::parse("xor", "").unwrap_err().to_string(),
+            "key is empty"
+        );
+        assert!(Op::parse("add", "x").is_err());
+    }
+
+    #[test]
+    fn test_transformed() {
+        let ops = vec![Op::Xor(vec![0x01, 0x02]), Op::Add(-1)];
+        let mut out: Vec<u8> = Vec::new();
+        Transformed::new(&[0x10, 0x10, 0x10][..], ops, 1)
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, [0x11, 0x10, 0x11]);
+        let mut bytes = [0x81, 0x0f];
+        Op::Rot(1).apply(&mut bytes, 0);
+        assert_eq!(bytes, [0x03, 0x1e]);
+        Op::Rot(-9).apply(&mut bytes, 0);
+        assert_eq!(bytes, [0x81, 0x0f]);
+    }
+}
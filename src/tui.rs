@@ -0,0 +1,539 @@
+//! interactive viewer
+use crate::args;
+use crate::buffer::read_line;
+use crate::output::{print_octets, print_offset, DumpOptions, Highlight};
+use crate::search::{Finder, Pattern};
+use crate::Format;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Key pressed in the viewer, decoded from terminal input.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Key {
+    /// arrow up
+    Up,
+    /// arrow down
+    Down,
+    /// page up
+    PageUp,
+    /// page down
+    PageDown,
+    /// home
+    Home,
+    /// end
+    End,
+    /// enter or return
+    Enter,
+    /// backspace
+    Backspace,
+    /// escape
+    Escape,
+    /// printable character or control code
+    Char(char),
+    /// unrecognized escape sequence
+    Unknown,
+}
+
+/// Decode the first key in `input`, returning the key and the number of
+/// bytes it used, or `None` if `input` is empty.
+///
+/// # Arguments
+///
+/// * `input` - bytes read from the terminal.
+pub fn parse_key(input: &[u8]) -> Option<(Key, usize)> {
+    const SEQUENCES: [(&[u8], Key); 10] = [
+        (b"\x1b[A", Key::Up),
+        (b"\x1b[B", Key::Down),
+        (b"\x1b[5~", Key::PageUp),
+        (b"\x1b[6~", Key::PageDown),
+        (b"\x1b[H", Key::Home),
+        (b"\x1b[1~", Key::Home),
+        (b"\x1b[F", Key::End),
+        (b"\x1b[4~", Key::End),
+        (b"\x1bOH", Key::Home),
+        (b"\x1bOF", Key::End),
+    ];
+    let first = *input.first()?;
+    if first == 0x1b {
+        if input.len() == 1 {
+            return Some((Key::Escape, 1));
+        }
+        for (sequence, key) in SEQUENCES.iter() {
+            if input.starts_with(sequence) {
+                return Some((*key, sequence.len()));
+            }
+        }
+        // skip an unknown CSI sequence up to its final byte
+        if input[1] == b'[' {
+            let end = input[2..]
+                .iter()
+                .position(|b| (0x40..=0x7e).contains(b))
+                .map_or(input.len(), |i| i + 3);
+            return Some((Key::Unknown, end));
+        }
+        return Some((Key::Escape, 1));
+    }
+    let key = match first {
+        b'\r' | b'\n' => Key::Enter,
+        0x08 | 0x7f => Key::Backspace,
+        b => Key::Char(b as char),
+    };
+    Some((key, 1))
+}
+
+/// Line editor shown in the status line while reading an offset or a
+/// search pattern.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Prompt {
+    Offset,
+    Search,
+}
+
+/// Scrollable hex view of a seekable input. Only the visible page is
+/// read, so inputs of any size open instantly.
+#[derive(Debug)]
+pub struct Viewer<R: Read + Seek> {
+    source: R,
+    len: u64,
+    top: u64,
+    rows: u64,
+    options: DumpOptions,
+    prompt: Option<(Prompt, String)>,
+    pattern: Option<Pattern>,
+    found: Option<u64>,
+    status: String,
+}
+
+impl<R: Read + Seek> Viewer<R> {
+    /// Viewer constructor, showing `rows` lines of `source`.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - input to be viewed.
+    /// * `options` - dump options.
+    /// * `rows` - number of lines shown, excluding the status line.
+    pub fn new(mut source: R, options: DumpOptions, rows: u64) -> io::Result<Viewer<R>> {
+        let len = source.seek(SeekFrom::End(0))?;
+        Ok(Viewer {
+            source,
+            len,
+            top: 0,
+            rows: rows.max(1),
+            options,
+            prompt: None,
+            pattern: None,
+            found: None,
+            status: String::new(),
+        })
+    }
+
+    /// Offset of the first byte shown.
+    pub fn top(&self) -> u64 {
+        self.top
+    }
+
+    /// Offset of the current search match, if any.
+    pub fn found(&self) -> Option<u64> {
+        self.found
+    }
+
+    /// Current dump options.
+    pub fn options(&self) -> &DumpOptions {
+        &self.options
+    }
+
+    /// Set number of lines shown, excluding the status line.
+    pub fn resize(&mut self, rows: u64) {
+        self.rows = rows.max(1);
+        self.scroll_to(self.top);
+    }
+
+    fn columns(&self) -> u64 {
+        self.options.column_width.max(1)
+    }
+
+    /// Largest top offset, where the last line is at the bottom.
+    fn last_top(&self) -> u64 {
+        let columns = self.columns();
+        let last_line = self.len.saturating_sub(1) / columns * columns;
+        last_line.saturating_sub((self.rows - 1) * columns)
+    }
+
+    fn scroll_to(&mut self, offset: u64) {
+        let columns = self.columns();
+        self.top = (offset / columns * columns).min(self.last_top());
+    }
+
+    fn scroll_by(&mut self, lines: i64) {
+        let delta = lines.unsigned_abs() * self.columns();
+        let offset = if lines < 0 {
+            self.top.saturating_sub(delta)
+        } else {
+            self.top.saturating_add(delta)
+        };
+        self.scroll_to(offset);
+    }
+
+    /// Handle a key press. Returns false when the viewer should quit.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - key pressed.
+    pub fn handle(&mut self, key: Key) -> io::Result<bool> {
+        if let Some((prompt, mut text)) = self.prompt.take() {
+            match key {
+                Key::Enter => self.submit(prompt, &text)?,
+                Key::Escape | Key::Char('\x03') => {}
+                Key::Backspace => {
+                    text.pop();
+                    self.prompt = Some((prompt, text));
+                }
+                Key::Char(c) if !c.is_control() => {
+                    text.push(c);
+                    self.prompt = Some((prompt, text));
+                }
+                _ => self.prompt = Some((prompt, text)),
+            }
+            return Ok(true);
+        }
+        self.status.clear();
+        let page = self.rows as i64;
+        match key {
+            Key::Char('q') | Key::Char('\x03') | Key::Escape => return Ok(false),
+            Key::Up | Key::Char('k') => self.scroll_by(-1),
+            Key::Down | Key::Char('j') => self.scroll_by(1),
+            Key::PageUp | Key::Char('b') => self.scroll_by(-page),
+            Key::PageDown | Key::Char(' ') => self.scroll_by(page),
+            Key::Home => self.scroll_to(0),
+            Key::End | Key::Char('G') => self.scroll_to(self.len),
+            Key::Char('g') => self.prompt = Some((Prompt::Offset, String::new())),
+            Key::Char('/') => self.prompt = Some((Prompt::Search, String::new())),
+            Key::Char('n') => self.search_next()?,
+            Key::Char('f') => self.options.format = next_format(self.options.format),
+            Key::Char('c') => self.options.colorize = !self.options.colorize,
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn submit(&mut self, prompt: Prompt, text: &str) -> io::Result<()> {
+        match prompt {
+            Prompt::Offset => match args::parse_size(text.trim()) {
+                Ok(offset) => self.scroll_to(offset),
+                Err(e) => self.status = format!("invalid offset: {}", e),
+            },
+            Prompt::Search => {
+                match parse_pattern(text) {
+                    Some(pattern) => self.pattern = Some(pattern),
+                    None => {
+                        self.status = "search pattern is empty".to_string();
+                        return Ok(());
+                    }
+                }
+                self.found = None;
+                self.search_from(self.top)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn search_next(&mut self) -> io::Result<()> {
+        match self.found {
+            Some(found) => self.search_from(found + 1),
+            None => self.search_from(self.top),
+        }
+    }
+
+    /// Stream the input from `start` through a `Finder` and scroll to the
+    /// first match.
+    fn search_from(&mut self, start: u64) -> io::Result<()> {
+        let pattern = match self.pattern.as_ref() {
+            Some(pattern) => pattern.clone(),
+            None => {
+                self.status = "no search pattern, press / to search".to_string();
+                return Ok(());
+            }
+        };
+        self.source.seek(SeekFrom::Start(start))?;
+        let mut finder = Finder::new(pattern, start);
+        let mut chunk = [0u8; 64 * 1024];
+        loop {
+            let n = self.source.read(&mut chunk)?;
+            if n == 0 {
+                self.status = "pattern not found".to_string();
+                return Ok(());
+            }
+            if let Some(found) = finder.feed(&chunk[..n]).first() {
+                let found = *found;
+                self.found = Some(found);
+                self.scroll_to(found);
+                return Ok(());
+            }
+        }
+    }
+
+    /// Draw the visible page and the status line.
+    ///
+    /// # Arguments
+    ///
+    /// * `w` - terminal output.
+    pub fn render(&mut self, w: &mut impl Write) -> io::Result<()> {
+        let mut screen: Vec<u8> = Vec::new();
+        write!(screen, "\x1b[H")?;
+        let highlights: Vec<Highlight> = match (self.found, self.pattern.as_ref()) {
+            (Some(found), Some(pattern)) => vec![Highlight {
+                range: found..found + pattern.len() as u64,
+                color: self.options.theme.highlight,
+            }],
+            _ => Vec::new(),
+        };
+        self.source.seek(SeekFrom::Start(self.top))?;
+        let page_len = self.rows * self.columns();
+        let mut page = (&mut self.source).take(page_len);
+        let mut offset = self.top;
+        for _ in 0..self.rows {
+            if let Some(line) = read_line(&mut page, self.options.column_width, offset)? {
+                print_offset(&mut screen, offset, &self.options)?;
+                print_octets(&mut screen, &line, &self.options, &highlights)?;
+                offset += line.bytes;
+            }
+            writeln!(screen, "\x1b[K")?;
+        }
+        match self.prompt.as_ref() {
+            Some((Prompt::Offset, text)) => write!(screen, "offset: {}", text)?,
+            Some((Prompt::Search, text)) => write!(screen, "search: {}", text)?,
+            None if !self.status.is_empty() => write!(screen, "{}", self.status)?,
+            None => write!(
+                screen,
+                "{} / {}  {:?}  arrows/PgUp/PgDn scroll  g/G offset/end  / search  n next  f format  c color  q quit",
+                self.options.offset_format.format(
+                    self.options.base_address.wrapping_add(self.top),
+                    self.options.offset_width
+                ),
+                self.len,
+                self.options.format
+            )?,
+        }
+        write!(screen, "\x1b[K")?;
+        w.write_all(&screen)?;
+        w.flush()
+    }
+}
+
+/// Parse a search pattern: text in double quotes is matched as ASCII,
+/// otherwise it is read as hex, which may have wildcards, falling back to
+/// ASCII when it is not.
+///
+/// # Arguments
+///
+/// * `text` - pattern as typed.
+pub fn parse_pattern(text: &str) -> Option<Pattern> {
+    let bytes = match text.strip_prefix('"') {
+        Some(ascii) => ascii.strip_suffix('"').unwrap_or(ascii).as_bytes().to_vec(),
+        None => match Pattern::parse_masked(text) {
+            Ok(pattern) => return Some(pattern),
+            Err(_) => text.as_bytes().to_vec(),
+        },
+    };
+    if bytes.is_empty() {
+        None
+    } else {
+        Some(Pattern::exact(bytes))
+    }
+}
+
+/// Octet formats cycled through with `f`.
+fn next_format(format: Format) -> Format {
+    match format {
+        Format::LowerHex => Format::UpperHex,
+        Format::UpperHex => Format::Octal,
+        Format::Octal => Format::Binary,
+        Format::Binary => Format::Decimal,
+        Format::Decimal => Format::SignedDecimal,
+        _ => Format::LowerHex,
+    }
+}
+
+/// Restores the terminal settings when dropped.
+#[cfg(all(unix, feature = "tui"))]
+struct RawMode {
+    original: libc::termios,
+}
+
+#[cfg(all(unix, feature = "tui"))]
+impl RawMode {
+    /// Turn off line buffering, echo and signal keys on stdin. Output
+    /// processing is kept, so `\n` still returns the cursor.
+    fn enable() -> io::Result<RawMode> {
+        let original = crate::sys::termios(libc::STDIN_FILENO)?;
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        crate::sys::set_termios(libc::STDIN_FILENO, &raw)?;
+        Ok(RawMode { original })
+    }
+}
+
+#[cfg(all(unix, feature = "tui"))]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = crate::sys::set_termios(libc::STDIN_FILENO, &self.original);
+    }
+}
+
+/// Open `path` in the interactive viewer until the user quits.
+///
+/// # Arguments
+///
+/// * `path` - file to be viewed.
+/// * `options` - initial dump options.
+#[cfg(all(unix, feature = "tui"))]
+pub fn run(path: &str, options: DumpOptions) -> io::Result<()> {
+    let file = std::fs::File::open(path)?;
+    let mut viewer = Viewer::new(file, options, crate::output::terminal_size().1 - 1)?;
+    let _raw = RawMode::enable()?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut stdin = io::stdin();
+    // alternate screen, hidden cursor
+    write!(out, "\x1b[?1049h\x1b[?25l")?;
+    let result = (|| -> io::Result<()> {
+        let mut input = [0u8; 64];
+        loop {
+            viewer.resize(crate::output::terminal_size().1 - 1);
+            viewer.render(&mut out)?;
+            let n = stdin.read(&mut input)?;
+            if n == 0 {
+                return Ok(());
+            }
+            let mut pending = &input[..n];
+            while let Some((key, used)) = parse_key(pending) {
+                if !viewer.handle(key)? {
+                    return Ok(());
+                }
+                pending = &pending[used..];
+            }
+        }
+    })();
+    write!(out, "\x1b[?25h\x1b[?1049l")?;
+    out.flush()?;
+    result
+}
+
+/// Open `path` in the interactive viewer until the user quits.
+///
+/// # Arguments
+///
+/// * `path` - file to be viewed.
+/// * `options` - initial dump options.
+#[cfg(not(all(unix, feature = "tui")))]
+pub fn run(_path: &str, _options: DumpOptions) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "--interactive requires hx to be built with the tui feature on a unix platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HexConfig;
+    use std::io::Cursor;
+
+    fn viewer(len: usize, rows: u64) -> Viewer<Cursor<Vec<u8>>> {
+        let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let options = HexConfig::new().columns(4).dump_options();
+        Viewer::new(Cursor::new(data), options, rows).unwrap()
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key(b""), None);
+        assert_eq!(parse_key(b"\x1b[Aj"), Some((Key::Up, 3)));
+        assert_eq!(parse_key(b"\x1b[6~"), Some((Key::PageDown, 4)));
+        assert_eq!(parse_key(b"\x1b"), Some((Key::Escape, 1)));
+        assert_eq!(parse_key(b"\x1b[1;5C"), Some((Key::Unknown, 6)));
+        assert_eq!(parse_key(b"\r"), Some((Key::Enter, 1)));
+        assert_eq!(parse_key(b"\x7f"), Some((Key::Backspace, 1)));
+        assert_eq!(parse_key(b"g"), Some((Key::Char('g'), 1)));
+    }
+
+    #[test]
+    fn test_parse_pattern() {
+        assert_eq!(parse_pattern("dead").unwrap().bytes, vec![0xde, 0xad]);
+        assert_eq!(parse_pattern("\"dead\"").unwrap().bytes, b"dead".to_vec());
+        assert_eq!(parse_pattern("hello").unwrap().bytes, b"hello".to_vec());
+        assert_eq!(parse_pattern("de??").unwrap().mask, vec![0xff, 0x00]);
+        assert_eq!(parse_pattern(""), None);
+    }
+
+    #[test]
+    fn test_viewer_scroll() {
+        // 10 lines of 4 bytes, 3 rows visible
+        let mut viewer = viewer(40, 3);
+        viewer.handle(Key::Down).unwrap();
+        assert_eq!(viewer.top(), 4);
+        viewer.handle(Key::PageDown).unwrap();
+        assert_eq!(viewer.top(), 16);
+        viewer.handle(Key::Char('G')).unwrap();
+        assert_eq!(viewer.top(), 28);
+        viewer.handle(Key::Down).unwrap();
+        assert_eq!(viewer.top(), 28);
+        viewer.handle(Key::PageUp).unwrap();
+        assert_eq!(viewer.top(), 16);
+        viewer.handle(Key::Home).unwrap();
+        assert_eq!(viewer.top(), 0);
+        viewer.handle(Key::Up).unwrap();
+        assert_eq!(viewer.top(), 0);
+        assert!(!viewer.handle(Key::Char('q')).unwrap());
+    }
+
+    #[test]
+    fn test_viewer_goto_offset() {
+        let mut viewer = viewer(40, 3);
+        for key in [
+            Key::Char('g'),
+            Key::Char('0'),
+            Key::Char('x'),
+            Key::Char('a'),
+        ] {
+            viewer.handle(key).unwrap();
+        }
+        viewer.handle(Key::Enter).unwrap();
+        assert_eq!(viewer.top(), 8);
+    }
+
+    #[test]
+    fn test_viewer_search() {
+        let mut viewer = viewer(512, 3);
+        for key in [Key::Char('/'), Key::Char('1'), Key::Char('4'), Key::Enter] {
+            viewer.handle(key).unwrap();
+        }
+        assert_eq!(viewer.found(), Some(0x14));
+        assert_eq!(viewer.top(), 0x14);
+        viewer.handle(Key::Char('n')).unwrap();
+        assert_eq!(viewer.found(), Some(0x114));
+        viewer.handle(Key::Char('n')).unwrap();
+        assert_eq!(viewer.found(), Some(0x114));
+    }
+
+    #[test]
+    fn test_viewer_toggles() {
+        let mut viewer = viewer(40, 3);
+        viewer.handle(Key::Char('f')).unwrap();
+        assert!(matches!(viewer.options().format, Format::UpperHex));
+        viewer.handle(Key::Char('c')).unwrap();
+        assert!(viewer.options().colorize);
+    }
+
+    #[test]
+    fn test_viewer_render() {
+        let mut viewer = viewer(6, 3);
+        let mut screen: Vec<u8> = Vec::new();
+        viewer.render(&mut screen).unwrap();
+        let screen = String::from_utf8(screen).unwrap();
+        assert!(screen.starts_with("\x1b[H0x000000: 0x00 0x01 0x02 0x03 ....\x1b[K\n"));
+        assert!(screen.contains("0x000004: 0x04 0x05"));
+        assert!(screen.contains("0x000000 / 6"));
+    }
+}